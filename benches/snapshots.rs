@@ -0,0 +1,86 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compares the time of the batch of the public API reads that takes a snapshot
+//! for every read with the same batch served from the single snapshot.
+//!
+//! Run with `cargo bench --bench snapshots`.
+
+extern crate bitcoin;
+extern crate btc_transaction_utils;
+extern crate byteorder;
+extern crate exonum;
+extern crate exonum_bitcoinrpc as bitcoinrpc;
+extern crate exonum_btc_anchoring;
+extern crate exonum_jsonrpc as jsonrpc;
+#[macro_use]
+extern crate exonum_testkit;
+extern crate libc;
+#[macro_use]
+extern crate log;
+extern crate rand;
+extern crate secp256k1;
+extern crate serde;
+#[macro_use]
+extern crate serde_json;
+
+#[macro_use]
+#[path = "../tests/testkit_extras/mod.rs"]
+pub mod testkit_extras;
+
+use std::time::{Duration, Instant};
+
+use exonum_btc_anchoring::api::PublicApi;
+use testkit_extras::AnchoringTestKit;
+
+/// Number of the batches in every run.
+const ITERATIONS: u32 = 10_000;
+
+fn read_batch(api: &PublicApi) {
+    api.actual_lect().unwrap();
+    api.actual_address().unwrap();
+    api.actual_config().unwrap();
+    api.lect_agreement().unwrap();
+    api.transition_status().unwrap();
+}
+
+fn read_batch_from_snapshot(api: &PublicApi) {
+    let snapshot = api.snapshot();
+    snapshot.actual_lect().unwrap();
+    snapshot.actual_address().unwrap();
+    snapshot.actual_config().unwrap();
+    snapshot.lect_agreement().unwrap();
+    snapshot.transition_status().unwrap();
+}
+
+fn run<F: Fn(&PublicApi)>(name: &str, api: &PublicApi, f: F) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f(api);
+    }
+    let elapsed = start.elapsed();
+    println!("{}: {} ns per batch", name, nanos(elapsed) / u64::from(ITERATIONS));
+}
+
+fn nanos(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000_000 + u64::from(duration.subsec_nanos())
+}
+
+fn main() {
+    let mut testkit = AnchoringTestKit::default();
+    let api = PublicApi::new(testkit.blockchain_mut().clone());
+
+    run("snapshot per read", &api, read_batch);
+    run("snapshot per batch", &api, read_batch_from_snapshot);
+}
@@ -0,0 +1,57 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! HTTP caching of the results that are stable between the config or lect changes.
+
+use iron::headers::{CacheControl, CacheDirective, ContentType, ETag, EntityTag, IfNoneMatch};
+use iron::prelude::*;
+use iron::status;
+use serde_json::{self, Value};
+
+use exonum::crypto::{Hash, HashStream};
+use exonum::encoding::serialize::encode_hex;
+
+/// Returns the entity tag that combines the given state `hashes`.
+pub fn etag_of<'a, I>(hashes: I) -> Hash
+where
+    I: IntoIterator<Item = &'a Hash>,
+{
+    hashes
+        .into_iter()
+        .fold(HashStream::new(), |stream, hash| stream.update(hash.as_ref()))
+        .hash()
+}
+
+/// Returns `304 Not Modified` if the `If-None-Match` header of the request matches
+/// the given `etag`, otherwise the `json` value. Both responses carry the tag and
+/// require the clients to revalidate the cached values.
+pub fn cached_response(req: &Request, etag: &Hash, json: &Value) -> IronResult<Response> {
+    let tag = EntityTag::strong(encode_hex(etag));
+    let not_modified = match req.headers.get::<IfNoneMatch>() {
+        Some(&IfNoneMatch::Any) => true,
+        Some(&IfNoneMatch::Items(ref tags)) => tags.iter().any(|item| item.weak_eq(&tag)),
+        None => false,
+    };
+
+    let mut res = if not_modified {
+        Response::with(status::NotModified)
+    } else {
+        let mut res = Response::with((status::Ok, serde_json::to_string_pretty(json).unwrap()));
+        res.headers.set(ContentType::json());
+        res
+    };
+    res.headers.set(ETag(tag));
+    res.headers.set(CacheControl(vec![CacheDirective::NoCache]));
+    Ok(res)
+}
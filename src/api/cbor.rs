@@ -0,0 +1,54 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Binary responses encoded by CBOR (RFC 7049) for the clients that request them
+//! by the `Accept: application/cbor` header.
+//!
+//! The values are encoded through their JSON representation, so the binary response
+//! has exactly the same structure as the JSON one and the object keys are ordered.
+
+use iron::headers::{Accept, ContentType};
+use iron::mime::{Mime, SubLevel, TopLevel};
+use iron::modifiers::Header;
+use iron::prelude::*;
+use iron::status;
+use serde_cbor;
+use serde_json::Value;
+
+use exonum::api::ApiError;
+
+/// Returns the `application/cbor` media type.
+pub fn cbor_mime() -> Mime {
+    Mime(TopLevel::Application, SubLevel::Ext("cbor".to_owned()), vec![])
+}
+
+/// Returns `true` if the `Accept` header of the request lists the `application/cbor` type.
+pub fn accepts_cbor(req: &Request) -> bool {
+    req.headers.get::<Accept>().map_or(false, |accept| {
+        accept.iter().any(|item| match item.item {
+            Mime(TopLevel::Application, SubLevel::Ext(ref sub_level), _) => sub_level == "cbor",
+            _ => false,
+        })
+    })
+}
+
+/// Returns the response with the CBOR-encoded `json` value.
+pub fn cbor_response(json: &Value) -> IronResult<Response> {
+    let body = serde_cbor::to_vec(json).map_err(|e| ApiError::InternalError(Box::new(e)))?;
+    Ok(Response::with((
+        status::Ok,
+        Header(ContentType(cbor_mime())),
+        body,
+    )))
+}
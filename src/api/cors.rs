@@ -0,0 +1,79 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use iron::headers::{AccessControlAllowMethods, AccessControlAllowOrigin, Origin};
+use iron::method::Method;
+use iron::prelude::*;
+use iron::{status, AfterMiddleware};
+
+/// Middleware that adds CORS headers to the responses for the allowed origins.
+#[derive(Debug, Clone)]
+pub struct CorsMiddleware {
+    allowed_origins: Vec<String>,
+}
+
+impl CorsMiddleware {
+    /// Creates middleware for the given list of `allowed_origins`.
+    /// The `*` origin allows requests from any origin.
+    pub fn new<I>(allowed_origins: I) -> CorsMiddleware
+    where
+        I: IntoIterator<Item = String>,
+    {
+        CorsMiddleware {
+            allowed_origins: allowed_origins.into_iter().collect(),
+        }
+    }
+
+    fn allowed_origin(&self, req: &Request) -> Option<String> {
+        let origin = req.headers.get::<Origin>()?.to_string();
+        if self.allowed_origins
+            .iter()
+            .any(|allowed| allowed == "*" || *allowed == origin)
+        {
+            Some(origin)
+        } else {
+            None
+        }
+    }
+
+    fn set_headers(&self, origin: String, res: &mut Response) {
+        res.headers.set(AccessControlAllowOrigin::Value(origin));
+        res.headers
+            .set(AccessControlAllowMethods(vec![Method::Get, Method::Options]));
+    }
+}
+
+impl AfterMiddleware for CorsMiddleware {
+    fn after(&self, req: &mut Request, mut res: Response) -> IronResult<Response> {
+        if let Some(origin) = self.allowed_origin(req) {
+            self.set_headers(origin, &mut res);
+        }
+        Ok(res)
+    }
+
+    fn catch(&self, req: &mut Request, mut err: IronError) -> IronResult<Response> {
+        match self.allowed_origin(req) {
+            // Router does not know about preflight requests, so we answer them here.
+            Some(origin) => if req.method == Method::Options {
+                let mut res = Response::with(status::Ok);
+                self.set_headers(origin, &mut res);
+                Ok(res)
+            } else {
+                self.set_headers(origin, &mut err.response);
+                Err(err)
+            },
+            None => Err(err),
+        }
+    }
+}
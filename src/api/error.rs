@@ -16,16 +16,99 @@ use std::error;
 use std::fmt;
 
 use exonum::api::ApiError;
+use exonum::crypto::Hash;
+
+use details::btc::TxId;
+use details::error::{PsbtError, TxBuildError};
+
+use super::MAX_PROOF_HEIGHTS_COUNT;
 
 #[derive(Debug)]
 pub enum Error {
     UnknownValidatorId(u32),
+    MalformedLect(TxId),
+    InvalidHeightRange { from: u64, to: u64 },
+    InvalidHeightsCount(usize),
+    InvalidTxId(String),
+    InvalidBlockHash(String),
+    UnknownPayloadVersion { txid: TxId, version: u8 },
+    RelayNotConfigured,
+    RelayUnavailable(String),
+    PayloadNotFound(TxId),
+    TxNotFound(TxId),
+    BlockNotFound(Hash),
+    MalformedRawTx,
+    TxBuild(TxBuildError),
+    NotEnoughBlocks { height: u64, count: u64 },
+    Pruned { height: u64, cutoff: u64 },
+    TransitionInProgress,
+    NoLectConsensus,
+    FeeRateNotAgreed,
+    AlreadyAnchored { height: u64, anchored_height: u64 },
+    WatchdogNotConfigured,
+    UnknownProposal(TxId),
+    NodeChannelNotConfigured,
+    NotValidator,
+    Psbt(PsbtError),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Error::UnknownValidatorId(id) => write!(f, "Unknown validator id={}", id),
+            Error::MalformedLect(ref txid) => write!(f, "Malformed lect txid={}", txid),
+            Error::InvalidHeightRange { from, to } => {
+                write!(f, "Invalid height range from={}, to={}", from, to)
+            }
+            Error::InvalidHeightsCount(count) => write!(
+                f,
+                "Invalid heights count={}, expected from 1 to {}",
+                count, MAX_PROOF_HEIGHTS_COUNT
+            ),
+            Error::InvalidTxId(ref txid) => write!(f, "Invalid txid={}", txid),
+            Error::InvalidBlockHash(ref hash) => write!(f, "Invalid block hash={}", hash),
+            Error::UnknownPayloadVersion { ref txid, version } => write!(
+                f,
+                "Unknown payload version={} of lect txid={}",
+                version, txid
+            ),
+            Error::RelayNotConfigured => write!(f, "Bitcoin relay is not configured"),
+            Error::RelayUnavailable(ref e) => write!(f, "Bitcoin relay is unavailable: {}", e),
+            Error::PayloadNotFound(ref txid) => {
+                write!(f, "Anchoring payload of txid={} is not found", txid)
+            }
+            Error::TxNotFound(ref txid) => write!(f, "Transaction txid={} is not found", txid),
+            Error::BlockNotFound(ref hash) => write!(f, "Block hash={} is not found", hash),
+            Error::MalformedRawTx => write!(f, "Malformed raw transaction"),
+            Error::TxBuild(ref e) => write!(f, "Unable to build anchoring transaction: {}", e),
+            Error::NotEnoughBlocks { height, count } => write!(
+                f,
+                "Block at height={} is not available, blocks count={}",
+                height, count
+            ),
+            Error::Pruned { height, cutoff } => write!(
+                f,
+                "Block at height={} is pruned, proofs are available from height={}",
+                height, cutoff
+            ),
+            Error::TransitionInProgress => write!(f, "Anchoring chain transition is in progress"),
+            Error::NoLectConsensus => write!(f, "Validators have not agreed on the lect"),
+            Error::FeeRateNotAgreed => write!(f, "Validators have not agreed on the fee rate"),
+            Error::AlreadyAnchored {
+                height,
+                anchored_height,
+            } => write!(
+                f,
+                "Block at height={} is already anchored, anchored_height={}",
+                height, anchored_height
+            ),
+            Error::WatchdogNotConfigured => write!(f, "Anchoring watchdog is not configured"),
+            Error::UnknownProposal(ref txid) => {
+                write!(f, "Anchoring transaction txid={} has no signatures", txid)
+            }
+            Error::NodeChannelNotConfigured => write!(f, "Node channel is not configured"),
+            Error::NotValidator => write!(f, "Node is not a validator"),
+            Error::Psbt(ref e) => write!(f, "Unable to import PSBT: {}", e),
         }
     }
 }
@@ -34,16 +117,76 @@ impl error::Error for Error {
     fn description(&self) -> &str {
         match *self {
             Error::UnknownValidatorId(_) => "UnknownValidatorId",
+            Error::MalformedLect(_) => "MalformedLect",
+            Error::InvalidHeightRange { .. } => "InvalidHeightRange",
+            Error::InvalidHeightsCount(_) => "InvalidHeightsCount",
+            Error::InvalidTxId(_) => "InvalidTxId",
+            Error::InvalidBlockHash(_) => "InvalidBlockHash",
+            Error::UnknownPayloadVersion { .. } => "UnknownPayloadVersion",
+            Error::RelayNotConfigured => "RelayNotConfigured",
+            Error::RelayUnavailable(_) => "RelayUnavailable",
+            Error::PayloadNotFound(_) => "PayloadNotFound",
+            Error::TxNotFound(_) => "TxNotFound",
+            Error::BlockNotFound(_) => "BlockNotFound",
+            Error::MalformedRawTx => "MalformedRawTx",
+            Error::TxBuild(_) => "TxBuild",
+            Error::NotEnoughBlocks { .. } => "NotEnoughBlocks",
+            Error::Pruned { .. } => "Pruned",
+            Error::TransitionInProgress => "TransitionInProgress",
+            Error::NoLectConsensus => "NoLectConsensus",
+            Error::FeeRateNotAgreed => "FeeRateNotAgreed",
+            Error::AlreadyAnchored { .. } => "AlreadyAnchored",
+            Error::WatchdogNotConfigured => "WatchdogNotConfigured",
+            Error::UnknownProposal(_) => "UnknownProposal",
+            Error::NodeChannelNotConfigured => "NodeChannelNotConfigured",
+            Error::NotValidator => "NotValidator",
+            Error::Psbt(_) => "Psbt",
         }
     }
 }
 
-impl Into<ApiError> for Error {
-    fn into(self) -> ApiError {
-        match self {
+impl From<TxBuildError> for Error {
+    fn from(e: TxBuildError) -> Error {
+        Error::TxBuild(e)
+    }
+}
+
+impl From<PsbtError> for Error {
+    fn from(e: PsbtError) -> Error {
+        Error::Psbt(e)
+    }
+}
+
+impl From<Error> for ApiError {
+    fn from(e: Error) -> ApiError {
+        match e {
             Error::UnknownValidatorId(id) => {
                 ApiError::BadRequest(format!("Unknown validator id={}", id))
             }
+            e @ Error::MalformedLect(_)
+            | e @ Error::UnknownPayloadVersion { .. }
+            | e @ Error::RelayNotConfigured
+            | e @ Error::RelayUnavailable(_)
+            | e @ Error::WatchdogNotConfigured
+            | e @ Error::NodeChannelNotConfigured
+            | e @ Error::TxBuild(_) => ApiError::InternalError(Box::new(e)),
+            e @ Error::InvalidHeightRange { .. }
+            | e @ Error::InvalidHeightsCount(_)
+            | e @ Error::InvalidTxId(_)
+            | e @ Error::InvalidBlockHash(_)
+            | e @ Error::MalformedRawTx
+            | e @ Error::TransitionInProgress
+            | e @ Error::NoLectConsensus
+            | e @ Error::FeeRateNotAgreed
+            | e @ Error::AlreadyAnchored { .. }
+            | e @ Error::NotValidator
+            | e @ Error::Psbt(_) => ApiError::BadRequest(e.to_string()),
+            e @ Error::PayloadNotFound(_)
+            | e @ Error::TxNotFound(_)
+            | e @ Error::BlockNotFound(_)
+            | e @ Error::NotEnoughBlocks { .. }
+            | e @ Error::Pruned { .. }
+            | e @ Error::UnknownProposal(_) => ApiError::NotFound(e.to_string()),
         }
     }
 }
@@ -0,0 +1,81 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export of the whole anchoring chain for the offline verification.
+
+use std::io::{self, Write};
+
+use iron::response::WriteBody;
+use serde_json;
+
+use exonum::blockchain::Blockchain;
+
+use blockchain::consensus_storage::AnchoringConfig;
+use blockchain::schema::AnchoringSchema;
+use details::btc::transactions::AnchoringTx;
+
+/// Anchoring transaction along with the height of the block it anchors.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExportedAnchoringTx {
+    /// Height of the anchored block.
+    pub height: u64,
+    /// Anchoring transaction.
+    pub tx: AnchoringTx,
+}
+
+/// Whole anchoring chain bundled with the actual configuration.
+///
+/// Consumers can check that every transaction anchors the block hash at its height
+/// and spends the funds of the addresses from the configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AnchoringChainExport {
+    /// Actual anchoring configuration.
+    pub config: AnchoringConfig,
+    /// Anchoring transactions ordered by the heights of the anchored blocks.
+    pub entries: Vec<ExportedAnchoringTx>,
+}
+
+/// Response body that streams the `AnchoringChainExport` in JSON entry by entry,
+/// so the chain is never buffered in memory as a whole.
+///
+/// The snapshot of the blockchain is taken once the body is being written.
+#[derive(Debug, Clone)]
+pub struct ChainExportBody {
+    blockchain: Blockchain,
+}
+
+impl ChainExportBody {
+    /// Creates the export of the anchoring chain from the given `blockchain`.
+    pub fn new(blockchain: Blockchain) -> ChainExportBody {
+        ChainExportBody { blockchain }
+    }
+}
+
+impl WriteBody for ChainExportBody {
+    fn write_body(&mut self, res: &mut Write) -> io::Result<()> {
+        let snapshot = self.blockchain.snapshot();
+        let schema = AnchoringSchema::new(&snapshot);
+
+        res.write_all(b"{\"config\":")?;
+        serde_json::to_writer(&mut *res, &schema.actual_anchoring_config())?;
+        res.write_all(b",\"entries\":[")?;
+        for (i, (height, tx)) in schema.anchoring_tx_chain().iter().enumerate() {
+            if i > 0 {
+                res.write_all(b",")?;
+            }
+            serde_json::to_writer(&mut *res, &ExportedAnchoringTx { height, tx })?;
+        }
+        res.write_all(b"]}")
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Logging of the API requests with the correlation identifiers.
+
+use iron::prelude::*;
+use iron::typemap::Key;
+use iron::{AfterMiddleware, BeforeMiddleware};
+use rand::{thread_rng, Rng};
+use router::Router;
+
+use exonum::helpers::Height;
+
+/// Header that carries the correlation identifier of the request.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+/// Maximum length of the correlation identifier accepted from the client.
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// Correlation identifier of the request.
+#[derive(Debug)]
+pub struct RequestId;
+
+impl Key for RequestId {
+    type Value = String;
+}
+
+/// Height of the blockchain snapshot that is used to serve the request.
+#[derive(Debug)]
+pub struct SnapshotHeight;
+
+impl Key for SnapshotHeight {
+    type Value = Height;
+}
+
+/// Middleware that assigns the correlation identifiers to the requests, logs their outcome
+/// and echoes the identifiers back in the `X-Request-Id` response header.
+///
+/// The identifier is taken from the `X-Request-Id` request header or generated if the header
+/// is absent or malformed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestLogger;
+
+impl RequestLogger {
+    fn request_id(req: &Request) -> String {
+        req.extensions
+            .get::<RequestId>()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn route(req: &Request) -> String {
+        format!("{} /{}", req.method, req.url.path().join("/"))
+    }
+
+    fn params(req: &Request) -> String {
+        req.extensions.get::<Router>().map_or_else(String::new, |params| {
+            params
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>()
+                .join("&")
+        })
+    }
+
+    fn snapshot_height(req: &Request) -> String {
+        req.extensions
+            .get::<SnapshotHeight>()
+            .map_or_else(|| "none".to_owned(), |height| height.to_string())
+    }
+}
+
+impl BeforeMiddleware for RequestLogger {
+    fn before(&self, req: &mut Request) -> IronResult<()> {
+        let request_id = incoming_request_id(req).unwrap_or_else(generate_request_id);
+        req.extensions.insert::<RequestId>(request_id);
+        Ok(())
+    }
+}
+
+impl AfterMiddleware for RequestLogger {
+    fn after(&self, req: &mut Request, mut res: Response) -> IronResult<Response> {
+        let request_id = RequestLogger::request_id(req);
+        info!(
+            "API request served, request_id={}, route={}, params={}, snapshot_height={}, \
+             outcome=ok, status={}",
+            request_id,
+            RequestLogger::route(req),
+            RequestLogger::params(req),
+            RequestLogger::snapshot_height(req),
+            res.status.map_or_else(String::new, |status| status.to_string())
+        );
+        res.headers
+            .set_raw(REQUEST_ID_HEADER, vec![request_id.into_bytes()]);
+        Ok(res)
+    }
+
+    fn catch(&self, req: &mut Request, mut err: IronError) -> IronResult<Response> {
+        let request_id = RequestLogger::request_id(req);
+        warn!(
+            "API request failed, request_id={}, route={}, params={}, snapshot_height={}, \
+             outcome=err, status={}, error={}",
+            request_id,
+            RequestLogger::route(req),
+            RequestLogger::params(req),
+            RequestLogger::snapshot_height(req),
+            err.response
+                .status
+                .map_or_else(String::new, |status| status.to_string()),
+            err.error
+        );
+        err.response
+            .headers
+            .set_raw(REQUEST_ID_HEADER, vec![request_id.into_bytes()]);
+        Err(err)
+    }
+}
+
+/// Returns the correlation identifier given by the client if it is safe to log.
+fn incoming_request_id(req: &Request) -> Option<String> {
+    let value = req.headers.get_raw(REQUEST_ID_HEADER)?.first()?;
+    let is_valid = !value.is_empty() && value.len() <= MAX_REQUEST_ID_LEN
+        && value
+            .iter()
+            .all(|c| c.is_ascii_alphanumeric() || b"-_.".contains(c));
+    if is_valid {
+        String::from_utf8(value.clone()).ok()
+    } else {
+        None
+    }
+}
+
+fn generate_request_id() -> String {
+    format!("{:016x}", thread_rng().gen::<u64>())
+}
@@ -14,31 +14,291 @@
 
 //! Anchoring rest API implementation.
 
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use iron::headers::ContentType;
+use iron::modifiers::Header;
 use iron::prelude::*;
+use iron::response::WriteBody;
+use iron::status;
 use router::Router;
+use serde_json::Value;
 
 use exonum::api::{Api, ApiError};
 use exonum::blockchain::{BlockProof, Blockchain, Schema as CoreSchema};
-use exonum::crypto::Hash;
+use exonum::crypto::{CryptoHash, Hash};
+use exonum::encoding::serialize::FromHex;
 use exonum::helpers::Height;
-use exonum::storage::{ListProof, MapProof};
+use exonum::storage::{ListProof, MapProof, Snapshot};
 
+use blockchain::consensus_storage::{AnchoringConfig, FeeStrategyConfig};
 use blockchain::dto::LectContent;
-use blockchain::schema::AnchoringSchema;
+use blockchain::schema::{AnchoringSchema, BtcHeightKey};
 use details::btc;
-use details::btc::transactions::{AnchoringTx, BitcoinTx, TxKind};
+use details::btc::fee::estimate_vsize;
+use details::btc::payload::PayloadError;
+use details::btc::transactions::{parse_payload, AnchoringTx, BitcoinTx, FundingTx,
+                                 TransactionBuilder, TxKind};
 use details::btc::TxId;
+use details::rpc::{BitcoinRelay, Result as RpcResult};
+use handler::{AnchoringWatchdog, TransitionProgress, WatchdogStatus};
+use observer::Milliseconds;
 use ANCHORING_SERVICE_ID;
 
+use self::cache::{cached_response, etag_of};
+use self::cbor::{accepts_cbor, cbor_response};
+use self::logging::SnapshotHeight;
+
+pub use self::cors::CorsMiddleware;
+pub use self::export::{AnchoringChainExport, ChainExportBody, ExportedAnchoringTx};
+pub use self::logging::{RequestLogger, REQUEST_ID_HEADER};
+pub use self::private::{AnchorNowRequest, AnchoringPsbt, CollectedSignatures, ImportedSignatures,
+                        InputSignature, NodeChannel, PrivateApi, PsbtImportRequest,
+                        ScheduledAnchor, ValidatorSignatures};
+pub use self::proof::{verify_anchor_depth, verify_anchored_block_header_proof, ProofError};
+pub use self::rate_limit::{RateLimit, RateLimitConfig, RateLimitExceeded, RateLimiter};
+pub use self::schema::{response_schema, validate_schema, SchemaError};
 pub use details::btc::payload::Payload;
 
+mod cache;
+mod cbor;
+mod cors;
 mod error;
+mod export;
+mod logging;
+mod private;
+mod proof;
+mod rate_limit;
+mod schema;
+pub mod subscriptions;
+
+/// Maximum number of lects that can be returned by the single `lects_of_validator` request.
+pub const MAX_LECTS_COUNT: u64 = 1000;
+/// Maximum number of block headers that can be covered by the single range proof.
+pub const MAX_PROOF_RANGE_LEN: u64 = 1000;
+/// Maximum number of heights that can be requested by the single batch proof request.
+pub const MAX_PROOF_HEIGHTS_COUNT: usize = 100;
+/// Maximum number of entries that can be returned by the single `anchored_blocks` request.
+pub const MAX_ANCHORED_BLOCKS_COUNT: u64 = 1000;
+/// Maximum number of bitcoin heights that can be covered by the single `height_map` request.
+pub const MAX_HEIGHT_MAP_COUNT: u64 = 1000;
+/// Maximum number of anchors that can be returned by the single `anchor_intervals` request.
+pub const MAX_ANCHOR_INTERVALS_COUNT: u64 = 1000;
+/// Default number of seconds during which the sync status is served from the cache.
+pub const SYNC_STATUS_CACHE_TIMEOUT: u64 = 5;
+/// Number of seconds during which the anchoring balance is served from the cache.
+pub const BALANCE_CACHE_TIMEOUT: u64 = 10;
+/// Number of seconds during which the confirmations of the actual lect are served from the cache.
+pub const CONFIRMATIONS_CACHE_TIMEOUT: u64 = 5;
+/// Number of seconds during which the depths of the anchors are served from the cache.
+pub const ANCHOR_DEPTH_CACHE_TIMEOUT: u64 = 60;
+/// Number of the latest anchored heights that are listed in the diagnostics.
+pub const DIAGNOSTICS_ANCHORED_HEIGHTS_COUNT: usize = 10;
 
 /// Public API implementation.
 #[derive(Debug, Clone)]
 pub struct PublicApi {
     /// Exonum blockchain instance.
     pub blockchain: Blockchain,
+    /// Origins that are allowed to make cross-origin requests.
+    /// If the list is empty, CORS headers are not sent.
+    pub allowed_origins: Vec<String>,
+    relay: Option<Arc<BitcoinRelay>>,
+    last_sync_status: Arc<Mutex<Option<SyncStatus>>>,
+    sync_status_cache_timeout: u64,
+    last_balance: Arc<Mutex<Option<AnchoringBalance>>>,
+    last_confirmations: Arc<Mutex<Option<LectConfirmations>>>,
+    anchor_depths: Arc<Mutex<HashMap<TxId, AnchorDepth>>>,
+    average_block_time: Milliseconds,
+    max_anchoring_lag: Option<u64>,
+    rate_limits: RateLimitConfig,
+    watchdog: Option<AnchoringWatchdog>,
+    transition_progress: Option<TransitionProgress>,
+    observer: bool,
+}
+
+/// Public API methods that read the blockchain state from the single snapshot.
+///
+/// The snapshot is taken once on creation, so the results of the different methods
+/// are consistent with each other.
+pub struct ApiSnapshot<'a> {
+    api: &'a PublicApi,
+    snapshot: Box<Snapshot>,
+}
+
+impl<'a> fmt::Debug for ApiSnapshot<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct(stringify!(ApiSnapshot))
+            .field("api", &self.api)
+            .finish()
+    }
+}
+
+/// Depth of the actual lect in the bitcoin blockchain.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SyncStatus {
+    /// Height of the bitcoin blockchain known to the `bitcoind`.
+    pub bitcoin_height: u64,
+    /// `Txid` of the lect agreed by +2/3 validators if there is one.
+    pub lect_txid: Option<TxId>,
+    /// Height of the bitcoin block that includes the lect if it has been confirmed.
+    pub lect_height: Option<u64>,
+    /// Number of confirmations of the lect.
+    pub confirmations: u64,
+    /// Unix timestamp in seconds when the values have been obtained from the `bitcoind`.
+    pub updated_at: u64,
+    /// `true` if the `bitcoind` is unreachable and the last known values are returned.
+    pub stale: bool,
+}
+
+/// Number of confirmations of the actual lect.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LectConfirmations {
+    /// `Txid` of the lect agreed by +2/3 validators.
+    pub txid: TxId,
+    /// Number of confirmations of the lect, `0` if it is in the mempool
+    /// and `None` if it is unknown to the `bitcoind`.
+    pub confirmations: Option<u64>,
+    /// Unix timestamp in seconds when the value has been obtained from the `bitcoind`.
+    pub updated_at: u64,
+}
+
+/// Depth of the anchoring transaction that proves the blocks in the bitcoin blockchain.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AnchorDepth {
+    /// `Txid` of the anchoring transaction for the nearest anchored block with a height
+    /// greater or equal than the heights of the proved blocks.
+    pub txid: TxId,
+    /// Number of confirmations of the transaction, `0` if it is not yet included
+    /// in the bitcoin blockchain and `None` if it is unknown because the `bitcoind`
+    /// is unavailable.
+    pub confirmations: Option<u64>,
+    /// Unix timestamp in seconds when the value has been obtained from the `bitcoind`.
+    pub updated_at: u64,
+}
+
+/// Funds available at the anchoring addresses.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AnchoringBalance {
+    /// Actual anchoring address followed by the following one if the transition is in progress.
+    pub addresses: Vec<btc::Address>,
+    /// Total value of the unspent outputs in satoshis.
+    pub total: u64,
+    /// Number of the unspent outputs.
+    pub utxo_count: u64,
+    /// Unix timestamp in seconds when the values have been obtained from the `bitcoind`.
+    pub updated_at: u64,
+    /// `true` if the values are served from the cache.
+    pub cached: bool,
+}
+
+/// Overall state of the anchoring service.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    /// All checks are passed.
+    Ok,
+    /// The service works, but it may stop anchoring soon.
+    Degraded,
+    /// The service does not anchor the blocks.
+    Down,
+}
+
+/// Results of the individual health checks.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HealthChecks {
+    /// `true` if the blockchain snapshot is readable.
+    pub snapshot: bool,
+    /// `true` if the `bitcoind` responds or `None` if the relay is not configured.
+    pub rpc: Option<bool>,
+    /// `true` if the anchoring chain has enough funds to pay the fee of the next transaction.
+    pub funding: bool,
+    /// Number of blocks committed since the latest anchored block.
+    pub anchoring_lag: u64,
+    /// `true` if the `anchoring_lag` exceeds the allowed threshold.
+    pub lagging: bool,
+}
+
+impl HealthChecks {
+    /// Returns the overall state according to the checks results.
+    ///
+    /// The service is down if the snapshot is not readable or the anchoring lags behind,
+    /// and it is degraded if the `bitcoind` is unreachable or the funds are exhausted.
+    pub fn state(&self) -> HealthState {
+        if !self.snapshot || self.lagging {
+            HealthState::Down
+        } else if self.rpc == Some(false) || !self.funding {
+            HealthState::Degraded
+        } else {
+            HealthState::Ok
+        }
+    }
+}
+
+/// Health of the anchoring service.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Health {
+    /// Overall state of the service.
+    pub status: HealthState,
+    /// Results of the individual checks.
+    pub checks: HealthChecks,
+}
+
+/// Reason why the node is not ready to serve the anchoring data.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotReadyReason {
+    /// The blockchain snapshot is not readable.
+    SnapshotUnavailable,
+    /// The latest anchored block lags behind the latest committed block.
+    Lagging,
+    /// The anchoring chain observer has not found the recent anchoring transaction.
+    ObserverLagging,
+    /// The observer waits for the manual intervention after the deep bitcoin reorganization.
+    ManualInterventionRequired,
+    /// The `bitcoind` does not respond.
+    RpcUnreachable,
+}
+
+/// Readiness of the node to serve the correct anchoring data.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Readiness {
+    /// `true` if all the readiness checks are passed.
+    pub ready: bool,
+    /// Reasons why the node is not ready, empty if it is ready.
+    pub reasons: Vec<NotReadyReason>,
+    /// Results of the health checks.
+    pub checks: HealthChecks,
+    /// Number of blocks committed since the latest block anchored by the transaction
+    /// that is found by the observer, `None` if there is no such transaction.
+    pub observer_lag: Option<u64>,
+}
+
+/// Estimated time until the next anchoring height.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NextAnchorEta {
+    /// Height of the latest committed block.
+    pub height: Height,
+    /// The nearest height above the latest committed block that should be anchored.
+    pub next_anchoring_height: Height,
+    /// Number of blocks to be committed until the next anchoring height.
+    pub blocks_remaining: u64,
+    /// Estimated number of seconds until the next anchoring height
+    /// based on the average block time.
+    pub seconds_remaining: u64,
+}
+
+/// Kind of the transaction in the anchoring chain.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnchoringTxKind {
+    /// Anchoring transaction with the payload.
+    Anchoring,
+    /// Funding transaction that refuels the anchoring address.
+    Funding,
 }
 
 /// Public information about the anchoring transaction in bitcoin.
@@ -46,10 +306,56 @@ pub struct PublicApi {
 pub struct AnchoringInfo {
     /// `Txid` of anchoring transaction.
     pub txid: TxId,
+    /// Kind of the transaction.
+    pub kind: AnchoringTxKind,
     /// Anchoring transaction payload.
     pub payload: Option<Payload>,
 }
 
+/// Decoded payload of the anchoring transaction.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct PayloadInfo {
+    /// Anchored block hash.
+    pub block_hash: Hash,
+    /// Anchored block height.
+    pub block_height: Height,
+    /// `Txid` of the previous transactions chain if it has been lost.
+    pub prev_txid: Option<TxId>,
+    /// Root hash of the anchored service table if the configuration specifies it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table_root: Option<Hash>,
+}
+
+impl From<Payload> for PayloadInfo {
+    fn from(payload: Payload) -> PayloadInfo {
+        PayloadInfo {
+            block_hash: payload.block_hash,
+            block_height: payload.block_height,
+            prev_txid: payload.prev_tx_chain,
+            table_root: payload.table_root,
+        }
+    }
+}
+
+/// Preview of the next anchoring transaction before it is signed by the validators.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct AnchoringTxPreview {
+    /// Unsigned anchoring transaction.
+    pub tx: AnchoringTx,
+    /// `Txid` of the unsigned transaction.
+    pub txid: TxId,
+    /// `Txid`s of the spent transactions.
+    pub inputs: Vec<TxId>,
+    /// Anchoring address that receives the change.
+    pub address: btc::Address,
+    /// Value of the anchoring output in satoshis.
+    pub amount: u64,
+    /// Fee of the transaction in satoshis.
+    pub fee: u64,
+    /// Anchoring transaction payload.
+    pub payload: Payload,
+}
+
 /// Public information about the lect transaction in exonum.
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct LectInfo {
@@ -59,193 +365,2625 @@ pub struct LectInfo {
     pub content: AnchoringInfo,
 }
 
-/// A proof of existence for an anchored or a non-anchored Exonum block at the given height.
+/// Public information about the funding transaction from the actual anchoring configuration.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct FundingTxInfo {
+    /// Information about funding transaction.
+    pub content: AnchoringInfo,
+    /// Whether the funding transaction has already been spent by the agreed anchoring chain.
+    pub spent: bool,
+}
+
+/// Funding transaction that has ever fed the anchoring address along with its
+/// consumption status.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct FundingHistoryEntry {
+    /// Txid of the funding transaction.
+    pub txid: TxId,
+    /// Amount sent to the anchoring address in satoshis.
+    pub amount: u64,
+    /// Height of the Exonum block in which the transaction has become known.
+    pub height: u64,
+    /// Whether the funding transaction has been spent by the agreed anchoring chain.
+    pub spent: bool,
+    /// Txid of the anchoring transaction that has spent the funding transaction.
+    pub spent_by: Option<TxId>,
+    /// Height of the Exonum block anchored by the spending transaction.
+    pub spent_at_height: Option<u64>,
+}
+
+/// Anchoring configuration that has taken effect at the specific height.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ConfigHistoryEntry {
+    /// Height of the Exonum block since which the configuration is actual.
+    pub actual_from: u64,
+    /// The anchoring configuration.
+    pub config: AnchoringConfig,
+}
+
+/// A page of the lects history for the specific validator.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct LectsInfo {
+    /// Total number of lects of the validator.
+    pub total_count: u64,
+    /// Requested lects in insertion order.
+    pub lects: Vec<LectInfo>,
+}
+
+/// Current lects of the validators from the actual anchoring configuration.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct LectAgreement {
+    /// Identifiers of the validators grouped by the `txid` of their current lect.
+    pub lects: HashMap<TxId, Vec<u32>>,
+    /// Number of validators that should agree on the lect to make it actual.
+    pub majority_count: u8,
+    /// Lects rejected because they conflict with the agreed lects of their validators.
+    pub conflicts: Vec<LectConflictInfo>,
+}
+
+/// Lect that is rejected because it conflicts with the agreed lect of the validator,
+/// i.e. it anchors the same block by another transaction.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct LectConflictInfo {
+    /// Identifier of the validator.
+    pub validator: u32,
+    /// Height of the block anchored by both lects.
+    pub height: u64,
+    /// Txid of the agreed lect.
+    pub agreed_txid: TxId,
+    /// Txid of the rejected lect.
+    pub conflicting_txid: TxId,
+    /// Hash of the exonum transaction that contains the rejected lect.
+    pub msg_hash: Hash,
+}
+
+/// Anchoring key of the validator from the actual anchoring configuration
+/// along with its current lect.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ValidatorInfo {
+    /// Identifier of the validator.
+    pub id: u32,
+    /// Public key that the validator uses to sign the anchoring transactions.
+    pub public_key: btc::PublicKey,
+    /// Whether the validator has reported a lect.
+    pub has_lect: bool,
+    /// Txid of the current lect of the validator if there is one.
+    pub lect_txid: Option<TxId>,
+}
+
+/// Confirmation depth of the current lect of the validator in the bitcoin blockchain.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ValidatorLectDepth {
+    /// Identifier of the validator.
+    pub id: u32,
+    /// Txid of the current lect of the validator.
+    pub txid: TxId,
+    /// Number of confirmations of the lect, zero for the transaction in the mempool
+    /// and `None` if the transaction is unknown to the bitcoin relay.
+    pub confirmations: Option<u64>,
+}
+
+/// Redeem scripts of the anchoring multisig addresses.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct RedeemScripts {
+    /// Redeem script of the actual anchoring address.
+    pub actual: btc::RedeemScript,
+    /// Redeem script of the following anchoring address if there is a following configuration.
+    pub following: Option<btc::RedeemScript>,
+}
+
+/// A page of the anchoring transactions chain.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct AnchoredBlocksInfo {
+    /// Total number of anchored blocks in the chain.
+    pub total_count: u64,
+    /// Requested anchored block heights with their anchoring transactions.
+    pub blocks: Vec<(u64, AnchoringInfo)>,
+}
+
+/// Pruning of the hashes of the anchored blocks, see `AnchoringSchema::prune_anchored_blocks`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PruningInfo {
+    /// Height below which the block hashes are pruned, so their proofs are unavailable.
+    pub cutoff: u64,
+    /// Number of the latest blocks whose hashes are kept according to the actual
+    /// configuration, `None` if the pruning is disabled.
+    pub retention: Option<u64>,
+}
+
+/// State of the anchoring chain observer.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ObserverStatus {
+    /// `true` if the observer has stopped following the anchoring chain because
+    /// of the bitcoin blockchain reorganization deeper than its `max_reorg_depth`.
+    pub manual_intervention_required: bool,
+    /// Depth of such reorganization in bitcoin blocks.
+    pub reorg_depth: Option<u64>,
+}
+
+/// Anchoring transaction along with the heights of the bitcoin block that contains it
+/// and the Exonum block that it anchors.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HeightMapEntry {
+    /// Height of the bitcoin block that contains the anchoring transaction.
+    pub btc_height: u64,
+    /// Height of the anchored Exonum block.
+    pub exonum_height: u64,
+    /// `Txid` of the anchoring transaction.
+    pub txid: TxId,
+}
+
+/// Anchor from the anchoring chain along with the intervals since the previous anchor.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AnchorInterval {
+    /// Height of the anchored Exonum block.
+    pub exonum_height: u64,
+    /// `Txid` of the anchoring transaction.
+    pub txid: TxId,
+    /// Unix timestamp in seconds of the bitcoin block that contains the anchoring transaction,
+    /// `None` if the relay has not reported it.
+    pub btc_time: Option<u64>,
+    /// Number of the Exonum blocks since the previous anchor,
+    /// `None` for the first anchor of the chain.
+    pub height_delta: Option<u64>,
+    /// Number of seconds between the bitcoin blocks of the previous anchor and this one,
+    /// `None` if any of the timestamps is unknown.
+    ///
+    /// The timestamps of the bitcoin blocks are not strictly monotonic,
+    /// so the value may be negative.
+    pub btc_time_delta: Option<i64>,
+}
+
+/// Summary of the anchoring configuration in the diagnostics.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConfigSummary {
+    /// Anchoring address.
+    pub address: btc::Address,
+    /// Number of the anchoring keys.
+    pub keys_count: u64,
+    /// Number of the signatures required to spend the funds of the anchoring address.
+    pub majority_count: u8,
+    /// Anchoring frequency in blocks.
+    pub frequency: u64,
+    /// Fee of the anchoring transactions in satoshis.
+    pub fee: u64,
+    /// `Txid`s of the funding transactions.
+    pub funding_txs: Vec<TxId>,
+}
+
+impl<'a> From<&'a AnchoringConfig> for ConfigSummary {
+    fn from(cfg: &'a AnchoringConfig) -> ConfigSummary {
+        ConfigSummary {
+            address: cfg.redeem_script().1,
+            keys_count: cfg.anchoring_keys.len() as u64,
+            majority_count: cfg.majority_count(),
+            frequency: cfg.frequency,
+            fee: cfg.fee,
+            funding_txs: cfg.funding_txs().into_iter().map(FundingTx::id).collect(),
+        }
+    }
+}
+
+/// State of the anchoring service collected for the bug reports.
+///
+/// The sections that are not available, e.g. the ones that need the unreachable `bitcoind`,
+/// are `None` and the reasons are listed in the `errors`.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Diagnostics {
+    /// Height of the blockchain.
+    pub height: Height,
+    /// Summary of the actual anchoring configuration.
+    pub actual_config: ConfigSummary,
+    /// Summary of the following anchoring configuration if there is one.
+    pub following_config: Option<ConfigSummary>,
+    /// Lect agreed by +2/3 validators.
+    pub actual_lect: Option<AnchoringInfo>,
+    /// Number of confirmations of the actual lect.
+    pub actual_lect_confirmations: Option<LectConfirmations>,
+    /// Funds available at the anchoring addresses.
+    pub funding_balance: Option<AnchoringBalance>,
+    /// Status of the transition to the following anchoring address.
+    pub transition: TransitionStatus,
+    /// Number of the transactions in the anchoring chain.
+    pub chain_length: u64,
+    /// Latest anchored heights in the descending order.
+    pub latest_anchored_heights: Vec<u64>,
+    /// Errors that prevented to collect some of the sections.
+    pub errors: Vec<String>,
+}
+
+/// Information about the transition of the anchoring chain to a new address.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct TransitionStatus {
+    /// Whether the anchoring chain is moving to the following address.
+    pub in_progress: bool,
+    /// Actual anchoring address.
+    pub actual_address: btc::Address,
+    /// Following anchoring address if the transition is in progress.
+    pub following_address: Option<btc::Address>,
+    /// Number of validators that have signed the transfer transaction to the following
+    /// address that is built by the node.
+    pub collected_signatures: u64,
+}
+
+/// A proof of existence for anchored or non-anchored Exonum blocks at the given heights.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AnchoredBlockHeaderProof {
     /// Latest authorized block in the blockchain.
     pub latest_authorized_block: BlockProof,
     /// Proof for the whole database table.
     pub to_table: MapProof<Hash, Hash>,
-    /// Proof for the specific header in this table.
+    /// Proof for the specific headers in this table.
+    pub to_block_header: ListProof<Hash>,
+    /// Depth of the anchoring transaction that proves the blocks or `None`
+    /// if the blocks have not been anchored yet.
+    #[serde(default)]
+    pub anchor_depth: Option<AnchorDepth>,
+}
+
+/// Heights of the blocks requested by the batch proof request.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProofHeights {
+    /// Heights of the anchored blocks in any order.
+    pub heights: Vec<u64>,
+}
+
+/// Raw bitcoin transaction supplied to verify that it anchors the Exonum block.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AnchorToVerify {
+    /// Hex of the serialized transaction.
+    pub tx: String,
+}
+
+/// Result of the verification of the user supplied anchoring transaction.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct AnchorVerification {
+    /// Txid of the transaction.
+    pub txid: TxId,
+    /// Decoded payload or `None` if the transaction is not an anchoring one.
+    pub payload: Option<PayloadInfo>,
+    /// Hash of the Exonum block at the anchored height or `None`
+    /// if there is no such block yet.
+    pub expected_block_hash: Option<Hash>,
+    /// Whether the transaction anchors the Exonum block at the anchored height.
+    pub valid: bool,
+}
+
+/// Raw bitcoin transaction supplied to find out how the anchoring service classifies it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TxToClassify {
+    /// Hex of the serialized transaction.
+    pub tx: String,
+}
+
+/// Kind of the arbitrary bitcoin transaction.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TxClass {
+    /// Transaction with the payload tagged by the actual payload prefix.
+    Anchoring,
+    /// Transaction that funds a segwit script address.
+    Funding,
+    /// Any other transaction.
+    Other,
+}
+
+/// Classification of the user supplied raw transaction.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct TxClassification {
+    /// Txid of the transaction.
+    pub txid: TxId,
+    /// Kind of the transaction.
+    pub kind: TxClass,
+    /// Decoded payload if the transaction is an anchoring one.
+    pub payload: Option<PayloadInfo>,
+}
+
+/// A proof for the block header at the specific height.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlockHeaderProof {
+    /// Height of the proved block.
+    pub height: u64,
+    /// Proof for the block header in the anchored blocks table.
     pub to_block_header: ListProof<Hash>,
+    /// Depth of the anchoring transaction that proves the block or `None`
+    /// if the block has not been anchored yet.
+    #[serde(default)]
+    pub anchor_depth: Option<AnchorDepth>,
+}
+
+/// Proofs of existence for Exonum blocks at the discrete heights that share
+/// the latest authorized block and the proof for the table.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnchoredBlockHeaderProofs {
+    /// Latest authorized block in the blockchain.
+    pub latest_authorized_block: BlockProof,
+    /// Proof for the whole database table.
+    pub to_table: MapProof<Hash, Hash>,
+    /// Proofs for the block headers in the requested order.
+    pub to_block_headers: Vec<BlockHeaderProof>,
 }
 
-impl From<BitcoinTx> for AnchoringInfo {
-    fn from(tx: BitcoinTx) -> AnchoringInfo {
-        match TxKind::from(tx) {
-            TxKind::Anchoring(tx) => AnchoringInfo {
+impl AnchoredBlockHeaderProofs {
+    /// Splits the batch into the separate proofs along with their heights, so each
+    /// of them can be checked by the `verify_anchored_block_header_proof`.
+    pub fn into_proofs(self) -> Vec<(u64, AnchoredBlockHeaderProof)> {
+        let latest_authorized_block = self.latest_authorized_block;
+        let to_table = self.to_table;
+        self.to_block_headers
+            .into_iter()
+            .map(|proof| {
+                let header_proof = AnchoredBlockHeaderProof {
+                    latest_authorized_block: latest_authorized_block.clone(),
+                    to_table: to_table.clone(),
+                    to_block_header: proof.to_block_header,
+                    anchor_depth: proof.anchor_depth,
+                };
+                (proof.height, header_proof)
+            })
+            .collect()
+    }
+}
+
+impl AnchoringInfo {
+    /// Tries to convert the given lect into the anchoring information, the lect is classified
+    /// by the given anchoring configuration.
+    fn try_from_lect(cfg: &AnchoringConfig, tx: BitcoinTx) -> Result<AnchoringInfo, error::Error> {
+        match cfg.tx_kind(tx) {
+            TxKind::Anchoring(tx) => Ok(AnchoringInfo {
                 txid: tx.id(),
+                kind: AnchoringTxKind::Anchoring,
                 payload: Some(tx.payload()),
-            },
-            TxKind::FundingTx(tx) => AnchoringInfo {
+            }),
+            TxKind::FundingTx(tx) => Ok(AnchoringInfo {
                 txid: tx.id(),
+                kind: AnchoringTxKind::Funding,
                 payload: None,
+            }),
+            TxKind::Other(tx) => match parse_payload(&tx) {
+                Err(PayloadError::UnknownVersion(version)) => {
+                    Err(error::Error::UnknownPayloadVersion {
+                        txid: tx.id(),
+                        version,
+                    })
+                }
+                _ => Err(error::Error::MalformedLect(tx.id())),
             },
-            TxKind::Other(tx) => panic!("Found incorrect lect transaction, content={:#?}", tx),
         }
     }
 }
 
-impl From<LectContent> for LectInfo {
-    fn from(content: LectContent) -> LectInfo {
-        LectInfo {
+impl LectInfo {
+    /// Tries to convert the given lect content into the lect information.
+    fn try_from_lect(
+        cfg: &AnchoringConfig,
+        content: LectContent,
+    ) -> Result<LectInfo, error::Error> {
+        Ok(LectInfo {
             hash: *content.msg_hash(),
-            content: AnchoringInfo::from(content.tx()),
+            content: AnchoringInfo::try_from_lect(cfg, content.tx())?,
+        })
+    }
+}
+
+impl From<AnchoringTx> for AnchoringInfo {
+    fn from(tx: AnchoringTx) -> AnchoringInfo {
+        AnchoringInfo {
+            txid: tx.id(),
+            kind: AnchoringTxKind::Anchoring,
+            payload: Some(tx.payload()),
+        }
+    }
+}
+
+impl From<FundingTx> for AnchoringInfo {
+    fn from(tx: FundingTx) -> AnchoringInfo {
+        AnchoringInfo {
+            txid: tx.id(),
+            kind: AnchoringTxKind::Funding,
+            payload: None,
         }
     }
 }
 
 impl PublicApi {
+    /// Creates public API for the given `blockchain` without CORS support.
+    pub fn new(blockchain: Blockchain) -> PublicApi {
+        PublicApi::with_cors(blockchain, Vec::new())
+    }
+
+    /// Creates public API for the given `blockchain` that accepts cross-origin requests
+    /// from the given `allowed_origins`.
+    pub fn with_cors<I>(blockchain: Blockchain, allowed_origins: I) -> PublicApi
+    where
+        I: IntoIterator<Item = String>,
+    {
+        PublicApi {
+            blockchain,
+            allowed_origins: allowed_origins.into_iter().collect(),
+            relay: None,
+            last_sync_status: Arc::default(),
+            sync_status_cache_timeout: SYNC_STATUS_CACHE_TIMEOUT,
+            last_balance: Arc::default(),
+            last_confirmations: Arc::default(),
+            anchor_depths: Arc::default(),
+            average_block_time: 1000,
+            max_anchoring_lag: None,
+            rate_limits: RateLimitConfig::default(),
+            watchdog: None,
+            transition_progress: None,
+            observer: false,
+        }
+    }
+
+    /// Uses the given number of blocks as the maximal anchoring lag for the health check.
+    /// By default the lag may not exceed the doubled anchoring frequency.
+    pub fn with_max_anchoring_lag(mut self, max_anchoring_lag: u64) -> PublicApi {
+        self.max_anchoring_lag = Some(max_anchoring_lag);
+        self
+    }
+
+    /// Uses the given average time between the blocks to estimate the time
+    /// until the next anchoring.
+    pub fn with_average_block_time(mut self, average_block_time: Milliseconds) -> PublicApi {
+        self.average_block_time = average_block_time;
+        self
+    }
+
+    /// Limits the rate of the requests to the routes, the requests are not limited by default.
+    pub fn with_rate_limits(mut self, rate_limits: RateLimitConfig) -> PublicApi {
+        self.rate_limits = rate_limits;
+        self
+    }
+
+    /// Serves the status of the given `watchdog` that is updated by the node handler.
+    /// Without the watchdog the `watchdog_status` method is not available.
+    pub fn with_watchdog(mut self, watchdog: AnchoringWatchdog) -> PublicApi {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    /// Counts the signatures of the transfer transaction that is built by the node handler
+    /// and shared through the given `progress`. Without it the `transition_status`
+    /// method reports no collected signatures.
+    pub fn with_transition_progress(mut self, progress: TransitionProgress) -> PublicApi {
+        self.transition_progress = Some(progress);
+        self
+    }
+
+    /// Requires the anchoring chain observer that runs along with the API to follow
+    /// the recent anchoring transactions for the node to be ready, see `readiness`.
+    pub fn with_observer(mut self) -> PublicApi {
+        self.observer = true;
+        self
+    }
+
+    /// Uses the given `relay` to request the bitcoin blockchain state.
+    /// Without the relay the `sync_status` and `anchoring_balance` methods are not available.
+    pub fn with_relay(mut self, relay: Box<BitcoinRelay>) -> PublicApi {
+        self.relay = Some(Arc::from(relay));
+        self
+    }
+
+    /// Serves the sync status from the cache during the given number of seconds,
+    /// `SYNC_STATUS_CACHE_TIMEOUT` by default.
+    pub fn with_sync_status_cache_timeout(mut self, timeout: u64) -> PublicApi {
+        self.sync_status_cache_timeout = timeout;
+        self
+    }
+
+    /// Creates an iron handler with the wired routes, the CORS and rate limiting
+    /// middlewares if needed.
+    pub fn handler(&self) -> Chain {
+        let mut router = Router::new();
+        self.wire(&mut router);
+
+        let mut chain = Chain::new(router);
+        if !self.allowed_origins.is_empty() {
+            chain.link_after(CorsMiddleware::new(self.allowed_origins.clone()));
+        }
+        chain.link((RequestLogger, RequestLogger));
+        // Rejected requests are logged along with their correlation ids.
+        if !self.rate_limits.is_empty() {
+            chain.link_before(RateLimiter::new(self.rate_limits.clone()));
+        }
+        chain
+    }
+
+    /// Returns the view of the API at the current state of the blockchain.
+    ///
+    /// Request handlers read the blockchain through a single view. Use it
+    /// to combine several reads without taking a separate snapshot for each of them.
+    pub fn snapshot(&self) -> ApiSnapshot {
+        ApiSnapshot {
+            api: self,
+            snapshot: self.blockchain.snapshot(),
+        }
+    }
+
+    /// Takes the snapshot to serve the given request and remembers its height for the log.
+    fn request_snapshot(&self, req: &mut Request) -> ApiSnapshot {
+        let snapshot = self.snapshot();
+        if let Some(height) = snapshot.height() {
+            req.extensions.insert::<SnapshotHeight>(height);
+        }
+        snapshot
+    }
+
     /// Returns information about the lect agreed by +2/3 validators if there is one.
     ///
     /// `GET /{api_prefix}/v1/actual_lect/`
     pub fn actual_lect(&self) -> Result<Option<AnchoringInfo>, ApiError> {
-        let snapshot = self.blockchain.snapshot();
-        let schema = AnchoringSchema::new(snapshot);
-        let actual_cfg = &schema.actual_anchoring_config();
-        Ok(schema.collect_lects(actual_cfg).map(AnchoringInfo::from))
+        self.snapshot().actual_lect()
     }
 
     /// Returns current lect for validator with given `id`.
     ///
     /// `GET /{api_prefix}/v1/actual_lect/:id`
     pub fn current_lect_of_validator(&self, id: u32) -> Result<LectInfo, ApiError> {
-        let snapshot = self.blockchain.snapshot();
-        let schema = AnchoringSchema::new(snapshot);
+        self.snapshot().current_lect_of_validator(id)
+    }
 
-        let actual_cfg = schema.actual_anchoring_config();
-        if let Some(key) = actual_cfg.anchoring_keys.get(id as usize) {
-            if let Some(lect) = schema.lects(key).last() {
-                return Ok(LectInfo::from(lect));
-            }
-        }
-        Err(error::Error::UnknownValidatorId(id).into())
+    /// Returns the current lects of all validators grouped by their `txid`
+    /// along with the rejected lects that conflict with the agreed ones.
+    ///
+    /// `GET /{api_prefix}/v1/actual_lect/agreement`
+    pub fn lect_agreement(&self) -> Result<LectAgreement, ApiError> {
+        self.snapshot().lect_agreement()
     }
 
-    /// Returns actual anchoring address.
+    /// Returns the anchoring keys of the validators from the actual anchoring configuration
+    /// and the txids of their current lects.
     ///
-    /// `GET /{api_prefix}/v1/address/actual`
-    pub fn actual_address(&self) -> Result<btc::Address, ApiError> {
-        let snapshot = self.blockchain.snapshot();
-        let schema = AnchoringSchema::new(snapshot);
-        Ok(schema.actual_anchoring_config().redeem_script().1)
+    /// `GET /{api_prefix}/v1/validators`
+    pub fn validators(&self) -> Result<Vec<ValidatorInfo>, ApiError> {
+        self.snapshot().validators()
     }
 
-    /// Returns the following anchoring address if the node is in a transition state.
+    /// Returns the current lects of the validators from the actual anchoring configuration
+    /// along with their confirmation depths reported by the bitcoin relay. The validators
+    /// that follow a minority bitcoin fork report the lects unknown to the relay.
     ///
-    /// `GET /{api_prefix}/v1/address/following`
-    pub fn following_address(&self) -> Result<Option<btc::Address>, ApiError> {
-        let snapshot = self.blockchain.snapshot();
-        let schema = AnchoringSchema::new(snapshot);
-        let following_addr = schema
-            .following_anchoring_config()
-            .map(|cfg| cfg.redeem_script().1);
-        Ok(following_addr)
+    /// `GET /{api_prefix}/v1/validators/depths`
+    pub fn validator_lect_depths(&self) -> Result<Vec<ValidatorLectDepth>, ApiError> {
+        self.snapshot().validator_lect_depths()
     }
 
-    /// Returns hex of the anchoring transaction for the nearest block with a height greater
-    /// or equal than the given.
+    /// Returns the funding transaction from the actual anchoring configuration
+    /// if it is specified.
     ///
-    /// `GET /{api_prefix}/v1/nearest_lect/:height`
-    pub fn nearest_lect(&self, height: u64) -> Result<Option<AnchoringTx>, ApiError> {
-        let snapshot = self.blockchain.snapshot();
-        let anchoring_schema = AnchoringSchema::new(&snapshot);
-        let tx_chain = anchoring_schema.anchoring_tx_chain();
+    /// `GET /{api_prefix}/v1/funding_tx/actual`
+    pub fn actual_funding_tx(&self) -> Result<Option<FundingTxInfo>, ApiError> {
+        self.snapshot().actual_funding_tx()
+    }
 
-        // TODO use binary find.
-        for (tx_height, tx) in &tx_chain {
-            if tx_height >= height {
-                return Ok(Some(tx));
-            }
-        }
-        Ok(None)
+    /// Returns all funding transactions from the actual anchoring configuration,
+    /// the `funding_tx` goes first.
+    ///
+    /// `GET /{api_prefix}/v1/funding_txs/actual`
+    pub fn actual_funding_txs(&self) -> Result<Vec<FundingTxInfo>, ApiError> {
+        self.snapshot().actual_funding_txs()
     }
 
-    /// A method that provides cryptographic proofs for Exonum blocks including those anchored to
-    /// Bitcoin blockchain. The proof is an apparent evidence of availability of a certain Exonum
-    /// block in the blockchain.
+    /// Returns every funding transaction that has fed the anchoring addresses in the order
+    /// they have become known, including the ones that are spent by the anchoring chain.
     ///
-    /// `GET /{api_prefix}/v1/block_header_proof/:height`
-    pub fn anchored_block_header_proof(&self, height: u64) -> AnchoredBlockHeaderProof {
-        let view = self.blockchain.snapshot();
-        let core_schema = CoreSchema::new(&view);
-        let anchoring_schema = AnchoringSchema::new(&view);
+    /// `GET /{api_prefix}/v1/funding/history`
+    pub fn funding_history(&self) -> Result<Vec<FundingHistoryEntry>, ApiError> {
+        self.snapshot().funding_history()
+    }
 
-        let max_height = core_schema.block_hashes_by_height().len() - 1;
+    /// Returns at most `count` lects of validator with given `id` starting from
+    /// the position `from` in insertion order.
+    ///
+    /// `GET /{api_prefix}/v1/lects/:id?from=&count=`
+    pub fn lects_of_validator(
+        &self,
+        id: u32,
+        from: u64,
+        count: u64,
+    ) -> Result<LectsInfo, ApiError> {
+        self.snapshot().lects_of_validator(id, from, count)
+    }
 
-        let latest_authorized_block = core_schema
-            .block_and_precommits(Height(max_height))
-            .unwrap();
-        let to_table: MapProof<Hash, Hash> =
-            core_schema.get_proof_to_service_table(ANCHORING_SERVICE_ID, 0);
-        let to_block_header = anchoring_schema.anchored_blocks().get_proof(height);
+    /// Returns at most `count` anchored blocks with the heights greater or equal than `from`
+    /// together with their anchoring transactions.
+    ///
+    /// `GET /{api_prefix}/v1/anchored_blocks?from=&count=`
+    pub fn anchored_blocks(&self, from: u64, count: u64) -> Result<AnchoredBlocksInfo, ApiError> {
+        self.snapshot().anchored_blocks(from, count)
+    }
 
-        AnchoredBlockHeaderProof {
-            latest_authorized_block,
-            to_table,
-            to_block_header,
-        }
+    /// Returns the anchoring transactions included in the bitcoin blocks with the heights
+    /// in the range `[from_btc_height, from_btc_height + count)` ordered by these heights.
+    ///
+    /// The heights of the bitcoin blocks are recorded by the anchoring chain observer,
+    /// so the bitcoin heights without anchors are skipped.
+    ///
+    /// `GET /{api_prefix}/v1/height_map?from=&count=`
+    pub fn height_map(
+        &self,
+        from_btc_height: u64,
+        count: u64,
+    ) -> Result<Vec<HeightMapEntry>, ApiError> {
+        self.snapshot().height_map(from_btc_height, count)
     }
-}
 
-impl Api for PublicApi {
-    fn wire(&self, router: &mut Router) {
-        let api = self.clone();
-        let actual_lect = move |_: &mut Request| -> IronResult<Response> {
-            let lect = api.actual_lect()?;
-            api.ok_response(&json!(lect))
-        };
+    /// Returns the last `count` anchors of the anchoring chain ordered by the anchored heights
+    /// together with the intervals since the previous anchors, in Exonum blocks and in
+    /// seconds between the bitcoin blocks. Widening intervals warn about an anchoring slowdown.
+    ///
+    /// `GET /{api_prefix}/v1/anchor_intervals?count=`
+    pub fn anchor_intervals(&self, count: u64) -> Result<Vec<AnchorInterval>, ApiError> {
+        self.snapshot().anchor_intervals(count)
+    }
 
-        let api = self.clone();
-        let current_lect_of_validator = move |req: &mut Request| -> IronResult<Response> {
-            let id = api.url_fragment(req, "id")?;
-            let info = api.current_lect_of_validator(id)?;
-            api.ok_response(&json!(info))
-        };
+    /// Returns the actual anchoring configuration.
+    ///
+    /// In addition to the configuration fields the response contains the redeem script
+    /// in hex, the anchoring address and the number of required signatures.
+    ///
+    /// `GET /{api_prefix}/v1/config/actual`
+    pub fn actual_config(&self) -> Result<AnchoringConfig, ApiError> {
+        self.snapshot().actual_config()
+    }
 
-        let api = self.clone();
-        let actual_address = move |_: &mut Request| -> IronResult<Response> {
-            let addr = api.actual_address()?.to_string();
-            api.ok_response(&json!(addr))
-        };
+    /// Returns every anchoring configuration that has taken effect with its activation
+    /// height, from the genesis one to the actual one.
+    ///
+    /// `GET /{api_prefix}/v1/config/history`
+    pub fn config_history(&self) -> Result<Vec<ConfigHistoryEntry>, ApiError> {
+        self.snapshot().config_history()
+    }
 
-        let api = self.clone();
-        let following_address = move |_: &mut Request| -> IronResult<Response> {
-            let addr = api.following_address()?.map(|addr| addr.to_string());
-            api.ok_response(&json!(addr))
-        };
+    /// Returns the following anchoring configuration if the node is in a transition state.
+    ///
+    /// In addition to the fields of the actual configuration the response contains
+    /// the `actual_from` height since which the following configuration takes effect.
+    ///
+    /// `GET /{api_prefix}/v1/config/following`
+    pub fn following_config(&self) -> Result<Option<AnchoringConfig>, ApiError> {
+        self.snapshot().following_config()
+    }
 
-        let api = self.clone();
-        let nearest_lect = move |req: &mut Request| -> IronResult<Response> {
-            let height = api.url_fragment(req, "height")?;
-            let lect = api.nearest_lect(height)?;
-            api.ok_response(&json!(lect))
-        };
+    /// Returns whether the anchoring chain is moving to a new address and how many
+    /// signatures on the transfer transaction have been collected.
+    ///
+    /// The transfer transaction is known only to the validator nodes, so the other nodes
+    /// report no collected signatures.
+    ///
+    /// `GET /{api_prefix}/v1/transition`
+    pub fn transition_status(&self) -> Result<TransitionStatus, ApiError> {
+        self.snapshot().transition_status()
+    }
 
-        let api = self.clone();
+    /// Returns the anchoring health metrics in the Prometheus text format.
+    ///
+    /// Gauges that depend on the agreed lect are omitted until the validators agree on an
+    /// anchoring transaction. The bitcoin height of the agreed lect is known once
+    /// the observer finds the lect in the anchoring chain.
+    ///
+    /// `GET /{api_prefix}/v1/metrics`
+    pub fn metrics(&self) -> String {
+        self.snapshot().metrics()
+    }
+
+    /// Returns actual anchoring address.
+    ///
+    /// `GET /{api_prefix}/v1/address/actual`
+    pub fn actual_address(&self) -> Result<btc::Address, ApiError> {
+        self.snapshot().actual_address()
+    }
+
+    /// Returns the following anchoring address if the node is in a transition state.
+    ///
+    /// `GET /{api_prefix}/v1/address/following`
+    pub fn following_address(&self) -> Result<Option<btc::Address>, ApiError> {
+        self.snapshot().following_address()
+    }
+
+    /// Returns the hex-encoded redeem scripts of the actual and following anchoring
+    /// addresses, so external signers and watch-only wallets can reconstruct them.
+    ///
+    /// `GET /{api_prefix}/v1/redeem_script`
+    pub fn redeem_scripts(&self) -> Result<RedeemScripts, ApiError> {
+        self.snapshot().redeem_scripts()
+    }
+
+    /// Returns the whole anchoring chain along with the actual configuration
+    /// as the body that is streamed in JSON.
+    ///
+    /// `GET /{api_prefix}/v1/export`
+    pub fn export_chain(&self) -> ChainExportBody {
+        ChainExportBody::new(self.blockchain.clone())
+    }
+
+    /// Returns information about the lect with the given `txid` if it is known
+    /// to the anchoring service.
+    ///
+    /// `GET /{api_prefix}/v1/lect_by_txid/:txid`
+    pub fn find_lect_by_txid(&self, txid: TxId) -> Result<Option<AnchoringInfo>, ApiError> {
+        self.snapshot().find_lect_by_txid(txid)
+    }
+
+    /// Returns the decoded payload of the anchoring transaction with the given `txid`
+    /// if it is known to the anchoring service.
+    ///
+    /// `GET /{api_prefix}/v1/payload/:txid`
+    pub fn payload_by_txid(&self, txid: TxId) -> Result<PayloadInfo, ApiError> {
+        self.snapshot().payload_by_txid(txid)
+    }
+
+    /// Returns the information about the previous anchor of the anchoring transaction
+    /// with the given `txid`, or `None` if the transaction is the first one in the chain.
+    ///
+    /// The previous anchor is the transaction recorded in the payload if the chain has been
+    /// lost, otherwise the one spent by the first input. Repeated calls traverse
+    /// the anchoring chain backwards.
+    ///
+    /// `GET /{api_prefix}/v1/prev_anchor/:txid`
+    pub fn prev_anchor(&self, txid: TxId) -> Result<Option<AnchoringInfo>, ApiError> {
+        self.snapshot().prev_anchor(txid)
+    }
+
+    /// Checks that the given raw bitcoin transaction is an anchoring one and the payload
+    /// of it matches the hash of the Exonum block at the anchored height.
+    ///
+    /// The transaction does not need to be known to the anchoring service, so third parties
+    /// can verify the transactions fetched from the bitcoin blockchain on their own.
+    ///
+    /// `POST /{api_prefix}/v1/verify_anchor`
+    pub fn verify_anchor(&self, raw_tx_hex: String) -> Result<AnchorVerification, ApiError> {
+        self.snapshot().verify_anchor(&raw_tx_hex)
+    }
+
+    /// Classifies the given raw bitcoin transaction in the same way as the anchoring service
+    /// does and decodes the payload of the anchoring transaction.
+    ///
+    /// The anchoring transactions are recognized by the payload prefix of the actual
+    /// configuration.
+    ///
+    /// `POST /{api_prefix}/v1/classify`
+    pub fn classify_tx(&self, raw_tx_hex: String) -> Result<TxClassification, ApiError> {
+        self.snapshot().classify_tx(&raw_tx_hex)
+    }
+
+    /// Returns hex of the transaction with the given `txid` if it is known
+    /// to the anchoring service as a lect.
+    ///
+    /// `GET /{api_prefix}/v1/tx/:txid/raw`
+    pub fn raw_tx(&self, txid: TxId) -> Result<String, ApiError> {
+        self.snapshot().raw_tx(txid)
+    }
+
+    /// Returns the state of the anchoring service for the bug reports: summaries
+    /// of the configurations, the actual lect, the funding balance, the transition status
+    /// and the latest anchored heights. It never contains the private keys.
+    ///
+    /// The sections that need the `bitcoind` are available only if the relay is set.
+    ///
+    /// `GET /{api_prefix}/v1/diagnostics`
+    pub fn diagnostics(&self) -> Result<Diagnostics, ApiError> {
+        self.snapshot().diagnostics()
+    }
+
+    /// Returns hex of the anchoring transaction for the nearest block with a height greater
+    /// or equal than the given.
+    ///
+    /// `GET /{api_prefix}/v1/nearest_lect/:height`
+    pub fn nearest_lect(&self, height: u64) -> Result<Option<AnchoringTx>, ApiError> {
+        self.snapshot().nearest_lect(height)
+    }
+
+    /// Returns hex of the anchoring transaction that anchors the block with the given
+    /// `block_hash`, that is the anchor of the nearest block with a height greater or equal
+    /// than the height of this block, or `None` if the block is not anchored yet.
+    ///
+    /// Returns an error if there is no such block in the blockchain.
+    ///
+    /// `GET /{api_prefix}/v1/anchor_for_block/:hash`
+    pub fn anchor_for_block(&self, block_hash: Hash) -> Result<Option<AnchoringTx>, ApiError> {
+        self.snapshot().anchor_for_block(block_hash)
+    }
+
+    /// A method that provides cryptographic proofs for Exonum blocks including those anchored to
+    /// Bitcoin blockchain. The proof is an apparent evidence of availability of a certain Exonum
+    /// block in the blockchain.
+    ///
+    /// Returns an error if the block at the given `height` has not been anchored yet.
+    ///
+    /// The proof contains the number of confirmations of the anchoring transaction
+    /// for the nearest anchored block, so the verifier can require the anchor to be
+    /// buried deep enough in the bitcoin blockchain. The number is cached for
+    /// `ANCHOR_DEPTH_CACHE_TIMEOUT` seconds.
+    ///
+    /// The proof is returned in CBOR if the request has the `Accept: application/cbor` header,
+    /// the binary proof has the same structure as the JSON one.
+    ///
+    /// `GET /{api_prefix}/v1/block_header_proof/:height`
+    pub fn anchored_block_header_proof(
+        &self,
+        height: u64,
+    ) -> Result<AnchoredBlockHeaderProof, ApiError> {
+        self.snapshot().anchored_block_header_proof(height)
+    }
+
+    /// Same as [`anchored_block_header_proof`](#method.anchored_block_header_proof)
+    /// but provides a single proof for the all blocks in the range `[from, to)`.
+    ///
+    /// Returns an error if some of the blocks in the range have not been anchored yet.
+    ///
+    /// `GET /{api_prefix}/v1/block_header_proof?from=&to=`
+    pub fn anchored_block_header_proof_range(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<AnchoredBlockHeaderProof, ApiError> {
+        self.snapshot().anchored_block_header_proof_range(from, to)
+    }
+
+    /// Same as [`anchored_block_header_proof`](#method.anchored_block_header_proof)
+    /// but provides the proofs for the blocks at the discrete `heights`, which share
+    /// the proofs for the latest authorized block and the table.
+    ///
+    /// Returns an error if there are no heights or more than `MAX_PROOF_HEIGHTS_COUNT`
+    /// of them, or if some of the blocks have not been anchored yet.
+    ///
+    /// `POST /{api_prefix}/v1/block_header_proofs`
+    pub fn anchored_block_header_proofs(
+        &self,
+        heights: &[u64],
+    ) -> Result<AnchoredBlockHeaderProofs, ApiError> {
+        self.snapshot().anchored_block_header_proofs(heights)
+    }
+
+    /// Returns the height below which the hashes of the anchored blocks are pruned,
+    /// the block header proofs for the lower heights are unavailable.
+    ///
+    /// `GET /{api_prefix}/v1/block_header_proof/cutoff`
+    pub fn pruning_info(&self) -> Result<PruningInfo, ApiError> {
+        self.snapshot().pruning_info()
+    }
+
+    /// Returns whether the anchoring chain observer waits for the manual intervention
+    /// after the bitcoin blockchain reorganization deeper than its `max_reorg_depth`.
+    ///
+    /// The orphaned anchoring transactions are kept in the chain in this case, and the new
+    /// ones are not added until the reorganization is resolved, e.g. the limit is raised.
+    ///
+    /// `GET /{api_prefix}/v1/observer/status`
+    pub fn observer_status(&self) -> Result<ObserverStatus, ApiError> {
+        self.snapshot().observer_status()
+    }
+
+    /// Returns the depth of the actual lect in the bitcoin blockchain.
+    ///
+    /// The status is cached for the `SYNC_STATUS_CACHE_TIMEOUT` seconds unless the actual lect
+    /// changes. If the `bitcoind` is unreachable, the last known values are returned and
+    /// marked as stale.
+    ///
+    /// `GET /{api_prefix}/v1/sync_status`
+    pub fn sync_status(&self) -> Result<SyncStatus, ApiError> {
+        self.snapshot().sync_status()
+    }
+
+    /// Returns whether the anchoring is stalled, i.e. no new anchor has been confirmed
+    /// within the time budget of the node, along with the time since the latest one.
+    ///
+    /// `GET /{api_prefix}/v1/watchdog`
+    pub fn watchdog_status(&self) -> Result<WatchdogStatus, ApiError> {
+        let watchdog = self.watchdog
+            .as_ref()
+            .ok_or(error::Error::WatchdogNotConfigured)?;
+        Ok(watchdog.status(Instant::now()))
+    }
+
+    /// Returns the number of confirmations of the lect agreed by +2/3 validators
+    /// if there is one.
+    ///
+    /// The value is cached for `CONFIRMATIONS_CACHE_TIMEOUT` seconds.
+    ///
+    /// `GET /{api_prefix}/v1/actual_lect/confirmations`
+    pub fn actual_lect_confirmations(&self) -> Result<Option<LectConfirmations>, ApiError> {
+        self.snapshot().actual_lect_confirmations()
+    }
+
+    /// Returns the funds available at the actual anchoring address and the following one
+    /// if the transition is in progress.
+    ///
+    /// The values are cached for `BALANCE_CACHE_TIMEOUT` seconds. They are also served
+    /// from the cache if the `bitcoind` is unreachable.
+    ///
+    /// `GET /{api_prefix}/v1/funding/balance`
+    pub fn anchoring_balance(&self) -> Result<AnchoringBalance, ApiError> {
+        self.snapshot().anchoring_balance()
+    }
+
+    /// Returns the health of the anchoring service for the load balancers and liveness probes.
+    ///
+    /// The HTTP status of the response is `503` if the service is down and `200` otherwise.
+    ///
+    /// `GET /{api_prefix}/v1/health`
+    pub fn health(&self) -> Result<Health, ApiError> {
+        self.snapshot().health()
+    }
+
+    /// Returns whether the node is ready to serve the correct anchoring data for the readiness
+    /// probes, which is stricter than the `health` liveness check.
+    ///
+    /// The node is ready if the latest anchored block does not lag behind the latest committed
+    /// block, the `bitcoind` responds if the relay is configured, and the observer has found
+    /// the recent anchoring transaction if it is required by `with_observer`. The lags are
+    /// limited by the same threshold as the anchoring lag of the health check.
+    ///
+    /// The HTTP status of the response is `503` if the node is not ready and `200` otherwise.
+    ///
+    /// `GET /{api_prefix}/v1/ready`
+    pub fn readiness(&self) -> Result<Readiness, ApiError> {
+        self.snapshot().readiness()
+    }
+
+    /// Returns the JSON Schema of the public API responses, e.g. `AnchoringInfo`, `LectInfo`
+    /// and `AnchoredBlockHeaderProof`, for the clients that generate the typed bindings.
+    /// The responses can be checked against it by `validate_schema`.
+    ///
+    /// `GET /{api_prefix}/v1/schema`
+    pub fn response_schema(&self) -> Value {
+        response_schema()
+    }
+
+    /// Returns the number of blocks until the next anchoring height and the estimated
+    /// time it takes to commit them. The time estimate is best-effort, it is based
+    /// on the configured average block time.
+    ///
+    /// `GET /{api_prefix}/v1/next_anchor_eta`
+    pub fn time_to_next_anchor(&self) -> Result<NextAnchorEta, ApiError> {
+        self.snapshot().time_to_next_anchor()
+    }
+
+    /// Returns the unsigned anchoring transaction that the validators are going to build next
+    /// or `None` if the agreed lect already anchors the latest anchoring height.
+    ///
+    /// The preview is built from the agreed state only, so it does not cover the transition
+    /// to the following anchoring address and the funding transactions unknown to the
+    /// anchoring configuration. The estimated fee rate should be agreed by the validators
+    /// to preview the transaction.
+    ///
+    /// `GET /{api_prefix}/v1/next_anchoring_tx`
+    pub fn next_anchoring_tx_preview(&self) -> Result<Option<AnchoringTxPreview>, ApiError> {
+        self.snapshot().next_anchoring_tx_preview()
+    }
+}
+
+impl<'a> ApiSnapshot<'a> {
+    /// Returns the height of the latest committed block in the snapshot
+    /// if the genesis block has been created.
+    pub fn height(&self) -> Option<Height> {
+        let count = CoreSchema::new(&self.snapshot).block_hashes_by_height().len();
+        count.checked_sub(1).map(Height)
+    }
+
+    /// Returns the entity tag of the results that change only with the configs,
+    /// i.e. the combined hash of the actual and the following configurations.
+    pub fn config_etag(&self) -> Hash {
+        let schema = CoreSchema::new(&self.snapshot);
+        let mut hashes = vec![schema.actual_configuration().hash()];
+        hashes.extend(schema.following_configuration().map(|cfg| cfg.hash()));
+        etag_of(&hashes)
+    }
+
+    /// Returns the entity tag of the lect results, which also includes the hashes
+    /// of the latest lects of the validators.
+    pub fn lect_etag(&self) -> Hash {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let mut hashes = vec![self.config_etag()];
+        for key in &schema.actual_anchoring_config().anchoring_keys {
+            hashes.extend(schema.lects(key).last().map(|lect| *lect.msg_hash()));
+        }
+        etag_of(&hashes)
+    }
+
+    /// Same as `PublicApi::actual_lect`.
+    pub fn actual_lect(&self) -> Result<Option<AnchoringInfo>, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let actual_cfg = &schema.actual_anchoring_config();
+        match schema.collect_lects(actual_cfg) {
+            Some(lect) => Ok(Some(AnchoringInfo::try_from_lect(actual_cfg, lect)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Same as `PublicApi::current_lect_of_validator`.
+    pub fn current_lect_of_validator(&self, id: u32) -> Result<LectInfo, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+
+        let actual_cfg = schema.actual_anchoring_config();
+        if let Some(key) = actual_cfg.anchoring_keys.get(id as usize) {
+            if let Some(lect) = schema.lects(key).last() {
+                return Ok(LectInfo::try_from_lect(&actual_cfg, lect)?);
+            }
+        }
+        Err(error::Error::UnknownValidatorId(id).into())
+    }
+
+    /// Same as `PublicApi::lect_agreement`.
+    pub fn lect_agreement(&self) -> Result<LectAgreement, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let actual_cfg = schema.actual_anchoring_config();
+
+        let mut lects = HashMap::new();
+        let mut conflicts = Vec::new();
+        for (id, key) in actual_cfg.anchoring_keys.iter().enumerate() {
+            if let Some(lect) = schema.lects(key).last() {
+                lects
+                    .entry(lect.tx().id())
+                    .or_insert_with(Vec::new)
+                    .push(id as u32);
+            }
+            conflicts.extend(schema.lect_conflicts(key).values().map(|conflict| {
+                LectConflictInfo {
+                    validator: id as u32,
+                    height: conflict.height(),
+                    agreed_txid: conflict.agreed_tx().id(),
+                    conflicting_txid: conflict.conflicting_tx().id(),
+                    msg_hash: *conflict.msg_hash(),
+                }
+            }));
+        }
+        Ok(LectAgreement {
+            lects,
+            majority_count: actual_cfg.majority_count(),
+            conflicts,
+        })
+    }
+
+    /// Same as `PublicApi::validator_lect_depths`.
+    pub fn validator_lect_depths(&self) -> Result<Vec<ValidatorLectDepth>, ApiError> {
+        let relay = self.api.relay
+            .as_ref()
+            .ok_or(error::Error::RelayNotConfigured)?;
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let lects = schema
+            .actual_anchoring_config()
+            .anchoring_keys
+            .iter()
+            .enumerate()
+            .filter_map(|(id, key)| schema.lect(key).map(|lect| (id as u32, lect.id())))
+            .collect::<Vec<_>>();
+
+        // The validators usually agree on the lect, so each txid is requested once.
+        let mut txids = Vec::new();
+        for &(_, txid) in &lects {
+            if !txids.contains(&txid) {
+                txids.push(txid);
+            }
+        }
+        let infos = relay
+            .get_transactions_info(&txids)
+            .map_err(|e| error::Error::RelayUnavailable(e.to_string()))?;
+        let depths = txids
+            .into_iter()
+            .zip(infos)
+            .map(|(txid, info)| (txid, info.map(|info| info.confirmations.unwrap_or(0))))
+            .collect::<HashMap<_, _>>();
+
+        Ok(lects
+            .into_iter()
+            .map(|(id, txid)| ValidatorLectDepth {
+                id,
+                txid,
+                confirmations: depths[&txid],
+            })
+            .collect())
+    }
+
+    /// Same as `PublicApi::validators`.
+    pub fn validators(&self) -> Result<Vec<ValidatorInfo>, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let actual_cfg = schema.actual_anchoring_config();
+
+        let validators = actual_cfg
+            .anchoring_keys
+            .iter()
+            .enumerate()
+            .map(|(id, key)| {
+                let lect_txid = schema.lect(key).map(|lect| lect.id());
+                ValidatorInfo {
+                    id: id as u32,
+                    public_key: *key,
+                    has_lect: lect_txid.is_some(),
+                    lect_txid,
+                }
+            })
+            .collect();
+        Ok(validators)
+    }
+
+    /// Same as `PublicApi::actual_funding_tx`.
+    pub fn actual_funding_tx(&self) -> Result<Option<FundingTxInfo>, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let actual_cfg = schema.actual_anchoring_config();
+
+        let info = actual_cfg.funding_tx.as_ref().map(|tx| {
+            let spent = schema.funding_tx_is_spent(&tx.id());
+            FundingTxInfo {
+                content: AnchoringInfo::from(tx.clone()),
+                spent,
+            }
+        });
+        Ok(info)
+    }
+
+    /// Same as `PublicApi::actual_funding_txs`.
+    pub fn actual_funding_txs(&self) -> Result<Vec<FundingTxInfo>, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let actual_cfg = schema.actual_anchoring_config();
+
+        let infos = actual_cfg
+            .funding_txs()
+            .into_iter()
+            .map(|tx| FundingTxInfo {
+                content: AnchoringInfo::from(tx.clone()),
+                spent: schema.funding_tx_is_spent(&tx.id()),
+            })
+            .collect();
+        Ok(infos)
+    }
+
+    /// Same as `PublicApi::funding_history`.
+    pub fn funding_history(&self) -> Result<Vec<FundingHistoryEntry>, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let actual_cfg = schema.actual_anchoring_config();
+        let known_txs = schema.known_txs();
+        let spent_funding_txs = schema.spent_funding_txs();
+
+        let entries = schema
+            .funding_history()
+            .iter()
+            .map(|record| {
+                let txid = record.tx().id();
+                let spent_by = spent_funding_txs.get(&txid);
+                let spent_at_height = spent_by
+                    .as_ref()
+                    .and_then(|spent_by| known_txs.get(spent_by))
+                    .and_then(|tx| match actual_cfg.tx_kind(tx) {
+                        TxKind::Anchoring(tx) => Some(tx.payload().block_height.0),
+                        TxKind::FundingTx(_) | TxKind::Other(_) => None,
+                    });
+                FundingHistoryEntry {
+                    txid,
+                    amount: record.amount(),
+                    height: record.height(),
+                    spent: spent_by.is_some(),
+                    spent_by,
+                    spent_at_height,
+                }
+            })
+            .collect();
+        Ok(entries)
+    }
+
+    /// Same as `PublicApi::lects_of_validator`.
+    pub fn lects_of_validator(
+        &self,
+        id: u32,
+        from: u64,
+        count: u64,
+    ) -> Result<LectsInfo, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+
+        let actual_cfg = schema.actual_anchoring_config();
+        let key = actual_cfg
+            .anchoring_keys
+            .get(id as usize)
+            .ok_or_else(|| error::Error::UnknownValidatorId(id))?;
+
+        let lects = schema.lects(key);
+        let count = ::std::cmp::min(count, MAX_LECTS_COUNT);
+        let page = lects
+            .iter_from(from)
+            .take(count as usize)
+            .map(|content| LectInfo::try_from_lect(&actual_cfg, content))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(LectsInfo {
+            total_count: lects.len(),
+            lects: page,
+        })
+    }
+
+    /// Same as `PublicApi::anchored_blocks`.
+    pub fn anchored_blocks(&self, from: u64, count: u64) -> Result<AnchoredBlocksInfo, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+
+        let tx_chain = schema.anchoring_tx_chain();
+        let count = ::std::cmp::min(count, MAX_ANCHORED_BLOCKS_COUNT);
+        let blocks = tx_chain
+            .iter_from(&from)
+            .take(count as usize)
+            .map(|(height, tx)| (height, AnchoringInfo::from(tx)))
+            .collect();
+        Ok(AnchoredBlocksInfo {
+            total_count: schema.anchoring_tx_chain_heights().len(),
+            blocks,
+        })
+    }
+
+    /// Same as `PublicApi::height_map`.
+    pub fn height_map(
+        &self,
+        from_btc_height: u64,
+        count: u64,
+    ) -> Result<Vec<HeightMapEntry>, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let tx_chain = schema.anchoring_tx_chain();
+
+        let count = ::std::cmp::min(count, MAX_HEIGHT_MAP_COUNT);
+        let to_btc_height = from_btc_height.saturating_add(count);
+        let from = BtcHeightKey {
+            btc_height: from_btc_height,
+            height: 0,
+        };
+        let entries = schema
+            .anchored_heights_by_btc_height()
+            .iter_from(&from)
+            .take_while(|key| key.btc_height < to_btc_height)
+            .filter_map(|key| {
+                tx_chain.get(&key.height).map(|tx| HeightMapEntry {
+                    btc_height: key.btc_height,
+                    exonum_height: key.height,
+                    txid: tx.id(),
+                })
+            })
+            .collect();
+        Ok(entries)
+    }
+
+    /// Same as `PublicApi::anchor_intervals`.
+    pub fn anchor_intervals(&self, count: u64) -> Result<Vec<AnchorInterval>, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let tx_chain = schema.anchoring_tx_chain();
+        let btc_times = schema.anchoring_tx_btc_times();
+
+        let count = ::std::cmp::min(count, MAX_ANCHOR_INTERVALS_COUNT);
+        let heights = schema.anchoring_tx_chain_heights();
+        // The anchor before the requested ones is only used to compute the first intervals.
+        let skip = heights.len().saturating_sub(count + 1);
+        let mut intervals: Vec<AnchorInterval> = Vec::new();
+        for height in heights.iter_from(skip) {
+            let tx = tx_chain.get(&height).unwrap();
+            let btc_time = btc_times.get(&height);
+            let (height_delta, btc_time_delta) = match intervals.last() {
+                Some(prev) => {
+                    let btc_time_delta = match (prev.btc_time, btc_time) {
+                        (Some(prev_time), Some(time)) => Some(time as i64 - prev_time as i64),
+                        _ => None,
+                    };
+                    (Some(height - prev.exonum_height), btc_time_delta)
+                }
+                None => (None, None),
+            };
+            intervals.push(AnchorInterval {
+                exonum_height: height,
+                txid: tx.id(),
+                btc_time,
+                height_delta,
+                btc_time_delta,
+            });
+        }
+        if intervals.len() as u64 > count {
+            intervals.remove(0);
+        }
+        Ok(intervals)
+    }
+
+    /// Same as `PublicApi::actual_config`.
+    pub fn actual_config(&self) -> Result<AnchoringConfig, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        Ok(schema.actual_anchoring_config())
+    }
+
+    /// Same as `PublicApi::config_history`.
+    pub fn config_history(&self) -> Result<Vec<ConfigHistoryEntry>, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let history = schema
+            .anchoring_configs_history()
+            .into_iter()
+            .map(|(actual_from, config)| ConfigHistoryEntry {
+                actual_from: actual_from.0,
+                config,
+            })
+            .collect();
+        Ok(history)
+    }
+
+    /// Same as `PublicApi::following_config`.
+    pub fn following_config(&self) -> Result<Option<AnchoringConfig>, ApiError> {
+        Ok(self.following_config_with_height().map(|(cfg, _)| cfg))
+    }
+
+    /// Same as `PublicApi::transition_status`.
+    pub fn transition_status(&self) -> Result<TransitionStatus, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+
+        let actual_address = schema.actual_anchoring_config().redeem_script().1;
+        let following_address = schema
+            .following_anchoring_config()
+            .map(|cfg| cfg.redeem_script().1)
+            .and_then(|addr| {
+                if addr != actual_address {
+                    Some(addr)
+                } else {
+                    None
+                }
+            });
+
+        let transition_progress = self.api.transition_progress.as_ref();
+        let collected_signatures = following_address
+            .as_ref()
+            .and_then(|addr| transition_progress.and_then(|progress| progress.transfer(addr)))
+            .map(|ntxid| {
+                schema
+                    .signatures(&ntxid)
+                    .iter()
+                    .map(|msg| msg.validator())
+                    .collect::<BTreeSet<_>>()
+                    .len() as u64
+            })
+            .unwrap_or(0);
+
+        Ok(TransitionStatus {
+            in_progress: following_address.is_some(),
+            actual_address,
+            following_address,
+            collected_signatures,
+        })
+    }
+
+    /// Same as `PublicApi::metrics`.
+    pub fn metrics(&self) -> String {
+        let current_height = CoreSchema::new(&self.snapshot).height();
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let actual_cfg = schema.actual_anchoring_config();
+
+        let actual_lect = schema.collect_lects(&actual_cfg);
+        let agreed_validators = actual_lect.as_ref().map_or(0, |lect| {
+            actual_cfg
+                .anchoring_keys
+                .iter()
+                .filter(|key| schema.lect(key).as_ref() == Some(lect))
+                .count()
+        });
+        let anchored_lect = actual_lect.and_then(|lect| match actual_cfg.tx_kind(lect) {
+            TxKind::Anchoring(tx) => Some(tx),
+            _ => None,
+        });
+        let anchored_height = anchored_lect.as_ref().map(|tx| tx.payload().block_height);
+        let lect_btc_height = anchored_lect.and_then(|lect| {
+            let height = lect.payload().block_height.0;
+            let chain_txid = schema.anchoring_tx_chain().get(&height).map(|tx| tx.id());
+            if chain_txid == Some(lect.id()) {
+                schema.anchoring_tx_btc_heights().get(&height)
+            } else {
+                None
+            }
+        });
+
+        let actual_address = actual_cfg.redeem_script().1;
+        let transition_in_progress = schema
+            .following_anchoring_config()
+            .map_or(false, |cfg| cfg.redeem_script().1 != actual_address);
+
+        let mut out = String::new();
+        write_gauge(
+            &mut out,
+            "anchoring_lect_agreed_validators",
+            "Number of validators whose current lect is the agreed one.",
+            agreed_validators as u64,
+        );
+        write_gauge(
+            &mut out,
+            "anchoring_transition_in_progress",
+            "Whether the anchoring chain is moving to the following address.",
+            transition_in_progress as u64,
+        );
+        if let Some(anchored_height) = anchored_height {
+            write_gauge(
+                &mut out,
+                "anchoring_anchored_height",
+                "Height of the latest Exonum block anchored by the agreed lect.",
+                anchored_height.0,
+            );
+            write_gauge(
+                &mut out,
+                "anchoring_blocks_since_last_anchor",
+                "Number of Exonum blocks committed after the latest anchored one.",
+                current_height.0.saturating_sub(anchored_height.0),
+            );
+        }
+        if let Some(lect_btc_height) = lect_btc_height {
+            write_gauge(
+                &mut out,
+                "anchoring_lect_btc_height",
+                "Height of the bitcoin block that contains the agreed lect.",
+                lect_btc_height,
+            );
+        }
+        out
+    }
+
+    /// Same as `PublicApi::actual_address`.
+    pub fn actual_address(&self) -> Result<btc::Address, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        Ok(schema.actual_anchoring_config().redeem_script().1)
+    }
+
+    /// Same as `PublicApi::following_address`.
+    pub fn following_address(&self) -> Result<Option<btc::Address>, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let following_addr = schema
+            .following_anchoring_config()
+            .map(|cfg| cfg.redeem_script().1);
+        Ok(following_addr)
+    }
+
+    /// Same as `PublicApi::redeem_scripts`.
+    pub fn redeem_scripts(&self) -> Result<RedeemScripts, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        Ok(RedeemScripts {
+            actual: schema.actual_anchoring_config().redeem_script().0,
+            following: schema
+                .following_anchoring_config()
+                .map(|cfg| cfg.redeem_script().0),
+        })
+    }
+
+    /// Same as `PublicApi::find_lect_by_txid`.
+    pub fn find_lect_by_txid(&self, txid: TxId) -> Result<Option<AnchoringInfo>, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let actual_cfg = schema.actual_anchoring_config();
+        match schema.known_txs().get(&txid) {
+            Some(tx) => Ok(Some(AnchoringInfo::try_from_lect(&actual_cfg, tx)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Same as `PublicApi::payload_by_txid`.
+    pub fn payload_by_txid(&self, txid: TxId) -> Result<PayloadInfo, ApiError> {
+        self.find_lect_by_txid(txid)?
+            .and_then(|info| info.payload)
+            .map(PayloadInfo::from)
+            .ok_or_else(|| error::Error::PayloadNotFound(txid).into())
+    }
+
+    /// Same as `PublicApi::prev_anchor`.
+    pub fn prev_anchor(&self, txid: TxId) -> Result<Option<AnchoringInfo>, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let actual_cfg = schema.actual_anchoring_config();
+        let known_txs = schema.known_txs();
+        let tx = match known_txs.get(&txid).map(|tx| actual_cfg.tx_kind(tx)) {
+            Some(TxKind::Anchoring(tx)) => tx,
+            _ => return Err(error::Error::PayloadNotFound(txid).into()),
+        };
+        let prev_txid = tx.payload().prev_tx_chain.unwrap_or_else(|| tx.prev_hash());
+        match known_txs.get(&prev_txid).map(|tx| actual_cfg.tx_kind(tx)) {
+            Some(TxKind::Anchoring(prev_tx)) => Ok(Some(prev_tx.into())),
+            _ => Ok(None),
+        }
+    }
+
+    /// Same as `PublicApi::verify_anchor`.
+    pub fn verify_anchor(&self, raw_tx_hex: &str) -> Result<AnchorVerification, ApiError> {
+        let tx = BitcoinTx::from_hex(raw_tx_hex).map_err(|_| error::Error::MalformedRawTx)?;
+        let txid = tx.id();
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let payload = match schema.actual_anchoring_config().tx_kind(tx) {
+            TxKind::Anchoring(tx) => Some(tx.payload()),
+            TxKind::FundingTx(_) | TxKind::Other(_) => None,
+        };
+
+        let core_schema = CoreSchema::new(&self.snapshot);
+        let expected_block_hash = payload
+            .as_ref()
+            .and_then(|payload| core_schema.block_hash_by_height(payload.block_height));
+        let valid = payload
+            .as_ref()
+            .map_or(false, |payload| Some(payload.block_hash) == expected_block_hash);
+        Ok(AnchorVerification {
+            txid,
+            payload: payload.map(PayloadInfo::from),
+            expected_block_hash,
+            valid,
+        })
+    }
+
+    /// Same as `PublicApi::classify_tx`.
+    pub fn classify_tx(&self, raw_tx_hex: &str) -> Result<TxClassification, ApiError> {
+        let tx = BitcoinTx::from_hex(raw_tx_hex).map_err(|_| error::Error::MalformedRawTx)?;
+        let txid = tx.id();
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let (kind, payload) = match schema.actual_anchoring_config().tx_kind(tx) {
+            TxKind::Anchoring(tx) => (TxClass::Anchoring, Some(PayloadInfo::from(tx.payload()))),
+            TxKind::FundingTx(_) => (TxClass::Funding, None),
+            TxKind::Other(_) => (TxClass::Other, None),
+        };
+        Ok(TxClassification {
+            txid,
+            kind,
+            payload,
+        })
+    }
+
+    /// Same as `PublicApi::raw_tx`.
+    pub fn raw_tx(&self, txid: TxId) -> Result<String, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        schema
+            .known_txs()
+            .get(&txid)
+            .map(|tx| tx.to_hex())
+            .ok_or_else(|| error::Error::TxNotFound(txid).into())
+    }
+
+    /// Same as `PublicApi::nearest_lect`.
+    pub fn nearest_lect(&self, height: u64) -> Result<Option<AnchoringTx>, ApiError> {
+        let anchoring_schema = AnchoringSchema::new(&self.snapshot);
+        Ok(anchoring_schema.nearest_anchoring_tx(height))
+    }
+
+    /// Same as `PublicApi::anchor_for_block`.
+    pub fn anchor_for_block(&self, block_hash: Hash) -> Result<Option<AnchoringTx>, ApiError> {
+        let block = CoreSchema::new(&self.snapshot)
+            .blocks()
+            .get(&block_hash)
+            .ok_or_else(|| ApiError::from(error::Error::BlockNotFound(block_hash)))?;
+        let anchoring_schema = AnchoringSchema::new(&self.snapshot);
+        Ok(anchoring_schema.nearest_anchoring_tx(block.height().0))
+    }
+
+    /// Same as `PublicApi::anchored_block_header_proof`.
+    pub fn anchored_block_header_proof(
+        &self,
+        height: u64,
+    ) -> Result<AnchoredBlockHeaderProof, ApiError> {
+        let anchoring_schema = AnchoringSchema::new(&self.snapshot);
+        let anchored_blocks = anchoring_schema.anchored_blocks();
+
+        let count = anchored_blocks.len();
+        if height >= count {
+            return Err(error::Error::NotEnoughBlocks { height, count }.into());
+        }
+        self.check_not_pruned(height)?;
+
+        let to_block_header = anchored_blocks.get_proof(height);
+        self.block_header_proof(&*self.snapshot, to_block_header, height)
+    }
+
+    /// Same as `PublicApi::anchored_block_header_proof_range`.
+    pub fn anchored_block_header_proof_range(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<AnchoredBlockHeaderProof, ApiError> {
+        let anchoring_schema = AnchoringSchema::new(&self.snapshot);
+        let anchored_blocks = anchoring_schema.anchored_blocks();
+
+        if from >= to || to - from > MAX_PROOF_RANGE_LEN {
+            return Err(error::Error::InvalidHeightRange { from, to }.into());
+        }
+        let count = anchored_blocks.len();
+        if to > count {
+            return Err(error::Error::NotEnoughBlocks {
+                height: to - 1,
+                count,
+            }.into());
+        }
+        self.check_not_pruned(from)?;
+
+        let to_block_header = anchored_blocks.get_range_proof(from, to);
+        self.block_header_proof(&*self.snapshot, to_block_header, to - 1)
+    }
+
+    /// Same as `PublicApi::anchored_block_header_proofs`.
+    pub fn anchored_block_header_proofs(
+        &self,
+        heights: &[u64],
+    ) -> Result<AnchoredBlockHeaderProofs, ApiError> {
+        if heights.is_empty() || heights.len() > MAX_PROOF_HEIGHTS_COUNT {
+            return Err(error::Error::InvalidHeightsCount(heights.len()).into());
+        }
+
+        let anchoring_schema = AnchoringSchema::new(&self.snapshot);
+        let anchored_blocks = anchoring_schema.anchored_blocks();
+        let count = anchored_blocks.len();
+        let mut to_block_headers = Vec::with_capacity(heights.len());
+        for &height in heights {
+            if height >= count {
+                return Err(error::Error::NotEnoughBlocks { height, count }.into());
+            }
+            self.check_not_pruned(height)?;
+            to_block_headers.push(BlockHeaderProof {
+                height,
+                to_block_header: anchored_blocks.get_proof(height),
+                anchor_depth: self.anchor_depth(height),
+            });
+        }
+
+        let (latest_authorized_block, to_table) = self.table_proof(&*self.snapshot)?;
+        Ok(AnchoredBlockHeaderProofs {
+            latest_authorized_block,
+            to_table,
+            to_block_headers,
+        })
+    }
+
+    /// Same as `PublicApi::pruning_info`.
+    pub fn pruning_info(&self) -> Result<PruningInfo, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        Ok(PruningInfo {
+            cutoff: schema.anchored_blocks_cutoff().get().unwrap_or(0),
+            retention: schema.actual_anchoring_config().anchored_blocks_retention,
+        })
+    }
+
+    /// Same as `PublicApi::observer_status`.
+    pub fn observer_status(&self) -> Result<ObserverStatus, ApiError> {
+        let reorg_depth = AnchoringSchema::new(&self.snapshot)
+            .observer_reorg_depth()
+            .get();
+        Ok(ObserverStatus {
+            manual_intervention_required: reorg_depth.is_some(),
+            reorg_depth,
+        })
+    }
+
+    // Returns an error if the hash of the anchored block at the given `height` is pruned.
+    fn check_not_pruned(&self, height: u64) -> Result<(), ApiError> {
+        let cutoff = AnchoringSchema::new(&self.snapshot)
+            .anchored_blocks_cutoff()
+            .get()
+            .unwrap_or(0);
+        if height < cutoff {
+            return Err(error::Error::Pruned { height, cutoff }.into());
+        }
+        Ok(())
+    }
+
+    /// Same as `PublicApi::diagnostics`.
+    pub fn diagnostics(&self) -> Result<Diagnostics, ApiError> {
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let mut errors = Vec::new();
+
+        let actual_lect = self.actual_lect().unwrap_or_else(|e| {
+            errors.push(e.to_string());
+            None
+        });
+        let actual_lect_confirmations = self.actual_lect_confirmations().unwrap_or_else(|e| {
+            errors.push(e.to_string());
+            None
+        });
+        let funding_balance = self.anchoring_balance().map(Some).unwrap_or_else(|e| {
+            errors.push(e.to_string());
+            None
+        });
+
+        let anchored_heights = schema.anchoring_tx_chain_heights();
+        let chain_length = anchored_heights.len();
+        let latest_anchored_heights = anchored_heights
+            .iter_from(chain_length.saturating_sub(DIAGNOSTICS_ANCHORED_HEIGHTS_COUNT as u64))
+            .collect::<Vec<_>>();
+        Ok(Diagnostics {
+            height: CoreSchema::new(&self.snapshot).height(),
+            actual_config: ConfigSummary::from(&schema.actual_anchoring_config()),
+            following_config: self.following_config()?.as_ref().map(ConfigSummary::from),
+            actual_lect,
+            actual_lect_confirmations,
+            funding_balance,
+            transition: self.transition_status()?,
+            chain_length,
+            latest_anchored_heights: latest_anchored_heights.into_iter().rev().collect(),
+            errors,
+        })
+    }
+
+    /// Same as `PublicApi::sync_status`.
+    pub fn sync_status(&self) -> Result<SyncStatus, ApiError> {
+        let relay = self.api.relay
+            .as_ref()
+            .ok_or(error::Error::RelayNotConfigured)?;
+        let lect_txid = self.actual_lect()?.map(|info| info.txid);
+
+        let last_status = self.api.last_sync_status.lock().unwrap().clone();
+        if let Some(ref status) = last_status {
+            if status.lect_txid == lect_txid
+                && unix_timestamp() < status.updated_at + self.api.sync_status_cache_timeout
+            {
+                return Ok(status.clone());
+            }
+        }
+
+        match fetch_sync_status(relay.as_ref(), lect_txid) {
+            Ok(status) => {
+                *self.api.last_sync_status.lock().unwrap() = Some(status.clone());
+                Ok(status)
+            }
+            Err(e) => {
+                warn!("Unable to get sync status from the bitcoind: {}", e);
+                let mut status = last_status
+                    .ok_or_else(|| error::Error::RelayUnavailable(e.to_string()))?;
+                status.stale = true;
+                Ok(status)
+            }
+        }
+    }
+
+    /// Same as `PublicApi::actual_lect_confirmations`.
+    pub fn actual_lect_confirmations(&self) -> Result<Option<LectConfirmations>, ApiError> {
+        let relay = self.api.relay
+            .as_ref()
+            .ok_or(error::Error::RelayNotConfigured)?;
+        let txid = match self.actual_lect()? {
+            Some(info) => info.txid,
+            None => return Ok(None),
+        };
+
+        let last_confirmations = self.api.last_confirmations.lock().unwrap().clone();
+        if let Some(ref confirmations) = last_confirmations {
+            if confirmations.txid == txid
+                && unix_timestamp() < confirmations.updated_at + CONFIRMATIONS_CACHE_TIMEOUT
+            {
+                return Ok(Some(confirmations.clone()));
+            }
+        }
+
+        let confirmations = relay
+            .get_transaction_info(txid)
+            .map_err(|e| error::Error::RelayUnavailable(e.to_string()))?
+            .map(|info| info.confirmations.unwrap_or(0));
+        let confirmations = LectConfirmations {
+            txid,
+            confirmations,
+            updated_at: unix_timestamp(),
+        };
+        *self.api.last_confirmations.lock().unwrap() = Some(confirmations.clone());
+        Ok(Some(confirmations))
+    }
+
+    /// Same as `PublicApi::anchoring_balance`.
+    pub fn anchoring_balance(&self) -> Result<AnchoringBalance, ApiError> {
+        let relay = self.api.relay
+            .as_ref()
+            .ok_or(error::Error::RelayNotConfigured)?;
+        let addresses = {
+            let schema = AnchoringSchema::new(&self.snapshot);
+            let mut addresses = vec![schema.actual_anchoring_config().redeem_script().1];
+            if let Some(cfg) = schema.following_anchoring_config() {
+                let following_address = cfg.redeem_script().1;
+                if following_address != addresses[0] {
+                    addresses.push(following_address);
+                }
+            }
+            addresses
+        };
+
+        let cached_balance = self.api
+            .last_balance
+            .lock()
+            .unwrap()
+            .clone()
+            .and_then(|balance| {
+                if balance.addresses == addresses {
+                    Some(balance)
+                } else {
+                    None
+                }
+            })
+            .map(|mut balance| {
+                balance.cached = true;
+                balance
+            });
+        if let Some(ref balance) = cached_balance {
+            if unix_timestamp() < balance.updated_at + BALANCE_CACHE_TIMEOUT {
+                return Ok(balance.clone());
+            }
+        }
+
+        match fetch_anchoring_balance(relay.as_ref(), addresses) {
+            Ok(balance) => {
+                *self.api.last_balance.lock().unwrap() = Some(balance.clone());
+                Ok(balance)
+            }
+            Err(e) => {
+                warn!("Unable to get anchoring balance from the bitcoind: {}", e);
+                cached_balance.ok_or_else(|| error::Error::RelayUnavailable(e.to_string()).into())
+            }
+        }
+    }
+
+    /// Same as `PublicApi::health`.
+    pub fn health(&self) -> Result<Health, ApiError> {
+        let rpc = self.api.relay
+            .as_ref()
+            .map(|relay| match relay.get_block_count() {
+                Ok(_) => true,
+                Err(e) => {
+                    warn!("Bitcoind is unreachable: {}", e);
+                    false
+                }
+            });
+        // The anchoring configuration cannot be read before the genesis block is created.
+        let height = match self.height() {
+            Some(height) => height,
+            None => {
+                let checks = HealthChecks {
+                    snapshot: false,
+                    rpc,
+                    funding: false,
+                    anchoring_lag: 0,
+                    lagging: false,
+                };
+                return Ok(Health {
+                    status: checks.state(),
+                    checks,
+                });
+            }
+        };
+
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let cfg = schema.actual_anchoring_config();
+        let addr = cfg.redeem_script().1;
+
+        let funding_tx = largest_unspent_funding_tx(&schema, &cfg, &addr)
+            .map(|(tx, out)| tx.output[out as usize].value)
+            .unwrap_or(0);
+        let (anchored_height, funds) = match schema.collect_lects(&cfg).map(|tx| cfg.tx_kind(tx)) {
+            Some(TxKind::Anchoring(lect)) => {
+                (lect.payload().block_height, lect.amount() + funding_tx)
+            }
+            _ => (Height::zero(), funding_tx),
+        };
+
+        let anchoring_lag = height.0.saturating_sub(anchored_height.0);
+        let max_anchoring_lag = self.api.max_anchoring_lag.unwrap_or(cfg.frequency * 2);
+        let checks = HealthChecks {
+            snapshot: true,
+            rpc,
+            funding: funds > cfg.fee,
+            anchoring_lag,
+            lagging: anchoring_lag > max_anchoring_lag,
+        };
+        Ok(Health {
+            status: checks.state(),
+            checks,
+        })
+    }
+
+    /// Same as `PublicApi::readiness`.
+    pub fn readiness(&self) -> Result<Readiness, ApiError> {
+        let checks = self.health()?.checks;
+        if !checks.snapshot {
+            return Ok(Readiness {
+                ready: false,
+                reasons: vec![NotReadyReason::SnapshotUnavailable],
+                checks,
+                observer_lag: None,
+            });
+        }
+
+        let schema = AnchoringSchema::new(&self.snapshot);
+        let height = CoreSchema::new(&self.snapshot).height();
+        let cfg = schema.actual_anchoring_config();
+        let max_anchoring_lag = self.api.max_anchoring_lag.unwrap_or(cfg.frequency * 2);
+        let observer_lag = schema
+            .anchoring_tx_chain()
+            .keys()
+            .last()
+            .map(|anchored_height| height.0.saturating_sub(anchored_height));
+
+        let mut reasons = Vec::new();
+        if checks.lagging {
+            reasons.push(NotReadyReason::Lagging);
+        }
+        if self.api.observer && observer_lag.map_or(true, |lag| lag > max_anchoring_lag) {
+            reasons.push(NotReadyReason::ObserverLagging);
+        }
+        if schema.observer_reorg_depth().exists() {
+            reasons.push(NotReadyReason::ManualInterventionRequired);
+        }
+        if checks.rpc == Some(false) {
+            reasons.push(NotReadyReason::RpcUnreachable);
+        }
+        Ok(Readiness {
+            ready: reasons.is_empty(),
+            reasons,
+            checks,
+            observer_lag,
+        })
+    }
+
+    /// Same as `PublicApi::time_to_next_anchor`.
+    pub fn time_to_next_anchor(&self) -> Result<NextAnchorEta, ApiError> {
+        let height = CoreSchema::new(&self.snapshot).height();
+        let cfg = AnchoringSchema::new(&self.snapshot).actual_anchoring_config();
+
+        let next_anchoring_height = Height(cfg.latest_anchoring_height(height).0 + cfg.frequency);
+        let blocks_remaining = next_anchoring_height.0 - height.0;
+        Ok(NextAnchorEta {
+            height,
+            next_anchoring_height,
+            blocks_remaining,
+            seconds_remaining: blocks_remaining * self.api.average_block_time / 1000,
+        })
+    }
+
+    /// Same as `PublicApi::next_anchoring_tx_preview`.
+    pub fn next_anchoring_tx_preview(&self) -> Result<Option<AnchoringTxPreview>, ApiError> {
+        let core_schema = CoreSchema::new(&self.snapshot);
+        let schema = AnchoringSchema::new(&self.snapshot);
+        if schema.following_anchoring_config().is_some() {
+            return Ok(None);
+        }
+
+        let cfg = schema.actual_anchoring_config();
+        let (redeem_script, addr) = cfg.redeem_script();
+        let height = cfg.latest_anchoring_height(core_schema.height());
+        let hash = core_schema
+            .block_hashes_by_height()
+            .get(height.0)
+            .unwrap();
+        let funds = largest_unspent_funding_tx(&schema, &cfg, &addr);
+
+        let builder = match schema.collect_lects(&cfg).map(|tx| cfg.tx_kind(tx)) {
+            Some(TxKind::Anchoring(lect)) => {
+                if lect.payload().block_height >= height {
+                    return Ok(None);
+                }
+                let builder = TransactionBuilder::with_prev_tx(&lect, 0);
+                match funds {
+                    Some((tx, out)) => builder.add_funds(&tx, out),
+                    None => builder,
+                }
+            }
+            Some(TxKind::FundingTx(_)) => match funds {
+                Some((tx, out)) => TransactionBuilder::with_prev_tx(&tx, out),
+                None => return Ok(None),
+            },
+            Some(TxKind::Other(tx)) => return Err(error::Error::MalformedLect(tx.id()).into()),
+            None => return Ok(None),
+        };
+        let builder = builder
+            .payload(height, hash)
+            .payload_prefix(cfg.payload_prefix.as_bytes())
+            .table_root(schema.anchored_table_root(height))
+            .anchor_output(cfg.anchor_output())
+            .size_limit(&redeem_script, cfg.majority_count() as usize, cfg.max_tx_vsize)
+            .send_to(addr.clone());
+
+        let draft = builder.clone().fee(0).into_transaction().map_err(error::Error::from)?;
+        let vsize = estimate_vsize(&draft.0, &redeem_script, cfg.majority_count() as usize);
+        let fee = match cfg.fee_strategy {
+            FeeStrategyConfig::Static => cfg.fee,
+            FeeStrategyConfig::EstimateSmartFee { .. } => {
+                let fee_rate = schema
+                    .agreed_fee_rate(&cfg)
+                    .ok_or(error::Error::FeeRateNotAgreed)?;
+                vsize * fee_rate / 1000
+            }
+        };
+        let tx = builder.fee(fee).into_transaction().map_err(error::Error::from)?;
+
+        Ok(Some(AnchoringTxPreview {
+            txid: tx.id(),
+            inputs: tx.input.iter().map(|input| TxId::from(input.prev_hash)).collect(),
+            address: addr,
+            amount: tx.amount(),
+            fee,
+            payload: tx.payload(),
+            tx,
+        }))
+    }
+
+    fn following_config_with_height(&self) -> Option<(AnchoringConfig, Height)> {
+        let actual_from = CoreSchema::new(&self.snapshot).following_configuration()?.actual_from;
+        let schema = AnchoringSchema::new(&self.snapshot);
+        schema
+            .following_anchoring_config()
+            .map(|cfg| (cfg, actual_from))
+    }
+
+    /// Returns the proof for the blocks up to the given `max_height`.
+    fn block_header_proof(
+        &self,
+        view: &Snapshot,
+        to_block_header: ListProof<Hash>,
+        max_height: u64,
+    ) -> Result<AnchoredBlockHeaderProof, ApiError> {
+        let (latest_authorized_block, to_table) = self.table_proof(view)?;
+        Ok(AnchoredBlockHeaderProof {
+            latest_authorized_block,
+            to_table,
+            to_block_header,
+            anchor_depth: self.anchor_depth(max_height),
+        })
+    }
+
+    /// Returns the depth of the anchoring transaction for the nearest anchored block
+    /// with a height greater or equal than the given one.
+    fn anchor_depth(&self, height: u64) -> Option<AnchorDepth> {
+        let txid = AnchoringSchema::new(&self.snapshot)
+            .nearest_anchoring_tx(height)?
+            .id();
+
+        let now = unix_timestamp();
+        let cached_depth = self.api.anchor_depths.lock().unwrap().get(&txid).cloned();
+        if let Some(depth) = cached_depth {
+            if now < depth.updated_at + ANCHOR_DEPTH_CACHE_TIMEOUT {
+                return Some(depth);
+            }
+        }
+
+        let confirmations = self.api.relay.as_ref().and_then(|relay| {
+            match relay.get_transaction_info(txid) {
+                Ok(info) => Some(info.and_then(|info| info.confirmations).unwrap_or(0)),
+                Err(e) => {
+                    warn!("Unable to get the anchor depth from the bitcoind: {}", e);
+                    None
+                }
+            }
+        });
+        let depth = AnchorDepth {
+            txid,
+            confirmations,
+            updated_at: now,
+        };
+        if confirmations.is_some() {
+            let mut anchor_depths = self.api.anchor_depths.lock().unwrap();
+            anchor_depths.retain(|_, cached| now < cached.updated_at + ANCHOR_DEPTH_CACHE_TIMEOUT);
+            anchor_depths.insert(txid, depth.clone());
+        }
+        Some(depth)
+    }
+
+    /// Returns the latest authorized block along with the proof for the anchored blocks table.
+    fn table_proof(&self, view: &Snapshot) -> Result<(BlockProof, MapProof<Hash, Hash>), ApiError> {
+        let core_schema = CoreSchema::new(view);
+        let count = core_schema.block_hashes_by_height().len();
+
+        let latest_authorized_block = count
+            .checked_sub(1)
+            .and_then(|max_height| core_schema.block_and_precommits(Height(max_height)))
+            .ok_or(error::Error::NotEnoughBlocks { height: 0, count })?;
+        let to_table: MapProof<Hash, Hash> =
+            core_schema.get_proof_to_service_table(ANCHORING_SERVICE_ID, 0);
+        Ok((latest_authorized_block, to_table))
+    }
+}
+
+/// Serializes the anchoring configuration along with the values derived from it.
+fn config_to_json(cfg: &AnchoringConfig) -> Value {
+    let (redeem_script, address) = cfg.redeem_script();
+    let mut value = json!(cfg);
+    if let Some(object) = value.as_object_mut() {
+        object.insert("majority_count".to_owned(), json!(cfg.majority_count()));
+        object.insert("redeem_script".to_owned(), json!(redeem_script));
+        object.insert("address".to_owned(), json!(address));
+    }
+    value
+}
+
+/// Returns the largest funding transaction from `cfg` or the committed top-ups that has not
+/// been spent by the anchoring chain along with its output to the given `addr`.
+fn largest_unspent_funding_tx<T: AsRef<Snapshot>>(
+    schema: &AnchoringSchema<T>,
+    cfg: &AnchoringConfig,
+    addr: &btc::Address,
+) -> Option<(FundingTx, u32)> {
+    schema
+        .funding_txs(cfg)
+        .into_iter()
+        .filter(|tx| !schema.funding_tx_is_spent(&tx.id()))
+        .filter_map(|tx| tx.find_out(addr).map(|out| (tx, out)))
+        .max_by_key(|&(ref tx, out)| tx.output[out as usize].value)
+}
+
+fn fetch_sync_status(relay: &BitcoinRelay, lect_txid: Option<TxId>) -> RpcResult<SyncStatus> {
+    let bitcoin_height = relay.get_block_count()?;
+    let confirmations = match lect_txid {
+        Some(txid) => relay.get_transaction_confirmations(txid)?.unwrap_or(0),
+        None => 0,
+    };
+    let lect_height = if confirmations > 0 {
+        Some((bitcoin_height + 1).saturating_sub(confirmations))
+    } else {
+        None
+    };
+    Ok(SyncStatus {
+        bitcoin_height,
+        lect_txid,
+        lect_height,
+        confirmations,
+        updated_at: unix_timestamp(),
+        stale: false,
+    })
+}
+
+fn fetch_anchoring_balance(
+    relay: &BitcoinRelay,
+    addresses: Vec<btc::Address>,
+) -> RpcResult<AnchoringBalance> {
+    let mut txids = HashSet::new();
+    let mut total = 0;
+    let mut utxo_count = 0;
+    for address in &addresses {
+        let script_pubkey = address.script_pubkey();
+        for info in relay.unspent_transactions(address)? {
+            if !txids.insert(info.body.id()) {
+                continue;
+            }
+            for output in &info.body.output {
+                if output.script_pubkey == script_pubkey {
+                    total += output.value;
+                    utxo_count += 1;
+                }
+            }
+        }
+    }
+    Ok(AnchoringBalance {
+        addresses,
+        total,
+        utxo_count,
+        updated_at: unix_timestamp(),
+        cached: false,
+    })
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn write_gauge(out: &mut String, name: &str, help: &str, value: u64) {
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} gauge", name).unwrap();
+    writeln!(out, "{} {}", name, value).unwrap();
+}
+
+impl Api for PublicApi {
+    fn wire(&self, router: &mut Router) {
+        let api = self.clone();
+        let actual_lect = move |req: &mut Request| -> IronResult<Response> {
+            let snapshot = api.request_snapshot(req);
+            let lect = snapshot.actual_lect()?;
+            cached_response(req, &snapshot.lect_etag(), &json!(lect))
+        };
+
+        let api = self.clone();
+        let current_lect_of_validator = move |req: &mut Request| -> IronResult<Response> {
+            let id = api.url_fragment(req, "id")?;
+            let snapshot = api.request_snapshot(req);
+            let info = snapshot.current_lect_of_validator(id)?;
+            cached_response(req, &snapshot.lect_etag(), &json!(info))
+        };
+
+        let api = self.clone();
+        let lect_agreement = move |req: &mut Request| -> IronResult<Response> {
+            let agreement = api.request_snapshot(req).lect_agreement()?;
+            api.ok_response(&json!(agreement))
+        };
+
+        let api = self.clone();
+        let actual_lect_confirmations = move |req: &mut Request| -> IronResult<Response> {
+            let confirmations = api.request_snapshot(req).actual_lect_confirmations()?;
+            api.ok_response(&json!(confirmations))
+        };
+
+        let api = self.clone();
+        let validators = move |req: &mut Request| -> IronResult<Response> {
+            let validators = api.request_snapshot(req).validators()?;
+            api.ok_response(&json!(validators))
+        };
+
+        let api = self.clone();
+        let validator_lect_depths = move |req: &mut Request| -> IronResult<Response> {
+            let depths = api.request_snapshot(req).validator_lect_depths()?;
+            api.ok_response(&json!(depths))
+        };
+
+        let api = self.clone();
+        let actual_funding_tx = move |req: &mut Request| -> IronResult<Response> {
+            let info = api.request_snapshot(req).actual_funding_tx()?;
+            api.ok_response(&json!(info))
+        };
+
+        let api = self.clone();
+        let actual_funding_txs = move |req: &mut Request| -> IronResult<Response> {
+            let infos = api.request_snapshot(req).actual_funding_txs()?;
+            api.ok_response(&json!(infos))
+        };
+
+        let api = self.clone();
+        let lects_of_validator = move |req: &mut Request| -> IronResult<Response> {
+            let id = api.url_fragment(req, "id")?;
+            let from = api.optional_param(req, "from")?.unwrap_or(0);
+            let count = api.optional_param(req, "count")?.unwrap_or(MAX_LECTS_COUNT);
+            let info = api.request_snapshot(req).lects_of_validator(id, from, count)?;
+            api.ok_response(&json!(info))
+        };
+
+        let api = self.clone();
+        let anchored_blocks = move |req: &mut Request| -> IronResult<Response> {
+            let from = api.optional_param(req, "from")?.unwrap_or(0);
+            let count = api.optional_param(req, "count")?
+                .unwrap_or(MAX_ANCHORED_BLOCKS_COUNT);
+            let info = api.request_snapshot(req).anchored_blocks(from, count)?;
+            api.ok_response(&json!(info))
+        };
+
+        let api = self.clone();
+        let height_map = move |req: &mut Request| -> IronResult<Response> {
+            let from = api.optional_param(req, "from")?.unwrap_or(0);
+            let count = api.optional_param(req, "count")?
+                .unwrap_or(MAX_HEIGHT_MAP_COUNT);
+            let entries = api.request_snapshot(req).height_map(from, count)?;
+            api.ok_response(&json!(entries))
+        };
+
+        let api = self.clone();
+        let anchor_intervals = move |req: &mut Request| -> IronResult<Response> {
+            let count = api.optional_param(req, "count")?
+                .unwrap_or(MAX_ANCHOR_INTERVALS_COUNT);
+            let intervals = api.request_snapshot(req).anchor_intervals(count)?;
+            api.ok_response(&json!(intervals))
+        };
+
+        let api = self.clone();
+        let actual_config = move |req: &mut Request| -> IronResult<Response> {
+            let snapshot = api.request_snapshot(req);
+            let cfg = snapshot.actual_config()?;
+            cached_response(req, &snapshot.config_etag(), &config_to_json(&cfg))
+        };
+
+        let api = self.clone();
+        let following_config = move |req: &mut Request| -> IronResult<Response> {
+            let snapshot = api.request_snapshot(req);
+            let cfg = snapshot.following_config_with_height().map(|(cfg, actual_from)| {
+                let mut value = config_to_json(&cfg);
+                value["actual_from"] = json!(actual_from);
+                value
+            });
+            cached_response(req, &snapshot.config_etag(), &json!(cfg))
+        };
+
+        let api = self.clone();
+        let config_history = move |req: &mut Request| -> IronResult<Response> {
+            let snapshot = api.request_snapshot(req);
+            let history = snapshot.config_history()?;
+            cached_response(req, &snapshot.config_etag(), &json!(history))
+        };
+
+        let api = self.clone();
+        let transition_status = move |req: &mut Request| -> IronResult<Response> {
+            let status = api.request_snapshot(req).transition_status()?;
+            api.ok_response(&json!(status))
+        };
+
+        let api = self.clone();
+        let metrics = move |req: &mut Request| -> IronResult<Response> {
+            let body = api.request_snapshot(req).metrics();
+            Ok(Response::with((
+                status::Ok,
+                Header(ContentType::plaintext()),
+                body,
+            )))
+        };
+
+        let api = self.clone();
+        let actual_address = move |req: &mut Request| -> IronResult<Response> {
+            let snapshot = api.request_snapshot(req);
+            let addr = snapshot.actual_address()?.to_string();
+            cached_response(req, &snapshot.config_etag(), &json!(addr))
+        };
+
+        let api = self.clone();
+        let following_address = move |req: &mut Request| -> IronResult<Response> {
+            let snapshot = api.request_snapshot(req);
+            let addr = snapshot.following_address()?.map(|addr| addr.to_string());
+            cached_response(req, &snapshot.config_etag(), &json!(addr))
+        };
+
+        let api = self.clone();
+        let redeem_scripts = move |req: &mut Request| -> IronResult<Response> {
+            let snapshot = api.request_snapshot(req);
+            let redeem_scripts = snapshot.redeem_scripts()?;
+            cached_response(req, &snapshot.config_etag(), &json!(redeem_scripts))
+        };
+
+        let api = self.clone();
+        let export_chain = move |_: &mut Request| -> IronResult<Response> {
+            let body: Box<WriteBody> = Box::new(api.export_chain());
+            Ok(Response::with((
+                status::Ok,
+                Header(ContentType::json()),
+                body,
+            )))
+        };
+
+        let api = self.clone();
+        let nearest_lect = move |req: &mut Request| -> IronResult<Response> {
+            let height = api.url_fragment(req, "height")?;
+            let lect = api.request_snapshot(req).nearest_lect(height)?;
+            api.ok_response(&json!(lect))
+        };
+
+        let api = self.clone();
+        let anchor_for_block = move |req: &mut Request| -> IronResult<Response> {
+            let hash: String = api.url_fragment(req, "hash")?;
+            let hash = Hash::from_hex(&hash)
+                .map_err(|_| ApiError::from(error::Error::InvalidBlockHash(hash.clone())))?;
+            let lect = api.request_snapshot(req).anchor_for_block(hash)?;
+            api.ok_response(&json!(lect))
+        };
+
+        let api = self.clone();
+        let lect_by_txid = move |req: &mut Request| -> IronResult<Response> {
+            let txid: String = api.url_fragment(req, "txid")?;
+            let txid = TxId::from_hex(&txid)
+                .map_err(|_| ApiError::from(error::Error::InvalidTxId(txid.clone())))?;
+            let info = api.request_snapshot(req).find_lect_by_txid(txid)?;
+            api.ok_response(&json!(info))
+        };
+
+        let api = self.clone();
+        let payload_by_txid = move |req: &mut Request| -> IronResult<Response> {
+            let txid: String = api.url_fragment(req, "txid")?;
+            let txid = TxId::from_hex(&txid)
+                .map_err(|_| ApiError::from(error::Error::InvalidTxId(txid.clone())))?;
+            let payload = api.request_snapshot(req).payload_by_txid(txid)?;
+            api.ok_response(&json!(payload))
+        };
+
+        let api = self.clone();
+        let prev_anchor = move |req: &mut Request| -> IronResult<Response> {
+            let txid: String = api.url_fragment(req, "txid")?;
+            let txid = TxId::from_hex(&txid)
+                .map_err(|_| ApiError::from(error::Error::InvalidTxId(txid.clone())))?;
+            let info = api.request_snapshot(req).prev_anchor(txid)?;
+            api.ok_response(&json!(info))
+        };
+
+        let api = self.clone();
+        let raw_tx = move |req: &mut Request| -> IronResult<Response> {
+            let txid: String = api.url_fragment(req, "txid")?;
+            let txid = TxId::from_hex(&txid)
+                .map_err(|_| ApiError::from(error::Error::InvalidTxId(txid.clone())))?;
+            let hex = api.request_snapshot(req).raw_tx(txid)?;
+            api.ok_response(&json!(hex))
+        };
+
+        let api = self.clone();
         let anchored_block_header_proof = move |req: &mut Request| -> IronResult<Response> {
             let height = api.url_fragment(req, "height")?;
-            let proof = api.anchored_block_header_proof(height);
+            let proof = api.request_snapshot(req).anchored_block_header_proof(height)?;
+            if accepts_cbor(req) {
+                cbor_response(&json!(proof))
+            } else {
+                api.ok_response(&json!(proof))
+            }
+        };
+
+        let api = self.clone();
+        let pruning_info = move |req: &mut Request| -> IronResult<Response> {
+            let info = api.request_snapshot(req).pruning_info()?;
+            api.ok_response(&json!(info))
+        };
+
+        let api = self.clone();
+        let observer_status = move |req: &mut Request| -> IronResult<Response> {
+            let status = api.request_snapshot(req).observer_status()?;
+            api.ok_response(&json!(status))
+        };
+
+        let api = self.clone();
+        let sync_status = move |req: &mut Request| -> IronResult<Response> {
+            let status = api.request_snapshot(req).sync_status()?;
+            api.ok_response(&json!(status))
+        };
+
+        let api = self.clone();
+        let watchdog_status = move |_: &mut Request| -> IronResult<Response> {
+            let status = api.watchdog_status()?;
+            api.ok_response(&json!(status))
+        };
+
+        let api = self.clone();
+        let next_anchoring_tx = move |req: &mut Request| -> IronResult<Response> {
+            let preview = api.request_snapshot(req).next_anchoring_tx_preview()?;
+            api.ok_response(&json!(preview))
+        };
+
+        let api = self.clone();
+        let anchoring_balance = move |req: &mut Request| -> IronResult<Response> {
+            let balance = api.request_snapshot(req).anchoring_balance()?;
+            api.ok_response(&json!(balance))
+        };
+
+        let api = self.clone();
+        let funding_history = move |req: &mut Request| -> IronResult<Response> {
+            let history = api.request_snapshot(req).funding_history()?;
+            api.ok_response(&json!(history))
+        };
+
+        let api = self.clone();
+        let diagnostics = move |req: &mut Request| -> IronResult<Response> {
+            let diagnostics = api.request_snapshot(req).diagnostics()?;
+            api.ok_response(&json!(diagnostics))
+        };
+
+        let api = self.clone();
+        let health = move |req: &mut Request| -> IronResult<Response> {
+            let health = api.request_snapshot(req).health()?;
+            let code = if health.status == HealthState::Down {
+                status::ServiceUnavailable
+            } else {
+                status::Ok
+            };
+            Ok(Response::with((
+                code,
+                Header(ContentType::json()),
+                json!(health).to_string(),
+            )))
+        };
+
+        let api = self.clone();
+        let readiness = move |req: &mut Request| -> IronResult<Response> {
+            let readiness = api.request_snapshot(req).readiness()?;
+            let code = if readiness.ready {
+                status::Ok
+            } else {
+                status::ServiceUnavailable
+            };
+            Ok(Response::with((
+                code,
+                Header(ContentType::json()),
+                json!(readiness).to_string(),
+            )))
+        };
+
+        let api = self.clone();
+        let schema = move |_: &mut Request| -> IronResult<Response> {
+            api.ok_response(&api.response_schema())
+        };
+
+        let api = self.clone();
+        let next_anchor_eta = move |req: &mut Request| -> IronResult<Response> {
+            let eta = api.request_snapshot(req).time_to_next_anchor()?;
+            api.ok_response(&json!(eta))
+        };
+
+        let api = self.clone();
+        let block_header_proof_range = move |req: &mut Request| -> IronResult<Response> {
+            let from = api.required_param(req, "from")?;
+            let to = api.required_param(req, "to")?;
+            let proof = api.request_snapshot(req).anchored_block_header_proof_range(from, to)?;
             api.ok_response(&json!(proof))
         };
 
+        let api = self.clone();
+        let verify_anchor = move |req: &mut Request| -> IronResult<Response> {
+            let request: AnchorToVerify = api.parse_body(req)?;
+            let verification = api.request_snapshot(req).verify_anchor(&request.tx)?;
+            api.ok_response(&json!(verification))
+        };
+
+        let api = self.clone();
+        let classify_tx = move |req: &mut Request| -> IronResult<Response> {
+            let request: TxToClassify = api.parse_body(req)?;
+            let classification = api.request_snapshot(req).classify_tx(&request.tx)?;
+            api.ok_response(&json!(classification))
+        };
+
+        let api = self.clone();
+        let block_header_proofs = move |req: &mut Request| -> IronResult<Response> {
+            let request: ProofHeights = api.parse_body(req)?;
+            let proofs = api.request_snapshot(req).anchored_block_header_proofs(&request.heights)?;
+            api.ok_response(&json!(proofs))
+        };
+
         router.get("/v1/address/actual", actual_address, "actual_address");
         router.get(
             "/v1/address/following",
             following_address,
             "following_address",
         );
+        router.get("/v1/redeem_script", redeem_scripts, "redeem_scripts");
+        router.get("/v1/export", export_chain, "export_chain");
+        router.get("/v1/config/actual", actual_config, "actual_config");
+        router.get(
+            "/v1/config/following",
+            following_config,
+            "following_config",
+        );
+        router.get("/v1/config/history", config_history, "config_history");
+        router.get("/v1/transition", transition_status, "transition_status");
+        router.get("/v1/metrics", metrics, "metrics");
+        router.get("/v1/sync_status", sync_status, "sync_status");
+        router.get("/v1/observer/status", observer_status, "observer_status");
+        router.get("/v1/watchdog", watchdog_status, "watchdog_status");
+        router.get("/v1/next_anchoring_tx", next_anchoring_tx, "next_anchoring_tx");
+        router.get("/v1/next_anchor_eta", next_anchor_eta, "next_anchor_eta");
+        router.get("/v1/funding/balance", anchoring_balance, "anchoring_balance");
+        router.get("/v1/funding/history", funding_history, "funding_history");
+        router.get("/v1/validators", validators, "validators");
+        router.get(
+            "/v1/validators/depths",
+            validator_lect_depths,
+            "validator_lect_depths",
+        );
+        router.get("/v1/health", health, "health");
+        router.get("/v1/ready", readiness, "readiness");
+        router.get("/v1/schema", schema, "schema");
+        router.get("/v1/diagnostics", diagnostics, "diagnostics");
         router.get("/v1/actual_lect/", actual_lect, "actual_lect");
+        router.get(
+            "/v1/actual_lect/agreement",
+            lect_agreement,
+            "lect_agreement",
+        );
+        router.get(
+            "/v1/actual_lect/confirmations",
+            actual_lect_confirmations,
+            "actual_lect_confirmations",
+        );
         router.get(
             "/v1/actual_lect/:id",
             current_lect_of_validator,
             "current_lect_of_validator",
         );
+        router.get(
+            "/v1/funding_tx/actual",
+            actual_funding_tx,
+            "actual_funding_tx",
+        );
+        router.get(
+            "/v1/funding_txs/actual",
+            actual_funding_txs,
+            "actual_funding_txs",
+        );
+        router.get("/v1/lects/:id", lects_of_validator, "lects_of_validator");
         router.get("/v1/nearest_lect/:height", nearest_lect, "nearest_lect");
+        router.get(
+            "/v1/anchor_for_block/:hash",
+            anchor_for_block,
+            "anchor_for_block",
+        );
+        router.get("/v1/lect_by_txid/:txid", lect_by_txid, "lect_by_txid");
+        router.get("/v1/payload/:txid", payload_by_txid, "payload_by_txid");
+        router.get("/v1/prev_anchor/:txid", prev_anchor, "prev_anchor");
+        router.get("/v1/tx/:txid/raw", raw_tx, "raw_tx");
+        router.get("/v1/anchored_blocks", anchored_blocks, "anchored_blocks");
+        router.get("/v1/height_map", height_map, "height_map");
+        router.get("/v1/anchor_intervals", anchor_intervals, "anchor_intervals");
+        router.get(
+            "/v1/block_header_proof",
+            block_header_proof_range,
+            "anchored_block_header_proof_range",
+        );
+        router.get(
+            "/v1/block_header_proof/cutoff",
+            pruning_info,
+            "pruning_info",
+        );
         router.get(
             "/v1/block_header_proof/:height",
             anchored_block_header_proof,
             "anchored_block_header_proof",
         );
+        router.post(
+            "/v1/block_header_proofs",
+            block_header_proofs,
+            "anchored_block_header_proofs",
+        );
+        router.post("/v1/verify_anchor", verify_anchor, "verify_anchor");
+        router.post("/v1/classify", classify_tx, "classify_tx");
     }
 }
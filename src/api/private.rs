@@ -0,0 +1,452 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Anchoring private API implementation.
+
+use std::collections::BTreeMap;
+
+use iron::prelude::*;
+use router::Router;
+
+use exonum::api::{Api, ApiError};
+use exonum::blockchain::{Blockchain, Schema as CoreSchema};
+use exonum::crypto::{PublicKey, SecretKey};
+use exonum::encoding::serialize::{encode_hex, FromHex};
+use exonum::helpers::{Height, ValidatorId};
+use exonum::node::{ApiSender, TransactionSend};
+use exonum::storage::Snapshot;
+
+use blockchain::consensus_storage::AnchoringConfig;
+use blockchain::dto::{MsgAnchoringAnchorRequest, MsgAnchoringSignature};
+use blockchain::schema::AnchoringSchema;
+use details::btc::psbt::{PartialSignature, Psbt};
+use details::btc::transactions::{AnchoringTx, RawBitcoinTx, TransactionBuilder, TxKind};
+use details::btc::TxId;
+
+use super::{error, largest_unspent_funding_tx};
+
+/// Private API implementation.
+///
+/// The methods are intended for the node administrators, so they are mounted
+/// on the private API listener only.
+#[derive(Debug, Clone)]
+pub struct PrivateApi {
+    /// Exonum blockchain instance.
+    pub blockchain: Blockchain,
+    /// Channel that sends the anchor requests and the signatures imported from the PSBTs
+    /// on behalf of the node.
+    pub node_channel: Option<NodeChannel>,
+}
+
+/// Channel to the node along with its service keys.
+#[derive(Debug, Clone)]
+pub struct NodeChannel {
+    /// Sender of the transactions to the node.
+    pub sender: ApiSender,
+    /// Service public key of the node.
+    pub public_key: PublicKey,
+    /// Service secret key of the node.
+    pub secret_key: SecretKey,
+}
+
+/// Signature of the anchoring transaction input.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InputSignature {
+    /// Index of the signed input.
+    pub input: u32,
+    /// Signature in hex.
+    pub signature: String,
+}
+
+/// Signatures of the anchoring transaction inputs made by the validator.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ValidatorSignatures {
+    /// Identifier of the validator.
+    pub validator: u32,
+    /// Signatures of the inputs ordered by the input index.
+    pub inputs: Vec<InputSignature>,
+}
+
+/// Request to anchor the block out of the anchoring schedule.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct AnchorNowRequest {
+    /// Height of the block to anchor, the latest committed block is anchored if it is absent.
+    #[serde(default)]
+    pub height: Option<u64>,
+}
+
+/// Anchoring scheduled out of the anchoring schedule.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScheduledAnchor {
+    /// Height of the block to anchor.
+    pub height: u64,
+    /// Height of the latest anchored block.
+    pub anchored_height: Option<u64>,
+}
+
+/// Signatures collected for the anchoring transaction.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct CollectedSignatures {
+    /// Unsigned anchoring transaction or `None` if there are no signatures for it.
+    pub tx: Option<AnchoringTx>,
+    /// Signatures grouped by the validators that made them.
+    pub validators: Vec<ValidatorSignatures>,
+    /// Number of validators that should sign all the inputs to finalize the transaction.
+    pub majority_count: u8,
+    /// Number of validators whose signatures are still required.
+    pub remaining: u8,
+}
+
+/// Partially signed anchoring transaction (BIP174).
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct AnchoringPsbt {
+    /// Unsigned anchoring transaction.
+    pub tx: AnchoringTx,
+    /// Base64-encoded PSBT with the collected signatures.
+    pub psbt: String,
+}
+
+/// Request to import the signatures of the node from the PSBT signed by the external tools.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PsbtImportRequest {
+    /// Base64-encoded PSBT.
+    pub psbt: String,
+}
+
+/// Signatures of the node imported from the PSBT.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImportedSignatures {
+    /// Identifier of the validator.
+    pub validator: u32,
+    /// Indices of the inputs whose signatures are sent to the anchoring round.
+    pub inputs: Vec<u32>,
+}
+
+impl PrivateApi {
+    /// Creates private API for the given `blockchain`.
+    pub fn new(blockchain: Blockchain) -> PrivateApi {
+        PrivateApi {
+            blockchain,
+            node_channel: None,
+        }
+    }
+
+    /// Sets the channel that sends the anchor requests and the signatures imported
+    /// from the PSBTs on behalf of the node with the given service keys.
+    pub fn with_node_channel(
+        mut self,
+        sender: ApiSender,
+        public_key: PublicKey,
+        secret_key: SecretKey,
+    ) -> PrivateApi {
+        self.node_channel = Some(NodeChannel {
+            sender,
+            public_key,
+            secret_key,
+        });
+        self
+    }
+
+    /// Creates an iron handler with the wired routes.
+    pub fn handler(&self) -> Chain {
+        let mut router = Router::new();
+        self.wire(&mut router);
+        Chain::new(router)
+    }
+
+    /// Returns the signatures collected for the anchoring transaction with the given
+    /// normalized `txid` and the number of validators that have not signed it yet.
+    ///
+    /// `GET /{api_prefix}/v1/signatures/:txid`
+    pub fn signatures(&self, txid: TxId) -> Result<CollectedSignatures, ApiError> {
+        let snapshot = self.blockchain.snapshot();
+        let schema = AnchoringSchema::new(&snapshot);
+        let majority_count = schema.actual_anchoring_config().majority_count();
+
+        let mut tx = None;
+        let mut signatures = BTreeMap::new();
+        for msg in schema.signatures(&txid).iter() {
+            signatures
+                .entry(msg.validator().0 as u32)
+                .or_insert_with(BTreeMap::new)
+                .insert(msg.input(), encode_hex(msg.signature()));
+            tx = Some(msg.tx());
+        }
+
+        let inputs_count = tx.as_ref().map_or(0, |tx| tx.input.len());
+        let signed_count = signatures
+            .values()
+            .filter(|inputs| inputs.len() == inputs_count)
+            .count();
+        let validators = signatures
+            .into_iter()
+            .map(|(validator, inputs)| ValidatorSignatures {
+                validator,
+                inputs: inputs
+                    .into_iter()
+                    .map(|(input, signature)| InputSignature { input, signature })
+                    .collect(),
+            })
+            .collect();
+        Ok(CollectedSignatures {
+            tx,
+            validators,
+            majority_count,
+            remaining: majority_count.saturating_sub(signed_count as u8),
+        })
+    }
+
+    /// Schedules the anchoring of the block at the requested height, or the latest committed
+    /// block by default, without waiting for the next anchoring height.
+    ///
+    /// The request is sent to the blockchain on behalf of the node if the funds suffice
+    /// to pay the fee. The anchoring transaction is built once +2/3 of the validators
+    /// have requested the block at this height or higher. The anchored heights keep growing,
+    /// so the block that is lower than the anchored one cannot be requested, and the regular
+    /// anchoring continues from the next anchoring height.
+    ///
+    /// `POST /{api_prefix}/v1/anchor_now`
+    pub fn anchor_now(&self, request: &AnchorNowRequest) -> Result<ScheduledAnchor, ApiError> {
+        let snapshot = self.blockchain.snapshot();
+        let core_schema = CoreSchema::new(&snapshot);
+        let schema = AnchoringSchema::new(&snapshot);
+        let latest_height = core_schema.height();
+        let height = request.height.map_or(latest_height, Height);
+        if height > latest_height {
+            return Err(error::Error::NotEnoughBlocks {
+                height: height.0,
+                count: latest_height.next().0,
+            }.into());
+        }
+        if schema.following_anchoring_config().is_some() {
+            return Err(error::Error::TransitionInProgress.into());
+        }
+
+        let cfg = schema.actual_anchoring_config();
+        let (redeem_script, addr) = cfg.redeem_script();
+        let funds = largest_unspent_funding_tx(&schema, &cfg, &addr);
+        let (builder, anchored_height) =
+            match schema.collect_lects(&cfg).map(|tx| cfg.tx_kind(tx)) {
+                Some(TxKind::Anchoring(lect)) => {
+                    let anchored_height = lect.payload().block_height;
+                    if anchored_height >= height {
+                        return Err(error::Error::AlreadyAnchored {
+                            height: height.0,
+                            anchored_height: anchored_height.0,
+                        }.into());
+                    }
+                    let builder = TransactionBuilder::with_prev_tx(&lect, 0);
+                    let builder = match funds {
+                        Some((tx, out)) => builder.add_funds(tx, out),
+                        None => builder,
+                    };
+                    (builder, Some(anchored_height.0))
+                }
+                // The chain is started on the next anchoring height regardless of the request.
+                Some(TxKind::FundingTx(tx)) => {
+                    let out = tx.find_out(&addr)
+                        .ok_or_else(|| error::Error::MalformedLect(tx.id()))?;
+                    (TransactionBuilder::with_prev_tx(&tx, out), None)
+                }
+                Some(TxKind::Other(tx)) => return Err(error::Error::MalformedLect(tx.id()).into()),
+                None => return Err(error::Error::NoLectConsensus.into()),
+            };
+
+        // Checks that the funds suffice to pay the fee.
+        let hash = core_schema.block_hashes_by_height().get(height.0).unwrap();
+        builder
+            .payload(height, hash)
+            .payload_prefix(cfg.payload_prefix.as_bytes())
+            .table_root(schema.anchored_table_root(height))
+            .anchor_output(cfg.anchor_output())
+            .size_limit(&redeem_script, cfg.majority_count() as usize, cfg.max_tx_vsize)
+            .send_to(addr)
+            .fee(cfg.fee)
+            .into_transaction()
+            .map_err(error::Error::from)?;
+
+        let node = self.node_channel
+            .as_ref()
+            .ok_or(error::Error::NodeChannelNotConfigured)?;
+        let validator = node_validator(&core_schema, node)?;
+        let msg = MsgAnchoringAnchorRequest::new(
+            &node.public_key,
+            ValidatorId(validator as u16),
+            height.0,
+            &node.secret_key,
+        );
+        node.sender.send(Box::new(msg)).map_err(ApiError::Io)?;
+        Ok(ScheduledAnchor {
+            height: height.0,
+            anchored_height,
+        })
+    }
+
+    /// Returns the anchoring transaction with the given normalized `txid` as the PSBT
+    /// with the signatures collected in its anchoring round.
+    ///
+    /// `GET /{api_prefix}/v1/psbt/:txid`
+    pub fn psbt(&self, txid: TxId) -> Result<AnchoringPsbt, ApiError> {
+        let snapshot = self.blockchain.snapshot();
+        let schema = AnchoringSchema::new(&snapshot);
+        let cfg = schema.actual_anchoring_config();
+        let msgs = schema.signatures(&txid).iter().collect::<Vec<_>>();
+        let tx = msgs.first()
+            .map(|msg| msg.tx())
+            .ok_or_else(|| error::Error::UnknownProposal(txid))?;
+
+        let signatures = msgs.iter()
+            .filter_map(|msg| {
+                let public_key = cfg.anchoring_keys.get(msg.validator().0 as usize)?;
+                Some(PartialSignature {
+                    input: msg.input(),
+                    public_key: *public_key,
+                    signature: msg.signature().to_vec(),
+                })
+            })
+            .collect::<Vec<_>>();
+        let prev_txs = spent_txs(&schema, &cfg, &tx);
+        let psbt = tx.to_psbt(
+            &cfg.redeem_script().0,
+            &prev_txs,
+            cfg.sighash_type,
+            &signatures,
+        );
+        Ok(AnchoringPsbt { tx, psbt })
+    }
+
+    /// Imports the signatures of the node's anchoring key from the PSBT signed by the external
+    /// tools and sends them to the anchoring round of the transaction, the inputs that
+    /// the node has already signed are skipped.
+    ///
+    /// `POST /{api_prefix}/v1/psbt`
+    pub fn import_psbt(&self, request: &PsbtImportRequest) -> Result<ImportedSignatures, ApiError> {
+        let node = self.node_channel
+            .as_ref()
+            .ok_or(error::Error::NodeChannelNotConfigured)?;
+        let snapshot = self.blockchain.snapshot();
+        let schema = AnchoringSchema::new(&snapshot);
+        let cfg = schema.actual_anchoring_config();
+        let validator = node_validator(&CoreSchema::new(&snapshot), node)?;
+        let anchoring_key = cfg.anchoring_keys
+            .get(validator)
+            .ok_or(error::Error::NotValidator)?;
+
+        let psbt = Psbt::from_base64(&request.psbt).map_err(error::Error::from)?;
+        let txid = AnchoringTx::from(psbt.tx().clone()).nid();
+        let msgs = schema.signatures(&txid).iter().collect::<Vec<_>>();
+        let tx = msgs.first()
+            .map(|msg| msg.tx())
+            .ok_or_else(|| error::Error::UnknownProposal(txid))?;
+        let prev_txs = spent_txs(&schema, &cfg, &tx);
+        let signatures = tx.psbt_signatures(&request.psbt, &cfg.redeem_script().0, &prev_txs)
+            .map_err(error::Error::from)?;
+
+        let mut inputs = Vec::new();
+        for signature in signatures {
+            let is_signed = msgs.iter().any(|msg| {
+                msg.validator().0 as usize == validator && msg.input() == signature.input
+            });
+            if signature.public_key != *anchoring_key || is_signed {
+                continue;
+            }
+            let msg = MsgAnchoringSignature::new(
+                &node.public_key,
+                ValidatorId(validator as u16),
+                tx.clone(),
+                signature.input,
+                &signature.signature,
+                &node.secret_key,
+            );
+            node.sender.send(Box::new(msg)).map_err(ApiError::Io)?;
+            inputs.push(signature.input);
+        }
+        Ok(ImportedSignatures {
+            validator: validator as u32,
+            inputs,
+        })
+    }
+}
+
+impl Api for PrivateApi {
+    fn wire(&self, router: &mut Router) {
+        let api = self.clone();
+        let signatures = move |req: &mut Request| -> IronResult<Response> {
+            let txid: String = api.url_fragment(req, "txid")?;
+            let txid = TxId::from_hex(&txid)
+                .map_err(|_| ApiError::from(error::Error::InvalidTxId(txid.clone())))?;
+            let signatures = api.signatures(txid)?;
+            api.ok_response(&json!(signatures))
+        };
+
+        let api = self.clone();
+        let anchor_now = move |req: &mut Request| -> IronResult<Response> {
+            let request: AnchorNowRequest = api.parse_body(req)?;
+            let anchor = api.anchor_now(&request)?;
+            api.ok_response(&json!(anchor))
+        };
+
+        let api = self.clone();
+        let psbt = move |req: &mut Request| -> IronResult<Response> {
+            let txid: String = api.url_fragment(req, "txid")?;
+            let txid = TxId::from_hex(&txid)
+                .map_err(|_| ApiError::from(error::Error::InvalidTxId(txid.clone())))?;
+            let psbt = api.psbt(txid)?;
+            api.ok_response(&json!(psbt))
+        };
+
+        let api = self.clone();
+        let import_psbt = move |req: &mut Request| -> IronResult<Response> {
+            let request: PsbtImportRequest = api.parse_body(req)?;
+            let imported = api.import_psbt(&request)?;
+            api.ok_response(&json!(imported))
+        };
+
+        router.get("/v1/signatures/:txid", signatures, "signatures");
+        router.post("/v1/anchor_now", anchor_now, "anchor_now");
+        router.get("/v1/psbt/:txid", psbt, "psbt");
+        router.post("/v1/psbt", import_psbt, "import_psbt");
+    }
+}
+
+/// Returns the transactions spent by the inputs of the anchoring `tx`.
+fn spent_txs<T: AsRef<Snapshot>>(
+    schema: &AnchoringSchema<T>,
+    cfg: &AnchoringConfig,
+    tx: &AnchoringTx,
+) -> Vec<RawBitcoinTx> {
+    tx.input
+        .iter()
+        .filter_map(|input| {
+            let prev_txid = input.prev_hash.into();
+            match schema.find_funding_tx(cfg, &prev_txid) {
+                Some(funding_tx) => Some(funding_tx.0),
+                None => schema.known_txs().get(&prev_txid).map(|tx| tx.0),
+            }
+        })
+        .collect()
+}
+
+/// Returns the identifier of the validator whose service key is used by the `node` channel.
+fn node_validator<T: AsRef<Snapshot>>(
+    core_schema: &CoreSchema<T>,
+    node: &NodeChannel,
+) -> Result<usize, error::Error> {
+    core_schema
+        .actual_configuration()
+        .validator_keys
+        .iter()
+        .position(|keys| keys.service_key == node.public_key)
+        .ok_or(error::Error::NotValidator)
+}
@@ -0,0 +1,178 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Client-side verification of the anchored block header proofs.
+
+use std::collections::BTreeSet;
+
+use exonum::blockchain::Blockchain;
+use exonum::crypto::{CryptoHash, Hash, PublicKey};
+
+use ANCHORING_SERVICE_ID;
+
+use super::AnchoredBlockHeaderProof;
+
+/// Errors that may occur during the anchored block header proof verification.
+#[derive(Debug, Clone, PartialEq, Eq, Display, Fail)]
+pub enum ProofError {
+    /// Precommit is signed by the validator that is not in the given list.
+    #[display(fmt = "Unable to find validator with id {}", _0)]
+    UnknownValidator(u16),
+    /// Precommit signature is not valid.
+    #[display(fmt = "Precommit of the validator {} has invalid signature", _0)]
+    InvalidSignature(u16),
+    /// Precommit is given for another block.
+    #[display(fmt = "Precommit of the validator {} does not match the block", _0)]
+    PrecommitMismatch(u16),
+    /// Block is not authorized by the majority of the validators.
+    #[display(fmt = "Block has {} precommits, but {} are required", actual, required)]
+    NotEnoughPrecommits {
+        /// Number of the distinct validators that signed the block.
+        actual: usize,
+        /// Number of the validators that make up the majority.
+        required: usize,
+    },
+    /// Proof for the service table is malformed.
+    #[display(fmt = "Malformed service table proof: {}", _0)]
+    MalformedTableProof(String),
+    /// Proof for the service table does not lead to the block state hash.
+    #[display(fmt = "Service table proof does not match the block state hash")]
+    StateHashMismatch,
+    /// Proof for the service table does not contain the anchored blocks table.
+    #[display(fmt = "Service table proof does not contain the anchored blocks table")]
+    MissingTable,
+    /// Proof for the block headers is malformed.
+    #[display(fmt = "Malformed block headers proof: {}", _0)]
+    MalformedHeadersProof(String),
+    /// Proof for the block headers does not contain the block at the given height.
+    #[display(fmt = "Block header at height {} is not proved", _0)]
+    MissingHeader(u64),
+    /// Proved block hash differs from the expected one.
+    #[display(fmt = "Block hash at height {} differs from the expected one", _0)]
+    HashMismatch(u64),
+    /// Proved blocks have not been anchored yet.
+    #[display(fmt = "Proved blocks have not been anchored yet")]
+    NotAnchored,
+    /// Number of confirmations of the anchoring transaction is unknown.
+    #[display(fmt = "Number of confirmations of the anchoring transaction is unknown")]
+    UnknownAnchorDepth,
+    /// Anchoring transaction is not buried deep enough in the bitcoin blockchain.
+    #[display(fmt = "Anchoring transaction has {} confirmations, but {} are required",
+              actual, required)]
+    NotEnoughConfirmations {
+        /// Number of confirmations of the anchoring transaction.
+        actual: u64,
+        /// Number of the required confirmations.
+        required: u64,
+    },
+}
+
+/// Verifies that the block at the given `height` with the `expected_hash` is proved by `proof`.
+///
+/// The verification consists of three steps:
+///
+/// - The latest authorized block should be signed by the majority of `validator_keys`,
+///   which are the consensus keys of the actual validators.
+/// - The service table proof should lead to the state hash of this block.
+/// - The block headers proof should lead to the root of the anchored blocks table
+///   and contain `expected_hash` at the `height`.
+pub fn verify_anchored_block_header_proof(
+    proof: &AnchoredBlockHeaderProof,
+    height: u64,
+    expected_hash: Hash,
+    validator_keys: &[PublicKey],
+) -> Result<(), ProofError> {
+    let block = &proof.latest_authorized_block.block;
+    let block_hash = block.hash();
+
+    // Checks precommits.
+    let mut signed_validators = BTreeSet::new();
+    for precommit in &proof.latest_authorized_block.precommits {
+        let validator_id = precommit.validator().0;
+        let key = validator_keys
+            .get(validator_id as usize)
+            .ok_or(ProofError::UnknownValidator(validator_id))?;
+        if !precommit.verify_signature(key) {
+            return Err(ProofError::InvalidSignature(validator_id));
+        }
+        if precommit.block_hash() != &block_hash || precommit.height() != block.height() {
+            return Err(ProofError::PrecommitMismatch(validator_id));
+        }
+        signed_validators.insert(validator_id);
+    }
+    let required = validator_keys.len() * 2 / 3 + 1;
+    if signed_validators.len() < required {
+        return Err(ProofError::NotEnoughPrecommits {
+            actual: signed_validators.len(),
+            required,
+        });
+    }
+
+    // Checks the state hash.
+    let checked_table_proof = proof
+        .to_table
+        .check()
+        .map_err(|e| ProofError::MalformedTableProof(format!("{:?}", e)))?;
+    if checked_table_proof.merkle_root() != *block.state_hash() {
+        return Err(ProofError::StateHashMismatch);
+    }
+    let table_location = Blockchain::service_table_unique_key(ANCHORING_SERVICE_ID, 0);
+    let table_root = checked_table_proof
+        .entries()
+        .into_iter()
+        .find(|entry| entry.0 == &table_location)
+        .map(|entry| *entry.1)
+        .ok_or(ProofError::MissingTable)?;
+
+    // Checks the block header.
+    let values = proof
+        .to_block_header
+        .validate(table_root, block.height().0)
+        .map_err(|e| ProofError::MalformedHeadersProof(format!("{:?}", e)))?;
+    let hash = values
+        .into_iter()
+        .find(|value| value.0 == height)
+        .map(|value| *value.1)
+        .ok_or(ProofError::MissingHeader(height))?;
+    if hash != expected_hash {
+        return Err(ProofError::HashMismatch(height));
+    }
+    Ok(())
+}
+
+/// Checks that the anchoring transaction that proves the blocks has at least
+/// `min_confirmations` confirmations, so the anchor can be treated as irreversible.
+///
+/// The depth is reported by the node that served the proof, so the check complements
+/// the [`verify_anchored_block_header_proof`](fn.verify_anchored_block_header_proof.html)
+/// and does not replace it.
+pub fn verify_anchor_depth(
+    proof: &AnchoredBlockHeaderProof,
+    min_confirmations: u64,
+) -> Result<u64, ProofError> {
+    let depth = proof
+        .anchor_depth
+        .as_ref()
+        .ok_or(ProofError::NotAnchored)?;
+    let confirmations = depth
+        .confirmations
+        .ok_or(ProofError::UnknownAnchorDepth)?;
+    if confirmations < min_confirmations {
+        return Err(ProofError::NotEnoughConfirmations {
+            actual: confirmations,
+            required: min_confirmations,
+        });
+    }
+    Ok(confirmations)
+}
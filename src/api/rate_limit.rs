@@ -0,0 +1,157 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rate limiting of the public API requests by the token buckets.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use iron::prelude::*;
+use iron::{status, BeforeMiddleware};
+
+/// Parameters of the token bucket.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Maximal number of requests that are served in a burst.
+    pub burst: u32,
+    /// Number of requests per second that are added to the bucket.
+    pub per_second: u32,
+}
+
+/// Rate limits of the public API routes.
+///
+/// The requests are not limited by default.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    /// Limit of the routes that have no limit of their own.
+    /// These routes are not limited if the value is absent.
+    #[serde(default)]
+    pub default: Option<RateLimit>,
+    /// Limits of the routes by the path prefixes, e.g. `/v1/block_header_proof/`.
+    /// The longest matching prefix is applied, the routes with the same prefix
+    /// share the bucket.
+    #[serde(default)]
+    pub routes: BTreeMap<String, RateLimit>,
+}
+
+impl RateLimitConfig {
+    /// Returns `true` if no limits are set.
+    pub fn is_empty(&self) -> bool {
+        self.default.is_none() && self.routes.is_empty()
+    }
+
+    /// Returns the prefix of the bucket along with the limit that applies to the given `path`.
+    fn limit(&self, path: &str) -> Option<(&str, RateLimit)> {
+        self.routes
+            .iter()
+            .filter(|&(prefix, _)| path.starts_with(prefix.as_str()))
+            .max_by_key(|&(prefix, _)| prefix.len())
+            .map(|(prefix, limit)| (prefix.as_str(), *limit))
+            .or_else(|| self.default.map(|limit| ("", limit)))
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit, now: Instant) -> TokenBucket {
+        TokenBucket {
+            tokens: f64::from(limit.burst),
+            updated_at: now,
+        }
+    }
+
+    /// Refills the bucket according to the elapsed time and takes a token if there is one.
+    fn try_take(&mut self, limit: RateLimit, now: Instant) -> bool {
+        let elapsed = now.duration_since(self.updated_at);
+        let elapsed = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) * 1e-9;
+        self.tokens = (self.tokens + elapsed * f64::from(limit.per_second))
+            .min(f64::from(limit.burst));
+        self.updated_at = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Error of the request that exceeds the rate limit of its route.
+#[derive(Debug)]
+pub struct RateLimitExceeded {
+    prefix: String,
+}
+
+impl fmt::Display for RateLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Rate limit of the route prefix '{}' is exceeded", self.prefix)
+    }
+}
+
+impl Error for RateLimitExceeded {
+    fn description(&self) -> &str {
+        "Rate limit is exceeded"
+    }
+}
+
+/// Middleware that rejects the requests exceeding the rate limits of their routes
+/// with the `429 Too Many Requests` status.
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// Creates the middleware with the given limits.
+    pub fn new(config: RateLimitConfig) -> RateLimiter {
+        RateLimiter {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl BeforeMiddleware for RateLimiter {
+    fn before(&self, req: &mut Request) -> IronResult<()> {
+        let path = format!("/{}", req.url.path().join("/"));
+        let (prefix, limit) = match self.config.limit(&path) {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets
+            .entry(prefix.to_owned())
+            .or_insert_with(|| TokenBucket::new(limit, now));
+        if bucket.try_take(limit, now) {
+            Ok(())
+        } else {
+            let error = RateLimitExceeded {
+                prefix: prefix.to_owned(),
+            };
+            let body = error.to_string();
+            Err(IronError::new(error, (status::TooManyRequests, body)))
+        }
+    }
+}
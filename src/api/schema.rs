@@ -0,0 +1,393 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! JSON Schema (draft-07) of the public API responses for the clients that generate
+//! the typed bindings.
+//!
+//! The definitions follow the Serde representation of the response types, including
+//! the types of the Exonum core that are encoded by its own rules, e.g. the heights
+//! of the block headers are strings. The definitions of the anchoring types forbid
+//! the unknown properties, so the new fields of the responses are caught by the
+//! validation of the responses against the schema.
+
+use serde_json::{Map, Value};
+
+/// Identifier of the JSON Schema draft the definitions conform to.
+const JSON_SCHEMA_DRAFT: &str = "http://json-schema.org/draft-07/schema#";
+
+/// Error that occurs when the value does not match the schema definition.
+#[derive(Debug, Clone, PartialEq, Eq, Display, Fail)]
+pub enum SchemaError {
+    /// Schema has no definition with the given name.
+    #[display(fmt = "Unknown schema definition {}", _0)]
+    UnknownDefinition(String),
+    /// Value has the type that is not allowed by the schema.
+    #[display(fmt = "Value at {} has unexpected type, expected {}", path, expected)]
+    UnexpectedType {
+        /// JSON pointer to the value.
+        path: String,
+        /// Allowed types.
+        expected: String,
+    },
+    /// Value is not among the allowed ones.
+    #[display(fmt = "Value at {} is not allowed", _0)]
+    NotAllowed(String),
+    /// Length of the string value is out of the allowed range.
+    #[display(fmt = "Value at {} has unexpected length", _0)]
+    UnexpectedLength(String),
+    /// Value matches none of the alternatives.
+    #[display(fmt = "Value at {} matches none of the alternatives", _0)]
+    NoMatchingAlternative(String),
+    /// Object misses the required property.
+    #[display(fmt = "Value at {} misses the property {}", path, property)]
+    MissingProperty {
+        /// JSON pointer to the object.
+        path: String,
+        /// Name of the missing property.
+        property: String,
+    },
+    /// Object has the property that is not defined by the schema.
+    #[display(fmt = "Value at {} has the unknown property {}", path, property)]
+    UnknownProperty {
+        /// JSON pointer to the object.
+        path: String,
+        /// Name of the unknown property.
+        property: String,
+    },
+}
+
+/// Returns the schema of the public API responses, the response types are described
+/// in the `definitions` under their names, e.g. `AnchoringInfo`, `LectInfo` and
+/// `AnchoredBlockHeaderProof`.
+pub fn response_schema() -> Value {
+    let definitions = vec![
+        ("Hash", hex_string(32)),
+        ("TxId", hex_string(32)),
+        ("Signature", hex_string(64)),
+        (
+            "AnchoringTxKind",
+            json!({ "type": "string", "enum": ["anchoring", "funding"] }),
+        ),
+        (
+            "Payload",
+            object(
+                json!({
+                    "block_height": { "type": "integer" },
+                    "block_hash": reference("Hash"),
+                    "prev_tx_chain": nullable("TxId"),
+                    "table_root": reference("Hash")
+                }),
+                &["block_height", "block_hash", "prev_tx_chain"],
+            ),
+        ),
+        (
+            "AnchoringInfo",
+            object(
+                json!({
+                    "txid": reference("TxId"),
+                    "kind": reference("AnchoringTxKind"),
+                    "payload": nullable("Payload")
+                }),
+                &["txid", "kind", "payload"],
+            ),
+        ),
+        (
+            "LectInfo",
+            object(
+                json!({
+                    "hash": reference("Hash"),
+                    "content": reference("AnchoringInfo")
+                }),
+                &["hash", "content"],
+            ),
+        ),
+        (
+            "AnchorDepth",
+            object(
+                json!({
+                    "txid": reference("TxId"),
+                    "confirmations": { "type": ["integer", "null"] },
+                    "updated_at": { "type": "integer" }
+                }),
+                &["txid", "confirmations", "updated_at"],
+            ),
+        ),
+        (
+            "AnchoredBlockHeaderProof",
+            object(
+                json!({
+                    "latest_authorized_block": reference("BlockProof"),
+                    "to_table": reference("MapProof"),
+                    "to_block_header": reference("ListProof"),
+                    "anchor_depth": nullable("AnchorDepth")
+                }),
+                &["latest_authorized_block", "to_table", "to_block_header"],
+            ),
+        ),
+        ("Block", block()),
+        ("Precommit", precommit()),
+        (
+            "BlockProof",
+            json!({
+                "type": "object",
+                "properties": {
+                    "block": reference("Block"),
+                    "precommits": { "type": "array", "items": reference("Precommit") }
+                },
+                "required": ["block", "precommits"]
+            }),
+        ),
+        ("MapProof", map_proof()),
+        ("ListProof", list_proof()),
+    ];
+    let definitions = definitions
+        .into_iter()
+        .map(|(name, definition)| (name.to_owned(), definition))
+        .collect::<Map<_, _>>();
+    json!({
+        "$schema": JSON_SCHEMA_DRAFT,
+        "title": "Exonum BTC anchoring public API responses",
+        "version": env!("CARGO_PKG_VERSION"),
+        "definitions": definitions
+    })
+}
+
+/// Validates the `value` against the given `definition` of the `schema`.
+///
+/// Only the keywords used by the `response_schema` are supported, i.e. `$ref`
+/// to the definitions, `type`, `enum`, `anyOf`, `minLength`, `maxLength`, `items`,
+/// `properties`, `required` and `additionalProperties` that forbids the unknown properties.
+pub fn validate_schema(schema: &Value, definition: &str, value: &Value) -> Result<(), SchemaError> {
+    let validator = Validator {
+        definitions: &schema["definitions"],
+    };
+    let definition_schema = validator.definition(definition)?;
+    validator.validate(definition_schema, value, "#")
+}
+
+struct Validator<'a> {
+    definitions: &'a Value,
+}
+
+impl<'a> Validator<'a> {
+    fn definition(&self, name: &str) -> Result<&'a Value, SchemaError> {
+        self.definitions
+            .get(name)
+            .ok_or_else(|| SchemaError::UnknownDefinition(name.to_owned()))
+    }
+
+    fn validate(&self, schema: &Value, value: &Value, path: &str) -> Result<(), SchemaError> {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let definition = self.definition(reference.trim_left_matches("#/definitions/"))?;
+            return self.validate(definition, value, path);
+        }
+
+        if let Some(alternatives) = schema.get("anyOf").and_then(Value::as_array) {
+            if !alternatives
+                .iter()
+                .any(|alternative| self.validate(alternative, value, path).is_ok())
+            {
+                return Err(SchemaError::NoMatchingAlternative(path.to_owned()));
+            }
+        }
+        if let Some(types) = schema.get("type") {
+            let matches = match *types {
+                Value::String(ref ty) => has_type(value, ty),
+                Value::Array(ref types) => types
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .any(|ty| has_type(value, ty)),
+                _ => true,
+            };
+            if !matches {
+                return Err(SchemaError::UnexpectedType {
+                    path: path.to_owned(),
+                    expected: types.to_string(),
+                });
+            }
+        }
+        if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+            if !allowed.contains(value) {
+                return Err(SchemaError::NotAllowed(path.to_owned()));
+            }
+        }
+
+        match *value {
+            Value::String(ref s) => {
+                let len = s.chars().count() as u64;
+                let min_len = schema.get("minLength").and_then(Value::as_u64);
+                let max_len = schema.get("maxLength").and_then(Value::as_u64);
+                if min_len.map_or(false, |min_len| len < min_len)
+                    || max_len.map_or(false, |max_len| len > max_len)
+                {
+                    return Err(SchemaError::UnexpectedLength(path.to_owned()));
+                }
+            }
+            Value::Array(ref items) => if let Some(items_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    self.validate(items_schema, item, &format!("{}/{}", path, i))?;
+                }
+            },
+            Value::Object(ref object) => {
+                if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                    for property in required.iter().filter_map(Value::as_str) {
+                        if !object.contains_key(property) {
+                            return Err(SchemaError::MissingProperty {
+                                path: path.to_owned(),
+                                property: property.to_owned(),
+                            });
+                        }
+                    }
+                }
+                let closed = schema.get("additionalProperties") == Some(&Value::Bool(false));
+                for (property, property_value) in object {
+                    match schema.get("properties").and_then(|p| p.get(property)) {
+                        Some(property_schema) => self.validate(
+                            property_schema,
+                            property_value,
+                            &format!("{}/{}", path, property),
+                        )?,
+                        None if closed => {
+                            return Err(SchemaError::UnknownProperty {
+                                path: path.to_owned(),
+                                property: property.to_owned(),
+                            })
+                        }
+                        None => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn has_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_u64() || value.is_i64(),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => false,
+    }
+}
+
+fn reference(name: &str) -> Value {
+    json!({ "$ref": format!("#/definitions/{}", name) })
+}
+
+fn nullable(name: &str) -> Value {
+    json!({ "anyOf": [reference(name), { "type": "null" }] })
+}
+
+fn hex_string(bytes: u64) -> Value {
+    json!({
+        "type": "string",
+        "minLength": bytes * 2,
+        "maxLength": bytes * 2
+    })
+}
+
+// Object of the anchoring type, which has no properties other than the given ones.
+fn object(properties: Value, required: &[&str]) -> Value {
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false
+    })
+}
+
+// The Exonum encoding represents the 64-bit integers, e.g. the heights, by the strings.
+fn block() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "schema_version": { "type": "integer" },
+            "proposer_id": { "type": "integer" },
+            "height": { "type": "string" },
+            "tx_count": { "type": "integer" },
+            "prev_hash": reference("Hash"),
+            "tx_hash": reference("Hash"),
+            "state_hash": reference("Hash")
+        },
+        "required": [
+            "schema_version",
+            "proposer_id",
+            "height",
+            "tx_count",
+            "prev_hash",
+            "tx_hash",
+            "state_hash"
+        ]
+    })
+}
+
+fn precommit() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "body": { "type": "object" },
+            "signature": reference("Signature"),
+            "message_id": { "type": "integer" },
+            "service_id": { "type": "integer" },
+            "protocol_version": { "type": "integer" }
+        },
+        "required": ["body", "signature", "message_id", "service_id", "protocol_version"]
+    })
+}
+
+fn map_proof() -> Value {
+    let missing_entry = json!({
+        "type": "object",
+        "properties": { "missing": reference("Hash") },
+        "required": ["missing"]
+    });
+    let entry = json!({
+        "type": "object",
+        "properties": { "key": reference("Hash"), "value": reference("Hash") },
+        "required": ["key", "value"]
+    });
+    let proof_entry = json!({
+        "type": "object",
+        "properties": { "path": { "type": "string" }, "hash": reference("Hash") },
+        "required": ["path", "hash"]
+    });
+    json!({
+        "type": "object",
+        "properties": {
+            "entries": { "type": "array", "items": { "anyOf": [missing_entry, entry] } },
+            "proof": { "type": "array", "items": proof_entry }
+        },
+        "required": ["entries", "proof"]
+    })
+}
+
+// The branches refer to the nested proofs or to the hashes of the omitted subtrees.
+fn list_proof() -> Value {
+    let branch = json!({ "anyOf": [reference("ListProof"), reference("Hash")] });
+    json!({
+        "type": "object",
+        "properties": {
+            "left": branch.clone(),
+            "right": branch,
+            "val": reference("Hash")
+        },
+        "additionalProperties": false
+    })
+}
@@ -0,0 +1,253 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! WebSocket subscriptions to the committed lects and the transition events.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use ws;
+
+use exonum::storage::Snapshot;
+
+use blockchain::schema::AnchoringSchema;
+use details::btc;
+use handler::TransitionEvent;
+
+use super::LectInfo;
+
+/// Resource that should be requested to subscribe to the committed lects.
+pub const LECTS_RESOURCE: &str = "/v1/ws/lects";
+/// Resource that should be requested to subscribe to the events of the transitions
+/// to the new anchoring addresses.
+pub const TRANSITION_RESOURCE: &str = "/v1/ws/transition";
+
+/// WebSocket server configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WebSocketConfig {
+    /// Address on which the WebSocket server listens.
+    pub listen_address: SocketAddr,
+    /// Size of the outgoing buffer of each connection in bytes.
+    /// Clients that do not read messages fast enough to fit into it are disconnected.
+    pub out_buffer_capacity: usize,
+}
+
+/// Sends the lects committed to the blockchain and the transition events
+/// to the WebSocket subscribers of the corresponding resources.
+///
+/// Nothing is sent until the notifier is attached to the running server.
+#[derive(Debug, Clone, Default)]
+pub struct LectsNotifier {
+    inner: Arc<Mutex<NotifierState>>,
+}
+
+#[derive(Default)]
+struct NotifierState {
+    broadcaster: Option<ws::Sender>,
+    lects_count: HashMap<btc::PublicKey, u64>,
+    subscribers: HashMap<u32, (&'static str, ws::Sender)>,
+}
+
+impl NotifierState {
+    fn send(&self, resource: &str, msg: &str) {
+        for &(subscribed_resource, ref subscriber) in self.subscribers.values() {
+            if subscribed_resource != resource {
+                continue;
+            }
+            if let Err(e) = subscriber.send(msg) {
+                warn!("Unable to send message to the subscriber of {}: {}", resource, e);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for NotifierState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NotifierState")
+            .field("attached", &self.broadcaster.is_some())
+            .field("lects_count", &self.lects_count)
+            .field("subscribers", &self.subscribers.len())
+            .finish()
+    }
+}
+
+impl LectsNotifier {
+    /// Creates a notifier that is not attached to any server.
+    pub fn new() -> LectsNotifier {
+        LectsNotifier::default()
+    }
+
+    /// Sends the lects that have been committed since the previous call to the subscribers.
+    ///
+    /// The first call after the notifier has been attached only remembers the lects
+    /// of the validators.
+    pub fn notify<T: AsRef<Snapshot>>(&self, snapshot: T) {
+        let mut state = self.inner.lock().unwrap();
+        if state.broadcaster.is_none() {
+            return;
+        }
+
+        let schema = AnchoringSchema::new(snapshot);
+        let actual_cfg = schema.actual_anchoring_config();
+        for key in &actual_cfg.anchoring_keys {
+            let lects = schema.lects(key);
+            let len = lects.len();
+            let from = *state.lects_count.get(key).unwrap_or(&len);
+            for content in lects.iter_from(from) {
+                let info = match LectInfo::try_from_lect(&actual_cfg, content) {
+                    Ok(info) => info,
+                    Err(e) => {
+                        warn!("Skipped lect notification: {}", e);
+                        continue;
+                    }
+                };
+                state.send(LECTS_RESOURCE, &json!(info).to_string());
+            }
+            state.lects_count.insert(*key, len);
+        }
+    }
+
+    /// Sends the transition event to the subscribers.
+    pub fn notify_transition(&self, event: &TransitionEvent) {
+        let state = self.inner.lock().unwrap();
+        state.send(TRANSITION_RESOURCE, &json!(event).to_string());
+    }
+
+    fn attach(&self, broadcaster: ws::Sender) {
+        let mut state = self.inner.lock().unwrap();
+        state.broadcaster = Some(broadcaster);
+        state.lects_count.clear();
+        state.subscribers.clear();
+    }
+
+    fn subscribe(&self, resource: &'static str, subscriber: ws::Sender) {
+        let mut state = self.inner.lock().unwrap();
+        state
+            .subscribers
+            .insert(subscriber.connection_id(), (resource, subscriber));
+    }
+
+    fn unsubscribe(&self, subscriber: &ws::Sender) {
+        let mut state = self.inner.lock().unwrap();
+        state.subscribers.remove(&subscriber.connection_id());
+    }
+
+    /// Stops the attached server.
+    pub fn shutdown(&self) {
+        let state = self.inner.lock().unwrap();
+        if let Some(ref broadcaster) = state.broadcaster {
+            if let Err(e) = broadcaster.shutdown() {
+                warn!("Unable to shutdown the WebSocket server: {}", e);
+            }
+        }
+    }
+}
+
+/// WebSocket server that pushes the committed lects to the subscribers of the
+/// `LECTS_RESOURCE` in the [`LectInfo`](../struct.LectInfo.html) format, and the transition
+/// events to the subscribers of the `TRANSITION_RESOURCE` in the
+/// [`TransitionEvent`](../../handler/enum.TransitionEvent.html) format.
+pub struct LectsServer {
+    socket: ws::WebSocket<SubscriberFactory>,
+}
+
+impl fmt::Debug for LectsServer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LectsServer")
+            .field("local_addr", &self.socket.local_addr().ok())
+            .finish()
+    }
+}
+
+impl LectsServer {
+    /// Binds the server to the address from the `config` and attaches the `notifier` to it.
+    pub fn bind(notifier: &LectsNotifier, config: &WebSocketConfig) -> ws::Result<LectsServer> {
+        let settings = ws::Settings {
+            out_buffer_capacity: config.out_buffer_capacity,
+            out_buffer_grow: false,
+            ..Default::default()
+        };
+        let socket = ws::Builder::new()
+            .with_settings(settings)
+            .build(SubscriberFactory {
+                notifier: notifier.clone(),
+            })?
+            .bind(config.listen_address)?;
+        notifier.attach(socket.broadcaster());
+        Ok(LectsServer { socket })
+    }
+
+    /// Returns the address the server is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Runs the server until it is stopped by the [`shutdown`][1] of its notifier.
+    ///
+    /// [1]: struct.LectsNotifier.html#method.shutdown
+    pub fn run(self) -> ws::Result<()> {
+        self.socket.run()?;
+        Ok(())
+    }
+}
+
+struct SubscriberFactory {
+    notifier: LectsNotifier,
+}
+
+impl ws::Factory for SubscriberFactory {
+    type Handler = Subscriber;
+
+    fn connection_made(&mut self, out: ws::Sender) -> Subscriber {
+        Subscriber {
+            out,
+            notifier: self.notifier.clone(),
+            resource: None,
+        }
+    }
+}
+
+struct Subscriber {
+    out: ws::Sender,
+    notifier: LectsNotifier,
+    resource: Option<&'static str>,
+}
+
+impl ws::Handler for Subscriber {
+    fn on_request(&mut self, req: &ws::Request) -> ws::Result<ws::Response> {
+        self.resource = [LECTS_RESOURCE, TRANSITION_RESOURCE]
+            .iter()
+            .cloned()
+            .find(|&resource| req.resource() == resource);
+        if self.resource.is_some() {
+            ws::Response::from_request(req)
+        } else {
+            Ok(ws::Response::new(404, "Not Found", Vec::new()))
+        }
+    }
+
+    fn on_open(&mut self, _: ws::Handshake) -> ws::Result<()> {
+        if let Some(resource) = self.resource {
+            self.notifier.subscribe(resource, self.out.clone());
+        }
+        Ok(())
+    }
+
+    fn on_close(&mut self, _: ws::CloseCode, _: &str) {
+        self.notifier.unsubscribe(&self.out);
+    }
+}
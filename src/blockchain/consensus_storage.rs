@@ -13,19 +13,32 @@
 // limitations under the License.
 
 use std::borrow::Cow;
+use std::cmp;
 
 use serde::{Deserialize, Deserializer};
 use serde_json;
+use toml;
 
 use exonum::crypto::{hash, CryptoHash, Hash};
 use exonum::helpers::Height;
 use exonum::storage::StorageValue;
 
 use details::btc;
-use details::btc::transactions::FundingTx;
+use details::btc::descriptor;
+use details::btc::fee::{dust_threshold, estimate_vsize};
+use details::btc::payload::{DEFAULT_PAYLOAD_PREFIX, PAYLOAD_PREFIX_LEN};
+use details::btc::transactions::{FundingTx, RawBitcoinTx, TransactionBuilder, TxKind,
+                                 MAX_STANDARD_TX_VSIZE};
+use details::error::{AddrError, ConfigError};
+
+/// Version of the TOML representation of the anchoring configuration
+/// that is produced by `AnchoringConfig::to_toml`.
+pub const ANCHORING_CONFIG_TOML_VERSION: u32 = 1;
 
 /// Public part of anchoring service configuration stored in blockchain.
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+///
+/// The deserialization rejects the change address of another network.
+#[derive(Serialize, Debug, Clone, PartialEq)]
 pub struct AnchoringConfig {
     /// Validators' public keys from which the current anchoring address can be calculated.
     pub anchoring_keys: Vec<btc::PublicKey>,
@@ -33,17 +46,188 @@ pub struct AnchoringConfig {
     /// If the anchoring transactions chain is empty, it will be the first transaction in the chain.
     /// Note: you must specify a suitable transaction before the network launching.
     pub funding_tx: Option<FundingTx>,
+    /// Additional transactions that fund the anchoring address.
+    /// The anchoring chain uses the largest unspent one among them and the `funding_tx`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub additional_funding_txs: Vec<FundingTx>,
     /// Fee for each transaction in chain.
     pub fee: u64,
+    /// Strategy to choose the fee of the anchoring transactions, the static `fee`
+    /// by default.
+    #[serde(skip_serializing_if = "FeeStrategyConfig::is_static")]
+    pub fee_strategy: FeeStrategyConfig,
     /// The frequency in blocks with which the generation of new anchoring
     /// transactions in the chain occurs.
+    /// Nodes reject the frequency below their `min_anchoring_frequency`.
     pub frequency: u64,
     /// The minimum number of confirmations in bitcoin network for the transition to a
     /// new anchoring address.
     pub utxo_confirmations: u64,
     /// The current bitcoin network type.
-    #[serde(serialize_with = "btc_network_to_str", deserialize_with = "btc_network_from_str")]
+    #[serde(serialize_with = "btc_network_to_str")]
     pub network: btc::Network,
+    /// Prefix that tags the payloads of the anchoring transactions, `EXONUM` by default.
+    /// Deployments that anchor to the same bitcoin network should use distinct prefixes,
+    /// the transactions with another prefix are not treated as anchoring ones.
+    /// The prefix should not be changed by the following configurations, since the whole
+    /// anchoring chain is classified by the actual one.
+    #[serde(skip_serializing_if = "is_default_payload_prefix")]
+    pub payload_prefix: String,
+    /// Fixed value of the anchoring output in satoshis. If set, the rest of the funds
+    /// is returned to the `change_address` by a separate output of the anchoring transaction.
+    /// The anchoring output alone cannot pay the fee of the next anchoring transaction,
+    /// so each of them spends one of the funding transactions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anchor_output_value: Option<u64>,
+    /// Address that receives the change of the anchoring transactions, e.g. the cold storage
+    /// of the operators, required if the `anchor_output_value` is set. Without the latter
+    /// the anchoring output keeps only the dust threshold, so the change address receives
+    /// the whole remainder and every anchoring transaction spends a funding transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change_address: Option<btc::Address>,
+    /// Signature hash type of the anchoring transaction inputs. Signatures of any
+    /// other type are rejected.
+    #[serde(skip_serializing_if = "btc::SighashType::is_all")]
+    pub sighash_type: btc::SighashType,
+    /// Maximal estimated virtual size of the signed anchoring transaction in vbytes,
+    /// the standard `100000` by default. Larger transactions are not proposed.
+    #[serde(skip_serializing_if = "is_default_max_tx_vsize")]
+    pub max_tx_vsize: u64,
+    /// Service table whose root hash at the anchored height is committed by the anchoring
+    /// transactions along with the block hash.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anchored_table: Option<AnchoredTable>,
+    /// Number of the latest blocks whose hashes are kept in the `anchored_blocks` table.
+    /// The older hashes are pruned, but never the ones from the latest anchored block,
+    /// so its proofs stay available. The pruned hashes are replaced with zeros, so their
+    /// proofs become unavailable while the table keeps its size. Nothing is pruned
+    /// if the value is absent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anchored_blocks_retention: Option<u64>,
+    /// Number of bitcoin blocks after which the unconfirmed lect is replaced by the
+    /// transaction with a higher fee and the same payload. The replacement is built once
+    /// +2/3 validators have observed the lect unconfirmed for that many bitcoin blocks.
+    /// Replacement is disabled if the value is absent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rbf_unconfirmed_btc_blocks: Option<u64>,
+}
+
+// Serde representation of the `AnchoringConfig` that is checked after the deserialization,
+// since the change address can be checked only against the network of the configuration.
+#[derive(Deserialize)]
+struct AnchoringConfigRepr {
+    anchoring_keys: Vec<btc::PublicKey>,
+    funding_tx: Option<FundingTx>,
+    #[serde(default)]
+    additional_funding_txs: Vec<FundingTx>,
+    fee: u64,
+    #[serde(default)]
+    fee_strategy: FeeStrategyConfig,
+    frequency: u64,
+    utxo_confirmations: u64,
+    #[serde(deserialize_with = "btc_network_from_str")]
+    network: btc::Network,
+    #[serde(default = "default_payload_prefix")]
+    payload_prefix: String,
+    #[serde(default)]
+    anchor_output_value: Option<u64>,
+    #[serde(default)]
+    change_address: Option<btc::Address>,
+    #[serde(default)]
+    sighash_type: btc::SighashType,
+    #[serde(default = "default_max_tx_vsize")]
+    max_tx_vsize: u64,
+    #[serde(default)]
+    anchored_table: Option<AnchoredTable>,
+    #[serde(default)]
+    anchored_blocks_retention: Option<u64>,
+    #[serde(default)]
+    rbf_unconfirmed_btc_blocks: Option<u64>,
+}
+
+/// Fee strategy of the anchoring transactions.
+///
+/// Validators sign only identical proposals, so the fee is derived from the consensus
+/// state only.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum FeeStrategyConfig {
+    /// Fixed `fee` of the anchoring configuration.
+    Static,
+    /// Fee rate agreed by the validators. Every validator estimates the rate by the
+    /// `estimatesmartfee` of its `bitcoind` and commits it to the blockchain, the median
+    /// of the committed rates is used once +2/3 validators have committed them.
+    EstimateSmartFee {
+        /// Number of blocks within the transaction is expected to be confirmed.
+        conf_target: u16,
+        /// Maximal fee rate in satoshis per 1000 virtual bytes, the agreed rate
+        /// is limited by it.
+        max_fee_rate: u64,
+    },
+}
+
+impl FeeStrategyConfig {
+    /// Returns `true` if the strategy is the static one.
+    pub fn is_static(&self) -> bool {
+        *self == FeeStrategyConfig::Static
+    }
+}
+
+impl Default for FeeStrategyConfig {
+    fn default() -> FeeStrategyConfig {
+        FeeStrategyConfig::Static
+    }
+}
+
+/// Service table whose root hash is anchored, the same one that is aggregated
+/// into the `state_hash` of the blocks.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnchoredTable {
+    /// Identifier of the service that owns the table.
+    pub service_id: u16,
+    /// Index of the table in the `state_hash` of the service.
+    pub table_idx: usize,
+}
+
+// Versioned TOML representation of the anchoring configuration for the hand-edited files.
+// Unlike the serde representation of the `AnchoringConfig` it records the signing threshold
+// and rejects the unknown fields. The change address is kept as a string to be checked
+// against the network of the configuration. The tables must follow the plain values
+// in TOML, so the `fee_strategy` and the `anchored_table` go last.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct AnchoringConfigToml {
+    version: u32,
+    #[serde(serialize_with = "btc_network_to_str", deserialize_with = "btc_network_from_str")]
+    network: btc::Network,
+    anchoring_keys: Vec<btc::PublicKey>,
+    threshold: u8,
+    fee: u64,
+    frequency: u64,
+    utxo_confirmations: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    funding_tx: Option<FundingTx>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    additional_funding_txs: Vec<FundingTx>,
+    #[serde(default = "default_payload_prefix",
+            skip_serializing_if = "is_default_payload_prefix")]
+    payload_prefix: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    anchor_output_value: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    change_address: Option<String>,
+    #[serde(default, skip_serializing_if = "btc::SighashType::is_all")]
+    sighash_type: btc::SighashType,
+    #[serde(default = "default_max_tx_vsize", skip_serializing_if = "is_default_max_tx_vsize")]
+    max_tx_vsize: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    anchored_blocks_retention: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rbf_unconfirmed_btc_blocks: Option<u64>,
+    #[serde(default, skip_serializing_if = "FeeStrategyConfig::is_static")]
+    fee_strategy: FeeStrategyConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    anchored_table: Option<AnchoredTable>,
 }
 
 impl Default for AnchoringConfig {
@@ -51,10 +235,20 @@ impl Default for AnchoringConfig {
         AnchoringConfig {
             anchoring_keys: vec![],
             funding_tx: None,
+            additional_funding_txs: Vec::new(),
             fee: 1000,
+            fee_strategy: FeeStrategyConfig::default(),
             frequency: 500,
             utxo_confirmations: 5,
             network: btc::Network::Testnet,
+            payload_prefix: default_payload_prefix(),
+            anchor_output_value: None,
+            change_address: None,
+            sighash_type: btc::SighashType::default(),
+            max_tx_vsize: MAX_STANDARD_TX_VSIZE,
+            anchored_table: None,
+            anchored_blocks_retention: None,
+            rbf_unconfirmed_btc_blocks: None,
         }
     }
 }
@@ -107,12 +301,186 @@ impl AnchoringConfig {
         (redeem_script, addr)
     }
 
+    /// Returns the anchoring address derived from the `anchoring_keys` and the `network`.
+    ///
+    /// The address depends only on the configuration, so it can be checked offline
+    /// before the configuration is proposed.
+    pub fn expected_address(&self) -> btc::Address {
+        self.redeem_script().1
+    }
+
+    /// Returns the output descriptor of the anchoring address with the checksum.
+    ///
+    /// The descriptor can be imported into a watch-only wallet by the `importdescriptors`
+    /// call of `bitcoind` to monitor the anchoring address.
+    pub fn output_descriptor(&self) -> String {
+        let keys = self.anchoring_keys
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let descriptor = format!("wsh(multi({},{}))", self.majority_count(), keys.join(","));
+        let checksum = descriptor::checksum(&descriptor)
+            .expect("Descriptor contains only hex characters and punctuation");
+        format!("{}#{}", descriptor, checksum)
+    }
+
     #[doc(hidden)]
     /// Returns the latest height below the given `height` which needs to be anchored.
     pub fn latest_anchoring_height(&self, height: Height) -> Height {
         Height(height.0 - height.0 % self.frequency as u64)
     }
 
+    /// Checks that the anchoring frequency is not below `min_frequency`.
+    /// The zero frequency is always rejected.
+    pub fn check_frequency(&self, min_frequency: u64) -> Result<(), ConfigError> {
+        let min_frequency = cmp::max(min_frequency, 1);
+        if self.frequency < min_frequency {
+            return Err(ConfigError::FrequencyTooLow {
+                frequency: self.frequency,
+                min_frequency,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that the payload prefix has the length expected by the payload format.
+    pub fn check_payload_prefix(&self) -> Result<(), ConfigError> {
+        if self.payload_prefix.len() != PAYLOAD_PREFIX_LEN {
+            return Err(ConfigError::InvalidPayloadPrefix(
+                self.payload_prefix.clone(),
+                PAYLOAD_PREFIX_LEN,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks that the change address, if any, belongs to the configured network.
+    pub fn check_change_address(&self) -> Result<(), ConfigError> {
+        if let Some(ref addr) = self.change_address {
+            if !addr.is_valid_for(self.network) {
+                return Err(ConfigError::InvalidChangeAddress(
+                    AddrError::NetworkMismatch {
+                        address: addr.to_string(),
+                        actual: addr.network,
+                        expected: self.network,
+                    },
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the fixed anchoring output value is above the dust threshold
+    /// and the change address is set for the configured network. The change address
+    /// also requires a funding transaction to the anchoring address, since the anchoring
+    /// chain itself does not keep the funds for the next anchoring transaction.
+    pub fn check_anchor_output(&self) -> Result<(), ConfigError> {
+        self.check_change_address()?;
+        if let Some(value) = self.anchor_output_value {
+            if self.change_address.is_none() {
+                return Err(ConfigError::ChangeAddressNotSet);
+            }
+            let threshold = dust_threshold(&self.redeem_script().1.script_pubkey());
+            if value < threshold {
+                return Err(ConfigError::AnchorOutputBelowDust { value, threshold });
+            }
+        }
+        if self.change_address.is_some() {
+            let addr = self.redeem_script().1;
+            if self.funding_txs().iter().all(|tx| tx.find_out(&addr).is_none()) {
+                return Err(ConfigError::AnchorOutputWithoutFunding);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the output of the funding transaction to the anchoring address is enough
+    /// to pay the given `fee` of the first anchoring transaction and keep its anchoring output,
+    /// as well as the change output if the `change_address` is set, above the dust threshold.
+    ///
+    /// With the `change_address` every anchoring transaction consumes the whole output
+    /// of a funding transaction, so each of the funding transactions to the anchoring address
+    /// is checked.
+    pub fn check_funding_amount(&self, fee: u64) -> Result<(), ConfigError> {
+        let addr = self.redeem_script().1;
+        let (funding_txs, required) = match self.anchor_output() {
+            Some((value, change_addr)) => {
+                let funding_txs = self.funding_txs()
+                    .into_iter()
+                    .filter(|tx| tx.find_out(&addr).is_some())
+                    .collect::<Vec<_>>();
+                let required = fee + value + dust_threshold(&change_addr.script_pubkey());
+                (funding_txs, required)
+            }
+            None => {
+                let funding_txs = self.funding_tx.iter().collect::<Vec<_>>();
+                (funding_txs, fee + dust_threshold(&addr.script_pubkey()))
+            }
+        };
+        for funding_tx in funding_txs {
+            let amount = funding_tx
+                .find_out(&addr)
+                .map_or(0, |out| funding_tx.output[out as usize].value);
+            if amount < required {
+                return Err(ConfigError::InsufficientFunding {
+                    txid: funding_tx.id().to_string(),
+                    amount,
+                    required,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the largest fee of the first anchoring transaction under the fee strategy.
+    /// The estimated fee is bounded by the `max_fee_rate`, the static `fee` is returned
+    /// if the size of the first anchoring transaction is unknown.
+    pub fn max_anchoring_fee(&self) -> u64 {
+        match self.fee_strategy {
+            FeeStrategyConfig::Static => self.fee,
+            FeeStrategyConfig::EstimateSmartFee { max_fee_rate, .. } => self
+                .first_anchoring_tx_vsize()
+                .map_or(self.fee, |vsize| vsize * max_fee_rate / 1000),
+        }
+    }
+
+    /// Returns the estimated virtual size of the first anchoring transaction that spends
+    /// the funding transaction if the latter is set.
+    pub fn first_anchoring_tx_vsize(&self) -> Option<u64> {
+        let funding_tx = self.funding_tx.as_ref()?;
+        let (redeem_script, addr) = self.redeem_script();
+        let out = funding_tx.find_out(&addr)?;
+        let draft = TransactionBuilder::with_prev_tx(funding_tx, out)
+            .payload(Height::zero(), Hash::zero())
+            .payload_prefix(self.payload_prefix.as_bytes())
+            .table_root(self.anchored_table.map(|_| Hash::zero()))
+            .anchor_output(self.anchor_output())
+            .send_to(addr)
+            .fee(0)
+            .into_transaction()
+            .ok()?;
+        Some(estimate_vsize(
+            &draft.0,
+            &redeem_script,
+            self.majority_count() as usize,
+        ))
+    }
+
+    /// Returns the value of the anchoring output along with the change address if the latter
+    /// is set. The value is the `anchor_output_value` or the dust threshold by default.
+    pub fn anchor_output(&self) -> Option<(u64, btc::Address)> {
+        let addr = self.change_address.as_ref()?;
+        let value = self.anchor_output_value
+            .unwrap_or_else(|| dust_threshold(&self.redeem_script().1.script_pubkey()));
+        Some((value, addr.clone()))
+    }
+
+    /// Classifies the given transaction, only the payloads tagged by the `payload_prefix`
+    /// are treated as anchoring ones.
+    pub fn tx_kind<T: Into<RawBitcoinTx>>(&self, tx: T) -> TxKind {
+        TxKind::with_prefix(tx.into(), self.payload_prefix.as_bytes())
+    }
+
     #[doc(hidden)]
     pub fn majority_count(&self) -> u8 {
         ::majority_count(self.anchoring_keys.len() as u8)
@@ -129,6 +497,119 @@ impl AnchoringConfig {
              funding_tx",
         )
     }
+
+    /// Returns the `funding_tx` followed by the additional funding transactions.
+    pub fn funding_txs(&self) -> Vec<&FundingTx> {
+        self.funding_tx
+            .iter()
+            .chain(self.additional_funding_txs.iter())
+            .collect()
+    }
+
+    /// Returns the funding transaction with the given `txid` if it is specified.
+    pub fn find_funding_tx(&self, txid: &btc::TxId) -> Option<&FundingTx> {
+        self.funding_txs().into_iter().find(|tx| tx.id() == *txid)
+    }
+
+    /// Returns the TOML representation of the configuration of the
+    /// `ANCHORING_CONFIG_TOML_VERSION` schema. Along with the configuration fields
+    /// it contains the `version` of the schema and the signing `threshold`
+    /// of the anchoring keys.
+    pub fn to_toml(&self) -> String {
+        let repr = AnchoringConfigToml {
+            version: ANCHORING_CONFIG_TOML_VERSION,
+            network: self.network,
+            anchoring_keys: self.anchoring_keys.clone(),
+            threshold: self.majority_count(),
+            fee: self.fee,
+            frequency: self.frequency,
+            utxo_confirmations: self.utxo_confirmations,
+            funding_tx: self.funding_tx.clone(),
+            additional_funding_txs: self.additional_funding_txs.clone(),
+            payload_prefix: self.payload_prefix.clone(),
+            anchor_output_value: self.anchor_output_value,
+            change_address: self.change_address.as_ref().map(ToString::to_string),
+            sighash_type: self.sighash_type,
+            max_tx_vsize: self.max_tx_vsize,
+            anchored_blocks_retention: self.anchored_blocks_retention,
+            rbf_unconfirmed_btc_blocks: self.rbf_unconfirmed_btc_blocks,
+            fee_strategy: self.fee_strategy,
+            anchored_table: self.anchored_table,
+        };
+        toml::to_string(&repr).expect("Anchoring configuration is always representable in TOML")
+    }
+
+    /// Parses the configuration from the TOML representation produced by `to_toml`.
+    ///
+    /// Unlike the serde deserialization, the unknown fields and the missing required ones
+    /// are rejected, as well as the schema of another `version`, the `threshold`
+    /// that differs from the one of the anchoring keys, the payload prefix of the wrong
+    /// length and the change address of another network.
+    pub fn from_toml(s: &str) -> Result<AnchoringConfig, ConfigError> {
+        let value = s.parse::<toml::Value>()
+            .map_err(|e| ConfigError::Toml(e.to_string()))?;
+        let version = value
+            .as_table()
+            .and_then(|table| table.get("version"))
+            .ok_or_else(|| ConfigError::Toml("missing field `version`".to_owned()))?;
+        if version.as_integer() != Some(i64::from(ANCHORING_CONFIG_TOML_VERSION)) {
+            return Err(ConfigError::UnsupportedTomlVersion {
+                version: version.to_string(),
+                supported: ANCHORING_CONFIG_TOML_VERSION,
+            });
+        }
+
+        let repr: AnchoringConfigToml = value
+            .try_into()
+            .map_err(|e| ConfigError::Toml(e.to_string()))?;
+        let change_address = match repr.change_address {
+            Some(ref addr) => Some(btc::Address::from_str_checked(addr, repr.network)
+                .map_err(ConfigError::InvalidChangeAddress)?),
+            None => None,
+        };
+        let cfg = AnchoringConfig {
+            anchoring_keys: repr.anchoring_keys,
+            funding_tx: repr.funding_tx,
+            additional_funding_txs: repr.additional_funding_txs,
+            fee: repr.fee,
+            fee_strategy: repr.fee_strategy,
+            frequency: repr.frequency,
+            utxo_confirmations: repr.utxo_confirmations,
+            network: repr.network,
+            payload_prefix: repr.payload_prefix,
+            anchor_output_value: repr.anchor_output_value,
+            change_address,
+            sighash_type: repr.sighash_type,
+            max_tx_vsize: repr.max_tx_vsize,
+            anchored_table: repr.anchored_table,
+            anchored_blocks_retention: repr.anchored_blocks_retention,
+            rbf_unconfirmed_btc_blocks: repr.rbf_unconfirmed_btc_blocks,
+        };
+        if repr.threshold != cfg.majority_count() {
+            return Err(ConfigError::ThresholdMismatch {
+                threshold: repr.threshold,
+                expected: cfg.majority_count(),
+            });
+        }
+        cfg.check_payload_prefix()?;
+        Ok(cfg)
+    }
+}
+
+fn default_payload_prefix() -> String {
+    DEFAULT_PAYLOAD_PREFIX.to_owned()
+}
+
+fn is_default_payload_prefix(prefix: &str) -> bool {
+    prefix == DEFAULT_PAYLOAD_PREFIX
+}
+
+fn default_max_tx_vsize() -> u64 {
+    MAX_STANDARD_TX_VSIZE
+}
+
+fn is_default_max_tx_vsize(vsize: &u64) -> bool {
+    *vsize == MAX_STANDARD_TX_VSIZE
 }
 
 fn btc_network_to_str<S>(network: &btc::Network, ser: S) -> Result<S::Ok, S::Error>
@@ -148,6 +629,35 @@ where
         .map_err(|_| ::serde::de::Error::unknown_variant(&s, VARIANTS))
 }
 
+impl<'de> Deserialize<'de> for AnchoringConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let repr = AnchoringConfigRepr::deserialize(deserializer)?;
+        let cfg = AnchoringConfig {
+            anchoring_keys: repr.anchoring_keys,
+            funding_tx: repr.funding_tx,
+            additional_funding_txs: repr.additional_funding_txs,
+            fee: repr.fee,
+            fee_strategy: repr.fee_strategy,
+            frequency: repr.frequency,
+            utxo_confirmations: repr.utxo_confirmations,
+            network: repr.network,
+            payload_prefix: repr.payload_prefix,
+            anchor_output_value: repr.anchor_output_value,
+            change_address: repr.change_address,
+            sighash_type: repr.sighash_type,
+            max_tx_vsize: repr.max_tx_vsize,
+            anchored_table: repr.anchored_table,
+            anchored_blocks_retention: repr.anchored_blocks_retention,
+            rbf_unconfirmed_btc_blocks: repr.rbf_unconfirmed_btc_blocks,
+        };
+        cfg.check_change_address().map_err(::serde::de::Error::custom)?;
+        Ok(cfg)
+    }
+}
+
 impl StorageValue for AnchoringConfig {
     fn into_bytes(self) -> Vec<u8> {
         serde_json::to_vec(&self).unwrap()
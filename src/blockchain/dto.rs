@@ -23,6 +23,10 @@ use service::ANCHORING_SERVICE_ID;
 
 pub const ANCHORING_MESSAGE_SIGNATURE: u16 = 0;
 pub const ANCHORING_MESSAGE_LATEST: u16 = 1;
+pub const ANCHORING_MESSAGE_TOP_UP: u16 = 2;
+pub const ANCHORING_MESSAGE_FEE_RATE: u16 = 3;
+pub const ANCHORING_MESSAGE_REPLACE_REQUEST: u16 = 4;
+pub const ANCHORING_MESSAGE_ANCHOR_REQUEST: u16 = 5;
 
 transactions! {
     Messages {
@@ -52,6 +56,46 @@ transactions! {
             /// Current lects count in the `lects` table for the current validator.
             lect_count: u64,
         }
+        /// Exonum message with the funding transaction sent by the validator
+        /// to top up the anchoring address.
+        struct MsgAnchoringTopUp {
+            /// Public key of validator.
+            from: &PublicKey,
+            /// Public key index in anchoring public keys list.
+            validator: ValidatorId,
+            /// Funding transaction content.
+            tx: BitcoinTx,
+        }
+        /// Exonum message with the fee rate of the anchoring transactions estimated
+        /// by the validator.
+        struct MsgAnchoringFeeRate {
+            /// Public key of validator.
+            from: &PublicKey,
+            /// Public key index in anchoring public keys list.
+            validator: ValidatorId,
+            /// Fee rate in satoshis per 1000 virtual bytes.
+            fee_per_kb: u64,
+        }
+        /// Exonum message with the vote of the validator for replacing the given lect
+        /// by the transaction with a higher fee.
+        struct MsgAnchoringReplaceRequest {
+            /// Public key of validator.
+            from: &PublicKey,
+            /// Public key index in anchoring public keys list.
+            validator: ValidatorId,
+            /// Unconfirmed lect to be replaced.
+            tx: AnchoringTx,
+        }
+        /// Exonum message with the vote of the validator for anchoring the block
+        /// at the given height out of the anchoring schedule.
+        struct MsgAnchoringAnchorRequest {
+            /// Public key of validator.
+            from: &PublicKey,
+            /// Public key index in anchoring public keys list.
+            validator: ValidatorId,
+            /// Height of the block to anchor.
+            height: u64,
+        }
     }
 }
 
@@ -65,6 +109,34 @@ encoding_struct! {
     }
 }
 
+encoding_struct! {
+    /// Funding transaction that has fed the anchoring address.
+    struct FundingTxRecord {
+        /// Funding transaction content.
+        tx: BitcoinTx,
+        /// Amount sent to the anchoring address in satoshis.
+        amount: u64,
+        /// Height of the block in which the transaction has become known.
+        height: u64,
+    }
+}
+
+encoding_struct! {
+    /// Lect that is rejected because it conflicts with the agreed lect of the validator.
+    struct LectConflict {
+        /// Hash of exonum transaction that contains the rejected lect.
+        msg_hash: &Hash,
+        /// Public key index in anchoring public keys list.
+        validator: ValidatorId,
+        /// Height of the block anchored by both lects.
+        height: u64,
+        /// Agreed lect of the validator.
+        agreed_tx: BitcoinTx,
+        /// Rejected lect.
+        conflicting_tx: BitcoinTx,
+    }
+}
+
 /// Constructs anchoring transaction from the given raw message.
 pub(crate) fn tx_from_raw(raw: RawTransaction) -> Result<Box<Transaction>, EncodingError> {
     Messages::tx_from_raw(raw).map(Into::into)
@@ -45,6 +45,21 @@ pub enum Error {
     /// Received another signature for given tx propose
     #[display(fmt = "Received another signature for given tx propose")]
     SignatureDifferent = 8,
+    /// Received top-up with the transaction that does not fund the actual anchoring address
+    #[display(fmt = "Received top-up with incorrect funding_tx")]
+    TopUpWithIncorrectFunding = 9,
+    /// Received signature of the type that differs from the configured one
+    #[display(fmt = "Received signature with unexpected sighash type")]
+    SignatureWithUnexpectedSighash = 10,
+    /// Received lect for the position that the validator lects have already passed
+    #[display(fmt = "Received lect with outdated count")]
+    LectStale = 11,
+    /// Received replace request for the transaction that is not the agreed lect
+    #[display(fmt = "Received replace request for non-lect")]
+    ReplacementOfNonLect = 12,
+    /// Received anchor request for the block that is not committed yet
+    #[display(fmt = "Received anchor request for uncommitted block")]
+    AnchorRequestOfUnknownBlock = 13,
 }
 
 impl Error {
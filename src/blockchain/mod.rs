@@ -27,6 +27,8 @@ mod tests;
 #[doc(hidden)]
 pub mod transactions;
 
-pub use self::dto::{LectContent, MsgAnchoringSignature, MsgAnchoringUpdateLatest};
+pub use self::dto::{FundingTxRecord, LectConflict, LectContent, MsgAnchoringAnchorRequest,
+                    MsgAnchoringFeeRate, MsgAnchoringReplaceRequest, MsgAnchoringSignature,
+                    MsgAnchoringTopUp, MsgAnchoringUpdateLatest};
 pub use self::error::Error;
-pub use self::schema::{AnchoringSchema, KnownSignatureId};
+pub use self::schema::{AnchoringSchema, BtcHeightKey, KnownSignatureId};
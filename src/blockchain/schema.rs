@@ -12,23 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::cmp;
 use std::collections::hash_map::{Entry, HashMap};
 
 use byteorder::{BigEndian, ByteOrder};
 use serde_json::value::from_value;
 
-use exonum::blockchain::{Schema, StoredConfiguration};
+use exonum::blockchain::{Blockchain, Schema, StoredConfiguration};
 use exonum::crypto::Hash;
 use exonum::helpers::{Height, ValidatorId};
-use exonum::storage::{Fork, ListIndex, MapIndex, ProofListIndex, Snapshot, StorageKey};
+use exonum::storage::{Entry as ValueEntry, Fork, KeySetIndex, ListIndex, MapIndex, ProofListIndex,
+                      Snapshot, StorageKey};
 
 use super::Error as ValidateError;
-use blockchain::consensus_storage::AnchoringConfig;
-use blockchain::dto::{LectContent, MsgAnchoringSignature};
+use blockchain::consensus_storage::{AnchoringConfig, FeeStrategyConfig};
+use blockchain::dto::{FundingTxRecord, LectConflict, LectContent, MsgAnchoringSignature};
 use details::btc;
-use details::btc::transactions::{AnchoringTx, BitcoinTx};
+use details::btc::transactions::{AnchoringTx, BitcoinTx, FundingTx, TxKind};
 use service::ANCHORING_SERVICE_NAME;
 
+/// Maximal number of the anchored block hashes that are pruned by a single block,
+/// so enabling the retention on a long chain does not slow down the block commit.
+pub const MAX_PRUNED_BLOCKS_PER_COMMIT: u64 = 1_000;
+
 /// Unique identifier of signature for the `AnchoringTx`.
 #[derive(Debug, Clone)]
 pub struct KnownSignatureId {
@@ -73,6 +79,34 @@ impl<'a> From<&'a MsgAnchoringSignature> for KnownSignatureId {
     }
 }
 
+/// Height of the bitcoin block that contains the anchoring transaction along with
+/// the height of the anchored block. The keys are ordered by the bitcoin heights first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BtcHeightKey {
+    /// Height of the bitcoin block.
+    pub btc_height: u64,
+    /// Height of the anchored block.
+    pub height: u64,
+}
+
+impl StorageKey for BtcHeightKey {
+    fn size(&self) -> usize {
+        16
+    }
+
+    fn write(&self, buffer: &mut [u8]) {
+        BigEndian::write_u64(&mut buffer[0..8], self.btc_height);
+        BigEndian::write_u64(&mut buffer[8..16], self.height);
+    }
+
+    fn read(buffer: &[u8]) -> Self {
+        BtcHeightKey {
+            btc_height: u64::read(&buffer[0..8]),
+            height: u64::read(&buffer[8..16]),
+        }
+    }
+}
+
 /// Anchoring information schema.
 #[derive(Debug)]
 pub struct AnchoringSchema<T> {
@@ -105,6 +139,16 @@ where
         MapIndex::new_in_family("btc_anchoring.lect_indexes", validator_key, &self.view)
     }
 
+    /// Returns table that keeps the rejected lects of the validator with the given
+    /// `validator_key` that conflict with its agreed lects, where key is the txid
+    /// of the rejected lect. Only the first rejection of the lect is recorded.
+    pub fn lect_conflicts(
+        &self,
+        validator_key: &btc::PublicKey,
+    ) -> MapIndex<&T, btc::TxId, LectConflict> {
+        MapIndex::new_in_family("btc_anchoring.lect_conflicts", validator_key, &self.view)
+    }
+
     /// Returns the table of known signatures, where key is the tuple `(txid, validator_id, input)`.
     ///
     /// [Read more](struct.KnownSignatureId.html).
@@ -112,21 +156,161 @@ where
         MapIndex::new("btc_anchoring.known_signatures", &self.view)
     }
 
+    /// Returns the table that keeps the latest fee rate of the anchoring transactions
+    /// estimated by the validator with the given `anchoring_key`.
+    pub fn fee_rates(&self) -> MapIndex<&T, btc::PublicKey, u64> {
+        MapIndex::new("btc_anchoring.fee_rates", &self.view)
+    }
+
+    /// Returns the table that keeps the unconfirmed lect which the validator with
+    /// the given `anchoring_key` has voted to replace by the transaction with a higher fee.
+    pub fn replacement_requests(&self) -> MapIndex<&T, btc::PublicKey, btc::TxId> {
+        MapIndex::new("btc_anchoring.replacement_requests", &self.view)
+    }
+
+    /// Returns the table that keeps the height of the block which the validator with
+    /// the given `anchoring_key` has voted to anchor out of the anchoring schedule.
+    pub fn anchor_requests(&self) -> MapIndex<&T, btc::PublicKey, u64> {
+        MapIndex::new("btc_anchoring.anchor_requests", &self.view)
+    }
+
     /// Returns the table that keeps the anchoring transaction for any known txid.
     pub fn known_txs(&self) -> MapIndex<&T, btc::TxId, BitcoinTx> {
         MapIndex::new("btc_anchoring.known_txs", &self.view)
     }
 
+    /// Returns table that maps `txid` of the funding transaction to the `txid`
+    /// of the agreed anchoring transaction that spends it.
+    pub fn spent_funding_txs(&self) -> MapIndex<&T, btc::TxId, btc::TxId> {
+        MapIndex::new("btc_anchoring.spent_funding_txs", &self.view)
+    }
+
+    /// Returns table that keeps the funding transactions sent by the validators
+    /// to top up the anchoring address.
+    pub fn top_up_funding_txs(&self) -> MapIndex<&T, btc::TxId, BitcoinTx> {
+        MapIndex::new("btc_anchoring.top_up_funding_txs", &self.view)
+    }
+
+    /// Returns table that keeps the top-ups of the anchoring address in the order they have
+    /// been committed, along with their amounts and heights.
+    pub fn top_up_history(&self) -> ListIndex<&T, FundingTxRecord> {
+        ListIndex::new("btc_anchoring.top_up_history", &self.view)
+    }
+
+    /// Returns table that keeps every funding transaction of the anchoring addresses
+    /// in the order they have become known, including the spent ones.
+    pub fn funding_history(&self) -> ListIndex<&T, FundingTxRecord> {
+        ListIndex::new("btc_anchoring.funding_history", &self.view)
+    }
+
+    /// Returns table that keeps the position in the [`funding_history`][1] for every
+    /// funding txid.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.funding_history
+    pub fn funding_history_indexes(&self) -> MapIndex<&T, btc::TxId, u64> {
+        MapIndex::new("btc_anchoring.funding_history_indexes", &self.view)
+    }
+
     /// Returns table that maps anchoring transactions to their heights.
     pub fn anchoring_tx_chain(&self) -> MapIndex<&T, u64, AnchoringTx> {
         MapIndex::new("btc_anchoring.tx_chain", &self.view)
     }
 
+    /// Returns the heights of the anchored blocks from the [`anchoring_tx_chain`][1] in the
+    /// ascending order, so the latest transactions of the chain are reached without
+    /// iterating over the whole chain.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchoring_tx_chain
+    pub fn anchoring_tx_chain_heights(&self) -> ListIndex<&T, u64> {
+        ListIndex::new("btc_anchoring.tx_chain_heights", &self.view)
+    }
+
+    /// Returns the anchoring transaction for the nearest anchored block with a height greater
+    /// or equal than the given `height`.
+    ///
+    /// The lookup seeks directly to the given key instead of scanning the whole chain.
+    pub fn nearest_anchoring_tx(&self, height: u64) -> Option<AnchoringTx> {
+        self.anchoring_tx_chain().values_from(&height).next()
+    }
+
+    /// Returns table that maps the heights of the anchored blocks to the hashes of the bitcoin
+    /// blocks that contain the corresponding transactions from the [`anchoring_tx_chain`][1].
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchoring_tx_chain
+    pub fn anchoring_tx_block_hashes(&self) -> MapIndex<&T, u64, btc::BlockHash> {
+        MapIndex::new("btc_anchoring.tx_chain_block_hashes", &self.view)
+    }
+
+    /// Returns table that maps the heights of the anchored blocks to the heights of the bitcoin
+    /// blocks that contain the corresponding transactions from the [`anchoring_tx_chain`][1].
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchoring_tx_chain
+    pub fn anchoring_tx_btc_heights(&self) -> MapIndex<&T, u64, u64> {
+        MapIndex::new("btc_anchoring.tx_chain_btc_heights", &self.view)
+    }
+
+    /// Returns table that maps the heights of the anchored blocks to the timestamps of the
+    /// bitcoin blocks that contain the corresponding transactions from the
+    /// [`anchoring_tx_chain`][1].
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchoring_tx_chain
+    pub fn anchoring_tx_btc_times(&self) -> MapIndex<&T, u64, u64> {
+        MapIndex::new("btc_anchoring.tx_chain_btc_times", &self.view)
+    }
+
+    /// Returns table that keeps the entries of the [`anchoring_tx_btc_heights`][1] ordered
+    /// by the heights of the bitcoin blocks.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchoring_tx_btc_heights
+    pub fn anchored_heights_by_btc_height(&self) -> KeySetIndex<&T, BtcHeightKey> {
+        KeySetIndex::new("btc_anchoring.tx_chain_heights_by_btc_height", &self.view)
+    }
+
+    /// Returns the depth of the bitcoin blockchain reorganization that orphans the blocks
+    /// of the [`anchoring_tx_chain`][1] and exceeds the `max_reorg_depth` of the observer.
+    ///
+    /// The entry is set while the observer waits for the manual intervention and does not
+    /// follow the anchoring chain.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchoring_tx_chain
+    pub fn observer_reorg_depth(&self) -> ValueEntry<&T, u64> {
+        ValueEntry::new("btc_anchoring.observer_reorg_depth", &self.view)
+    }
+
     /// Returns a list of hashes of Exonum blocks headers.
     pub fn anchored_blocks(&self) -> ProofListIndex<&T, Hash> {
         ProofListIndex::new("btc_anchoring.anchored_blocks", &self.view)
     }
 
+    /// Returns the height below which the hashes in the [`anchored_blocks`][1] are pruned,
+    /// see `AnchoringConfig::anchored_blocks_retention`. The proofs for the blocks below
+    /// this height are unavailable.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchored_blocks
+    pub fn anchored_blocks_cutoff(&self) -> ValueEntry<&T, u64> {
+        ValueEntry::new("btc_anchoring.anchored_blocks_cutoff", &self.view)
+    }
+
+    /// Returns a table that keeps the root hashes of the anchored table
+    /// by the heights of the blocks, see `AnchoringConfig::anchored_table`.
+    pub fn anchored_table_roots(&self) -> MapIndex<&T, u64, Hash> {
+        MapIndex::new("btc_anchoring.anchored_table_roots", &self.view)
+    }
+
+    /// Returns the root hash of the table that is anchored at the given committed `height`
+    /// or `None` if the configuration at this height does not specify the anchored table.
+    pub fn anchored_table_root(&self, height: Height) -> Option<Hash> {
+        let table = self.anchoring_config_by_height(height).anchored_table?;
+        let schema = Schema::new(&self.view);
+        if height.0 + 1 == schema.block_hashes_by_height().len() {
+            // The root of the last committed block is recorded only in the next one.
+            let key = Blockchain::service_table_unique_key(table.service_id, table.table_idx);
+            schema.state_hash_aggregator().get(&key)
+        } else {
+            self.anchored_table_roots().get(&height.0)
+        }
+    }
+
     /// Returns the actual anchoring configuration.
     pub fn actual_anchoring_config(&self) -> AnchoringConfig {
         let schema = Schema::new(&self.view);
@@ -167,6 +351,24 @@ where
         self.parse_config(&stored)
     }
 
+    /// Returns the configurations that have taken effect along with their activation
+    /// heights, from the genesis one to the actual one.
+    pub fn anchoring_configs_history(&self) -> Vec<(Height, AnchoringConfig)> {
+        let schema = Schema::new(&self.view);
+        let next_height = Height(schema.block_hashes_by_height().len());
+        schema
+            .configs_actual_from()
+            .iter()
+            .take_while(|cfg_ref| cfg_ref.actual_from() <= next_height)
+            .map(|cfg_ref| {
+                let stored = schema
+                    .configuration_by_hash(cfg_ref.cfg_hash())
+                    .expect("Configuration is absent in the configs table");
+                (cfg_ref.actual_from(), self.parse_config(&stored))
+            })
+            .collect()
+    }
+
     /// Returns `lect` for validator with the given `public_key`.
     pub fn lect(&self, validator_key: &btc::PublicKey) -> Option<BitcoinTx> {
         self.lects(validator_key).last().map(|x| x.tx())
@@ -211,6 +413,97 @@ where
         }
     }
 
+    /// Returns the median of the fee rates estimated by the anchoring keys of the given
+    /// config if at least +2/3 of them have estimated the rate. The median is limited
+    /// by the `max_fee_rate` of the config.
+    pub fn agreed_fee_rate(&self, cfg: &AnchoringConfig) -> Option<u64> {
+        let fee_rates = self.fee_rates();
+        let mut rates = cfg.anchoring_keys
+            .iter()
+            .filter_map(|key| fee_rates.get(key))
+            .collect::<Vec<_>>();
+        if rates.len() < cfg.majority_count() as usize {
+            return None;
+        }
+        rates.sort();
+        let fee_rate = rates[rates.len() / 2];
+        match cfg.fee_strategy {
+            FeeStrategyConfig::EstimateSmartFee { max_fee_rate, .. } => {
+                Some(cmp::min(fee_rate, max_fee_rate))
+            }
+            FeeStrategyConfig::Static => Some(fee_rate),
+        }
+    }
+
+    /// Returns the total amount of the top-ups committed at the given `height` or later.
+    pub fn top_ups_amount_since(&self, height: Height) -> u64 {
+        let history = self.top_up_history();
+        let mut amount = 0;
+        for idx in (0..history.len()).rev() {
+            let record = history.get(idx).unwrap();
+            if record.height() < height.0 {
+                break;
+            }
+            amount += record.amount();
+        }
+        amount
+    }
+
+    /// Returns the funding transactions of the given `cfg` followed by the committed
+    /// top-ups that are not in the `cfg` yet.
+    pub fn funding_txs(&self, cfg: &AnchoringConfig) -> Vec<FundingTx> {
+        let mut funding_txs = cfg.funding_txs().into_iter().cloned().collect::<Vec<_>>();
+        for tx in self.top_up_funding_txs().values() {
+            if cfg.find_funding_tx(&tx.id()).is_none() {
+                funding_txs.push(FundingTx::from(tx));
+            }
+        }
+        funding_txs
+    }
+
+    /// Returns the funding transaction with the given `txid` if it is specified
+    /// in the given `cfg` or has been committed as a top-up.
+    pub fn find_funding_tx(&self, cfg: &AnchoringConfig, txid: &btc::TxId) -> Option<FundingTx> {
+        cfg.find_funding_tx(txid)
+            .cloned()
+            .or_else(|| self.top_up_funding_txs().get(txid).map(FundingTx::from))
+    }
+
+    /// Checks whether +2/3 of the anchoring keys of the given config have voted
+    /// to replace the lect with the given `txid`.
+    pub fn replacement_agreed(&self, cfg: &AnchoringConfig, txid: &btc::TxId) -> bool {
+        let requests = self.replacement_requests();
+        let count = cfg.anchoring_keys
+            .iter()
+            .filter(|key| requests.get(key).as_ref() == Some(txid))
+            .count();
+        count >= cfg.majority_count() as usize
+    }
+
+    /// Returns the highest height of the block that +2/3 of the anchoring keys of the given
+    /// config have voted to anchor, a vote for the higher block counts for the lower one.
+    pub fn agreed_anchor_request(&self, cfg: &AnchoringConfig) -> Option<Height> {
+        let requests = self.anchor_requests();
+        let mut heights = cfg.anchoring_keys
+            .iter()
+            .filter_map(|key| requests.get(key))
+            .collect::<Vec<_>>();
+        let majority_count = cfg.majority_count() as usize;
+        if heights.len() < majority_count {
+            return None;
+        }
+        heights.sort_by(|a, b| b.cmp(a));
+        Some(Height(heights[majority_count - 1]))
+    }
+
+    /// Checks whether the funding transaction with the given `txid` has been spent
+    /// by the agreed anchoring chain, see [`spent_funding_txs`][1].
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.spent_funding_txs
+    pub fn funding_tx_is_spent(&self, txid: &btc::TxId) -> bool {
+        self.spent_funding_txs().contains(txid)
+    }
+
     /// Returns position in `lects` table of validator with the given `anchoring_key`
     /// for transaction with the given `txid`.
     pub fn find_lect_position(
@@ -221,6 +514,64 @@ where
         self.lect_indexes(anchoring_key).get(txid)
     }
 
+    /// Returns the current lect of the validator with the given `validator_key` if the given
+    /// lect `tx` conflicts with it.
+    ///
+    /// The lects conflict if the current one is agreed by the validators from the actual
+    /// configuration and both of them anchor the same block, but the new one neither spends
+    /// the current one, e.g. to transfer the funds to the following address, nor replaces it
+    /// by spending the same outputs with a higher fee.
+    pub fn conflicting_lect(
+        &self,
+        validator_key: &btc::PublicKey,
+        tx: &BitcoinTx,
+    ) -> Option<AnchoringTx> {
+        let cfg = self.actual_anchoring_config();
+        let tx = match cfg.tx_kind(tx.clone()) {
+            TxKind::Anchoring(tx) => tx,
+            TxKind::FundingTx(_) | TxKind::Other(_) => return None,
+        };
+        let lect = match self.lect(validator_key).map(|lect| cfg.tx_kind(lect)) {
+            Some(TxKind::Anchoring(lect)) => lect,
+            _ => return None,
+        };
+        if lect.id() == tx.id() || lect.payload().block_height != tx.payload().block_height {
+            return None;
+        }
+        if tx.prev_hash() == lect.id() || is_fee_replacement(&lect, &tx) {
+            return None;
+        }
+
+        let txid = lect.id();
+        let supporters_count = cfg.anchoring_keys
+            .iter()
+            .filter(|key| self.find_lect_position(key, &txid).is_some())
+            .count();
+        if supporters_count >= cfg.majority_count() as usize {
+            Some(lect)
+        } else {
+            None
+        }
+    }
+
+    /// Checks whether +2/3 of the anchoring keys of the actual configuration have reported
+    /// the conflicting lect with the given `txid` or have already switched to it.
+    ///
+    /// This is the explicit way to replace the agreed lect, e.g. after the reorganization
+    /// of the bitcoin blockchain, while the conflicting lect of the single validator
+    /// is never accepted.
+    pub fn conflicting_lect_agreed(&self, txid: &btc::TxId) -> bool {
+        let cfg = self.actual_anchoring_config();
+        let count = cfg.anchoring_keys
+            .iter()
+            .filter(|key| {
+                self.lect_conflicts(key).contains(txid)
+                    || self.lect(key).map(|lect| lect.id()) == Some(*txid)
+            })
+            .count();
+        count >= cfg.majority_count() as usize
+    }
+
     /// Returns the `state_hash` for anchoring tables.
     ///
     /// It contains a list of `root_hash` of the actual `lects` tables.
@@ -271,6 +622,16 @@ impl<'a> AnchoringSchema<&'a mut Fork> {
         MapIndex::new_in_family("btc_anchoring.lect_indexes", validator_key, &mut self.view)
     }
 
+    /// Mutable variant of the [`lect_conflicts`][1] index.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.lect_conflicts
+    pub fn lect_conflicts_mut(
+        &mut self,
+        validator_key: &btc::PublicKey,
+    ) -> MapIndex<&mut Fork, btc::TxId, LectConflict> {
+        MapIndex::new_in_family("btc_anchoring.lect_conflicts", validator_key, &mut self.view)
+    }
+
     /// Mutable variant of the [`known_signatures`][1] index.
     ///
     /// [1]: struct.AnchoringSchema.html#method.known_signatures
@@ -280,6 +641,27 @@ impl<'a> AnchoringSchema<&'a mut Fork> {
         MapIndex::new("btc_anchoring.known_signatures", &mut self.view)
     }
 
+    /// Mutable variant of the [`fee_rates`][1] index.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.fee_rates
+    pub fn fee_rates_mut(&mut self) -> MapIndex<&mut Fork, btc::PublicKey, u64> {
+        MapIndex::new("btc_anchoring.fee_rates", &mut self.view)
+    }
+
+    /// Mutable variant of the [`replacement_requests`][1] index.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.replacement_requests
+    pub fn replacement_requests_mut(&mut self) -> MapIndex<&mut Fork, btc::PublicKey, btc::TxId> {
+        MapIndex::new("btc_anchoring.replacement_requests", &mut self.view)
+    }
+
+    /// Mutable variant of the [`anchor_requests`][1] index.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchor_requests
+    pub fn anchor_requests_mut(&mut self) -> MapIndex<&mut Fork, btc::PublicKey, u64> {
+        MapIndex::new("btc_anchoring.anchor_requests", &mut self.view)
+    }
+
     /// Mutable variant of the [`known_txs`][1] index.
     ///
     /// [1]: struct.AnchoringSchema.html#method.known_txs
@@ -287,6 +669,41 @@ impl<'a> AnchoringSchema<&'a mut Fork> {
         MapIndex::new("btc_anchoring.known_txs", &mut self.view)
     }
 
+    /// Mutable variant of the [`spent_funding_txs`][1] index.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.spent_funding_txs
+    pub fn spent_funding_txs_mut(&mut self) -> MapIndex<&mut Fork, btc::TxId, btc::TxId> {
+        MapIndex::new("btc_anchoring.spent_funding_txs", &mut self.view)
+    }
+
+    /// Mutable variant of the [`top_up_funding_txs`][1] index.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.top_up_funding_txs
+    pub fn top_up_funding_txs_mut(&mut self) -> MapIndex<&mut Fork, btc::TxId, BitcoinTx> {
+        MapIndex::new("btc_anchoring.top_up_funding_txs", &mut self.view)
+    }
+
+    /// Mutable variant of the [`top_up_history`][1] index.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.top_up_history
+    pub fn top_up_history_mut(&mut self) -> ListIndex<&mut Fork, FundingTxRecord> {
+        ListIndex::new("btc_anchoring.top_up_history", &mut self.view)
+    }
+
+    /// Mutable variant of the [`funding_history`][1] index.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.funding_history
+    pub fn funding_history_mut(&mut self) -> ListIndex<&mut Fork, FundingTxRecord> {
+        ListIndex::new("btc_anchoring.funding_history", &mut self.view)
+    }
+
+    /// Mutable variant of the [`funding_history_indexes`][1] index.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.funding_history_indexes
+    pub fn funding_history_indexes_mut(&mut self) -> MapIndex<&mut Fork, btc::TxId, u64> {
+        MapIndex::new("btc_anchoring.funding_history_indexes", &mut self.view)
+    }
+
     /// Mutable variant of the [`anchoring_tx_chain`][1] index.
     ///
     /// [1]: struct.AnchoringSchema.html#method.anchoring_tx_chain
@@ -294,6 +711,70 @@ impl<'a> AnchoringSchema<&'a mut Fork> {
         MapIndex::new("btc_anchoring.tx_chain", &mut self.view)
     }
 
+    /// Mutable variant of the [`anchoring_tx_chain_heights`][1] index.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchoring_tx_chain_heights
+    pub fn anchoring_tx_chain_heights_mut(&mut self) -> ListIndex<&mut Fork, u64> {
+        ListIndex::new("btc_anchoring.tx_chain_heights", &mut self.view)
+    }
+
+    /// Mutable variant of the [`anchoring_tx_block_hashes`][1] index.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchoring_tx_block_hashes
+    pub fn anchoring_tx_block_hashes_mut(&mut self) -> MapIndex<&mut Fork, u64, btc::BlockHash> {
+        MapIndex::new("btc_anchoring.tx_chain_block_hashes", &mut self.view)
+    }
+
+    /// Mutable variant of the [`anchoring_tx_btc_heights`][1] index.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchoring_tx_btc_heights
+    pub fn anchoring_tx_btc_heights_mut(&mut self) -> MapIndex<&mut Fork, u64, u64> {
+        MapIndex::new("btc_anchoring.tx_chain_btc_heights", &mut self.view)
+    }
+
+    /// Mutable variant of the [`anchoring_tx_btc_times`][1] index.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchoring_tx_btc_times
+    pub fn anchoring_tx_btc_times_mut(&mut self) -> MapIndex<&mut Fork, u64, u64> {
+        MapIndex::new("btc_anchoring.tx_chain_btc_times", &mut self.view)
+    }
+
+    /// Mutable variant of the [`anchored_heights_by_btc_height`][1] index.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchored_heights_by_btc_height
+    pub fn anchored_heights_by_btc_height_mut(&mut self) -> KeySetIndex<&mut Fork, BtcHeightKey> {
+        KeySetIndex::new("btc_anchoring.tx_chain_heights_by_btc_height", &mut self.view)
+    }
+
+    /// Sets the height of the bitcoin block that contains the anchoring transaction
+    /// for the given anchored `height` or removes it if the `btc_height` is `None`.
+    /// The [`anchored_heights_by_btc_height`][1] index is updated accordingly.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchored_heights_by_btc_height
+    pub fn set_anchoring_tx_btc_height(&mut self, height: u64, btc_height: Option<u64>) {
+        if let Some(prev_btc_height) = self.anchoring_tx_btc_heights().get(&height) {
+            self.anchored_heights_by_btc_height_mut().remove(&BtcHeightKey {
+                btc_height: prev_btc_height,
+                height,
+            });
+        }
+        match btc_height {
+            Some(btc_height) => {
+                self.anchoring_tx_btc_heights_mut().put(&height, btc_height);
+                self.anchored_heights_by_btc_height_mut()
+                    .insert(BtcHeightKey { btc_height, height });
+            }
+            None => self.anchoring_tx_btc_heights_mut().remove(&height),
+        }
+    }
+
+    /// Mutable variant of the [`observer_reorg_depth`][1] entry.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.observer_reorg_depth
+    pub fn observer_reorg_depth_mut(&mut self) -> ValueEntry<&mut Fork, u64> {
+        ValueEntry::new("btc_anchoring.observer_reorg_depth", &mut self.view)
+    }
+
     /// Mutable variant of the [`anchored_blocks`][1] index.
     ///
     /// [1]: struct.AnchoringSchema.html#method.anchored_blocks
@@ -301,11 +782,96 @@ impl<'a> AnchoringSchema<&'a mut Fork> {
         ProofListIndex::new("btc_anchoring.anchored_blocks", &mut self.view)
     }
 
+    /// Mutable variant of the [`anchored_blocks_cutoff`][1] entry.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchored_blocks_cutoff
+    pub fn anchored_blocks_cutoff_mut(&mut self) -> ValueEntry<&mut Fork, u64> {
+        ValueEntry::new("btc_anchoring.anchored_blocks_cutoff", &mut self.view)
+    }
+
+    /// Mutable variant of the [`anchored_table_roots`][1] index.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchored_table_roots
+    pub fn anchored_table_roots_mut(&mut self) -> MapIndex<&mut Fork, u64, Hash> {
+        MapIndex::new("btc_anchoring.anchored_table_roots", &mut self.view)
+    }
+
+    /// Remembers the root hash of the anchored table at the last committed `height`,
+    /// since the core schema keeps only the latest roots of the service tables.
+    pub fn add_anchored_table_root(&mut self, height: Height) {
+        if let Some(root) = self.anchored_table_root(height) {
+            self.anchored_table_roots_mut().put(&height.0, root);
+        }
+    }
+
+    /// Prunes the hashes of the anchored blocks that are older than the
+    /// `anchored_blocks_retention` of the given `cfg` by replacing them with zeros.
+    ///
+    /// The pruning only hides the proofs of the old blocks, it does not reclaim the storage:
+    /// the [`anchored_blocks`][1] list keeps its length and merkle nodes, since its root
+    /// hash is a part of the service state hash.
+    ///
+    /// The cutoff never exceeds the height anchored by the lect agreed by the validators,
+    /// so the chain tip and the blocks after it are always verifiable. The cutoff only grows
+    /// and at most `MAX_PRUNED_BLOCKS_PER_COMMIT` hashes are pruned per call.
+    ///
+    /// [1]: struct.AnchoringSchema.html#method.anchored_blocks
+    pub fn prune_anchored_blocks(&mut self, cfg: &AnchoringConfig) {
+        let retention = match cfg.anchored_blocks_retention {
+            Some(retention) => retention,
+            None => return,
+        };
+        let tip = match self.collect_lects(cfg).map(|lect| cfg.tx_kind(lect)) {
+            Some(TxKind::Anchoring(tx)) => tx.payload().block_height.0,
+            _ => return,
+        };
+        let cutoff = self.anchored_blocks_cutoff().get().unwrap_or(0);
+        let target = cmp::min(self.anchored_blocks().len().saturating_sub(retention), tip);
+        let new_cutoff = cmp::min(target, cutoff + MAX_PRUNED_BLOCKS_PER_COMMIT);
+        if new_cutoff <= cutoff {
+            return;
+        }
+
+        {
+            let mut anchored_blocks = self.anchored_blocks_mut();
+            for height in cutoff..new_cutoff {
+                anchored_blocks.set(height, Hash::zero());
+            }
+        }
+        self.anchored_blocks_cutoff_mut().set(new_cutoff);
+    }
+
     /// Creates and commits the genesis anchoring configuration from the proposed `cfg`.
     pub fn create_genesis_config(&mut self, cfg: &AnchoringConfig) {
         for validator_key in &cfg.anchoring_keys {
             self.add_lect(validator_key, cfg.funding_tx().clone(), Hash::zero());
         }
+        self.add_funding_txs(cfg, Height::zero());
+    }
+
+    /// Adds the funding transactions from the given `cfg` to the funding history
+    /// if they are not there yet.
+    pub fn add_funding_txs(&mut self, cfg: &AnchoringConfig, height: Height) {
+        let (_, addr) = cfg.redeem_script();
+        for tx in cfg.funding_txs() {
+            self.add_funding_tx_to_history(tx, &addr, height);
+        }
+    }
+
+    fn add_funding_tx_to_history(&mut self, tx: &FundingTx, addr: &btc::Address, height: Height) {
+        let txid = tx.id();
+        if self.funding_history_indexes().contains(&txid) {
+            return;
+        }
+        let amount = tx.find_out(addr)
+            .map_or(0, |out| tx.output[out as usize].value);
+        let idx = self.funding_history().len();
+        self.funding_history_mut().push(FundingTxRecord::new(
+            BitcoinTx::from(tx.clone()),
+            amount,
+            height.0,
+        ));
+        self.funding_history_indexes_mut().put(&txid, idx);
     }
 
     /// Adds `lect` from validator with the given `public key`.
@@ -323,7 +889,50 @@ impl<'a> AnchoringSchema<&'a mut Fork> {
         };
 
         self.known_txs_mut().put(&txid, tx.clone());
-        self.lect_indexes_mut(validator_key).put(&txid, idx)
+        self.lect_indexes_mut(validator_key).put(&txid, idx);
+        if let TxKind::Anchoring(tx) = self.actual_anchoring_config().tx_kind(tx) {
+            self.mark_spent_funding_txs(&tx);
+        }
+    }
+
+    /// Adds the funding transaction sent by the validator to top up the anchoring address.
+    pub fn add_top_up_funding_tx<Tx>(&mut self, tx: Tx)
+    where
+        Tx: Into<BitcoinTx>,
+    {
+        let tx = tx.into();
+        // The top-up is added in the block that is being created.
+        let height = Height(Schema::new(&self.view).block_hashes_by_height().len());
+        let (_, addr) = self.actual_anchoring_config().redeem_script();
+        let funding_tx = FundingTx::from(tx.clone());
+        if !self.top_up_funding_txs().contains(&tx.id()) {
+            let amount = funding_tx
+                .find_out(&addr)
+                .map_or(0, |out| funding_tx.output[out as usize].value);
+            self.top_up_history_mut()
+                .push(FundingTxRecord::new(tx.clone(), amount, height.0));
+        }
+        self.top_up_funding_txs_mut().put(&tx.id(), tx);
+        self.add_funding_tx_to_history(&funding_tx, &addr, height);
+    }
+
+    /// Remembers the funding transactions that are spent by the given anchoring `tx`
+    /// if it has become the lect agreed by the validators.
+    fn mark_spent_funding_txs(&mut self, tx: &AnchoringTx) {
+        let cfg = self.actual_anchoring_config();
+        if self.collect_lects(&cfg).map(|lect| lect.id()) != Some(tx.id()) {
+            return;
+        }
+
+        let txid = tx.id();
+        for input in &tx.input {
+            let prev_txid = btc::TxId::from(input.prev_hash);
+            let is_funding_tx = cfg.find_funding_tx(&prev_txid).is_some()
+                || self.funding_history_indexes().contains(&prev_txid);
+            if is_funding_tx {
+                self.spent_funding_txs_mut().put(&prev_txid, txid);
+            }
+        }
     }
 
     /// Adds signature to known if it is correct.
@@ -340,6 +949,18 @@ impl<'a> AnchoringSchema<&'a mut Fork> {
     }
 }
 
+/// Checks that the `tx` spends the same outputs as the `lect` and pays a higher fee.
+fn is_fee_replacement(lect: &AnchoringTx, tx: &AnchoringTx) -> bool {
+    let outpoints = |tx: &AnchoringTx| {
+        tx.input
+            .iter()
+            .map(|input| (input.prev_hash, input.prev_index))
+            .collect::<Vec<_>>()
+    };
+    let outputs_value = |tx: &AnchoringTx| tx.output.iter().map(|output| output.value).sum::<u64>();
+    outpoints(lect) == outpoints(tx) && outputs_value(tx) < outputs_value(lect)
+}
+
 impl<T> AnchoringSchema<T> {
     /// Converts schema back into snapshot.
     pub fn into_snapshot(self) -> T {
@@ -13,17 +13,24 @@
 // limitations under the License.
 
 use exonum::crypto::{hash, PublicKey, Signature};
-use exonum::encoding::serialize::FromHex;
-use exonum::helpers::ValidatorId;
+use exonum::encoding::serialize::{encode_hex, FromHex};
+use exonum::helpers::{Height, ValidatorId};
 use exonum::storage::StorageValue;
 
-use bitcoin::blockdata::transaction::SigHashType;
+use bitcoin::blockdata::script::Script;
+use bitcoin::blockdata::transaction::{SigHashType, TxIn, TxOut};
 use bitcoin::network::serialize::BitcoinHash;
+use bitcoin::util::hash::Sha256dHash;
 use serde_json;
 
+use blockchain::consensus_storage::{AnchoredTable, AnchoringConfig, FeeStrategyConfig};
 use blockchain::dto::{LectContent, MsgAnchoringSignature, MsgAnchoringUpdateLatest};
 use details::btc;
-use details::btc::transactions::{AnchoringTx, BitcoinTx};
+use details::btc::fee::dust_threshold;
+use details::btc::transactions::{AnchoringTx, BitcoinTx, FundingTx, RawBitcoinTx,
+                                 TransactionBuilder};
+use details::error::{AddrError, ConfigError};
+use handler::{collect_signatures, is_anchoring_height};
 use details::tests::{dummy_anchoring_txs, gen_anchoring_keys, make_signatures};
 
 #[test]
@@ -113,6 +120,90 @@ fn test_sighash_type_single_in_msg_signature() {
     assert!(!msg.verify_content());
 }
 
+#[test]
+fn test_sighash_type_anyonecanpay_in_msg_signature() {
+    let (pub_keys, priv_keys) = gen_anchoring_keys(4);
+    let cfg = AnchoringConfig::new(btc::Network::Testnet, pub_keys.clone());
+    let (redeem_script, _) = cfg.redeem_script();
+    let (prev_tx, tx) = dummy_anchoring_txs(&redeem_script);
+
+    let btc_signature = tx.sign_input_with_sighash(
+        &redeem_script,
+        0,
+        &prev_tx.0,
+        &priv_keys[0],
+        btc::SighashType::AllPlusAnyoneCanPay,
+    );
+    assert_eq!(
+        btc::SighashType::of_signature(&btc_signature),
+        Some(btc::SighashType::AllPlusAnyoneCanPay)
+    );
+    assert!(tx.verify_input(&redeem_script, 0, &prev_tx.0, &pub_keys[0], &btc_signature));
+    assert!(!tx.verify_input(&redeem_script, 0, &prev_tx.0, &pub_keys[1], &btc_signature));
+
+    let msg = MsgAnchoringSignature::new_with_signature(
+        &PublicKey::zero(),
+        ValidatorId(0),
+        tx,
+        0,
+        &btc_signature,
+        &Signature::zero(),
+    );
+    assert!(msg.verify_content());
+}
+
+#[test]
+fn test_collect_signatures_sighash_type() {
+    let (pub_keys, priv_keys) = gen_anchoring_keys(4);
+    let mut cfg = AnchoringConfig::new(btc::Network::Testnet, pub_keys.clone());
+    cfg.sighash_type = btc::SighashType::AllPlusAnyoneCanPay;
+    let (redeem_script, _) = cfg.redeem_script();
+    let (prev_tx, tx) = dummy_anchoring_txs(&redeem_script);
+
+    let make_msg = |validator: usize, sighash_type: btc::SighashType| {
+        let btc_signature = tx.sign_input_with_sighash(
+            &redeem_script,
+            0,
+            &prev_tx.0,
+            &priv_keys[validator],
+            sighash_type,
+        );
+        MsgAnchoringSignature::new_with_signature(
+            &PublicKey::zero(),
+            ValidatorId(validator as u16),
+            tx.clone(),
+            0,
+            &btc_signature,
+            &Signature::zero(),
+        )
+    };
+
+    // Signatures of the configured type are combined into the valid transaction.
+    let matching = (0..3)
+        .map(|validator| make_msg(validator, btc::SighashType::AllPlusAnyoneCanPay))
+        .collect::<Vec<_>>();
+    let signatures = collect_signatures(&tx, &cfg, matching.clone()).unwrap();
+    assert_eq!(signatures[&0].len(), 3);
+    for (signature, pub_key) in signatures[&0].iter().zip(&pub_keys) {
+        assert!(tx.verify_input(&redeem_script, 0, &prev_tx.0, pub_key, signature));
+    }
+    let signed_tx = tx.clone().finalize(&redeem_script, signatures);
+    assert_eq!(signed_tx.id(), tx.nid());
+
+    // Signature of the other type is skipped, so there is no majority.
+    let mismatching = vec![
+        matching[0].clone(),
+        matching[1].clone(),
+        make_msg(2, btc::SighashType::All),
+    ];
+    assert!(collect_signatures(&tx, &cfg, mismatching.clone()).is_none());
+
+    // The same signatures are rejected if the other type is configured.
+    cfg.sighash_type = btc::SighashType::All;
+    assert!(collect_signatures(&tx, &cfg, matching).is_none());
+    assert!(collect_signatures(&tx, &cfg, mismatching).is_none());
+}
+
 #[test]
 fn test_signed_input_in_msg_signature_tx_body() {
     let (pub_keys, priv_keys) = gen_anchoring_keys(4);
@@ -162,3 +253,467 @@ fn test_nonexistent_input_in_msg_signature_tx_body() {
     );
     assert!(!msg.verify_content());
 }
+
+#[test]
+fn test_anchoring_config_uncompressed_key() {
+    let (pub_keys, _) = gen_anchoring_keys(4);
+    let cfg = AnchoringConfig::new(btc::Network::Testnet, pub_keys.clone());
+    let mut value = serde_json::to_value(&cfg).unwrap();
+    assert_eq!(
+        serde_json::from_value::<AnchoringConfig>(value.clone()).unwrap(),
+        cfg
+    );
+
+    let uncompressed_key = encode_hex(&pub_keys[0].0.serialize_uncompressed()[..]);
+    assert_eq!(uncompressed_key.len(), 130);
+    value["anchoring_keys"][0] = json!(uncompressed_key);
+    let e = serde_json::from_value::<AnchoringConfig>(value).unwrap_err();
+    assert!(e.to_string().contains("is uncompressed"));
+    assert!(btc::PublicKey::from_hex(&uncompressed_key).is_err());
+}
+
+#[test]
+fn test_anchoring_config_check_frequency() {
+    let mut cfg = AnchoringConfig::default();
+    cfg.frequency = 9;
+    assert_eq!(
+        cfg.check_frequency(10),
+        Err(ConfigError::FrequencyTooLow {
+            frequency: 9,
+            min_frequency: 10,
+        })
+    );
+    cfg.frequency = 10;
+    assert_eq!(cfg.check_frequency(10), Ok(()));
+    cfg.frequency = 11;
+    assert_eq!(cfg.check_frequency(10), Ok(()));
+
+    // The zero frequency is rejected even without the minimum.
+    cfg.frequency = 0;
+    assert_eq!(
+        cfg.check_frequency(0),
+        Err(ConfigError::FrequencyTooLow {
+            frequency: 0,
+            min_frequency: 1,
+        })
+    );
+}
+
+#[test]
+fn test_anchoring_config_payload_prefix() {
+    let mut cfg = AnchoringConfig::default();
+    assert_eq!(cfg.check_payload_prefix(), Ok(()));
+    // The default prefix is omitted to keep the hash of the existing configurations.
+    let value = serde_json::to_value(&cfg).unwrap();
+    assert!(value.get("payload_prefix").is_none());
+    assert_eq!(serde_json::from_value::<AnchoringConfig>(value).unwrap(), cfg);
+
+    cfg.payload_prefix = "NETONE".to_owned();
+    assert_eq!(cfg.check_payload_prefix(), Ok(()));
+    let value = serde_json::to_value(&cfg).unwrap();
+    assert_eq!(value["payload_prefix"], json!("NETONE"));
+    assert_eq!(serde_json::from_value::<AnchoringConfig>(value).unwrap(), cfg);
+
+    cfg.payload_prefix = "NET".to_owned();
+    assert_eq!(
+        cfg.check_payload_prefix(),
+        Err(ConfigError::InvalidPayloadPrefix("NET".to_owned(), 6))
+    );
+}
+
+#[test]
+fn test_anchoring_config_anchor_output() {
+    let (pub_keys, _) = gen_anchoring_keys(4);
+    let mut cfg = AnchoringConfig::new(btc::Network::Testnet, pub_keys);
+    assert_eq!(cfg.check_anchor_output(), Ok(()));
+    assert_eq!(cfg.anchor_output(), None);
+    let value = serde_json::to_value(&cfg).unwrap();
+    assert!(value.get("anchor_output_value").is_none());
+    assert!(value.get("change_address").is_none());
+
+    cfg.anchor_output_value = Some(1000);
+    assert_eq!(
+        cfg.check_anchor_output(),
+        Err(ConfigError::ChangeAddressNotSet)
+    );
+
+    let change_addr = btc::Address::from("2N1mHzwKTmjnC7JjqeGFBRKYE4WDTjTfop1");
+    cfg.change_address = Some(change_addr.clone());
+    // Every anchoring transaction spends a funding transaction along with the lect.
+    assert_eq!(
+        cfg.check_anchor_output(),
+        Err(ConfigError::AnchorOutputWithoutFunding)
+    );
+    let addr = cfg.redeem_script().1;
+    cfg.funding_tx = Some(FundingTx::from(RawBitcoinTx {
+        version: 2,
+        lock_time: 0,
+        input: Vec::new(),
+        output: vec![TxOut {
+            value: 10_000,
+            script_pubkey: addr.script_pubkey(),
+        }],
+    }));
+    assert_eq!(cfg.check_anchor_output(), Ok(()));
+    assert_eq!(cfg.anchor_output(), Some((1000, change_addr)));
+    let value = serde_json::to_value(&cfg).unwrap();
+    assert_eq!(serde_json::from_value::<AnchoringConfig>(value).unwrap(), cfg);
+
+    cfg.anchor_output_value = Some(329);
+    assert_eq!(
+        cfg.check_anchor_output(),
+        Err(ConfigError::AnchorOutputBelowDust {
+            value: 329,
+            threshold: 330,
+        })
+    );
+
+    let (redeem_script, _) = cfg.redeem_script();
+    let mainnet_addr = btc::Address::from_script(&redeem_script, btc::Network::Bitcoin);
+    cfg.anchor_output_value = Some(1000);
+    cfg.change_address = Some(mainnet_addr.clone());
+    assert_eq!(
+        cfg.check_anchor_output(),
+        Err(ConfigError::InvalidChangeAddress(
+            AddrError::NetworkMismatch {
+                address: mainnet_addr.to_string(),
+                actual: btc::Network::Bitcoin,
+                expected: btc::Network::Testnet,
+            }
+        ))
+    );
+    // The configuration with such an address is rejected by the deserialization as well.
+    let value = serde_json::to_value(&cfg).unwrap();
+    let err = serde_json::from_value::<AnchoringConfig>(value).unwrap_err();
+    assert!(err.to_string().contains("Invalid change address"), "{}", err);
+}
+
+#[test]
+fn test_anchoring_config_check_funding_amount() {
+    let (pub_keys, _) = gen_anchoring_keys(4);
+    let mut cfg = AnchoringConfig::new(btc::Network::Testnet, pub_keys);
+    assert_eq!(cfg.check_funding_amount(cfg.fee), Ok(()));
+    assert_eq!(cfg.first_anchoring_tx_vsize(), None);
+
+    let addr = cfg.redeem_script().1;
+    let funding_tx = |value| {
+        FundingTx::from(RawBitcoinTx {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                prev_hash: Sha256dHash::from_data(&[]),
+                prev_index: 0,
+                script_sig: Script::new(),
+                sequence: 0xFFFF_FFFF,
+                witness: Vec::default(),
+            }],
+            output: vec![TxOut {
+                value,
+                script_pubkey: addr.script_pubkey(),
+            }],
+        })
+    };
+    let required = cfg.fee + dust_threshold(&addr.script_pubkey());
+
+    cfg.funding_tx = Some(funding_tx(required));
+    assert_eq!(cfg.check_funding_amount(cfg.fee), Ok(()));
+    assert!(cfg.first_anchoring_tx_vsize().unwrap() > 0);
+    // The larger fee of the estimating strategy is not affordable.
+    assert!(cfg.check_funding_amount(cfg.fee + 1).is_err());
+
+    let tx = funding_tx(required - 1);
+    cfg.funding_tx = Some(tx.clone());
+    assert_eq!(
+        cfg.check_funding_amount(cfg.fee),
+        Err(ConfigError::InsufficientFunding {
+            txid: tx.id().to_string(),
+            amount: required - 1,
+            required,
+        })
+    );
+
+    // The estimating strategy is checked against the fee at the maximal rate.
+    cfg.fee_strategy = FeeStrategyConfig::EstimateSmartFee {
+        conf_target: 6,
+        max_fee_rate: 20_000,
+    };
+    let max_fee = cfg.first_anchoring_tx_vsize().unwrap() * 20_000 / 1000;
+    assert_eq!(cfg.max_anchoring_fee(), max_fee);
+    let required = max_fee + dust_threshold(&addr.script_pubkey());
+
+    cfg.funding_tx = Some(funding_tx(required));
+    assert_eq!(cfg.check_funding_amount(cfg.max_anchoring_fee()), Ok(()));
+
+    let tx = funding_tx(required - 1);
+    cfg.funding_tx = Some(tx.clone());
+    assert_eq!(
+        cfg.check_funding_amount(cfg.max_anchoring_fee()),
+        Err(ConfigError::InsufficientFunding {
+            txid: tx.id().to_string(),
+            amount: required - 1,
+            required,
+        })
+    );
+}
+
+#[test]
+fn test_anchoring_config_change_address() {
+    let (pub_keys, _) = gen_anchoring_keys(4);
+    let mut cfg = AnchoringConfig::new(btc::Network::Testnet, pub_keys);
+    let addr = cfg.redeem_script().1;
+    let change_addr = btc::Address::from("2N1mHzwKTmjnC7JjqeGFBRKYE4WDTjTfop1");
+    let funding_tx = FundingTx::from(RawBitcoinTx {
+        version: 2,
+        lock_time: 0,
+        input: vec![TxIn {
+            prev_hash: Sha256dHash::from_data(&[]),
+            prev_index: 0,
+            script_sig: Script::new(),
+            sequence: 0xFFFF_FFFF,
+            witness: Vec::default(),
+        }],
+        output: vec![TxOut {
+            value: 10_000,
+            script_pubkey: addr.script_pubkey(),
+        }],
+    });
+
+    // The anchoring output keeps only the dust threshold without the fixed value.
+    cfg.change_address = Some(change_addr.clone());
+    cfg.funding_tx = Some(funding_tx.clone());
+    let anchor_value = dust_threshold(&addr.script_pubkey());
+    assert_eq!(cfg.check_anchor_output(), Ok(()));
+    assert_eq!(
+        cfg.anchor_output(),
+        Some((anchor_value, change_addr.clone()))
+    );
+
+    // The rest of the funds goes to the change address rather than the anchoring one.
+    let tx = TransactionBuilder::with_prev_tx(&funding_tx, 0)
+        .fee(cfg.fee)
+        .payload(Height(0), hash(&[]))
+        .anchor_output(cfg.anchor_output())
+        .send_to(addr.clone())
+        .into_transaction()
+        .unwrap();
+    assert_eq!(tx.amount(), anchor_value);
+    assert_eq!(tx.output[0].script_pubkey, addr.script_pubkey());
+    assert_eq!(tx.output[2].value, 10_000 - cfg.fee - anchor_value);
+    assert_eq!(tx.output[2].script_pubkey, change_addr.script_pubkey());
+
+    // The funding transaction should also cover the change output.
+    let required = cfg.fee + anchor_value + dust_threshold(&change_addr.script_pubkey());
+    assert_eq!(cfg.check_funding_amount(10_000 - required), Ok(()));
+    assert_eq!(
+        cfg.check_funding_amount(10_000 - required + 1),
+        Err(ConfigError::InsufficientFunding {
+            txid: funding_tx.id().to_string(),
+            amount: 10_000,
+            required: 10_001,
+        })
+    );
+
+    // Every anchoring transaction consumes a whole funding transaction, so each of them
+    // should pay for one.
+    let mut small_tx = funding_tx.0.clone();
+    small_tx.output[0].value = required - 1;
+    let small_tx = FundingTx::from(small_tx);
+    cfg.additional_funding_txs = vec![small_tx.clone()];
+    assert_eq!(
+        cfg.check_funding_amount(cfg.fee),
+        Err(ConfigError::InsufficientFunding {
+            txid: small_tx.id().to_string(),
+            amount: required - 1,
+            required,
+        })
+    );
+    cfg.change_address = None;
+    assert_eq!(cfg.check_funding_amount(cfg.fee), Ok(()));
+}
+
+#[test]
+fn test_anchoring_config_toml_round_trip() {
+    let (pub_keys, _) = gen_anchoring_keys(4);
+    let mut cfg = AnchoringConfig::new(btc::Network::Testnet, pub_keys);
+    let funding_tx = FundingTx::from(RawBitcoinTx {
+        version: 2,
+        lock_time: 0,
+        input: vec![TxIn {
+            prev_hash: Sha256dHash::from_data(&[]),
+            prev_index: 0,
+            script_sig: Script::new(),
+            sequence: 0xFFFF_FFFF,
+            witness: Vec::default(),
+        }],
+        output: vec![TxOut {
+            value: 100_000,
+            script_pubkey: cfg.redeem_script().1.script_pubkey(),
+        }],
+    });
+    cfg.funding_tx = Some(funding_tx);
+    cfg.fee = 2_000;
+    cfg.fee_strategy = FeeStrategyConfig::EstimateSmartFee {
+        conf_target: 6,
+        max_fee_rate: 10_000,
+    };
+    cfg.frequency = 100;
+    cfg.sighash_type = btc::SighashType::AllPlusAnyoneCanPay;
+    cfg.anchored_table = Some(AnchoredTable {
+        service_id: 3,
+        table_idx: 1,
+    });
+
+    let toml = cfg.to_toml();
+    assert!(toml.contains("version = 1"));
+    assert!(toml.contains("threshold = 3"));
+    assert_eq!(AnchoringConfig::from_toml(&toml), Ok(cfg));
+}
+
+#[test]
+fn test_anchoring_config_toml_change_address() {
+    let (pub_keys, _) = gen_anchoring_keys(4);
+    let networks = [
+        btc::Network::Bitcoin,
+        btc::Network::Testnet,
+        btc::Network::Regtest,
+    ];
+    for &network in &networks {
+        let mut cfg = AnchoringConfig::new(network, pub_keys.clone());
+        cfg.anchor_output_value = Some(1000);
+        // Regtest shares the bech32 prefix with testnet, so only its base58 addresses
+        // are distinguishable.
+        cfg.change_address = Some(match network {
+            btc::Network::Regtest => btc::Address::from("2N1mHzwKTmjnC7JjqeGFBRKYE4WDTjTfop1"),
+            _ => cfg.redeem_script().1,
+        });
+
+        let toml = cfg.to_toml();
+        assert_eq!(AnchoringConfig::from_toml(&toml), Ok(cfg));
+    }
+}
+
+#[test]
+fn test_anchoring_config_toml_rejected() {
+    let (pub_keys, _) = gen_anchoring_keys(4);
+    let cfg = AnchoringConfig::new(btc::Network::Testnet, pub_keys);
+    let toml = cfg.to_toml();
+    let without = |field: &str| {
+        toml.lines()
+            .filter(|line| !line.starts_with(field))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    match AnchoringConfig::from_toml(&without("threshold")) {
+        Err(ConfigError::Toml(e)) => assert!(e.contains("missing field `threshold`"), "{}", e),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+    assert_eq!(
+        AnchoringConfig::from_toml(&without("version")),
+        Err(ConfigError::Toml("missing field `version`".to_owned()))
+    );
+    assert_eq!(
+        AnchoringConfig::from_toml(&toml.replace("version = 1", "version = 2")),
+        Err(ConfigError::UnsupportedTomlVersion {
+            version: "2".to_owned(),
+            supported: 1,
+        })
+    );
+    assert_eq!(
+        AnchoringConfig::from_toml(&toml.replace("threshold = 3", "threshold = 2")),
+        Err(ConfigError::ThresholdMismatch {
+            threshold: 2,
+            expected: 3,
+        })
+    );
+    match AnchoringConfig::from_toml(&format!("fee_rate = 10\n{}", toml)) {
+        Err(ConfigError::Toml(e)) => assert!(e.contains("unknown field `fee_rate`"), "{}", e),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+    assert_eq!(
+        AnchoringConfig::from_toml(&format!("payload_prefix = \"NET\"\n{}", toml)),
+        Err(ConfigError::InvalidPayloadPrefix("NET".to_owned(), 6))
+    );
+
+    let mainnet_addr = "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy";
+    let with_change = format!("change_address = \"{}\"\n{}", mainnet_addr, toml);
+    assert_eq!(
+        AnchoringConfig::from_toml(&with_change),
+        Err(ConfigError::InvalidChangeAddress(
+            AddrError::NetworkMismatch {
+                address: mainnet_addr.to_owned(),
+                actual: btc::Network::Bitcoin,
+                expected: btc::Network::Testnet,
+            }
+        ))
+    );
+    match AnchoringConfig::from_toml(&format!("change_address = \"bc1\"\n{}", toml)) {
+        Err(ConfigError::InvalidChangeAddress(AddrError::Parse { .. })) => {}
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn test_anchoring_config_output_descriptor() {
+    let (pub_keys, _) = gen_anchoring_keys(4);
+    let cfg = AnchoringConfig::new(btc::Network::Testnet, pub_keys);
+
+    let descriptor = cfg.output_descriptor();
+    let mut parts = descriptor.split('#');
+    let (body, checksum) = (parts.next().unwrap(), parts.next().unwrap());
+    assert_eq!(btc::descriptor::checksum(body), Some(checksum.to_owned()));
+
+    let multi = body.trim_left_matches("wsh(multi(")
+        .trim_right_matches("))")
+        .split(',')
+        .collect::<Vec<_>>();
+    let quorum = multi[0].parse::<usize>().unwrap();
+    let keys = multi[1..]
+        .iter()
+        .map(|key| btc::PublicKey::from_hex(key).unwrap().0)
+        .collect::<Vec<_>>();
+    assert_eq!(quorum, 3);
+    assert_eq!(keys.len(), 4);
+
+    let redeem_script = btc::RedeemScriptBuilder::with_public_keys(keys)
+        .quorum(quorum)
+        .to_script()
+        .unwrap();
+    let addr = btc::Address::from_script(&redeem_script, btc::Network::Testnet);
+    assert_eq!(addr, cfg.redeem_script().1);
+}
+
+#[test]
+fn test_anchoring_config_expected_address() {
+    let pub_keys = [
+        "03475ab0e9cfc6015927e662f6f8f088de12287cee1a3237aeb497d1763064690c",
+        "02a63948315dda66506faf4fecd54b085c08b13932a210fa5806e3691c69819aa0",
+        "0230cb2805476bf984d2236b56ff5da548dfe116daf2982608d898d9ecb3dceb49",
+        "036e4777c8d19ccaa67334491e777f221d37fd85d5786a4e5214b281cf0133d65e",
+    ].iter()
+        .map(|x| btc::PublicKey::from_hex(x).unwrap())
+        .collect::<Vec<_>>();
+
+    let cfg = AnchoringConfig::new(btc::Network::Testnet, pub_keys.clone());
+    assert_eq!(
+        cfg.expected_address().to_string(),
+        "tb1qa0wsdpa7z3epusphnva6r4sxmyu8p52qfkttetd95u2std5tl9ysc3mnss"
+    );
+    assert_eq!(cfg.expected_address(), cfg.redeem_script().1);
+
+    let cfg = AnchoringConfig::new(btc::Network::Bitcoin, pub_keys);
+    assert_eq!(
+        cfg.expected_address().to_string(),
+        "bc1qa0wsdpa7z3epusphnva6r4sxmyu8p52qfkttetd95u2std5tl9ys0edu2l"
+    );
+}
+
+#[test]
+fn test_is_anchoring_height() {
+    let mut cfg = AnchoringConfig::default();
+    cfg.frequency = 10;
+    assert!(is_anchoring_height(Height(0), &cfg));
+    assert!(!is_anchoring_height(Height(19), &cfg));
+    assert!(is_anchoring_height(Height(20), &cfg));
+    assert!(!is_anchoring_height(Height(21), &cfg));
+}
@@ -12,8 +12,6 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use bitcoin::blockdata::transaction::SigHashType;
-
 use exonum::blockchain::{ExecutionResult, Schema, Transaction};
 use exonum::crypto::CryptoHash;
 use exonum::helpers::Height;
@@ -22,16 +20,19 @@ use exonum::storage::{Fork, Snapshot};
 
 use super::Error as ValidateError;
 use blockchain::consensus_storage::AnchoringConfig;
-use blockchain::dto::{MsgAnchoringSignature, MsgAnchoringUpdateLatest};
+use blockchain::dto::{LectConflict, MsgAnchoringAnchorRequest, MsgAnchoringFeeRate,
+                      MsgAnchoringReplaceRequest, MsgAnchoringSignature, MsgAnchoringTopUp,
+                      MsgAnchoringUpdateLatest};
 use blockchain::schema::AnchoringSchema;
 use details::btc;
-use details::btc::transactions::{AnchoringTx, BitcoinTx, FundingTx, TxKind};
+use details::btc::transactions::{parse_payload_with_prefix, AnchoringTx, BitcoinTx, FundingTx,
+                                 TxKind};
 
 impl MsgAnchoringSignature {
     pub fn verify_content(&self) -> bool {
-        // Do not verify signatures other than SigHashType::All
-        let sighash_type_all = SigHashType::All.as_u32() as u8;
-        if self.signature().last() != Some(&sighash_type_all) {
+        // Do not verify signatures of the unsupported types,
+        // the configured one is checked during the validation.
+        if btc::SighashType::of_signature(self.signature()).is_none() {
             warn!(
                 "Received msg with incorrect signature type, content={:#?}",
                 self
@@ -87,11 +88,16 @@ impl MsgAnchoringSignature {
             if tx_script_pubkey != &addr.script_pubkey() {
                 return Err(ValidateError::MsgWithIncorrectAddress);
             }
-            verify_anchoring_tx_payload(&tx, &core_schema)?;
+            verify_anchoring_tx_payload(&tx, &core_schema, &anchoring_schema)?;
+            let sighash_type = btc::SighashType::of_signature(self.signature());
+            if sighash_type != Some(anchoring_cfg.sighash_type) {
+                return Err(ValidateError::SignatureWithUnexpectedSighash);
+            }
             // Checks whether funding tx is suitable as prev tx because they are not added to
             // the known_txs automatically.
-            let prev_tx = if anchoring_cfg.funding_tx().id() == prev_txid {
-                anchoring_cfg.funding_tx().clone().0
+            let funding_tx = anchoring_schema.find_funding_tx(&anchoring_cfg, &prev_txid);
+            let prev_tx = if let Some(funding_tx) = funding_tx {
+                funding_tx.0
             } else {
                 anchoring_schema
                     .known_txs()
@@ -145,9 +151,9 @@ impl MsgAnchoringUpdateLatest {
 
         let anchoring_cfg = anchoring_schema.actual_anchoring_config();
         let key = &anchoring_cfg.anchoring_keys[id];
-        match TxKind::from(tx.clone()) {
+        match anchoring_cfg.tx_kind(tx.clone()) {
             TxKind::Anchoring(tx) => {
-                verify_anchoring_tx_payload(&tx, &core_schema)?;
+                verify_anchoring_tx_payload(&tx, &core_schema, &anchoring_schema)?;
                 verify_anchoring_tx_prev_hash(&tx, &anchoring_schema)?;
             }
             TxKind::FundingTx(tx) => {
@@ -157,7 +163,13 @@ impl MsgAnchoringUpdateLatest {
             TxKind::Other(_) => return Err(ValidateError::LectWithIncorrectContent),
         }
 
-        if anchoring_schema.lects(key).len() != self.lect_count() {
+        let lects_count = anchoring_schema.lects(key).len();
+        // The lects of the validator only advance, so the update for the passed count
+        // is outdated, e.g. the replayed or the delayed one.
+        if self.lect_count() < lects_count {
+            return Err(ValidateError::LectStale);
+        }
+        if self.lect_count() != lects_count {
             return Err(ValidateError::LectWithWrongCount);
         }
 
@@ -172,7 +184,186 @@ impl Transaction for MsgAnchoringUpdateLatest {
 
     fn execute(&self, view: &mut Fork) -> ExecutionResult {
         let (key, tx) = self.validate(view)?;
-        AnchoringSchema::new(view).add_lect(&key, tx, self.hash());
+        let mut anchoring_schema = AnchoringSchema::new(view);
+        // The conflicting lect is not added, but recorded, so the error would roll it back.
+        if let Some(agreed_lect) = anchoring_schema.conflicting_lect(&key, &tx) {
+            let txid = tx.id();
+            if !anchoring_schema.lect_conflicts(&key).contains(&txid) {
+                warn!(
+                    "Rejected lect that conflicts with the agreed one, validator={}, \
+                     agreed_txid={}, conflicting_txid={}",
+                    self.validator(),
+                    agreed_lect.id(),
+                    txid
+                );
+                let conflict = LectConflict::new(
+                    &self.hash(),
+                    self.validator(),
+                    agreed_lect.payload().block_height.0,
+                    agreed_lect.clone().into(),
+                    tx.clone(),
+                );
+                anchoring_schema.lect_conflicts_mut(&key).put(&txid, conflict);
+            }
+            if !anchoring_schema.conflicting_lect_agreed(&txid) {
+                return Ok(());
+            }
+            warn!(
+                "Agreed lect is replaced by the conflicting one reported by +2/3 validators, \
+                 validator={}, agreed_txid={}, conflicting_txid={}",
+                self.validator(),
+                agreed_lect.id(),
+                txid
+            );
+            anchoring_schema.lect_conflicts_mut(&key).remove(&txid);
+        }
+        anchoring_schema.add_lect(&key, tx, self.hash());
+        Ok(())
+    }
+}
+
+impl MsgAnchoringTopUp {
+    pub fn validate(&self, view: &Fork) -> Result<FundingTx, ValidateError> {
+        let anchoring_schema = AnchoringSchema::new(view);
+        let core_schema = Schema::new(view);
+
+        let id = self.validator().0 as usize;
+        let actual_cfg = core_schema.actual_configuration();
+        if actual_cfg.validator_keys.get(id).map(|k| k.service_key) != Some(*self.from()) {
+            return Err(ValidateError::MsgFromNonValidator);
+        }
+
+        // Funding tx should send funds to the actual anchoring address.
+        let anchoring_cfg = anchoring_schema.actual_anchoring_config();
+        let (_, addr) = anchoring_cfg.redeem_script();
+        match anchoring_cfg.tx_kind(self.tx()) {
+            TxKind::FundingTx(ref tx) if tx.find_out(&addr).is_some() => Ok(tx.clone()),
+            _ => Err(ValidateError::TopUpWithIncorrectFunding),
+        }
+    }
+}
+
+impl Transaction for MsgAnchoringTopUp {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.from())
+    }
+
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let tx = self.validate(view)?;
+        AnchoringSchema::new(view).add_top_up_funding_tx(tx);
+        Ok(())
+    }
+}
+
+impl MsgAnchoringFeeRate {
+    pub fn validate(&self, view: &Fork) -> Result<btc::PublicKey, ValidateError> {
+        let anchoring_schema = AnchoringSchema::new(view);
+        let core_schema = Schema::new(view);
+
+        let id = self.validator().0 as usize;
+        let actual_cfg = core_schema.actual_configuration();
+        if actual_cfg.validator_keys.get(id).map(|k| k.service_key) != Some(*self.from()) {
+            return Err(ValidateError::MsgFromNonValidator);
+        }
+
+        let anchoring_cfg = anchoring_schema.actual_anchoring_config();
+        anchoring_cfg
+            .anchoring_keys
+            .get(id)
+            .cloned()
+            .ok_or(ValidateError::MsgFromNonValidator)
+    }
+}
+
+impl Transaction for MsgAnchoringFeeRate {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.from())
+    }
+
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let key = self.validate(view)?;
+        AnchoringSchema::new(view)
+            .fee_rates_mut()
+            .put(&key, self.fee_per_kb());
+        Ok(())
+    }
+}
+
+impl MsgAnchoringReplaceRequest {
+    pub fn validate(&self, view: &Fork) -> Result<btc::PublicKey, ValidateError> {
+        let anchoring_schema = AnchoringSchema::new(view);
+        let core_schema = Schema::new(view);
+
+        let id = self.validator().0 as usize;
+        let actual_cfg = core_schema.actual_configuration();
+        if actual_cfg.validator_keys.get(id).map(|k| k.service_key) != Some(*self.from()) {
+            return Err(ValidateError::MsgFromNonValidator);
+        }
+
+        let anchoring_cfg = anchoring_schema.actual_anchoring_config();
+        let key = anchoring_cfg
+            .anchoring_keys
+            .get(id)
+            .cloned()
+            .ok_or(ValidateError::MsgFromNonValidator)?;
+        // Only the agreed lect can be replaced, so the replacement spends the same outputs.
+        let lect = anchoring_schema.collect_lects(&anchoring_cfg);
+        if lect.map(|lect| lect.id()) != Some(self.tx().id()) {
+            return Err(ValidateError::ReplacementOfNonLect);
+        }
+        Ok(key)
+    }
+}
+
+impl Transaction for MsgAnchoringReplaceRequest {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.from())
+    }
+
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let key = self.validate(view)?;
+        AnchoringSchema::new(view)
+            .replacement_requests_mut()
+            .put(&key, self.tx().id());
+        Ok(())
+    }
+}
+
+impl MsgAnchoringAnchorRequest {
+    pub fn validate(&self, view: &Fork) -> Result<btc::PublicKey, ValidateError> {
+        let anchoring_schema = AnchoringSchema::new(view);
+        let core_schema = Schema::new(view);
+
+        let id = self.validator().0 as usize;
+        let actual_cfg = core_schema.actual_configuration();
+        if actual_cfg.validator_keys.get(id).map(|k| k.service_key) != Some(*self.from()) {
+            return Err(ValidateError::MsgFromNonValidator);
+        }
+
+        let anchoring_cfg = anchoring_schema.actual_anchoring_config();
+        let key = anchoring_cfg
+            .anchoring_keys
+            .get(id)
+            .cloned()
+            .ok_or(ValidateError::MsgFromNonValidator)?;
+        // The request is executed within the block that is not committed yet.
+        if self.height() >= core_schema.block_hashes_by_height().len() {
+            return Err(ValidateError::AnchorRequestOfUnknownBlock);
+        }
+        Ok(key)
+    }
+}
+
+impl Transaction for MsgAnchoringAnchorRequest {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.from())
+    }
+
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let key = self.validate(view)?;
+        AnchoringSchema::new(view)
+            .anchor_requests_mut()
+            .put(&key, self.height());
         Ok(())
     }
 }
@@ -186,13 +377,14 @@ where
 {
     // If tx has `prev_tx_chain` should be used it instead of `prev_hash`.
     let prev_txid = tx.payload().prev_tx_chain.unwrap_or_else(|| tx.prev_hash());
-    // Get `AnchoringConfig` for prev_tx
+    // Get `AnchoringConfig` for prev_tx, the payload prefix is kept by the configurations.
     let anchoring_cfg = {
         let prev_tx = anchoring_schema
             .known_txs()
             .get(&prev_txid)
             .ok_or_else(|| ValidateError::LectWithoutQuorum)?;
-        let cfg_height = match TxKind::from(prev_tx) {
+        let actual_cfg = anchoring_schema.actual_anchoring_config();
+        let cfg_height = match actual_cfg.tx_kind(prev_tx) {
             TxKind::Anchoring(tx) => Ok(tx.payload().block_height),
             TxKind::FundingTx(_) => Ok(Height::zero()),
             TxKind::Other(_) => Err(ValidateError::LectWithIncorrectContent),
@@ -235,12 +427,26 @@ where
     }
 }
 
-fn verify_anchoring_tx_payload<T>(tx: &AnchoringTx, schema: &Schema<T>) -> Result<(), ValidateError>
+fn verify_anchoring_tx_payload<T>(
+    tx: &AnchoringTx,
+    schema: &Schema<T>,
+    anchoring_schema: &AnchoringSchema<T>,
+) -> Result<(), ValidateError>
 where
     T: AsRef<Snapshot>,
 {
-    let payload = tx.payload();
-    if schema.block_hashes_by_height().get(payload.block_height.0) == Some(payload.block_hash) {
+    // The anchoring transactions of any prefix are decoded, so the prefix is checked here.
+    let prefix = anchoring_schema.actual_anchoring_config().payload_prefix;
+    let payload = parse_payload_with_prefix(tx, prefix.as_bytes())
+        .map_err(|_| ValidateError::MsgWithIncorrectPayload)?;
+    // The recover payloads have no room for the root of the anchored table.
+    let table_root = match payload.prev_tx_chain {
+        Some(_) => None,
+        None => anchoring_schema.anchored_table_root(payload.block_height),
+    };
+    if schema.block_hashes_by_height().get(payload.block_height.0) == Some(payload.block_hash)
+        && payload.table_root == table_root
+    {
         Ok(())
     } else {
         Err(ValidateError::MsgWithIncorrectPayload)
@@ -30,8 +30,9 @@ use exonum::helpers::fabric::{keys, Argument, CommandExtension, CommandName, Con
 use exonum::node::NodeConfig;
 
 use super::{gen_btc_keypair, AnchoringConfig, AnchoringNodeConfig, AnchoringRpcConfig};
+use details::btc::payload::DEFAULT_PAYLOAD_PREFIX;
 use details::btc::{self, PrivateKey, PublicKey};
-use details::rpc::{BitcoinRelay, RpcClient};
+use details::rpc::{BitcoinRelay, RetryingRpc, RpcClient, RpcRetryConfig};
 use observer::AnchoringObserverConfig;
 use service::AnchoringService;
 
@@ -102,11 +103,7 @@ impl CommandExtension for GenerateNodeConfig {
             .clone()
             .try_into()
             .unwrap();
-        let network = match network.as_str() {
-            "testnet" => Network::Testnet,
-            "bitcoin" => Network::Bitcoin,
-            _ => panic!("Wrong network type"),
-        };
+        let network: Network = network.parse().expect("Wrong network type");
 
         let (p, s) = gen_btc_keypair(network);
         let mut services_public_configs = context
@@ -200,6 +197,14 @@ impl CommandExtension for GenerateCommonConfig {
                 "anchoring-network",
                 false,
             ),
+            Argument::new_named(
+                "ANCHORING_PAYLOAD_PREFIX",
+                false,
+                "Prefix of the anchoring transactions payload, should consist of 6 bytes.",
+                None,
+                "anchoring-payload-prefix",
+                false,
+            ),
         ]
     }
 
@@ -215,6 +220,9 @@ impl CommandExtension for GenerateCommonConfig {
         let network = context
             .arg::<String>("ANCHORING_NETWORK")
             .expect("No network type found.");
+        let payload_prefix = context
+            .arg::<String>("ANCHORING_PAYLOAD_PREFIX")
+            .unwrap_or_else(|_| DEFAULT_PAYLOAD_PREFIX.to_owned());
 
         let mut values: BTreeMap<String, Value> = context.get(keys::SERVICES_CONFIG).expect(
             "Expected services_config \
@@ -236,6 +244,10 @@ impl CommandExtension for GenerateCommonConfig {
                     "anchoring_network".to_owned(),
                     Value::try_from(network).unwrap(),
                 ),
+                (
+                    "anchoring_payload_prefix".to_owned(),
+                    Value::try_from(payload_prefix).unwrap(),
+                ),
             ].into_iter(),
         );
         context.set(keys::SERVICES_CONFIG, values);
@@ -321,13 +333,17 @@ impl CommandExtension for Finalize {
             .expect("Anchoring fee not found")
             .clone()
             .try_into()?;
-
-        let network = match network.as_str() {
-            "testnet" => Network::Testnet,
-            "bitcoin" => Network::Bitcoin,
-            _ => panic!("Wrong network type"),
+        // The common config may be generated before the payload prefix was introduced.
+        let payload_prefix: String = match common_config
+            .services_config
+            .get("anchoring_payload_prefix")
+        {
+            Some(prefix) => prefix.clone().try_into()?,
+            None => DEFAULT_PAYLOAD_PREFIX.to_owned(),
         };
 
+        let network: Network = network.parse().expect("Wrong network type");
+
         let priv_key: PrivateKey = PrivateKey::from_str(&sec_key).unwrap();
         //TODO: validate config keys
         let _pub_key = PublicKey::from_hex(&pub_key).unwrap();
@@ -343,7 +359,7 @@ impl CommandExtension for Finalize {
                 PublicKey::from_hex(&key).unwrap()
             })
             .collect::<Vec<_>>();
-        let client = RpcClient::from(rpc.clone());
+        let client = RetryingRpc::new(RpcClient::from(rpc.clone()), RpcRetryConfig::default());
         let mut anchoring_config = AnchoringNodeConfig::new(Some(rpc));
         anchoring_config.observer = observer;
 
@@ -377,6 +393,10 @@ impl CommandExtension for Finalize {
         genesis_cfg.fee = fee;
         genesis_cfg.frequency = frequency;
         genesis_cfg.utxo_confirmations = utxo_confirmations;
+        genesis_cfg.payload_prefix = payload_prefix;
+        genesis_cfg.check_frequency(anchoring_config.min_anchoring_frequency)?;
+        genesis_cfg.check_payload_prefix()?;
+        genesis_cfg.check_funding_amount(genesis_cfg.max_anchoring_fee())?;
 
         node_config.services_configs.insert(
             "anchoring_service".to_owned(),
@@ -0,0 +1,79 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checksums of the output script descriptors that are accepted by the `importdescriptors`
+//! call of `bitcoind`.
+//!
+//! See [BIP 380](https://github.com/bitcoin/bips/blob/master/bip-0380.mediawiki).
+
+/// Characters allowed in the descriptors, the position of the character is used
+/// to compute the checksum.
+const INPUT_CHARSET: &[u8] = b"0123456789()[],'/*abcdefgh@:$%{}\
+IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~\
+ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+/// Characters of the checksum.
+const CHECKSUM_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+/// Length of the checksum.
+const CHECKSUM_LEN: usize = 8;
+
+fn polymod(c: u64, value: u64) -> u64 {
+    const GENERATORS: [u64; 5] = [
+        0xf5_dee5_1989,
+        0xa9_fdca_3312,
+        0x1b_ab10_e32d,
+        0x37_06b1_677a,
+        0x64_4d62_6ffd,
+    ];
+
+    let c0 = c >> 35;
+    let mut c = ((c & 0x7_ffff_ffff) << 5) ^ value;
+    for (i, generator) in GENERATORS.iter().enumerate() {
+        if (c0 >> i) & 1 == 1 {
+            c ^= generator;
+        }
+    }
+    c
+}
+
+/// Computes the checksum of the given `descriptor`.
+///
+/// Returns `None` if the descriptor contains the characters that are not allowed.
+pub fn checksum(descriptor: &str) -> Option<String> {
+    let mut c = 1;
+    let mut class = 0;
+    let mut class_count = 0;
+    for ch in descriptor.bytes() {
+        let pos = INPUT_CHARSET.iter().position(|&x| x == ch)? as u64;
+        c = polymod(c, pos & 31);
+        class = class * 3 + (pos >> 5);
+        class_count += 1;
+        if class_count == 3 {
+            c = polymod(c, class);
+            class = 0;
+            class_count = 0;
+        }
+    }
+    if class_count > 0 {
+        c = polymod(c, class);
+    }
+    for _ in 0..CHECKSUM_LEN {
+        c = polymod(c, 0);
+    }
+    c ^= 1;
+
+    let checksum = (0..CHECKSUM_LEN)
+        .map(|i| CHECKSUM_CHARSET[((c >> (5 * (CHECKSUM_LEN - 1 - i))) & 31) as usize] as char)
+        .collect();
+    Some(checksum)
+}
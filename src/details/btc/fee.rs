@@ -0,0 +1,205 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strategies to choose a fee for the anchoring transactions.
+
+use std::fmt;
+
+use bitcoin::blockdata::script::Script;
+use bitcoin::network::serialize::serialize;
+
+use details::btc::transactions::RawBitcoinTx;
+use details::btc::RedeemScript;
+use details::rpc::{BitcoinRelay, Error as RpcError, Result};
+
+/// Maximal length of the DER encoded signature with the sighash type byte.
+const MAX_SIGNATURE_LEN: u64 = 73;
+/// Size of the segwit marker and flag bytes.
+const SEGWIT_MARKER_LEN: u64 = 2;
+/// Witness scale factor from the BIP141.
+const WITNESS_SCALE_FACTOR: u64 = 4;
+/// Default `bitcoind` incremental relay fee in satoshis per kilobyte.
+const INCREMENTAL_RELAY_FEE_PER_KB: u64 = 1000;
+/// Default `bitcoind` minimal relay fee in satoshis per kilobyte.
+const MIN_RELAY_FEE_PER_KB: u64 = 1000;
+/// Default `bitcoind` dust relay fee in satoshis per kilobyte.
+const DUST_RELAY_FEE_PER_KB: u64 = 3000;
+/// Size of the input that spends a non-witness output, as assumed by `bitcoind`.
+const SPENDING_INPUT_SIZE: u64 = 32 + 4 + 1 + 107 + 4;
+/// Virtual size of the input that spends a witness output, as assumed by `bitcoind`.
+const SPENDING_WITNESS_INPUT_SIZE: u64 = 32 + 4 + 1 + 107 / WITNESS_SCALE_FACTOR + 4;
+
+/// Source of the fee rate for the anchoring transactions.
+pub trait FeeStrategy: fmt::Debug {
+    /// Returns the fee rate in satoshis per 1000 virtual bytes.
+    fn fee_per_kb(&self) -> Result<u64>;
+
+    /// Returns the fee for a transaction with the given virtual size.
+    fn fee_for_vsize(&self, vsize: u64) -> Result<u64> {
+        Ok(vsize * self.fee_per_kb()? / 1000)
+    }
+}
+
+/// Fixed fee from the anchoring configuration.
+///
+/// The fee is charged for the whole transaction regardless of its size, so
+/// the configured value is reported as the rate too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaticFee(pub u64);
+
+impl FeeStrategy for StaticFee {
+    fn fee_per_kb(&self) -> Result<u64> {
+        Ok(self.0)
+    }
+
+    fn fee_for_vsize(&self, _vsize: u64) -> Result<u64> {
+        Ok(self.0)
+    }
+}
+
+/// Fee rate in satoshis per 1000 virtual bytes agreed by the validators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AgreedFeeRate(pub u64);
+
+impl FeeStrategy for AgreedFeeRate {
+    fn fee_per_kb(&self) -> Result<u64> {
+        Ok(self.0)
+    }
+}
+
+/// Fee rate estimated by the `estimatesmartfee` call of the connected `bitcoind`.
+pub struct RpcEstimateFee<'a> {
+    relay: &'a BitcoinRelay,
+    conf_target: u16,
+}
+
+impl<'a> RpcEstimateFee<'a> {
+    /// Creates the strategy which expects the transaction to be confirmed
+    /// within `conf_target` blocks.
+    pub fn new(relay: &'a BitcoinRelay, conf_target: u16) -> RpcEstimateFee<'a> {
+        RpcEstimateFee { relay, conf_target }
+    }
+}
+
+impl<'a> fmt::Debug for RpcEstimateFee<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct(stringify!(RpcEstimateFee))
+            .field("relay", &self.relay.config().host)
+            .field("conf_target", &self.conf_target)
+            .finish()
+    }
+}
+
+impl<'a> FeeStrategy for RpcEstimateFee<'a> {
+    fn fee_per_kb(&self) -> Result<u64> {
+        self.relay.estimate_fee(self.conf_target)?.ok_or_else(|| {
+            RpcError::NoInformation(format!(
+                "Unable to estimate fee for {} blocks",
+                self.conf_target
+            ))
+        })
+    }
+}
+
+/// Estimates the virtual size of the given unsigned transaction after all its inputs
+/// are spent by `signatures_count` signatures and the given redeem script.
+pub fn estimate_vsize(
+    tx: &RawBitcoinTx,
+    redeem_script: &RedeemScript,
+    signatures_count: usize,
+) -> u64 {
+    let mut unsigned_tx = tx.clone();
+    for input in &mut unsigned_tx.input {
+        input.witness.clear();
+    }
+    let base_size = serialize(&unsigned_tx).unwrap().len() as u64;
+
+    // Witness stack consists of the empty item, the signatures and the redeem script.
+    let script_len = redeem_script.as_ref().len() as u64;
+    let items_count = signatures_count as u64 + 2;
+    let input_witness_size = varint_len(items_count)
+        + 1
+        + signatures_count as u64 * (1 + MAX_SIGNATURE_LEN)
+        + varint_len(script_len)
+        + script_len;
+    let witness_size = SEGWIT_MARKER_LEN + input_witness_size * tx.input.len() as u64;
+
+    let weight = base_size * WITNESS_SCALE_FACTOR + witness_size;
+    (weight + WITNESS_SCALE_FACTOR - 1) / WITNESS_SCALE_FACTOR
+}
+
+/// Returns the fee for the replacement of a transaction that paid `old_fee`.
+///
+/// According to the BIP125 the replacement should pay for its own relay
+/// in addition to the fee of the replaced transaction.
+pub fn replacement_fee(old_fee: u64, estimated_fee: u64, vsize: u64) -> u64 {
+    let min_fee = old_fee + vsize * INCREMENTAL_RELAY_FEE_PER_KB / 1000;
+    ::std::cmp::max(min_fee, estimated_fee)
+}
+
+/// Returns the actual virtual size of the given signed transaction.
+pub fn tx_vsize(tx: &RawBitcoinTx) -> u64 {
+    let mut stripped_tx = tx.clone();
+    for input in &mut stripped_tx.input {
+        input.witness.clear();
+    }
+    let base_size = serialize(&stripped_tx).unwrap().len() as u64;
+    let total_size = serialize(tx).unwrap().len() as u64;
+
+    let weight = base_size * (WITNESS_SCALE_FACTOR - 1) + total_size;
+    (weight + WITNESS_SCALE_FACTOR - 1) / WITNESS_SCALE_FACTOR
+}
+
+/// Returns the fee of the child transaction that raises the fee rate of the package
+/// of the unconfirmed parent and the child to the rate of the given `strategy`.
+///
+/// This child-pays-for-parent (CPFP) technique unsticks the parent that does not signal
+/// the replaceability, e.g. the funding transaction. The child always pays at least
+/// for its own relay.
+pub fn cpfp_fee(
+    strategy: &FeeStrategy,
+    parent_fee: u64,
+    parent_vsize: u64,
+    child_vsize: u64,
+) -> Result<u64> {
+    let package_fee = strategy.fee_for_vsize(parent_vsize + child_vsize)?;
+    let min_fee = child_vsize * MIN_RELAY_FEE_PER_KB / 1000;
+    Ok(::std::cmp::max(package_fee.saturating_sub(parent_fee), min_fee))
+}
+
+/// Returns the minimal value of an output with the given `script_pubkey`
+/// that is not considered as dust by `bitcoind`.
+///
+/// Outputs below this value make the transaction non-standard. The default dust relay fee
+/// is the same for the mainnet, testnet and regtest, so only the script type matters:
+/// the threshold is 540 satoshis for P2SH and 330 satoshis for P2WSH outputs.
+pub fn dust_threshold(script_pubkey: &Script) -> u64 {
+    let script_len = script_pubkey.len() as u64;
+    let output_size = 8 + varint_len(script_len) + script_len;
+    let input_size = if script_pubkey.is_v0_p2wsh() || script_pubkey.is_v0_p2wpkh() {
+        SPENDING_WITNESS_INPUT_SIZE
+    } else {
+        SPENDING_INPUT_SIZE
+    };
+    (output_size + input_size) * DUST_RELAY_FEE_PER_KB / 1000
+}
+
+fn varint_len(n: u64) -> u64 {
+    match n {
+        0...0xFC => 1,
+        0xFD...0xFFFF => 3,
+        0x1_0000...0xFFFF_FFFF => 5,
+        _ => 9,
+    }
+}
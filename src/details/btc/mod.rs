@@ -14,9 +14,12 @@
 
 //! Module contains some wrappers over types from `Bitcoin` crate.
 
+pub mod descriptor;
+pub mod fee;
 pub mod payload;
 mod private_key;
 mod public_key;
+pub mod psbt;
 pub mod transactions;
 mod types;
 
@@ -30,7 +33,9 @@ use exonum::encoding::serialize::FromHexError;
 
 #[doc(hidden)]
 /// For test purpose only
-pub use self::types::{Address, PrivateKey, PublicKey, RawTransaction, Signature, TxId};
+pub use self::types::{Address, BlockHash, PrivateKey, PublicKey, RawTransaction, Signature,
+                      TxId};
+pub use self::types::SighashType;
 pub use bitcoin::network::constants::Network;
 #[doc(hidden)]
 pub use btc_transaction_utils::multisig::{RedeemScript, RedeemScriptBuilder, RedeemScriptError};
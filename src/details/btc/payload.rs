@@ -22,11 +22,16 @@ use exonum::helpers::Height;
 
 use details::btc;
 
-const PAYLOAD_PREFIX: &[u8] = b"EXONUM";
+/// Prefix of the anchoring payloads if the anchoring configuration does not specify another one.
+pub const DEFAULT_PAYLOAD_PREFIX: &str = "EXONUM";
+/// Length of the payload prefix in bytes.
+pub const PAYLOAD_PREFIX_LEN: usize = 6;
+
 const PAYLOAD_HEADER_LEN: usize = 8;
 const PAYLOAD_V1: u8 = 1;
 const PAYLOAD_V1_KIND_REGULAR: u8 = 0;
 const PAYLOAD_V1_KIND_RECOVER: u8 = 1;
+const PAYLOAD_V1_KIND_REGULAR_WITH_TABLE: u8 = 2;
 
 /// Anchoring transaction payload.
 ///
@@ -34,14 +39,21 @@ const PAYLOAD_V1_KIND_RECOVER: u8 = 1;
 ///
 /// | Position in bytes 	| Description                   	                |
 /// |-------------------	|----------------------------------------------     |
-/// | 0..6               	| ASCII-encoded prefix `EXONUM` 	                |
+/// | 0..6               	| Prefix, ASCII-encoded `EXONUM` by default         |
 /// | 6                 	| Version byte, currently is 1             	        |
-/// | 7                     | Payload kind: (0 is regular, 1 is recover)        |
+/// | 7                     | Payload kind: (0 is regular, 1 is recover,        |
+/// |                       | 2 is regular with table root)                     |
 /// | 8..16                 | Block height                                      |
 /// | 16..48                | Block hash                                        |
 /// | 48..80 (Optionally)   | Txid of previous tx chain (only for recover kind) |
+/// |                       | or root hash of the anchored table                |
+///
+/// In this way the length of `regular` payload is 48, and for other kinds is 80.
+/// The `recover` payloads do not carry the root hash of the anchored table, since
+/// both hashes would not fit into the 80 bytes of the standard `OP_RETURN` output.
 ///
-/// In this way the length of `regular` payload is 48, and for `recover` is 80.
+/// The prefix distinguishes the payloads of the different anchoring deployments
+/// that use the same bitcoin network, see `AnchoringConfig::payload_prefix`.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Payload {
     /// Anchored block height
@@ -50,12 +62,37 @@ pub struct Payload {
     pub block_hash: Hash,
     /// `Txid` of previous transactions chain if it has been lost.
     pub prev_tx_chain: Option<btc::TxId>,
+    /// Root hash of the service table at the anchored height,
+    /// see `AnchoringConfig::anchored_table`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table_root: Option<Hash>,
+}
+
+/// Error that occurs while building or parsing the anchoring payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, Fail)]
+pub enum PayloadError {
+    /// Script does not contain the anchoring payload.
+    #[display(fmt = "Script does not contain anchoring payload")]
+    NotFound,
+    /// Payload version is not supported by this node.
+    #[display(fmt = "Unknown payload version {}", _0)]
+    UnknownVersion(u8),
+    /// Payload has the known version but its content is malformed.
+    #[display(fmt = "Malformed payload of version {}", _0)]
+    Malformed(u8),
+    /// Payload is well-formed but it is tagged by the prefix of another deployment.
+    #[display(fmt = "Payload has another prefix")]
+    PrefixMismatch,
+    /// Payload prefix does not have the length of `PAYLOAD_PREFIX_LEN` bytes.
+    #[display(fmt = "Payload prefix has wrong length {}", _0)]
+    InvalidPrefixLength(usize),
 }
 
 #[derive(Debug)]
 enum PayloadV1 {
     Regular(Height, Hash),
     Recover(Height, Hash, btc::TxId),
+    RegularWithTable(Height, Hash, Hash),
 }
 
 #[derive(Debug, Default)]
@@ -63,6 +100,8 @@ pub struct PayloadV1Builder {
     block_hash: Option<Hash>,
     block_height: Option<Height>,
     prev_tx_chain: Option<btc::TxId>,
+    table_root: Option<Hash>,
+    prefix: Option<Vec<u8>>,
 }
 
 pub type PayloadBuilder = PayloadV1Builder;
@@ -92,6 +131,16 @@ impl PayloadV1 {
                 let txid = btc::TxId::from_slice(&data[40..72]).unwrap();
                 Some(PayloadV1::Recover(Height(block_height), block_hash, txid))
             }
+            PAYLOAD_V1_KIND_REGULAR_WITH_TABLE => {
+                if data.len() != 72 {
+                    return None;
+                }
+
+                let block_height = LittleEndian::read_u64(&data[0..8]);
+                let block_hash = Hash::from_slice(&data[8..40]).unwrap();
+                let table_root = Hash::from_slice(&data[40..72]).unwrap();
+                Some(PayloadV1::RegularWithTable(Height(block_height), block_hash, table_root))
+            }
             _ => None,
         }
     }
@@ -113,13 +162,18 @@ impl PayloadV1 {
                 buf[8..40].copy_from_slice(hash.as_ref());
                 buf[40..72].copy_from_slice(txid.as_bytes());
             }
+            PayloadV1::RegularWithTable(height, hash, table_root) => {
+                LittleEndian::write_u64(&mut buf[0..8], height.0);
+                buf[8..40].copy_from_slice(hash.as_ref());
+                buf[40..72].copy_from_slice(table_root.as_ref());
+            }
         };
     }
 
     fn len(&self) -> usize {
         match *self {
             PayloadV1::Regular(..) => 40,
-            PayloadV1::Recover(..) => 72,
+            PayloadV1::Recover(..) | PayloadV1::RegularWithTable(..) => 72,
         }
     }
 
@@ -127,21 +181,25 @@ impl PayloadV1 {
         match *self {
             PayloadV1::Regular(..) => PAYLOAD_V1_KIND_REGULAR,
             PayloadV1::Recover(..) => PAYLOAD_V1_KIND_RECOVER,
+            PayloadV1::RegularWithTable(..) => PAYLOAD_V1_KIND_REGULAR_WITH_TABLE,
         }
     }
 
-    fn into_script(self) -> Script {
+    fn into_script(self, prefix: &[u8]) -> Result<Script, PayloadError> {
+        if prefix.len() != PAYLOAD_PREFIX_LEN {
+            return Err(PayloadError::InvalidPrefixLength(prefix.len()));
+        }
         let len = self.len() + PAYLOAD_HEADER_LEN;
         let mut buf = vec![0; len];
         // Serialize header
-        buf[0..6].copy_from_slice(PAYLOAD_PREFIX);
+        buf[0..6].copy_from_slice(prefix);
         buf[6] = PAYLOAD_V1;
         self.write(&mut buf[7..]);
         // Build script
-        Builder::new()
+        Ok(Builder::new()
             .push_opcode(All::OP_RETURN)
             .push_slice(buf.as_ref())
-            .into_script()
+            .into_script())
     }
 }
 
@@ -151,6 +209,8 @@ impl PayloadV1Builder {
             block_hash: None,
             block_height: None,
             prev_tx_chain: None,
+            table_root: None,
+            prefix: None,
         }
     }
 
@@ -169,49 +229,84 @@ impl PayloadV1Builder {
         self
     }
 
-    pub fn into_script(self) -> Script {
+    /// Sets the root hash of the anchored table, it is omitted by the `recover` payloads.
+    pub fn table_root(mut self, root: Option<Hash>) -> PayloadV1Builder {
+        self.table_root = root;
+        self
+    }
+
+    pub fn prefix(mut self, prefix: &[u8]) -> PayloadV1Builder {
+        self.prefix = Some(prefix.to_vec());
+        self
+    }
+
+    pub fn into_script(self) -> Result<Script, PayloadError> {
         let block_height = self.block_height.expect("Block height is not set");
         let block_hash = self.block_hash.expect("Block hash is not set");
 
-        let payload = match self.prev_tx_chain {
-            Some(txid) => PayloadV1::Recover(block_height, block_hash, txid),
-            None => PayloadV1::Regular(block_height, block_hash),
+        let prefix = self.prefix
+            .unwrap_or_else(|| DEFAULT_PAYLOAD_PREFIX.as_bytes().to_vec());
+
+        let payload = match (self.prev_tx_chain, self.table_root) {
+            (Some(txid), _) => PayloadV1::Recover(block_height, block_hash, txid),
+            (None, Some(root)) => PayloadV1::RegularWithTable(block_height, block_hash, root),
+            (None, None) => PayloadV1::Regular(block_height, block_hash),
         };
-        payload.into_script()
+        payload.into_script(&prefix)
     }
 }
 
 impl Payload {
     /// Tries to extract payload from given `Script`
     pub fn from_script(script: &Script) -> Option<Payload> {
+        Payload::parse(script).ok()
+    }
+
+    /// Same as [`from_script`](#method.from_script) but reports the reason
+    /// why the payload cannot be extracted.
+    pub fn parse(script: &Script) -> Result<Payload, PayloadError> {
+        Payload::parse_with_prefix(script, DEFAULT_PAYLOAD_PREFIX.as_bytes())
+    }
+
+    /// Same as [`parse`](#method.parse) but expects the payload tagged by the given `prefix`.
+    ///
+    /// The well-formed payload with another prefix is reported as `PrefixMismatch`,
+    /// other scripts with another prefix are reported as not containing the payload.
+    pub fn parse_with_prefix(script: &Script, prefix: &[u8]) -> Result<Payload, PayloadError> {
+        let (payload_prefix, payload) = Payload::parse_any_prefix(script);
+        if payload_prefix == Some(prefix) {
+            payload
+        } else if payload.is_ok() {
+            Err(PayloadError::PrefixMismatch)
+        } else {
+            Err(PayloadError::NotFound)
+        }
+    }
+
+    /// Extracts the payload regardless of its prefix. Returns the prefix along
+    /// with the parsing result if the script has the `OP_RETURN` data long enough
+    /// to contain the payload header.
+    pub fn parse_any_prefix(script: &Script) -> (Option<&[u8]>, Result<Payload, PayloadError>) {
         let mut instructions = script.into_iter();
-        instructions
-            .next()
-            .and_then(|instr| {
-                if instr == Instruction::Op(All::OP_RETURN) {
-                    instructions.next()
-                } else {
-                    None
-                }
-            })
-            .and_then(|instr| {
-                if let Instruction::PushBytes(bytes) = instr {
-                    if bytes.len() < PAYLOAD_HEADER_LEN {
-                        return None;
-                    }
-                    if &bytes[0..6] != PAYLOAD_PREFIX {
-                        return None;
-                    }
-                    // Parse metadata
-                    let version = bytes[6];
-                    match version {
-                        PAYLOAD_V1 => PayloadV1::read(&bytes[7..]).map(Payload::from),
-                        _ => None,
-                    }
-                } else {
-                    None
-                }
-            })
+        if instructions.next() != Some(Instruction::Op(All::OP_RETURN)) {
+            return (None, Err(PayloadError::NotFound));
+        }
+        let bytes = match instructions.next() {
+            Some(Instruction::PushBytes(bytes)) => bytes,
+            _ => return (None, Err(PayloadError::NotFound)),
+        };
+        if bytes.len() < PAYLOAD_HEADER_LEN {
+            return (None, Err(PayloadError::NotFound));
+        }
+        // Parse metadata
+        let version = bytes[PAYLOAD_PREFIX_LEN];
+        let payload = match version {
+            PAYLOAD_V1 => PayloadV1::read(&bytes[PAYLOAD_PREFIX_LEN + 1..])
+                .map(Payload::from)
+                .ok_or(PayloadError::Malformed(version)),
+            _ => Err(PayloadError::UnknownVersion(version)),
+        };
+        (Some(&bytes[0..PAYLOAD_PREFIX_LEN]), payload)
     }
 }
 
@@ -222,11 +317,19 @@ impl From<PayloadV1> for Payload {
                 block_height: height,
                 block_hash: hash,
                 prev_tx_chain: None,
+                table_root: None,
             },
             PayloadV1::Recover(height, hash, txid) => Payload {
                 block_height: height,
                 block_hash: hash,
                 prev_tx_chain: Some(txid),
+                table_root: None,
+            },
+            PayloadV1::RegularWithTable(height, hash, table_root) => Payload {
+                block_height: height,
+                block_hash: hash,
+                prev_tx_chain: None,
+                table_root: Some(table_root),
             },
         }
     }
@@ -242,7 +345,7 @@ mod tests {
     use details::btc;
     use details::btc::HexValueEx;
 
-    use super::{Payload, PayloadBuilder};
+    use super::{Payload, PayloadBuilder, PayloadError};
 
     #[test]
     fn test_payload_regular_serialize() {
@@ -250,7 +353,8 @@ mod tests {
         let payload_script = PayloadBuilder::new()
             .block_hash(block_hash)
             .block_height(Height(1234))
-            .into_script();
+            .into_script()
+            .unwrap();
 
         assert_eq!(
             payload_script.to_hex(),
@@ -281,7 +385,8 @@ mod tests {
             .block_hash(block_hash)
             .block_height(Height(1234))
             .prev_tx_chain(Some(prev_txid))
-            .into_script();
+            .into_script()
+            .unwrap();
 
         assert_eq!(
             payload_script.to_hex(),
@@ -325,4 +430,152 @@ mod tests {
             Script::from_hex("a91472b7506704dc074fa46359251052e781d96f939a87").unwrap();
         assert_eq!(Payload::from_script(&script_pubkey), None);
     }
+
+    #[test]
+    fn test_payload_regular_round_trip() {
+        let block_hash = hash(&[1, 2, 3]);
+        let payload_script = PayloadBuilder::new()
+            .block_hash(block_hash)
+            .block_height(Height(1234))
+            .into_script()
+            .unwrap();
+
+        let payload = Payload::parse(&payload_script).unwrap();
+        assert_eq!(
+            payload,
+            Payload {
+                block_height: Height(1234),
+                block_hash,
+                prev_tx_chain: None,
+                table_root: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_payload_recover_round_trip() {
+        let block_hash = hash(&[1, 2, 3]);
+        let prev_txid = btc::TxId::from_slice(hash(&[4, 5, 6]).as_ref()).unwrap();
+        let payload_script = PayloadBuilder::new()
+            .block_hash(block_hash)
+            .block_height(Height(1234))
+            .prev_tx_chain(Some(prev_txid))
+            .into_script()
+            .unwrap();
+
+        let payload = Payload::parse(&payload_script).unwrap();
+        assert_eq!(
+            payload,
+            Payload {
+                block_height: Height(1234),
+                block_hash,
+                prev_tx_chain: Some(prev_txid),
+                table_root: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_payload_table_root_round_trip() {
+        let block_hash = hash(&[1, 2, 3]);
+        let table_root = hash(&[7, 8, 9]);
+        let payload_script = PayloadBuilder::new()
+            .block_hash(block_hash)
+            .block_height(Height(1234))
+            .table_root(Some(table_root))
+            .into_script()
+            .unwrap();
+        // The payload fits into the standard `OP_RETURN` output.
+        assert_eq!(payload_script.len(), 83);
+
+        let payload = Payload::parse(&payload_script).unwrap();
+        assert_eq!(
+            payload,
+            Payload {
+                block_height: Height(1234),
+                block_hash,
+                prev_tx_chain: None,
+                table_root: Some(table_root),
+            }
+        );
+    }
+
+    #[test]
+    fn test_payload_recover_omits_table_root() {
+        let block_hash = hash(&[1, 2, 3]);
+        let prev_txid = btc::TxId::from_slice(hash(&[4, 5, 6]).as_ref()).unwrap();
+        let payload_script = PayloadBuilder::new()
+            .block_hash(block_hash)
+            .block_height(Height(1234))
+            .prev_tx_chain(Some(prev_txid))
+            .table_root(Some(hash(&[7, 8, 9])))
+            .into_script()
+            .unwrap();
+
+        let payload = Payload::parse(&payload_script).unwrap();
+        assert_eq!(payload.prev_tx_chain, Some(prev_txid));
+        assert_eq!(payload.table_root, None);
+    }
+
+    #[test]
+    fn test_payload_custom_prefix() {
+        let block_hash = hash(&[1, 2, 3]);
+        let payload_script = PayloadBuilder::new()
+            .block_hash(block_hash)
+            .block_height(Height(1234))
+            .prefix(b"NETONE")
+            .into_script()
+            .unwrap();
+
+        let payload = Payload::parse_with_prefix(&payload_script, b"NETONE").unwrap();
+        assert_eq!(payload.block_hash, block_hash);
+        assert_eq!(payload.block_height, Height(1234));
+        assert_eq!(
+            Payload::parse_with_prefix(&payload_script, b"NETTWO"),
+            Err(PayloadError::PrefixMismatch)
+        );
+        assert_eq!(
+            Payload::parse(&payload_script),
+            Err(PayloadError::PrefixMismatch)
+        );
+        assert_eq!(
+            Payload::parse_any_prefix(&payload_script),
+            (Some(&b"NETONE"[..]), Ok(payload))
+        );
+        assert_eq!(
+            PayloadBuilder::new()
+                .block_hash(block_hash)
+                .block_height(Height(1234))
+                .prefix(b"NET")
+                .into_script(),
+            Err(PayloadError::InvalidPrefixLength(3))
+        );
+    }
+
+    #[test]
+    fn test_payload_unknown_version() {
+        // Regular payload with the version byte set to 2
+        let payload_script = Script::from_hex(
+            "6a3045584f4e554d0200d204000000000000e3b0c44298fc1c14\
+             9afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        ).unwrap();
+        assert_eq!(
+            Payload::parse(&payload_script),
+            Err(PayloadError::UnknownVersion(2))
+        );
+        assert_eq!(Payload::from_script(&payload_script), None);
+    }
+
+    #[test]
+    fn test_payload_malformed() {
+        // Regular payload without the last byte of the block hash
+        let payload_script = Script::from_hex(
+            "6a2f45584f4e554d0100d204000000000000e3b0c44298fc1c14\
+             9afbf4c8996fb92427ae41e4649b934ca495991b7852b8",
+        ).unwrap();
+        assert_eq!(
+            Payload::parse(&payload_script),
+            Err(PayloadError::Malformed(1))
+        );
+    }
 }
@@ -14,11 +14,17 @@
 
 use bitcoin::network::constants::Network;
 use secp256k1::key;
+use secp256k1::Secp256k1;
 
-use super::types::{PrivateKey, RawPrivkey};
+use super::types::{PrivateKey, PublicKey, RawPrivkey};
 
 impl PrivateKey {
     pub fn from_key(network: Network, sk: key::SecretKey, compressed: bool) -> PrivateKey {
         RawPrivkey::from_secret_key(sk, compressed, network).into()
     }
+
+    /// Returns the public key that corresponds to this private key.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::from_secret_key(&Secp256k1::new(), self.secret_key()).unwrap()
+    }
 }
@@ -0,0 +1,298 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Partially signed bitcoin transactions (BIP174) that pass the anchoring transactions
+//! to the standard bitcoin tools and the external signers.
+
+use std::io::{self, Cursor, Read};
+
+use base64;
+use bitcoin::blockdata::script::Script;
+use bitcoin::blockdata::transaction::TxOut;
+use bitcoin::network::serialize::{deserialize, serialize};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use secp256k1::Secp256k1;
+use secp256k1::key::PublicKey as RawPublicKey;
+
+use details::btc;
+use details::btc::SighashType;
+use details::btc::transactions::RawBitcoinTx;
+use details::error::PsbtError;
+
+const PSBT_MAGIC: &[u8] = b"psbt\xff";
+const PSBT_GLOBAL_UNSIGNED_TX: u8 = 0x00;
+const PSBT_IN_WITNESS_UTXO: u8 = 0x01;
+const PSBT_IN_PARTIAL_SIG: u8 = 0x02;
+const PSBT_IN_SIGHASH_TYPE: u8 = 0x03;
+const PSBT_IN_WITNESS_SCRIPT: u8 = 0x05;
+const PSBT_IN_BIP32_DERIVATION: u8 = 0x06;
+
+type PsbtMap = Vec<(Vec<u8>, Vec<u8>)>;
+
+/// Signature of the anchoring transaction input made by the anchoring key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialSignature {
+    /// Index of the signed input.
+    pub input: u32,
+    /// Anchoring key that made the signature.
+    pub public_key: btc::PublicKey,
+    /// DER-encoded signature followed by the sighash type byte.
+    pub signature: btc::Signature,
+}
+
+/// Partially signed bitcoin transaction (BIP174).
+///
+/// Only the records that are used to sign the anchoring transaction inputs are
+/// interpreted, the other ones are kept as is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Psbt {
+    tx: RawBitcoinTx,
+    global: PsbtMap,
+    inputs: Vec<PsbtMap>,
+    outputs: Vec<PsbtMap>,
+}
+
+impl Psbt {
+    /// Creates the blank PSBT for the given transaction, its input scripts and witnesses
+    /// are dropped.
+    pub fn new(mut tx: RawBitcoinTx) -> Psbt {
+        for input in &mut tx.input {
+            input.script_sig = Script::new();
+            input.witness.clear();
+        }
+        let inputs = vec![PsbtMap::new(); tx.input.len()];
+        let outputs = vec![PsbtMap::new(); tx.output.len()];
+        Psbt {
+            tx,
+            global: PsbtMap::new(),
+            inputs,
+            outputs,
+        }
+    }
+
+    /// Returns the unsigned transaction.
+    pub fn tx(&self) -> &RawBitcoinTx {
+        &self.tx
+    }
+
+    /// Sets the output that is spent by the given segwit `input`.
+    pub fn set_witness_utxo(&mut self, input: u32, prev_out: &TxOut) {
+        let value = serialize(prev_out).expect("Unable to serialize transaction output");
+        self.insert(input, vec![PSBT_IN_WITNESS_UTXO], value);
+    }
+
+    /// Sets the witness script of the given `input`.
+    pub fn set_witness_script(&mut self, input: u32, redeem_script: &btc::RedeemScript) {
+        let script: &Script = redeem_script.as_ref();
+        self.insert(input, vec![PSBT_IN_WITNESS_SCRIPT], script.data());
+    }
+
+    /// Sets the sighash type of the signatures of the given `input`.
+    pub fn set_sighash_type(&mut self, input: u32, sighash_type: SighashType) {
+        let mut value = Vec::new();
+        value
+            .write_u32::<LittleEndian>(u32::from(sighash_type.as_u8()))
+            .unwrap();
+        self.insert(input, vec![PSBT_IN_SIGHASH_TYPE], value);
+    }
+
+    /// Sets the BIP32 derivation of the `pub_key` that signs the given `input`,
+    /// the derivation path starts from the master key with the given `fingerprint`.
+    pub fn set_bip32_derivation(
+        &mut self,
+        input: u32,
+        pub_key: &btc::PublicKey,
+        fingerprint: &[u8],
+        derivation_path: &[u32],
+    ) {
+        let mut value = fingerprint.to_vec();
+        for index in derivation_path {
+            value.write_u32::<LittleEndian>(*index).unwrap();
+        }
+        let key = key_with_type(PSBT_IN_BIP32_DERIVATION, &pub_key.serialize());
+        self.insert(input, key, value);
+    }
+
+    /// Adds the partial `signature` made by the `pub_key` to the given `input`.
+    /// The signature is DER-encoded and ends with the sighash type byte.
+    pub fn add_partial_signature(
+        &mut self,
+        input: u32,
+        pub_key: &btc::PublicKey,
+        signature: &[u8],
+    ) {
+        let key = key_with_type(PSBT_IN_PARTIAL_SIG, &pub_key.serialize());
+        self.insert(input, key, signature.to_vec());
+    }
+
+    /// Returns the partial signature of the given `input` made by the `pub_key`.
+    pub fn partial_signature(&self, input: u32, pub_key: &btc::PublicKey) -> Option<&[u8]> {
+        let key = key_with_type(PSBT_IN_PARTIAL_SIG, &pub_key.serialize());
+        self.inputs.get(input as usize).and_then(|records| {
+            records
+                .iter()
+                .find(|&&(ref k, _)| *k == key)
+                .map(|&(_, ref value)| value.as_slice())
+        })
+    }
+
+    /// Returns all the partial signatures of the inputs in the order of the inputs.
+    pub fn partial_signatures(&self) -> Result<Vec<PartialSignature>, PsbtError> {
+        let context = Secp256k1::without_caps();
+        let mut signatures = Vec::new();
+        for (input, records) in self.inputs.iter().enumerate() {
+            for &(ref key, ref value) in records {
+                if key.first() != Some(&PSBT_IN_PARTIAL_SIG) {
+                    continue;
+                }
+                let public_key = RawPublicKey::from_slice(&context, &key[1..])
+                    .map_err(|e| psbt_error(&e))?;
+                signatures.push(PartialSignature {
+                    input: input as u32,
+                    public_key: btc::PublicKey::from(public_key),
+                    signature: value.clone(),
+                });
+            }
+        }
+        Ok(signatures)
+    }
+
+    /// Returns the binary representation of the PSBT.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = PSBT_MAGIC.to_vec();
+        let tx = serialize(&self.tx).expect("Unable to serialize transaction");
+        write_record(&mut out, &[PSBT_GLOBAL_UNSIGNED_TX], &tx);
+        for map in Some(&self.global)
+            .into_iter()
+            .chain(&self.inputs)
+            .chain(&self.outputs)
+        {
+            for &(ref key, ref value) in map {
+                write_record(&mut out, key, value);
+            }
+            out.push(0);
+        }
+        out
+    }
+
+    /// Parses the PSBT from the binary representation.
+    pub fn deserialize(bytes: &[u8]) -> Result<Psbt, PsbtError> {
+        if !bytes.starts_with(PSBT_MAGIC) {
+            return Err(psbt_error("invalid magic bytes"));
+        }
+        let mut cursor = Cursor::new(&bytes[PSBT_MAGIC.len()..]);
+        let mut global = read_map(&mut cursor).map_err(|e| psbt_error(&e))?;
+        let pos = global
+            .iter()
+            .position(|&(ref key, _)| *key == [PSBT_GLOBAL_UNSIGNED_TX])
+            .ok_or_else(|| psbt_error("unsigned transaction is missing"))?;
+        let (_, tx) = global.remove(pos);
+        let tx: RawBitcoinTx = deserialize(&tx).map_err(|e| psbt_error(&e))?;
+
+        let inputs = read_maps(&mut cursor, tx.input.len()).map_err(|e| psbt_error(&e))?;
+        let outputs = read_maps(&mut cursor, tx.output.len()).map_err(|e| psbt_error(&e))?;
+        Ok(Psbt {
+            tx,
+            global,
+            inputs,
+            outputs,
+        })
+    }
+
+    /// Returns the base64 representation of the PSBT.
+    pub fn to_base64(&self) -> String {
+        base64::encode(&self.serialize())
+    }
+
+    /// Parses the PSBT from the base64 representation.
+    pub fn from_base64(s: &str) -> Result<Psbt, PsbtError> {
+        let bytes = base64::decode(s).map_err(|e| psbt_error(&e))?;
+        Psbt::deserialize(&bytes)
+    }
+
+    fn insert(&mut self, input: u32, key: Vec<u8>, value: Vec<u8>) {
+        let records = &mut self.inputs[input as usize];
+        records.retain(|&(ref k, _)| *k != key);
+        records.push((key, value));
+    }
+}
+
+fn key_with_type(key_type: u8, key_data: &[u8]) -> Vec<u8> {
+    let mut key = vec![key_type];
+    key.extend_from_slice(key_data);
+    key
+}
+
+fn write_compact_size(out: &mut Vec<u8>, len: usize) {
+    if len < 0xFD {
+        out.push(len as u8);
+    } else if len <= 0xFFFF {
+        out.push(0xFD);
+        out.write_u16::<LittleEndian>(len as u16).unwrap();
+    } else {
+        out.push(0xFE);
+        out.write_u32::<LittleEndian>(len as u32).unwrap();
+    }
+}
+
+fn write_record(out: &mut Vec<u8>, key: &[u8], value: &[u8]) {
+    write_compact_size(out, key.len());
+    out.extend_from_slice(key);
+    write_compact_size(out, value.len());
+    out.extend_from_slice(value);
+}
+
+fn read_compact_size<R: Read>(reader: &mut R) -> io::Result<usize> {
+    Ok(match reader.read_u8()? {
+        0xFD => reader.read_u16::<LittleEndian>()? as usize,
+        0xFE => reader.read_u32::<LittleEndian>()? as usize,
+        0xFF => reader.read_u64::<LittleEndian>()? as usize,
+        len => len as usize,
+    })
+}
+
+fn read_bytes<R: Read>(reader: &mut R, len: usize) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Unexpected end of the record",
+        ));
+    }
+    Ok(bytes)
+}
+
+// Reads the key-value records until the zero-length key that terminates the map.
+fn read_map<R: Read>(reader: &mut R) -> io::Result<PsbtMap> {
+    let mut map = PsbtMap::new();
+    loop {
+        let key_len = read_compact_size(reader)?;
+        if key_len == 0 {
+            return Ok(map);
+        }
+        let key = read_bytes(reader, key_len)?;
+        let value_len = read_compact_size(reader)?;
+        let value = read_bytes(reader, value_len)?;
+        map.push((key, value));
+    }
+}
+
+fn read_maps<R: Read>(reader: &mut R, count: usize) -> io::Result<Vec<PsbtMap>> {
+    (0..count).map(|_| read_map(reader)).collect()
+}
+
+fn psbt_error<E: ToString + ?Sized>(e: &E) -> PsbtError {
+    PsbtError::Malformed(e.to_string())
+}
@@ -12,17 +12,41 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fmt;
+
 use secp256k1::key;
 use secp256k1::Error;
 use secp256k1::Secp256k1;
 
+use exonum::encoding::serialize::{FromHex, FromHexError, ToHex};
 use exonum::storage::StorageKey;
 
+use details::error::PublicKeyError;
+
 use super::types::{PublicKey, RawPublicKey};
 
 const PUBLIC_KEY_SIZE: usize = 33;
 
 impl PublicKey {
+    /// Parses the hex encoded public key that must be in the compressed form.
+    ///
+    /// Uncompressed keys are rejected, so the redeem script is built only from
+    /// the compressed ones as the segwit requires.
+    pub fn from_hex_compressed(hex: &str) -> Result<PublicKey, PublicKeyError> {
+        let parse_error = |reason: String| PublicKeyError::Parse {
+            key: hex.to_owned(),
+            reason,
+        };
+        let bytes = Vec::<u8>::from_hex(hex).map_err(|e| parse_error(e.to_string()))?;
+        let context = Secp256k1::without_caps();
+        let raw =
+            RawPublicKey::from_slice(&context, &bytes).map_err(|e| parse_error(e.to_string()))?;
+        if bytes.len() != PUBLIC_KEY_SIZE {
+            return Err(PublicKeyError::Uncompressed(hex.to_owned()));
+        }
+        Ok(PublicKey(raw))
+    }
+
     pub fn from_secret_key(secp: &Secp256k1, sk: &key::SecretKey) -> Result<PublicKey, Error> {
         let raw = RawPublicKey::from_secret_key(secp, sk)?;
         Ok(PublicKey::from(raw))
@@ -33,6 +57,56 @@ impl PublicKey {
     }
 }
 
+impl FromHex for PublicKey {
+    type Error = FromHexError;
+
+    fn from_hex<T: AsRef<[u8]>>(v: T) -> Result<Self, Self::Error> {
+        let context = Secp256k1::without_caps();
+        let bytes = Vec::<u8>::from_hex(v)?;
+        // Uncompressed keys are not allowed in the redeem script.
+        if bytes.len() != PUBLIC_KEY_SIZE {
+            return Err(FromHexError::InvalidStringLength);
+        }
+        match RawPublicKey::from_slice(&context, bytes.as_ref()) {
+            Ok(key) => Ok(PublicKey(key)),
+            Err(_) => Err(FromHexError::InvalidStringLength),
+        }
+    }
+}
+
+impl ::std::str::FromStr for PublicKey {
+    type Err = PublicKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PublicKey::from_hex_compressed(s)
+    }
+}
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_hex(f)
+    }
+}
+
+impl ::serde::Serialize for PublicKey {
+    fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {
+        ser.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> ::serde::Deserialize<'de> for PublicKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {
+        let hex = String::deserialize(deserializer)?;
+        PublicKey::from_hex_compressed(&hex).map_err(::serde::de::Error::custom)
+    }
+}
+
 impl StorageKey for PublicKey {
     fn size(&self) -> usize {
         PUBLIC_KEY_SIZE
@@ -18,32 +18,43 @@ use std::ops::Deref;
 
 use bitcoin::blockdata::script::Script;
 use bitcoin::blockdata::transaction::{TxIn, TxOut};
+use bitcoin::network::encodable::ConsensusEncodable;
 use bitcoin::network::serialize::{deserialize, serialize, serialize_hex, BitcoinHash};
+use bitcoin::util::hash::{Sha256dEncoder, Sha256dHash};
 use bitcoin::util::privkey::Privkey;
 use bitcoinrpc;
 use btc_transaction_utils::{p2wsh, InputSignature, InputSignatureRef, TxInRef};
 use secp256k1::key::{PublicKey, SecretKey};
+use secp256k1::{Message, Secp256k1, Signature};
 
 use exonum::crypto::{hash, Hash};
-use exonum::encoding::serialize::{FromHex, FromHexError};
+use exonum::encoding::serialize::{encode_hex, FromHex, FromHexError};
 use exonum::helpers::Height;
 use exonum::storage::StorageValue;
 
 use details::btc;
-use details::btc::payload::{Payload, PayloadBuilder};
-use details::btc::{HexValueEx, RedeemScript, TxId};
-use details::error::Error as InternalError;
+use details::btc::fee::{dust_threshold, estimate_vsize};
+use details::btc::payload::{Payload, PayloadBuilder, PayloadError, DEFAULT_PAYLOAD_PREFIX};
+use details::btc::psbt::{PartialSignature, Psbt};
+use details::btc::{HexValueEx, RedeemScript, SighashType, TxId};
+use details::error::{PsbtError, TxBuildError};
 use details::rpc::{Error as RpcError, RpcClient};
 
 pub type RawBitcoinTx = ::bitcoin::blockdata::transaction::Transaction;
 
 const ANCHORING_TX_FUNDS_OUTPUT: u32 = 0;
 const ANCHORING_TX_DATA_OUTPUT: u32 = 1;
+const ANCHORING_TX_CHANGE_OUTPUT: u32 = 2;
+/// Input sequence number that signals the replace-by-fee opt-in (BIP125).
+const ANCHORING_TX_INPUT_SEQUENCE: u32 = 0xFFFF_FFFD;
+/// Maximal virtual size of the standard transaction that is relayed by `bitcoind`.
+pub const MAX_STANDARD_TX_VSIZE: u64 = 100_000;
 
 /// Anchoring transaction struct is strict:
 /// - Zero input is previous anchoring tx or initial funding tx
 /// - Zero output is next anchoring tx
 /// - First output is anchored metadata
+/// - Second output is the optional change of the fixed anchoring output value
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct AnchoringTx(pub RawBitcoinTx);
 /// Funding transaction always has an output to `p2sh` address
@@ -64,13 +75,28 @@ pub trait TxFromRaw: Sized {
     fn from_raw(raw: RawBitcoinTx) -> Option<Self>;
 }
 
-#[derive(Debug)]
+/// Builder of the anchoring transactions.
+///
+/// Validators sign the proposal only if they build byte-identical transactions,
+/// so the layout does not depend on the order of the builder calls:
+///
+/// - The first input spends the previous transaction given to `with_prev_tx`,
+///   it links the anchoring chain.
+/// - The rest of the inputs are the additional funds sorted by the txid
+///   of the spent transaction and then by the output index (as in BIP69).
+/// - The outputs keep the fixed positions that identify the anchoring transactions:
+///   the anchoring output, the data output and the optional change output.
+#[derive(Debug, Clone)]
 pub struct TransactionBuilder {
     inputs: Vec<(RawBitcoinTx, u32)>,
     output: Option<btc::Address>,
     fee: Option<u64>,
     payload: Option<(Height, Hash)>,
+    payload_prefix: Option<Vec<u8>>,
     prev_tx_chain: Option<TxId>,
+    table_root: Option<Hash>,
+    anchor_output: Option<(u64, btc::Address)>,
+    size_limit: Option<(RedeemScript, usize, u64)>,
 }
 
 impl HexValueEx for RawBitcoinTx {
@@ -140,6 +166,17 @@ impl AnchoringTx {
         TxId::from(self.0.input[0].prev_hash)
     }
 
+    /// Returns the transaction among the `prev_txs` whose output is spent by the given `input`.
+    /// The additional inputs are sorted by the builder, so the `prev_txs` may be in any order.
+    pub fn find_prev_tx<'a>(
+        &self,
+        input: u32,
+        prev_txs: &'a [RawBitcoinTx],
+    ) -> Option<&'a RawBitcoinTx> {
+        let prev_hash = self.0.input[input as usize].prev_hash;
+        prev_txs.iter().find(|tx| tx.txid() == prev_hash)
+    }
+
     pub fn sign_input(
         &self,
         redeem_script: &btc::RedeemScript,
@@ -147,12 +184,37 @@ impl AnchoringTx {
         prev_tx: &RawBitcoinTx,
         priv_key: &Privkey,
     ) -> btc::Signature {
-        sign_tx_input(
+        self.sign_input_with_sighash(redeem_script, input, prev_tx, priv_key, SighashType::All)
+    }
+
+    /// Returns the hash of the given `input` that is signed by the signatures
+    /// of the given `sighash_type`.
+    pub fn signature_hash(
+        &self,
+        redeem_script: &btc::RedeemScript,
+        input: u32,
+        prev_tx: &RawBitcoinTx,
+        sighash_type: SighashType,
+    ) -> Sha256dHash {
+        tx_input_signature_hash(self, input as usize, redeem_script, prev_tx, sighash_type)
+    }
+
+    /// Same as `sign_input` but makes the signature of the given `sighash_type`.
+    pub fn sign_input_with_sighash(
+        &self,
+        redeem_script: &btc::RedeemScript,
+        input: u32,
+        prev_tx: &RawBitcoinTx,
+        priv_key: &Privkey,
+        sighash_type: SighashType,
+    ) -> btc::Signature {
+        sign_tx_input_with_sighash(
             self,
             input as usize,
             redeem_script,
             prev_tx,
             priv_key.secret_key(),
+            sighash_type,
         )
     }
 
@@ -181,6 +243,154 @@ impl AnchoringTx {
     ) -> AnchoringTx {
         finalize_anchoring_transaction(self, redeem_script, signatures)
     }
+
+    /// Returns the base64-encoded PSBT (BIP174) of the transaction for the standard bitcoin
+    /// tools and the external signers.
+    ///
+    /// Each input carries the witness script, the sighash type and the spent output from
+    /// the `prev_txs`, which may be given in any order, along with the collected `signatures`.
+    pub fn to_psbt(
+        &self,
+        redeem_script: &btc::RedeemScript,
+        prev_txs: &[RawBitcoinTx],
+        sighash_type: SighashType,
+        signatures: &[PartialSignature],
+    ) -> String {
+        let mut psbt = Psbt::new(self.0.clone());
+        for (input, txin) in self.0.input.iter().enumerate() {
+            let input = input as u32;
+            if let Some(prev_tx) = self.find_prev_tx(input, prev_txs) {
+                psbt.set_witness_utxo(input, &prev_tx.output[txin.prev_index as usize]);
+            }
+            psbt.set_witness_script(input, redeem_script);
+            psbt.set_sighash_type(input, sighash_type);
+        }
+        for signature in signatures {
+            psbt.add_partial_signature(
+                signature.input,
+                &signature.public_key,
+                &signature.signature,
+            );
+        }
+        psbt.to_base64()
+    }
+
+    /// Returns the signatures of the anchoring keys from the PSBT signed by the external tools.
+    ///
+    /// The signatures are verified against the `prev_txs` and ordered by the inputs and then
+    /// by the keys of the `redeem_script`, so they can be passed to `finalize` as is.
+    /// The signatures of the keys that are absent in the `redeem_script` are skipped.
+    pub fn psbt_signatures(
+        &self,
+        psbt: &str,
+        redeem_script: &btc::RedeemScript,
+        prev_txs: &[RawBitcoinTx],
+    ) -> Result<Vec<PartialSignature>, PsbtError> {
+        let psbt = Psbt::from_base64(psbt)?;
+        if psbt.tx() != Psbt::new(self.0.clone()).tx() {
+            return Err(PsbtError::TxMismatch);
+        }
+
+        let keys = redeem_script.content().public_keys;
+        let mut signatures = Vec::new();
+        for signature in psbt.partial_signatures()? {
+            let position = match keys.iter().position(|key| *key == signature.public_key.0) {
+                Some(position) => position,
+                None => continue,
+            };
+            let input = signature.input;
+            let prev_tx = self.find_prev_tx(input, prev_txs)
+                .ok_or(PsbtError::PrevTxNotFound(input))?;
+            if !self.verify_input(
+                redeem_script,
+                input,
+                prev_tx,
+                &signature.public_key,
+                &signature.signature,
+            ) {
+                return Err(PsbtError::InvalidSignature {
+                    input,
+                    public_key: encode_hex(&signature.public_key.serialize()[..]),
+                });
+            }
+            signatures.push((position, signature));
+        }
+        signatures.sort_by_key(|&(position, ref signature)| (signature.input, position));
+        Ok(signatures
+            .into_iter()
+            .map(|(_, signature)| signature)
+            .collect())
+    }
+
+    /// Returns the fee of the transaction, `prev_txs` are the transactions
+    /// spent by the inputs in any order.
+    ///
+    /// Fails if the transaction spent by any input is missing, or if the outputs
+    /// exceed the inputs.
+    pub fn fee(&self, prev_txs: &[RawBitcoinTx]) -> Result<u64, TxBuildError> {
+        let mut available_funds = 0;
+        for input in self.inputs() {
+            let prev_index = self.0.input[input as usize].prev_index as usize;
+            let output = self.find_prev_tx(input, prev_txs)
+                .and_then(|tx| tx.output.get(prev_index))
+                .ok_or(TxBuildError::PrevTxNotFound { input })?;
+            available_funds += output.value;
+        }
+        let spent_funds: u64 = self.0.output.iter().map(|output| output.value).sum();
+        available_funds
+            .checked_sub(spent_funds)
+            .ok_or(TxBuildError::InsufficientFunds)
+    }
+
+    /// Creates the unsigned replacement of the transaction that pays the `new_fee`.
+    ///
+    /// The replacement spends the same inputs and carries the byte-identical payload,
+    /// so the proofs issued for the replaced transaction stay valid. The fee delta is
+    /// taken from the change output, or from the anchoring output if there is no change.
+    pub fn replace_with_fee(
+        &self,
+        prev_txs: &[RawBitcoinTx],
+        new_fee: u64,
+    ) -> Result<AnchoringTx, TxBuildError> {
+        let old_fee = self.fee(prev_txs)?;
+        if new_fee <= old_fee {
+            return Err(TxBuildError::FeeNotIncreased {
+                fee: new_fee,
+                old_fee,
+            });
+        }
+
+        let mut tx = self.0.clone();
+        for input in &mut tx.input {
+            input.script_sig = Script::new();
+            input.witness.clear();
+        }
+        {
+            let delta = new_fee - old_fee;
+            let has_change = tx.output.len() > ANCHORING_TX_CHANGE_OUTPUT as usize;
+            let out = if has_change {
+                ANCHORING_TX_CHANGE_OUTPUT
+            } else {
+                ANCHORING_TX_FUNDS_OUTPUT
+            };
+            let output = &mut tx.output[out as usize];
+            let threshold = dust_threshold(&output.script_pubkey);
+            let value = output.value.saturating_sub(delta);
+            if output.value < delta || value < threshold {
+                return Err(if has_change {
+                    TxBuildError::ChangeBelowDust { value, threshold }
+                } else {
+                    TxBuildError::OutputBelowDust { value, threshold }
+                });
+            }
+            output.value = value;
+        }
+
+        if find_tagged_payload(&tx)? != find_tagged_payload(&self.0)? {
+            return Err(TxBuildError::PayloadChanged);
+        }
+        Ok(AnchoringTx::from(tx))
+    }
 }
 
 impl fmt::Debug for AnchoringTx {
@@ -205,22 +415,37 @@ impl fmt::Debug for FundingTx {
     }
 }
 
-impl From<RawBitcoinTx> for TxKind {
-    fn from(tx: RawBitcoinTx) -> TxKind {
-        if find_payload(&tx).is_some() {
-            TxKind::Anchoring(AnchoringTx::from(tx))
-        } else {
-            // Finds output with funds and p2wsh script_pubkey
-            for out in &tx.output {
-                if out.value > 0 && out.script_pubkey.is_v0_p2wsh() {
-                    return TxKind::FundingTx(FundingTx::from(tx.clone()));
+impl TxKind {
+    /// Classifies the given transaction. Only the transactions with the payload tagged
+    /// by the given `prefix` are treated as the anchoring ones.
+    ///
+    /// The classification depends on the outputs only, so the inputs may be spent
+    /// by any number of signatures, e.g. by the signatures of the previous validators
+    /// during the transition, or not signed at all.
+    pub fn with_prefix(tx: RawBitcoinTx, prefix: &[u8]) -> TxKind {
+        match parse_payload_with_prefix(&tx, prefix) {
+            Ok(_) => TxKind::Anchoring(AnchoringTx::from(tx)),
+            Err(PayloadError::NotFound) => {
+                // Finds output with funds and p2wsh script_pubkey
+                for out in &tx.output {
+                    if out.value > 0 && out.script_pubkey.is_v0_p2wsh() {
+                        return TxKind::FundingTx(FundingTx::from(tx.clone()));
+                    }
                 }
+                TxKind::Other(BitcoinTx::from(tx))
             }
-            TxKind::Other(BitcoinTx::from(tx))
+            // Transaction with the unsupported payload should not be treated as funding.
+            Err(_) => TxKind::Other(BitcoinTx::from(tx)),
         }
     }
 }
 
+impl From<RawBitcoinTx> for TxKind {
+    fn from(tx: RawBitcoinTx) -> TxKind {
+        TxKind::with_prefix(tx, DEFAULT_PAYLOAD_PREFIX.as_bytes())
+    }
+}
+
 impl From<BitcoinTx> for TxKind {
     fn from(tx: BitcoinTx) -> TxKind {
         TxKind::from(tx.0)
@@ -235,8 +460,11 @@ impl TxFromRaw for BitcoinTx {
 
 impl TxFromRaw for AnchoringTx {
     fn from_raw(raw: RawBitcoinTx) -> Option<AnchoringTx> {
-        if let TxKind::Anchoring(tx) = TxKind::from(raw) {
-            Some(tx)
+        // The payload prefix is unknown while decoding, so the anchoring transactions
+        // of any deployment are decoded. The messages check the prefix of the actual
+        // configuration during the validation.
+        if find_payload(&raw).is_some() {
+            Some(AnchoringTx(raw))
         } else {
             None
         }
@@ -259,8 +487,12 @@ impl TransactionBuilder {
             inputs: vec![(prev_tx.clone(), out)],
             output: None,
             payload: None,
+            payload_prefix: None,
             fee: None,
             prev_tx_chain: None,
+            table_root: None,
+            anchor_output: None,
+            size_limit: None,
         }
     }
 
@@ -279,6 +511,11 @@ impl TransactionBuilder {
         self
     }
 
+    pub fn payload_prefix(mut self, prefix: &[u8]) -> TransactionBuilder {
+        self.payload_prefix = Some(prefix.to_vec());
+        self
+    }
+
     pub fn send_to(mut self, addr: btc::Address) -> TransactionBuilder {
         self.output = Some(addr);
         self
@@ -289,7 +526,32 @@ impl TransactionBuilder {
         self
     }
 
-    pub fn into_transaction(mut self) -> Result<AnchoringTx, InternalError> {
+    /// Sets the root hash of the anchored table that is committed along with the block hash.
+    pub fn table_root(mut self, root: Option<Hash>) -> TransactionBuilder {
+        self.table_root = root;
+        self
+    }
+
+    /// Sets the fixed value of the anchoring output, the rest of the funds
+    /// is sent to the given change address.
+    pub fn anchor_output(mut self, output: Option<(u64, btc::Address)>) -> TransactionBuilder {
+        self.anchor_output = output;
+        self
+    }
+
+    /// Limits the estimated virtual size of the transaction after its inputs are spent
+    /// by `signatures_count` signatures and the given redeem script.
+    pub fn size_limit(
+        mut self,
+        redeem_script: &RedeemScript,
+        signatures_count: usize,
+        limit: u64,
+    ) -> TransactionBuilder {
+        self.size_limit = Some((redeem_script.clone(), signatures_count, limit));
+        self
+    }
+
+    pub fn into_transaction(mut self) -> Result<AnchoringTx, TxBuildError> {
         let available_funds: u64 = self.inputs
             .iter()
             .map(|&(ref tx, out)| tx.output[out as usize].value)
@@ -299,29 +561,79 @@ impl TransactionBuilder {
         let fee = self.fee.expect("Fee is not set");
         let (height, block_hash) = self.payload.take().expect("Payload is not set");
         if available_funds < fee {
-            return Err(InternalError::InsufficientFunds);
+            return Err(TxBuildError::InsufficientFunds);
         }
         let total_funds = available_funds - fee;
+        let (out_funds, change) = match self.anchor_output.take() {
+            Some((value, change_addr)) => {
+                if value > total_funds {
+                    return Err(TxBuildError::AnchorOutputExceedsFunds {
+                        value,
+                        available: total_funds,
+                    });
+                }
+                let change_value = total_funds - value;
+                let change_script = change_addr.script_pubkey();
+                let threshold = dust_threshold(&change_script);
+                if change_value < threshold {
+                    return Err(TxBuildError::ChangeBelowDust {
+                        value: change_value,
+                        threshold,
+                    });
+                }
+                let change = TxOut {
+                    value: change_value,
+                    script_pubkey: change_script,
+                };
+                (value, Some(change))
+            }
+            None => (total_funds, None),
+        };
+        let threshold = dust_threshold(&addr.script_pubkey());
+        if out_funds < threshold {
+            return Err(TxBuildError::OutputBelowDust {
+                value: out_funds,
+                threshold,
+            });
+        }
 
-        let tx = create_anchoring_transaction(
-            &addr,
-            height,
-            block_hash,
-            self.inputs.iter(),
-            total_funds,
-            self.prev_tx_chain,
-        );
+        let prefix = self.payload_prefix
+            .unwrap_or_else(|| DEFAULT_PAYLOAD_PREFIX.as_bytes().to_vec());
+        let metadata_script = PayloadBuilder::new()
+            .block_hash(block_hash)
+            .block_height(height)
+            .prev_tx_chain(self.prev_tx_chain)
+            .table_root(self.table_root)
+            .prefix(&prefix)
+            .into_script()?;
+        self.inputs[1..].sort_by_key(|&(ref tx, out)| (bip69_txid(&tx.txid()), out));
+        let mut tx =
+            create_anchoring_transaction(&addr, metadata_script, self.inputs.iter(), out_funds);
+        // The change output goes after the data one to keep the outputs layout.
+        tx.0.output.extend(change);
+        if let Some((redeem_script, signatures_count, limit)) = self.size_limit {
+            let vsize = estimate_vsize(&tx.0, &redeem_script, signatures_count);
+            if vsize > limit {
+                return Err(TxBuildError::TooLarge { vsize, limit });
+            }
+        }
         Ok(tx)
     }
 }
 
+// BIP69 compares the txids in the reversed byte order, i.e. as they are displayed.
+fn bip69_txid(txid: &Sha256dHash) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    bytes.copy_from_slice(&txid[..]);
+    bytes.reverse();
+    bytes
+}
+
 fn create_anchoring_transaction<'a, I>(
     addr: &btc::Address,
-    block_height: Height,
-    block_hash: Hash,
+    metadata_script: Script,
     inputs: I,
     out_funds: u64,
-    prev_chain_txid: Option<TxId>,
 ) -> AnchoringTx
 where
     I: Iterator<Item = &'a (RawBitcoinTx, u32)>,
@@ -331,16 +643,11 @@ where
             prev_hash: unspent_tx.txid(),
             prev_index: utxo_vout,
             script_sig: Script::new(),
-            sequence: 0xFFFF_FFFF,
+            sequence: ANCHORING_TX_INPUT_SEQUENCE,
             witness: Vec::default(),
         })
         .collect::<Vec<_>>();
 
-    let metadata_script = PayloadBuilder::new()
-        .block_hash(block_hash)
-        .block_height(block_height)
-        .prev_tx_chain(prev_chain_txid)
-        .into_script();
     let outputs = vec![
         TxOut {
             value: out_funds,
@@ -375,6 +682,49 @@ pub fn sign_tx_input(
         .into()
 }
 
+/// Same as `sign_tx_input` but makes the signature of the given `sighash_type`.
+pub fn sign_tx_input_with_sighash(
+    tx: &RawBitcoinTx,
+    input: usize,
+    subscript: &RedeemScript,
+    prev_tx: &RawBitcoinTx,
+    sec_key: &SecretKey,
+    sighash_type: SighashType,
+) -> Vec<u8> {
+    match sighash_type {
+        SighashType::All => sign_tx_input(tx, input, subscript, prev_tx, sec_key),
+        SighashType::AllPlusAnyoneCanPay => {
+            let context = Secp256k1::new();
+            let sighash = anyonecanpay_signature_hash(tx, input, subscript, prev_tx);
+            let msg = Message::from_slice(&sighash[..]).unwrap();
+            let mut signature = context
+                .sign(&msg, sec_key)
+                .unwrap()
+                .serialize_der(&context);
+            signature.push(sighash_type.as_u8());
+            signature
+        }
+    }
+}
+
+/// Returns the BIP143 hash of the transaction `input` that is signed by the signatures
+/// of the given `sighash_type`.
+pub fn tx_input_signature_hash(
+    tx: &RawBitcoinTx,
+    input: usize,
+    subscript: &RedeemScript,
+    prev_tx: &RawBitcoinTx,
+    sighash_type: SighashType,
+) -> Sha256dHash {
+    match sighash_type {
+        SighashType::All => p2wsh::InputSigner::new(subscript.clone())
+            .signature_hash(TxInRef::new(tx, input), prev_tx),
+        SighashType::AllPlusAnyoneCanPay => {
+            anyonecanpay_signature_hash(tx, input, subscript, prev_tx)
+        }
+    }
+}
+
 pub fn verify_tx_input(
     tx: &RawBitcoinTx,
     input: usize,
@@ -383,6 +733,18 @@ pub fn verify_tx_input(
     pub_key: &PublicKey,
     signature: &[u8],
 ) -> bool {
+    if SighashType::of_signature(signature) == Some(SighashType::AllPlusAnyoneCanPay) {
+        let context = Secp256k1::without_caps();
+        let sighash = anyonecanpay_signature_hash(tx, input, subscript, prev_tx);
+        let content = &signature[..signature.len() - 1];
+        return Message::from_slice(&sighash[..])
+            .and_then(|msg| {
+                let signature = Signature::from_der(&context, content)?;
+                context.verify(&msg, &signature, pub_key)
+            })
+            .is_ok();
+    }
+
     let signer = p2wsh::InputSigner::new(subscript.clone());
     InputSignatureRef::from_bytes(signer.secp256k1_context(), signature)
         .and_then(|signature| {
@@ -391,6 +753,42 @@ pub fn verify_tx_input(
         .is_ok()
 }
 
+/// Computes the BIP143 signature hash of the given input for the `SIGHASH_ALL|ANYONECANPAY`
+/// signature, which does not commit to the other inputs of the transaction.
+fn anyonecanpay_signature_hash(
+    tx: &RawBitcoinTx,
+    input: usize,
+    subscript: &RedeemScript,
+    prev_tx: &RawBitcoinTx,
+) -> Sha256dHash {
+    let txin = &tx.input[input];
+    let value = prev_tx.output[txin.prev_index as usize].value;
+    let hash_outputs = {
+        let mut enc = Sha256dEncoder::new();
+        for txout in &tx.output {
+            txout.consensus_encode(&mut enc).unwrap();
+        }
+        enc.into_hash()
+    };
+
+    let mut enc = Sha256dEncoder::new();
+    tx.version.consensus_encode(&mut enc).unwrap();
+    // Neither the previous outputs nor the sequences of the other inputs are committed.
+    Sha256dHash::default().consensus_encode(&mut enc).unwrap();
+    Sha256dHash::default().consensus_encode(&mut enc).unwrap();
+    txin.prev_hash.consensus_encode(&mut enc).unwrap();
+    txin.prev_index.consensus_encode(&mut enc).unwrap();
+    subscript.as_ref().consensus_encode(&mut enc).unwrap();
+    value.consensus_encode(&mut enc).unwrap();
+    txin.sequence.consensus_encode(&mut enc).unwrap();
+    hash_outputs.consensus_encode(&mut enc).unwrap();
+    tx.lock_time.consensus_encode(&mut enc).unwrap();
+    u32::from(SighashType::AllPlusAnyoneCanPay.as_u8())
+        .consensus_encode(&mut enc)
+        .unwrap();
+    enc.into_hash()
+}
+
 fn finalize_anchoring_transaction(
     mut anchoring_tx: AnchoringTx,
     redeem_script: &btc::RedeemScript,
@@ -406,8 +804,34 @@ fn finalize_anchoring_transaction(
     anchoring_tx
 }
 
+/// Tries to extract the anchoring payload from the data output of the given transaction.
+pub fn parse_payload(tx: &RawBitcoinTx) -> Result<Payload, PayloadError> {
+    parse_payload_with_prefix(tx, DEFAULT_PAYLOAD_PREFIX.as_bytes())
+}
+
+/// Same as `parse_payload` but expects the payload tagged by the given `prefix`.
+pub fn parse_payload_with_prefix(
+    tx: &RawBitcoinTx,
+    prefix: &[u8],
+) -> Result<Payload, PayloadError> {
+    tx.output
+        .get(ANCHORING_TX_DATA_OUTPUT as usize)
+        .ok_or(PayloadError::NotFound)
+        .and_then(|output| Payload::parse_with_prefix(&output.script_pubkey, prefix))
+}
+
+/// Extracts the payload regardless of its prefix.
 fn find_payload(tx: &RawBitcoinTx) -> Option<Payload> {
     tx.output
         .get(ANCHORING_TX_DATA_OUTPUT as usize)
-        .and_then(|output| Payload::from_script(&output.script_pubkey))
+        .and_then(|output| Payload::parse_any_prefix(&output.script_pubkey).1.ok())
+}
+
+/// Extracts the payload along with its prefix.
+fn find_tagged_payload(tx: &RawBitcoinTx) -> Result<(Vec<u8>, Payload), PayloadError> {
+    let output = tx.output
+        .get(ANCHORING_TX_DATA_OUTPUT as usize)
+        .ok_or(PayloadError::NotFound)?;
+    let (prefix, payload) = Payload::parse_any_prefix(&output.script_pubkey);
+    Ok((prefix.unwrap_or_default().to_vec(), payload?))
 }
@@ -14,9 +14,11 @@
 
 use std::fmt;
 use std::ops::Deref;
+use std::str::FromStr;
 
 use bitcoin::blockdata::script::Builder;
 pub use bitcoin::blockdata::script::Script as RawScript;
+use bitcoin::blockdata::transaction::SigHashType as RawSigHashType;
 pub use bitcoin::blockdata::transaction::Transaction as RawTransaction;
 use bitcoin::network::constants::Network;
 pub use bitcoin::util::address::Address as RawAddress;
@@ -24,12 +26,13 @@ use bitcoin::util::hash::Sha256dHash;
 pub use bitcoin::util::privkey::Privkey as RawPrivkey;
 use btc_transaction_utils::{multisig::RedeemScript, p2wsh};
 pub use secp256k1::key::PublicKey as RawPublicKey;
-use secp256k1::Secp256k1;
 
 use exonum::encoding::serialize::{encode_hex, FromHex, FromHexError, ToHex};
 use exonum::encoding::Field;
 use exonum::storage::StorageKey;
 
+use details::error::AddrError;
+
 use super::HexValueEx;
 
 #[derive(Debug, Clone, Copy, PartialEq, Hash, Eq)]
@@ -42,6 +45,8 @@ pub struct PublicKey(pub RawPublicKey);
 pub struct Address(pub RawAddress);
 
 pub type Signature = Vec<u8>;
+/// Hash of the bitcoin block, it has the same representation as the transaction id.
+pub type BlockHash = TxId;
 
 implement_wrapper! {Sha256dHash, TxId}
 implement_wrapper! {RawPublicKey, PublicKey}
@@ -51,7 +56,6 @@ implement_wrapper! {RawPrivkey, PrivateKey}
 implement_str_conversion! {RawAddress, Address}
 implement_str_conversion! {RawPrivkey, PrivateKey}
 
-implement_serde_hex! {PublicKey}
 implement_serde_hex! {TxId}
 implement_serde_string! {Address}
 implement_serde_string! {PrivateKey}
@@ -101,19 +105,6 @@ impl ToHex for TxId {
     }
 }
 
-impl FromHex for PublicKey {
-    type Error = FromHexError;
-
-    fn from_hex<T: AsRef<[u8]>>(v: T) -> Result<Self, Self::Error> {
-        let context = Secp256k1::without_caps();
-        let bytes = Vec::<u8>::from_hex(v)?;
-        match RawPublicKey::from_slice(&context, bytes.as_ref()) {
-            Ok(key) => Ok(PublicKey(key)),
-            Err(_) => Err(FromHexError::InvalidStringLength),
-        }
-    }
-}
-
 impl ToHex for PublicKey {
     fn write_hex<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
         self.serialize().as_ref().write_hex(w)
@@ -149,8 +140,85 @@ impl StorageKey for TxId {
 }
 
 impl Address {
+    /// Creates the native segwit (p2wsh) address for the given `redeem_script`.
+    /// Such addresses are encoded in the bech32 format, and their inputs are signed
+    /// by the BIP143 algorithm.
     pub fn from_script(redeem_script: &RedeemScript, network: Network) -> Address {
         let raw_address = p2wsh::address(redeem_script, network);
         Address(raw_address)
     }
+
+    /// Parses the address and checks that it belongs to the given `network`.
+    pub fn from_str_checked(s: &str, network: Network) -> Result<Address, AddrError> {
+        let addr = Address::from_str(s).map_err(|e| AddrError::Parse {
+            address: s.to_owned(),
+            reason: e.to_string(),
+        })?;
+        if !addr.is_valid_for(network) {
+            return Err(AddrError::NetworkMismatch {
+                address: s.to_owned(),
+                actual: addr.network,
+                expected: network,
+            });
+        }
+        Ok(addr)
+    }
+
+    /// Checks whether the address can be used in the given `network`.
+    ///
+    /// Base58 addresses of the regtest share their version bytes with the testnet ones,
+    /// so they are parsed as testnet addresses and accepted for both networks.
+    pub fn is_valid_for(&self, network: Network) -> bool {
+        if self.network == network {
+            return true;
+        }
+        let script_pubkey = self.script_pubkey();
+        let is_witness = script_pubkey.is_v0_p2wsh() || script_pubkey.is_v0_p2wpkh();
+        network == Network::Regtest && self.network == Network::Testnet && !is_witness
+    }
+}
+
+/// Signature hash type of the anchoring transaction inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SighashType {
+    /// Signature commits to all the inputs and outputs of the transaction.
+    All,
+    /// Signature commits to all the outputs, but only to the signed input,
+    /// so the inputs can be added later, for example to bump the fee.
+    AllPlusAnyoneCanPay,
+}
+
+impl SighashType {
+    /// Returns the byte that is appended to the signatures of this type.
+    pub fn as_u8(self) -> u8 {
+        match self {
+            SighashType::All => RawSigHashType::All.as_u32() as u8,
+            SighashType::AllPlusAnyoneCanPay => {
+                RawSigHashType::AllPlusAnyoneCanPay.as_u32() as u8
+            }
+        }
+    }
+
+    /// Returns the type of the given signature or `None` if it is not supported.
+    pub fn of_signature(signature: &[u8]) -> Option<SighashType> {
+        match signature.last() {
+            Some(&byte) if byte == SighashType::All.as_u8() => Some(SighashType::All),
+            Some(&byte) if byte == SighashType::AllPlusAnyoneCanPay.as_u8() => {
+                Some(SighashType::AllPlusAnyoneCanPay)
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks whether it is the default `All` type.
+    pub fn is_all(&self) -> bool {
+        *self == SighashType::All
+    }
+}
+
+impl Default for SighashType {
+    fn default() -> SighashType {
+        SighashType::All
+    }
 }
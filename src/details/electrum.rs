@@ -0,0 +1,293 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Relay to the bitcoin network through the Electrum server, e.g. `ElectrumX` or `electrs`,
+//! for the nodes that do not run the full `bitcoind` node.
+
+use std::io::{self, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bitcoin::blockdata::script::Script;
+use jsonrpc;
+use serde::de::DeserializeOwned;
+use serde_json::{self, Value};
+
+use exonum::crypto::hash;
+use exonum::encoding::serialize::{encode_hex, FromHex};
+
+use details::btc;
+use details::btc::transactions::{BitcoinTx, FundingTx};
+use details::http::{connect, read_line};
+use details::rpc::{check_address_network, error_message_contains, AnchoringRpcConfig,
+                   BitcoinRelay, Error, Result, RpcConnectionConfig, TxInfo, SATOSHI_DIVISOR};
+
+/// Messages of the errors that are returned by the servers for the unknown transactions.
+const TX_NOT_FOUND_MESSAGES: &[&str] = &["No such mempool or blockchain transaction", "not found"];
+
+/// Electrum server configuration.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ElectrumConfig {
+    /// Address of the server TCP endpoint, e.g. `127.0.0.1:50001`.
+    pub address: String,
+}
+
+/// Entry of the `blockchain.scripthash.get_history`
+/// and `blockchain.scripthash.listunspent` responses.
+#[derive(Debug, Deserialize)]
+struct ScriptHashEntry {
+    tx_hash: String,
+    /// Height of the block that contains the transaction, zero or negative for the mempool.
+    height: i64,
+}
+
+/// Header of the best block returned by the `blockchain.headers.subscribe` call.
+#[derive(Debug, Deserialize)]
+struct BestHeader {
+    height: u64,
+}
+
+/// Relay that queries the Electrum server by the line-delimited JSON-RPC over TCP.
+///
+/// The server indexes the transactions of all addresses, so there is no need to watch them.
+/// Sending funds is not supported because the server has no wallet. The transport errors
+/// drop the connection and fail with the retryable errors, so the calls are repeated
+/// on a new connection if the relay is wrapped into `RetryingRpc`.
+#[derive(Debug)]
+pub struct ElectrumRelay {
+    config: ElectrumConfig,
+    network: btc::Network,
+    connection: RpcConnectionConfig,
+    stream: Mutex<Option<BufReader<TcpStream>>>,
+    nonce: AtomicUsize,
+}
+
+impl ElectrumRelay {
+    /// Creates the relay for the given Electrum server of the given `network`.
+    /// The addresses of other networks are rejected by the relay.
+    pub fn new(
+        config: ElectrumConfig,
+        network: btc::Network,
+        connection: RpcConnectionConfig,
+    ) -> ElectrumRelay {
+        ElectrumRelay {
+            config,
+            network,
+            connection,
+            stream: Mutex::new(None),
+            nonce: AtomicUsize::new(0),
+        }
+    }
+
+    fn request<T: DeserializeOwned>(&self, method: &str, params: Vec<Value>) -> Result<T> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": self.nonce.fetch_add(1, Ordering::SeqCst) + 1,
+        });
+        let mut stream = self.stream.lock().unwrap();
+        let response = match self.exchange(&mut stream, &request) {
+            Ok(response) => response,
+            Err(e) => {
+                *stream = None;
+                return Err(Error::Other(e));
+            }
+        };
+        response.into_result().map_err(Error::from)
+    }
+
+    fn exchange(
+        &self,
+        stream: &mut Option<BufReader<TcpStream>>,
+        request: &Value,
+    ) -> io::Result<jsonrpc::Response> {
+        if stream.is_none() {
+            *stream = Some(BufReader::new(connect(&self.config.address, &self.connection)?));
+        }
+        let reader = stream.as_mut().unwrap();
+
+        let mut body = serde_json::to_vec(request)?;
+        body.push(b'\n');
+        reader.get_mut().write_all(&body)?;
+        reader.get_mut().flush()?;
+
+        let mut line = String::new();
+        loop {
+            line.clear();
+            read_line(reader, &mut line)?;
+            let response: Value = serde_json::from_str(&line)?;
+            // Notifications of the subscriptions have no ids.
+            if response.get("method").is_some() {
+                continue;
+            }
+            let response: jsonrpc::Response = serde_json::from_value(response)?;
+            if response.id == request["id"] {
+                return Ok(response);
+            }
+        }
+    }
+
+    fn best_height(&self) -> Result<u64> {
+        let header: BestHeader = self.request("blockchain.headers.subscribe", Vec::new())?;
+        Ok(header.height)
+    }
+}
+
+impl BitcoinRelay for ElectrumRelay {
+    fn get_transaction(&self, txid: btc::TxId) -> Result<Option<BitcoinTx>> {
+        let params = vec![json!(txid.to_string())];
+        match self.request::<String>("blockchain.transaction.get", params) {
+            Ok(tx) => BitcoinTx::from_hex(&tx)
+                .map(Some)
+                .map_err(|_| invalid_data(format!("Malformed transaction {}", txid))),
+            Err(Error::NoInformation(_)) => Ok(None),
+            Err(ref e) if is_tx_not_found(e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn get_transaction_info(&self, txid: btc::TxId) -> Result<Option<TxInfo>> {
+        let tx = match self.get_transaction(txid)? {
+            Some(tx) => tx,
+            None => return Ok(None),
+        };
+        // The height of the transaction is known from the history of any of its outputs.
+        let confirmations = match tx.output.first() {
+            Some(output) => {
+                let params = vec![json!(script_hash(&output.script_pubkey))];
+                let history: Vec<ScriptHashEntry> =
+                    self.request("blockchain.scripthash.get_history", params)?;
+                let txid = txid.to_string();
+                match history.into_iter().find(|entry| entry.tx_hash == txid) {
+                    Some(entry) => confirmations_at(entry.height, self.best_height()?),
+                    None => None,
+                }
+            }
+            None => None,
+        };
+        Ok(Some(TxInfo {
+            body: tx,
+            confirmations,
+            block_hash: None,
+            block_time: None,
+        }))
+    }
+
+    fn watch_address(&self, _addr: &btc::Address, _rescan: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn send_transaction(&self, tx: BitcoinTx) -> Result<()> {
+        let params = vec![json!(tx.to_hex())];
+        self.request::<String>("blockchain.transaction.broadcast", params).map(drop)
+    }
+
+    fn send_to_address(&self, _addr: &btc::Address, _satoshis: u64) -> Result<FundingTx> {
+        Err(unsupported("send_to_address"))
+    }
+
+    fn unspent_transactions(&self, addr: &btc::Address) -> Result<Vec<TxInfo>> {
+        check_address_network(addr, self.network)?;
+        let params = vec![json!(script_hash(&addr.script_pubkey()))];
+        let unspent_txs: Vec<ScriptHashEntry> =
+            self.request("blockchain.scripthash.listunspent", params)?;
+        if unspent_txs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let best_height = self.best_height()?;
+        let mut txs = Vec::new();
+        for entry in unspent_txs {
+            let txid = btc::TxId::from_hex(&entry.tx_hash)
+                .map_err(|_| invalid_data(format!("Malformed txid {}", entry.tx_hash)))?;
+            // Like `listunspent` of the `bitcoind`, the mempool outputs have zero confirmations.
+            let confirmations = Some(confirmations_at(entry.height, best_height).unwrap_or(0));
+            if let Some(raw_tx) = self.get_transaction(txid)? {
+                // The transactions are classified by the caller according to the payload
+                // prefix of its configuration.
+                txs.push(TxInfo {
+                    body: BitcoinTx::from(raw_tx),
+                    confirmations,
+                    block_hash: None,
+                    block_time: None,
+                });
+            }
+        }
+        Ok(txs)
+    }
+
+    fn estimate_fee(&self, conf_target: u16) -> Result<Option<u64>> {
+        // The fee rate is measured in bitcoins per kilobyte, `-1` means that there is no estimate.
+        let rate: f64 = self.request("blockchain.estimatefee", vec![json!(conf_target)])?;
+        if rate > 0.0 {
+            Ok(Some((rate * SATOSHI_DIVISOR).round() as u64))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn get_block_count(&self) -> Result<u64> {
+        self.best_height()
+    }
+
+    fn is_block_in_best_chain(&self, _block_hash: btc::BlockHash) -> Result<bool> {
+        Err(unsupported("is_block_in_best_chain"))
+    }
+
+    fn config(&self) -> AnchoringRpcConfig {
+        AnchoringRpcConfig {
+            host: self.config.address.clone(),
+            username: None,
+            password: None,
+        }
+    }
+}
+
+/// Returns the Electrum script hash, i.e. the reversed `SHA-256` of the script in hex.
+pub fn script_hash(script: &Script) -> String {
+    let mut bytes = hash(&script.data()).as_ref().to_vec();
+    bytes.reverse();
+    encode_hex(bytes)
+}
+
+/// Returns the number of confirmations of the transaction at the given `height`.
+fn confirmations_at(height: i64, best_height: u64) -> Option<u64> {
+    if height > 0 {
+        Some((best_height + 1).saturating_sub(height as u64))
+    } else {
+        None
+    }
+}
+
+fn is_tx_not_found(e: &Error) -> bool {
+    match *e {
+        Error::Rpc(jsonrpc::Error::Rpc(_)) => TX_NOT_FOUND_MESSAGES
+            .iter()
+            .any(|pattern| error_message_contains(e, pattern)),
+        _ => false,
+    }
+}
+
+fn invalid_data(msg: String) -> Error {
+    Error::Other(io::Error::new(io::ErrorKind::InvalidData, msg))
+}
+
+fn unsupported(method: &str) -> Error {
+    Error::Other(io::Error::new(
+        io::ErrorKind::Other,
+        format!("`{}` is not supported by the Electrum relay", method),
+    ))
+}
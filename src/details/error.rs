@@ -14,6 +14,8 @@
 
 use std::io;
 
+use details::btc::payload::PayloadError;
+use details::btc::Network;
 use details::rpc::Error as RpcError;
 
 /// Service error.
@@ -22,10 +24,268 @@ pub enum Error {
     /// Rpc error.
     #[display(fmt = "{}", _0)]
     Rpc(RpcError),
-    /// Insufficient funds to create anchoring transaction.
-    #[display(fmt = "Insufficient funds to create anchoring transaction.")]
-    InsufficientFunds,
+    /// Unable to build anchoring transaction.
+    #[display(fmt = "{}", _0)]
+    TxBuild(TxBuildError),
     /// An input output error.
     #[display(fmt = "{}", _0)]
     Io(io::Error),
+    /// Signing backend is unable to sign the anchoring transaction.
+    #[display(fmt = "{}", _0)]
+    Signer(SignerError),
+}
+
+/// Errors that may occur during the anchoring transaction building.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, Fail)]
+pub enum TxBuildError {
+    /// Insufficient funds to create anchoring transaction.
+    #[display(fmt = "Insufficient funds to create anchoring transaction.")]
+    InsufficientFunds,
+    /// Anchoring output is below the dust threshold, so the transaction is non-standard.
+    #[display(fmt = "Anchoring output value {} is below the dust threshold {}.", value, threshold)]
+    OutputBelowDust {
+        /// Value of the anchoring output in satoshis.
+        value: u64,
+        /// Minimal standard value of the anchoring output in satoshis.
+        threshold: u64,
+    },
+    /// Fixed anchoring output value exceeds the funds left after paying the fee.
+    #[display(fmt = "Anchoring output value {} exceeds the available funds {}.", value, available)]
+    AnchorOutputExceedsFunds {
+        /// Fixed value of the anchoring output in satoshis.
+        value: u64,
+        /// Funds left after paying the fee in satoshis.
+        available: u64,
+    },
+    /// Change output is below the dust threshold, so the transaction is non-standard.
+    #[display(fmt = "Change output value {} is below the dust threshold {}.", value, threshold)]
+    ChangeBelowDust {
+        /// Value of the change output in satoshis.
+        value: u64,
+        /// Minimal standard value of the change output in satoshis.
+        threshold: u64,
+    },
+    /// Estimated size of the signed transaction exceeds the limit, so the transaction
+    /// would not be relayed. Switch to the segwit address or reduce the validators count.
+    #[display(fmt = "Estimated transaction size {} vbytes exceeds the limit {} vbytes.",
+              vsize, limit)]
+    TooLarge {
+        /// Estimated virtual size of the signed transaction.
+        vsize: u64,
+        /// Maximal allowed virtual size of the transaction.
+        limit: u64,
+    },
+    /// Fee of the replacement transaction does not exceed the fee of the replaced one,
+    /// so the replacement would be rejected by the BIP125 rules.
+    #[display(fmt = "Replacement fee {} does not exceed the fee {} of the replaced transaction.",
+              fee, old_fee)]
+    FeeNotIncreased {
+        /// Fee of the replacement transaction in satoshis.
+        fee: u64,
+        /// Fee of the replaced transaction in satoshis.
+        old_fee: u64,
+    },
+    /// Transaction spent by the input is not among the given ones.
+    #[display(fmt = "Transaction spent by the input {} is not found.", input)]
+    PrevTxNotFound {
+        /// Index of the input.
+        input: u32,
+    },
+    /// Payload of the replacement differs from the payload of the replaced transaction.
+    #[display(fmt = "Payload of the replacement differs from the replaced transaction.")]
+    PayloadChanged,
+    /// Payload of the anchoring transaction cannot be built, e.g. the payload prefix
+    /// has the wrong length.
+    #[display(fmt = "Unable to build the payload: {}.", _0)]
+    Payload(PayloadError),
+}
+
+impl From<TxBuildError> for Error {
+    fn from(e: TxBuildError) -> Error {
+        Error::TxBuild(e)
+    }
+}
+
+impl From<PayloadError> for TxBuildError {
+    fn from(e: PayloadError) -> TxBuildError {
+        TxBuildError::Payload(e)
+    }
+}
+
+impl From<SignerError> for Error {
+    fn from(e: SignerError) -> Error {
+        Error::Signer(e)
+    }
+}
+
+/// Errors that may occur during the anchoring address parsing.
+#[derive(Debug, Clone, PartialEq, Eq, Display, Fail)]
+pub enum AddrError {
+    /// Address string is not a valid bitcoin address.
+    #[display(fmt = "Unable to parse bitcoin address {}: {}", address, reason)]
+    Parse {
+        /// Given address string.
+        address: String,
+        /// Description of the parsing error.
+        reason: String,
+    },
+    /// Address belongs to another bitcoin network.
+    #[display(fmt = "Address {} is generated for the {} network, but the {} network is expected",
+              address, actual, expected)]
+    NetworkMismatch {
+        /// Given address string.
+        address: String,
+        /// Network the address is generated for.
+        actual: Network,
+        /// Configured network.
+        expected: Network,
+    },
+}
+
+/// Errors that may occur during the anchoring configuration validation.
+#[derive(Debug, Clone, PartialEq, Eq, Display, Fail)]
+pub enum ConfigError {
+    /// Anchoring frequency is below the allowed minimum, so the anchoring transactions
+    /// would drain the funds too quickly.
+    #[display(fmt = "Anchoring frequency {} is below the minimum {}", frequency, min_frequency)]
+    FrequencyTooLow {
+        /// Configured anchoring frequency.
+        frequency: u64,
+        /// Minimal allowed anchoring frequency.
+        min_frequency: u64,
+    },
+    /// Payload prefix does not fit into the payload header.
+    #[display(fmt = "Payload prefix {:?} should be {} bytes long", _0, _1)]
+    InvalidPayloadPrefix(String, usize),
+    /// Fixed anchoring output value is set, but there is no address for the change.
+    #[display(fmt = "Change address should be set along with the anchoring output value")]
+    ChangeAddressNotSet,
+    /// Change address is not suitable for the configured network.
+    #[display(fmt = "Invalid change address: {}", _0)]
+    InvalidChangeAddress(AddrError),
+    /// Fixed anchoring output value is below the dust threshold.
+    #[display(fmt = "Anchoring output value {} is below the dust threshold {}", value, threshold)]
+    AnchorOutputBelowDust {
+        /// Configured value of the anchoring output in satoshis.
+        value: u64,
+        /// Minimal standard value of the anchoring output in satoshis.
+        threshold: u64,
+    },
+    /// Change address is set, but the configuration has no funding transactions
+    /// to the anchoring address, while every anchoring transaction spends one of them.
+    #[display(fmt = "Change address is set without funding transactions to the anchoring address")]
+    AnchorOutputWithoutFunding,
+    /// Funding transaction cannot pay for the first anchoring transaction.
+    #[display(fmt = "Funding transaction {} sends {} satoshis to the anchoring address, \
+                     at least {} are required to pay the fee and stay above the dust threshold",
+              txid, amount, required)]
+    InsufficientFunding {
+        /// Txid of the funding transaction.
+        txid: String,
+        /// Value of the funding transaction output to the anchoring address in satoshis.
+        amount: u64,
+        /// Minimal value of the output in satoshis.
+        required: u64,
+    },
+    /// Funding transaction is unknown to the bitcoin relay, so it has never been mined
+    /// or has been lost.
+    #[display(fmt = "Funding transaction {} is not found by the bitcoin relay", _0)]
+    FundingTxNotFound(String),
+    /// Funding transaction does not have enough confirmations in the bitcoin blockchain.
+    #[display(fmt = "Funding transaction {} has {} confirmations, at least {} are required",
+              txid, confirmations, required)]
+    FundingTxUnconfirmed {
+        /// Txid of the funding transaction.
+        txid: String,
+        /// Current number of the funding transaction confirmations.
+        confirmations: u64,
+        /// Minimal number of confirmations.
+        required: u64,
+    },
+    /// TOML representation of the anchoring configuration is malformed, has the unknown
+    /// fields or misses the required ones.
+    #[display(fmt = "Invalid TOML anchoring configuration: {}", _0)]
+    Toml(String),
+    /// TOML representation of the anchoring configuration has an unsupported schema version.
+    #[display(fmt = "TOML anchoring configuration version {} is not supported, expected {}",
+              version, supported)]
+    UnsupportedTomlVersion {
+        /// Given version.
+        version: String,
+        /// Version of the schema supported by the node.
+        supported: u32,
+    },
+    /// Signing threshold differs from the one defined by the number of the anchoring keys.
+    #[display(fmt = "Threshold {} does not match the {} signatures required by the anchoring keys",
+              threshold, expected)]
+    ThresholdMismatch {
+        /// Given threshold.
+        threshold: u8,
+        /// Number of signatures required by the anchoring keys.
+        expected: u8,
+    },
+    /// Bitcoin relay is unable to check the funding transaction.
+    #[display(fmt = "Unable to check the funding transaction {}: {}", txid, reason)]
+    FundingTxCheckFailed {
+        /// Txid of the funding transaction.
+        txid: String,
+        /// Description of the relay error.
+        reason: String,
+    },
+}
+
+/// Errors that may occur during the anchoring public key parsing.
+#[derive(Debug, Clone, PartialEq, Eq, Display, Fail)]
+pub enum PublicKeyError {
+    /// String is not a valid hex encoded public key.
+    #[display(fmt = "Unable to parse public key {}: {}", key, reason)]
+    Parse {
+        /// Given key string.
+        key: String,
+        /// Description of the parsing error.
+        reason: String,
+    },
+    /// Public key is in the uncompressed form. Such keys produce a different redeem script
+    /// and are not allowed in the segwit scripts.
+    #[display(fmt = "Public key {} is uncompressed, only compressed keys are allowed", _0)]
+    Uncompressed(String),
+}
+
+/// Errors that may occur during the signing of the anchoring transaction inputs.
+#[derive(Debug, Clone, PartialEq, Eq, Display, Fail)]
+pub enum SignerError {
+    /// Signing backend has no private key for the anchoring address.
+    #[display(fmt = "Private key for the address {} is not found", _0)]
+    KeyNotFound(String),
+    /// External signer is unavailable or has rejected the request.
+    #[display(fmt = "External signer failed: {}", _0)]
+    External(String),
+    /// Hardware wallet is unavailable or has rejected the transaction.
+    #[display(fmt = "Hardware wallet failed: {}", _0)]
+    Hwi(String),
+    /// Signature made by the backend does not match the anchoring key of the node.
+    #[display(fmt = "Signature of the input {} does not match the anchoring key", _0)]
+    InvalidSignature(u32),
+}
+
+/// Errors that may occur during the import of the partially signed anchoring transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Display, Fail)]
+pub enum PsbtError {
+    /// PSBT is not a valid base64 encoded BIP174 structure.
+    #[display(fmt = "Malformed PSBT: {}", _0)]
+    Malformed(String),
+    /// Unsigned transaction of the PSBT differs from the anchoring transaction.
+    #[display(fmt = "PSBT transaction differs from the anchoring transaction")]
+    TxMismatch,
+    /// Transaction spent by the input is unknown, so its signatures cannot be verified.
+    #[display(fmt = "Transaction spent by the input {} is not found", _0)]
+    PrevTxNotFound(u32),
+    /// Partial signature does not match the input and the anchoring key.
+    #[display(fmt = "Signature of the input {} by the key {} is incorrect", input, public_key)]
+    InvalidSignature {
+        /// Index of the signed input.
+        input: u32,
+        /// Hex-encoded anchoring key that made the signature.
+        public_key: String,
+    },
 }
@@ -0,0 +1,240 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimal HTTP transport of the `bitcoind` RPC requests with the timeouts
+//! and the pool of the keep-alive connections.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use iron::headers::{Authorization, Basic, Headers};
+
+use super::rpc::RpcConnectionConfig;
+
+/// Port of the endpoints that are given without the explicit one.
+const DEFAULT_HTTP_PORT: u16 = 80;
+
+/// Response to the HTTP request.
+#[derive(Debug)]
+pub struct HttpResponse {
+    /// Status code.
+    pub status: u16,
+    /// Response body.
+    pub body: Vec<u8>,
+    keep_alive: bool,
+}
+
+/// Pool of the keep-alive connections to the single HTTP endpoint.
+///
+/// The connections are established with the connect timeout, and every read or write
+/// on them is limited by the read timeout, so a hung server leads to the `TimedOut`
+/// or `WouldBlock` error instead of blocking the caller.
+#[derive(Debug)]
+pub struct HttpConnectionPool {
+    authority: String,
+    path: String,
+    authorization: String,
+    config: RpcConnectionConfig,
+    idle: Mutex<Vec<TcpStream>>,
+}
+
+impl HttpConnectionPool {
+    /// Creates the pool for the given endpoint `url`, e.g. `http://127.0.0.1:18332`.
+    pub fn new(
+        url: &str,
+        username: Option<String>,
+        password: Option<String>,
+        config: RpcConnectionConfig,
+    ) -> HttpConnectionPool {
+        let url = if url.starts_with("http://") {
+            &url["http://".len()..]
+        } else {
+            url
+        };
+        let (authority, path) = match url.find('/') {
+            Some(pos) => (&url[..pos], &url[pos..]),
+            None => (url, "/"),
+        };
+        let authority = if authority.contains(':') {
+            authority.to_owned()
+        } else {
+            format!("{}:{}", authority, DEFAULT_HTTP_PORT)
+        };
+        let authorization = username.map_or_else(String::new, |username| {
+            let mut headers = Headers::new();
+            headers.set(Authorization(Basic { username, password }));
+            headers.to_string()
+        });
+
+        HttpConnectionPool {
+            authority,
+            path: path.to_owned(),
+            authorization,
+            config,
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Sends the `POST` request with the given json `body`.
+    ///
+    /// An idle connection is reused if there is one. If it turns out to be closed
+    /// by the server, the request is repeated on a new connection.
+    pub fn post(&self, body: &[u8]) -> io::Result<HttpResponse> {
+        let idle = self.idle.lock().unwrap().pop();
+        if let Some(stream) = idle {
+            match self.send(&stream, body) {
+                Ok(response) => return Ok(self.release(stream, response)),
+                Err(ref e) if is_closed_connection(e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let stream = self.connect()?;
+        let response = self.send(&stream, body)?;
+        Ok(self.release(stream, response))
+    }
+
+    fn connect(&self) -> io::Result<TcpStream> {
+        connect(&self.authority, &self.config)
+    }
+
+    fn send(&self, mut stream: &TcpStream, body: &[u8]) -> io::Result<HttpResponse> {
+        let head = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\n{}Content-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: keep-alive\r\n\r\n",
+            self.path,
+            self.authority,
+            self.authorization,
+            body.len()
+        );
+        stream.write_all(head.as_bytes())?;
+        stream.write_all(body)?;
+        stream.flush()?;
+        read_response(stream)
+    }
+
+    fn release(&self, stream: TcpStream, response: HttpResponse) -> HttpResponse {
+        if response.keep_alive {
+            let mut idle = self.idle.lock().unwrap();
+            if idle.len() < self.config.pool_size {
+                idle.push(stream);
+            }
+        }
+        response
+    }
+}
+
+/// Connects to the given `authority`, e.g. `127.0.0.1:18332`, with the connect timeout
+/// and limits every read or write on the resulting stream by the read timeout.
+pub fn connect(authority: &str, config: &RpcConnectionConfig) -> io::Result<TcpStream> {
+    let connect_timeout = Duration::from_millis(config.connect_timeout);
+    let read_timeout = Duration::from_millis(config.read_timeout);
+
+    let mut last_error = None;
+    for addr in authority.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, connect_timeout) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(read_timeout))?;
+                stream.set_write_timeout(Some(read_timeout))?;
+                stream.set_nodelay(true)?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Unable to resolve {}", authority),
+        )
+    }))
+}
+
+fn read_response(stream: &TcpStream) -> io::Result<HttpResponse> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    read_line(&mut reader, &mut line)?;
+    let status = line.split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| invalid_data(format!("Malformed HTTP status line: {}", line.trim())))?;
+
+    let mut keep_alive = !line.starts_with("HTTP/1.0");
+    let mut content_length = None;
+    loop {
+        line.clear();
+        read_line(&mut reader, &mut line)?;
+        let header = line.trim();
+        if header.is_empty() {
+            break;
+        }
+
+        let mut parts = header.splitn(2, ':');
+        let name = parts.next().unwrap_or_default().trim();
+        let value = parts.next().unwrap_or_default().trim();
+        if name.eq_ignore_ascii_case("Content-Length") {
+            let len = value
+                .parse::<usize>()
+                .map_err(|_| invalid_data(format!("Malformed Content-Length: {}", value)))?;
+            content_length = Some(len);
+        } else if name.eq_ignore_ascii_case("Connection") {
+            keep_alive = value.eq_ignore_ascii_case("keep-alive");
+        }
+    }
+
+    let mut body = Vec::new();
+    match content_length {
+        Some(len) => {
+            body.resize(len, 0);
+            reader.read_exact(&mut body)?;
+        }
+        None => {
+            reader.read_to_end(&mut body)?;
+            keep_alive = false;
+        }
+    }
+    Ok(HttpResponse {
+        status,
+        body,
+        keep_alive,
+    })
+}
+
+/// Reads the line, an `UnexpectedEof` error is returned if the connection is closed.
+pub fn read_line<R: BufRead>(reader: &mut R, line: &mut String) -> io::Result<()> {
+    if reader.read_line(line)? == 0 {
+        Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Connection is closed by the server",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn is_closed_connection(e: &io::Error) -> bool {
+    match e.kind() {
+        io::ErrorKind::UnexpectedEof
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::BrokenPipe => true,
+        _ => false,
+    }
+}
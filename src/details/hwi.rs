@@ -0,0 +1,190 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Signing of the anchoring transactions by the hardware wallets through the
+//! [HWI](https://github.com/bitcoin-core/HWI) command line tool.
+//!
+//! The hardware wallets do not sign the bare signature hashes, so the input is passed
+//! to the `hwi signtx` command as the partially signed bitcoin transaction (BIP174)
+//! that contains the spent output, the witness script and the derivation path
+//! of the anchoring key.
+
+use std::process::Command;
+
+use bitcoin::util::hash::Sha256dHash;
+use serde_json::{self, Value};
+
+use exonum::encoding::serialize::FromHex;
+
+use details::btc;
+use details::btc::psbt::Psbt;
+use details::error::SignerError;
+use details::signer::{InputSigningRequest, SignerBackend};
+
+/// Offset of the hardened BIP32 derivation indices.
+const BIP32_HARDENED: u32 = 0x8000_0000;
+
+/// Configuration of the hardware wallet that is accessed by the HWI.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct HwiConfig {
+    /// Path to the `hwi` executable.
+    #[serde(default = "default_hwi_command")]
+    pub command: String,
+    /// Hex-encoded fingerprint of the master key of the wallet, e.g. `8038ecd5`.
+    pub fingerprint: String,
+    /// Derivation path of the anchoring key, e.g. `m/48'/1'/0'/2'/0/0`.
+    pub derivation_path: String,
+    /// Anchoring public key derived by the `derivation_path`.
+    pub public_key: btc::PublicKey,
+    /// Additional arguments of the `hwi` command, for example `["--chain", "test"]`.
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// Signer that requests the signatures from the hardware wallet by the `hwi signtx` command.
+///
+/// The wallet holds the single anchoring key, which is used for all the anchoring addresses.
+#[derive(Debug)]
+pub struct HwiSigner {
+    config: HwiConfig,
+    fingerprint: Vec<u8>,
+    derivation_path: Vec<u32>,
+}
+
+impl HwiSigner {
+    /// Creates the signer, returns an error if the fingerprint or the derivation path
+    /// of the `config` is malformed.
+    pub fn new(config: HwiConfig) -> Result<HwiSigner, SignerError> {
+        let fingerprint = Vec::<u8>::from_hex(&config.fingerprint)
+            .ok()
+            .and_then(|fingerprint| if fingerprint.len() == 4 {
+                Some(fingerprint)
+            } else {
+                None
+            })
+            .ok_or_else(|| hwi_error(format!("Malformed fingerprint {}", config.fingerprint)))?;
+        let derivation_path = parse_derivation_path(&config.derivation_path).ok_or_else(|| {
+            hwi_error(format!(
+                "Malformed derivation path {}",
+                config.derivation_path
+            ))
+        })?;
+        Ok(HwiSigner {
+            config,
+            fingerprint,
+            derivation_path,
+        })
+    }
+
+    /// Returns the PSBT with the given input to be signed by the wallet.
+    pub fn psbt(&self, request: &InputSigningRequest) -> Psbt {
+        let input = request.input;
+        let prev_out = request.tx.input[input as usize].prev_index as usize;
+        let mut psbt = Psbt::new(request.tx.clone());
+        psbt.set_witness_utxo(input, &request.prev_tx.output[prev_out]);
+        psbt.set_sighash_type(input, request.sighash_type);
+        psbt.set_witness_script(input, request.redeem_script);
+        psbt.set_bip32_derivation(
+            input,
+            &self.config.public_key,
+            &self.fingerprint,
+            &self.derivation_path,
+        );
+        psbt
+    }
+
+    fn sign_tx(&self, psbt: &Psbt) -> Result<Psbt, SignerError> {
+        let output = Command::new(&self.config.command)
+            .args(&self.config.args)
+            .arg("--fingerprint")
+            .arg(&self.config.fingerprint)
+            .arg("signtx")
+            .arg(psbt.to_base64())
+            .output()
+            .map_err(|e| hwi_error(format!("Unable to run {}: {}", self.config.command, e)))?;
+        let response: Value = serde_json::from_slice(&output.stdout).map_err(|_| {
+            hwi_error(format!(
+                "Unexpected output, status={}, stderr={}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        })?;
+        if let Some(error) = response.get("error") {
+            return Err(hwi_error(error.to_string()));
+        }
+        match response.get("psbt") {
+            Some(&Value::String(ref psbt)) => {
+                Psbt::from_base64(psbt).map_err(|e| hwi_error(e.to_string()))
+            }
+            _ => Err(hwi_error(format!("Unexpected response {}", response))),
+        }
+    }
+}
+
+impl SignerBackend for HwiSigner {
+    fn sign(&self, _: &Sha256dHash, _: &btc::Address) -> Result<Vec<u8>, SignerError> {
+        Err(hwi_error("Hardware wallet signs only the whole transactions"))
+    }
+
+    fn sign_input(&self, request: &InputSigningRequest) -> Result<Vec<u8>, SignerError> {
+        let psbt = self.psbt(request);
+        let signed = self.sign_tx(&psbt)?;
+        if signed.tx() != psbt.tx() {
+            return Err(hwi_error("Signed transaction differs from the requested one"));
+        }
+        let mut signature = signed
+            .partial_signature(request.input, &self.config.public_key)
+            .ok_or_else(|| hwi_error(format!("Input {} is not signed", request.input)))?
+            .to_vec();
+        // The sighash type byte is appended by the handler.
+        if signature.pop() != Some(request.sighash_type.as_u8()) {
+            return Err(hwi_error("Signature has unexpected sighash type"));
+        }
+        Ok(signature)
+    }
+
+    fn public_key(&self, _: &btc::Address) -> Result<Option<btc::PublicKey>, SignerError> {
+        Ok(Some(self.config.public_key))
+    }
+}
+
+fn default_hwi_command() -> String {
+    "hwi".to_owned()
+}
+
+/// Parses the BIP32 derivation path, the hardened indices are marked by `'` or `h`.
+fn parse_derivation_path(path: &str) -> Option<Vec<u32>> {
+    let mut parts = path.split('/');
+    if parts.next() != Some("m") {
+        return None;
+    }
+    parts
+        .map(|part| {
+            let (index, hardened) = if part.ends_with('\'') || part.ends_with('h') {
+                (&part[..part.len() - 1], true)
+            } else {
+                (part, false)
+            };
+            let index = index.parse::<u32>().ok()?;
+            if index >= BIP32_HARDENED {
+                return None;
+            }
+            Some(if hardened { index + BIP32_HARDENED } else { index })
+        })
+        .collect()
+}
+
+fn hwi_error<S: Into<String>>(message: S) -> SignerError {
+    SignerError::Hwi(message.into())
+}
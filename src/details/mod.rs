@@ -16,8 +16,12 @@
 mod macros;
 
 pub mod btc;
+pub mod electrum;
 pub mod error;
+mod http;
+pub mod hwi;
 pub mod rpc;
+pub mod signer;
 
 #[cfg(test)]
 pub mod tests;
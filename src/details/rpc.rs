@@ -12,14 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::cmp;
+use std::io;
 use std::string::ToString;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
 
 use bitcoinrpc;
+use jsonrpc;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{self, Value};
 
 use exonum::encoding::serialize::FromHex;
 
 use details::btc;
-use details::btc::transactions::{BitcoinTx, FundingTx, TxKind};
+use details::btc::transactions::{BitcoinTx, FundingTx};
+use details::http::HttpConnectionPool;
 
 pub use bitcoinrpc::Client as RpcClient;
 
@@ -43,6 +53,52 @@ pub struct AnchoringRpcConfig {
     pub password: Option<String>,
 }
 
+/// Backoff parameters of the retries of the failed `bitcoind` RPC calls.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RpcRetryConfig {
+    /// Maximal number of attempts of each call, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry in milliseconds.
+    pub initial_delay: u64,
+    /// Maximal delay between the retries in milliseconds.
+    /// Each next delay is twice as long as the previous one.
+    pub max_delay: u64,
+}
+
+impl Default for RpcRetryConfig {
+    fn default() -> RpcRetryConfig {
+        RpcRetryConfig {
+            max_attempts: 6,
+            initial_delay: 500,
+            max_delay: 4000,
+        }
+    }
+}
+
+/// Timeouts and the connection pool size of the `bitcoind` RPC client.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RpcConnectionConfig {
+    /// Timeout of the connection establishment in milliseconds.
+    pub connect_timeout: u64,
+    /// Timeout of waiting for the response data in milliseconds.
+    pub read_timeout: u64,
+    /// Maximal number of the idle connections that are kept open for the next calls.
+    pub pool_size: usize,
+}
+
+impl Default for RpcConnectionConfig {
+    fn default() -> RpcConnectionConfig {
+        RpcConnectionConfig {
+            connect_timeout: 5_000,
+            read_timeout: 30_000,
+            pool_size: 4,
+        }
+    }
+}
+
+/// `bitcoind` error code which is returned while the node is starting or reindexing.
+pub const RPC_IN_WARMUP: i32 = -28;
+
 /// Client for the `Bitcoind` RPC API, for more information visit
 /// this [site](https://en.bitcoin.it/wiki/Original_Bitcoin_client/API_calls_list).
 #[derive(Debug)]
@@ -61,6 +117,26 @@ pub struct TxInfo {
     pub body: BitcoinTx,
     /// Number of confirmations.
     pub confirmations: Option<u64>,
+    /// Hash of the block that contains the transaction.
+    pub block_hash: Option<btc::BlockHash>,
+    /// Unix timestamp in seconds of the block that contains the transaction.
+    pub block_time: Option<u64>,
+}
+
+/// Verbose information about bitcoin transaction returned by the `getrawtransaction` call.
+///
+/// Unlike `bitcoinrpc::RawTransactionInfo` it keeps the hash of the block
+/// that contains the transaction.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RawTxInfo {
+    /// Transaction hex.
+    pub hex: String,
+    /// Number of confirmations.
+    pub confirmations: Option<u64>,
+    /// Hash of the block that contains the transaction.
+    pub blockhash: Option<String>,
+    /// Unix timestamp in seconds of the block that contains the transaction.
+    pub blocktime: Option<u64>,
 }
 
 impl From<bitcoinrpc::RawTransactionInfo> for TxInfo {
@@ -69,6 +145,19 @@ impl From<bitcoinrpc::RawTransactionInfo> for TxInfo {
             body: BitcoinTx::from_hex(info.hex.expect("Transaction hex is absent in response."))
                 .unwrap(),
             confirmations: info.confirmations,
+            block_hash: None,
+            block_time: None,
+        }
+    }
+}
+
+impl From<RawTxInfo> for TxInfo {
+    fn from(info: RawTxInfo) -> Self {
+        TxInfo {
+            body: BitcoinTx::from_hex(info.hex).unwrap(),
+            confirmations: info.confirmations,
+            block_hash: info.blockhash.and_then(|hash| btc::BlockHash::from_hex(hash).ok()),
+            block_time: info.blocktime,
         }
     }
 }
@@ -89,7 +178,8 @@ pub trait BitcoinRelay: 'static + ::std::fmt::Debug + Send + Sync {
     /// Sends funds to the given address.
     fn send_to_address(&self, addr: &btc::Address, satoshis: u64) -> Result<FundingTx>;
 
-    /// Lists unspent transactions for the given address.
+    /// Lists unspent transactions for the given address. The transactions are not
+    /// classified, see `AnchoringConfig::tx_kind`.
     fn unspent_transactions(&self, addr: &btc::Address) -> Result<Vec<TxInfo>>;
 
     /// Retrieves information about confirmations for transaction with the given id.
@@ -98,34 +188,102 @@ pub trait BitcoinRelay: 'static + ::std::fmt::Debug + Send + Sync {
         Ok(info.and_then(|x| x.confirmations))
     }
 
+    /// Estimates the fee rate in satoshis per kilobyte needed for a transaction
+    /// to be confirmed within `conf_target` blocks.
+    ///
+    /// Returns `None` if the node does not have enough data to estimate the fee.
+    fn estimate_fee(&self, conf_target: u16) -> Result<Option<u64>>;
+
+    /// Retrieves information about the transaction with the given id along with
+    /// the transaction `prev_txid` that it spends.
+    ///
+    /// Relays that support the batches of calls do it in a single round trip.
+    fn get_transaction_info_with_prev(
+        &self,
+        txid: btc::TxId,
+        prev_txid: btc::TxId,
+    ) -> Result<(Option<TxInfo>, Option<BitcoinTx>)> {
+        let info = self.get_transaction_info(txid)?;
+        let prev_tx = self.get_transaction(prev_txid)?;
+        Ok((info, prev_tx))
+    }
+
+    /// Retrieves information about the transactions with the given ids,
+    /// the results are returned in the order of the `txids`.
+    ///
+    /// Relays that support the batches of calls do it in a single round trip.
+    fn get_transactions_info(&self, txids: &[btc::TxId]) -> Result<Vec<Option<TxInfo>>> {
+        txids
+            .iter()
+            .map(|txid| self.get_transaction_info(*txid))
+            .collect()
+    }
+
+    /// Returns the number of blocks in the longest bitcoin blockchain.
+    fn get_block_count(&self) -> Result<u64>;
+
+    /// Checks that the block with the given hash belongs to the longest bitcoin blockchain.
+    ///
+    /// Returns `false` for the unknown blocks and for the blocks orphaned by a reorganization.
+    fn is_block_in_best_chain(&self, block_hash: btc::BlockHash) -> Result<bool>;
+
     /// Returns an actual relay configuration.
     fn config(&self) -> AnchoringRpcConfig;
 }
 
-macro_rules! retry {
-    ($expr:expr) => {{
-        use std::thread;
-        use std::time::Duration;
+/// Converts the `estimatesmartfee` response to the fee rate in satoshis per kilobyte.
+pub fn fee_rate_from_estimate(response: &Value) -> Option<u64> {
+    response
+        .get("feerate")
+        .and_then(Value::as_f64)
+        .map(|rate| (rate * SATOSHI_DIVISOR).round() as u64)
+}
 
-        let mut delay = Duration::from_millis(500);
-        let delay_increment = Duration::from_millis(1000);
+/// Checks that the `getblockheader` response describes a block from the longest blockchain.
+///
+/// `bitcoind` reports `-1` confirmations for the blocks that are not in the longest chain.
+pub fn block_is_in_best_chain(response: &Value) -> bool {
+    response
+        .get("confirmations")
+        .and_then(Value::as_i64)
+        .map_or(false, |confirmations| confirmations >= 0)
+}
 
-        let mut res = $expr;
-        for _ in 0..5 {
-            if res.is_ok() {
-                break;
-            }
-            res = $expr;
-            thread::sleep(delay);
-            delay += delay_increment;
-        }
-        res
-    }};
+/// Checks that the address belongs to the `network` of the relay, so the addresses
+/// of another network are never passed to the bitcoin node.
+pub fn check_address_network(addr: &btc::Address, network: btc::Network) -> Result<()> {
+    if addr.is_valid_for(network) {
+        Ok(())
+    } else {
+        Err(Error::Other(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Address {} does not belong to the {} network", addr, network),
+        )))
+    }
+}
+
+/// Sends the request through the raw json rpc client, it is used for the calls
+/// that are absent in `exonum_bitcoinrpc` or whose responses are parsed incompletely.
+fn raw_request<T: DeserializeOwned>(
+    rpc: &RpcClient,
+    method: &str,
+    params: Vec<Value>,
+) -> Result<T> {
+    let client = jsonrpc::client::Client::new(
+        rpc.url().to_owned(),
+        rpc.username().clone(),
+        rpc.password().clone(),
+    );
+    let request = client.build_request(method.to_owned(), params);
+    client
+        .send_request(&request)
+        .and_then(|response| response.into_result())
+        .map_err(Error::from)
 }
 
 impl BitcoinRelay for RpcClient {
     fn get_transaction(&self, txid: btc::TxId) -> Result<Option<BitcoinTx>> {
-        let r = retry!(self.getrawtransaction(&txid.to_string()));
+        let r = self.getrawtransaction(&txid.to_string());
         match r {
             Ok(tx) => Ok(Some(BitcoinTx::from_hex(tx).unwrap())),
             Err(bitcoinrpc::Error::NoInformation(_)) => Ok(None),
@@ -134,7 +292,8 @@ impl BitcoinRelay for RpcClient {
     }
 
     fn get_transaction_info(&self, txid: btc::TxId) -> Result<Option<TxInfo>> {
-        let info = match retry!(self.getrawtransaction_verbose(&txid.to_string())) {
+        let params = vec![json!(txid.to_string()), json!(1)];
+        let info: RawTxInfo = match raw_request(self, "getrawtransaction", params) {
             Ok(info) => Ok(info),
             Err(bitcoinrpc::Error::NoInformation(_)) => return Ok(None),
             Err(e) => Err(e),
@@ -143,46 +302,61 @@ impl BitcoinRelay for RpcClient {
     }
 
     fn watch_address(&self, addr: &btc::Address, rescan: bool) -> Result<()> {
-        retry!(self.importaddress(&addr.to_string(), "multisig", false, rescan))
+        self.importaddress(&addr.to_string(), "multisig", false, rescan)
     }
 
     fn send_transaction(&self, tx: BitcoinTx) -> Result<()> {
         let tx_hex = tx.to_hex();
-        retry!(self.sendrawtransaction(&tx_hex).map(drop))
+        self.sendrawtransaction(&tx_hex).map(drop)
     }
 
     fn send_to_address(&self, addr: &btc::Address, satoshis: u64) -> Result<FundingTx> {
         let addr = addr.to_string();
         let funds_str = (satoshis as f64 / SATOSHI_DIVISOR).to_string();
-        let utxo_txid = retry!(self.sendtoaddress(&addr, &funds_str))?;
+        let utxo_txid = self.sendtoaddress(&addr, &funds_str)?;
         // TODO rewrite Error types to avoid unwraps.
         let utxo_txid = btc::TxId::from_hex(&utxo_txid).unwrap();
         Ok(FundingTx::from(self.get_transaction(utxo_txid)?.unwrap()))
     }
 
     fn unspent_transactions(&self, addr: &btc::Address) -> Result<Vec<TxInfo>> {
-        let unspent_txs = retry!(self.listunspent(0, 9_999_999, &[addr.to_string()]))?;
+        let unspent_txs = self.listunspent(0, 9_999_999, &[addr.to_string()])?;
         let mut txs = Vec::new();
         for info in unspent_txs {
             let txid = btc::TxId::from_hex(&info.txid).unwrap();
             let confirmations = Some(info.confirmations);
             if let Some(raw_tx) = self.get_transaction(txid)? {
-                match TxKind::from(raw_tx) {
-                    TxKind::Anchoring(tx) => txs.push(TxInfo {
-                        body: tx.into(),
-                        confirmations,
-                    }),
-                    TxKind::FundingTx(tx) => txs.push(TxInfo {
-                        body: tx.into(),
-                        confirmations,
-                    }),
-                    TxKind::Other(_) => {}
-                }
+                // The transactions are classified by the caller according to the payload
+                // prefix of its configuration.
+                txs.push(TxInfo {
+                    body: BitcoinTx::from(raw_tx),
+                    confirmations,
+                    block_hash: None,
+                    block_time: None,
+                });
             }
         }
         Ok(txs)
     }
 
+    fn estimate_fee(&self, conf_target: u16) -> Result<Option<u64>> {
+        let response: Value = raw_request(self, "estimatesmartfee", vec![json!(conf_target)])?;
+        Ok(fee_rate_from_estimate(&response))
+    }
+
+    fn get_block_count(&self) -> Result<u64> {
+        self.getblockcount()
+    }
+
+    fn is_block_in_best_chain(&self, block_hash: btc::BlockHash) -> Result<bool> {
+        let params = vec![json!(block_hash.to_string())];
+        match raw_request::<Value>(self, "getblockheader", params) {
+            Ok(header) => Ok(block_is_in_best_chain(&header)),
+            Err(bitcoinrpc::Error::NoInformation(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
     fn config(&self) -> AnchoringRpcConfig {
         AnchoringRpcConfig {
             host: self.url().to_string(),
@@ -192,8 +366,411 @@ impl BitcoinRelay for RpcClient {
     }
 }
 
+/// Client for the `bitcoind` RPC API that limits the duration of the calls by the connect
+/// and read timeouts and reuses the connections.
+///
+/// The calls that exceed the timeouts fail with the retryable errors,
+/// so they are repeated if the client is wrapped into `RetryingRpc`.
+#[derive(Debug)]
+pub struct PooledRpcClient {
+    rpc: AnchoringRpcConfig,
+    network: btc::Network,
+    pool: HttpConnectionPool,
+    nonce: AtomicUsize,
+}
+
+impl PooledRpcClient {
+    /// Creates the client for the given `rpc` endpoint of the node in the given `network`.
+    /// The addresses of other networks are rejected by the client.
+    pub fn new(
+        rpc: AnchoringRpcConfig,
+        network: btc::Network,
+        connection: RpcConnectionConfig,
+    ) -> PooledRpcClient {
+        let pool = HttpConnectionPool::new(
+            &rpc.host,
+            rpc.username.clone(),
+            rpc.password.clone(),
+            connection,
+        );
+        PooledRpcClient {
+            rpc,
+            network,
+            pool,
+            nonce: AtomicUsize::new(0),
+        }
+    }
+
+    /// Sends the given calls in a single HTTP request as the JSON-RPC batch.
+    ///
+    /// The responses are matched with the calls by their ids and returned in the order
+    /// of the calls, so the failure of a single call does not affect the other ones.
+    pub fn batch_request(&self, calls: &[(&str, Vec<Value>)]) -> Result<Vec<jsonrpc::Response>> {
+        let requests = calls
+            .iter()
+            .map(|&(method, ref params)| self.build_request(method, params.clone()))
+            .collect::<Vec<_>>();
+        let mut responses: Vec<jsonrpc::Response> = self.post(&requests)?;
+        requests
+            .iter()
+            .map(|request| {
+                let pos = responses
+                    .iter()
+                    .position(|response| response.id == request.id)
+                    .ok_or(jsonrpc::Error::NonceMismatch)?;
+                Ok(responses.swap_remove(pos))
+            })
+            .collect()
+    }
+
+    fn request<T: DeserializeOwned>(&self, method: &str, params: Vec<Value>) -> Result<T> {
+        let request = self.build_request(method, params);
+        let response: jsonrpc::Response = self.post(&request)?;
+        if response.id != request.id {
+            return Err(jsonrpc::Error::NonceMismatch.into());
+        }
+        response.into_result().map_err(Error::from)
+    }
+
+    fn build_request(&self, method: &str, params: Vec<Value>) -> jsonrpc::Request {
+        jsonrpc::Request {
+            method: method.to_owned(),
+            params,
+            id: json!(self.nonce.fetch_add(1, Ordering::SeqCst) + 1),
+        }
+    }
+
+    fn post<B: Serialize, R: DeserializeOwned>(&self, body: &B) -> Result<R> {
+        let body = serde_json::to_vec(body).map_err(jsonrpc::Error::Json)?;
+        let http_response = self.pool.post(&body).map_err(Error::Other)?;
+        serde_json::from_slice(&http_response.body).map_err(|_| {
+            Error::Other(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unexpected response, HTTP status={}", http_response.status),
+            ))
+        })
+    }
+}
+
+impl BitcoinRelay for PooledRpcClient {
+    fn get_transaction(&self, txid: btc::TxId) -> Result<Option<BitcoinTx>> {
+        let params = vec![json!(txid.to_string()), json!(0)];
+        match self.request::<String>("getrawtransaction", params) {
+            Ok(tx) => Ok(Some(BitcoinTx::from_hex(tx).unwrap())),
+            Err(bitcoinrpc::Error::NoInformation(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn get_transaction_info(&self, txid: btc::TxId) -> Result<Option<TxInfo>> {
+        let params = vec![json!(txid.to_string()), json!(1)];
+        let info: RawTxInfo = match self.request("getrawtransaction", params) {
+            Ok(info) => Ok(info),
+            Err(bitcoinrpc::Error::NoInformation(_)) => return Ok(None),
+            Err(e) => Err(e),
+        }?;
+        Ok(Some(info.into()))
+    }
+
+    fn get_transaction_info_with_prev(
+        &self,
+        txid: btc::TxId,
+        prev_txid: btc::TxId,
+    ) -> Result<(Option<TxInfo>, Option<BitcoinTx>)> {
+        let calls = [
+            ("getrawtransaction", vec![json!(txid.to_string()), json!(1)]),
+            ("getrawtransaction", vec![json!(prev_txid.to_string()), json!(0)]),
+        ];
+        let mut responses = self.batch_request(&calls)?.into_iter();
+        let info = match responses.next().unwrap().into_result::<RawTxInfo>().map_err(Error::from) {
+            Ok(info) => Some(info.into()),
+            Err(bitcoinrpc::Error::NoInformation(_)) => None,
+            Err(e) => return Err(e),
+        };
+        let prev_tx = match responses.next().unwrap().into_result::<String>().map_err(Error::from) {
+            Ok(tx) => Some(BitcoinTx::from_hex(tx).unwrap()),
+            Err(bitcoinrpc::Error::NoInformation(_)) => None,
+            Err(e) => return Err(e),
+        };
+        Ok((info, prev_tx))
+    }
+
+    fn get_transactions_info(&self, txids: &[btc::TxId]) -> Result<Vec<Option<TxInfo>>> {
+        if txids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let calls = txids
+            .iter()
+            .map(|txid| ("getrawtransaction", vec![json!(txid.to_string()), json!(1)]))
+            .collect::<Vec<_>>();
+        self.batch_request(&calls)?
+            .into_iter()
+            .map(|response| match response.into_result::<RawTxInfo>().map_err(Error::from) {
+                Ok(info) => Ok(Some(info.into())),
+                Err(bitcoinrpc::Error::NoInformation(_)) => Ok(None),
+                Err(e) => Err(e),
+            })
+            .collect()
+    }
+
+    fn watch_address(&self, addr: &btc::Address, rescan: bool) -> Result<()> {
+        check_address_network(addr, self.network)?;
+        let params = vec![
+            json!(addr.to_string()),
+            json!("multisig"),
+            json!(false),
+            json!(rescan),
+        ];
+        // `importaddress` returns `null` result.
+        match self.request::<Option<bool>>("importaddress", params) {
+            Ok(_) | Err(Error::Rpc(jsonrpc::Error::NoErrorOrResult)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn send_transaction(&self, tx: BitcoinTx) -> Result<()> {
+        let params = vec![json!(tx.to_hex())];
+        self.request::<String>("sendrawtransaction", params).map(drop)
+    }
+
+    fn send_to_address(&self, addr: &btc::Address, satoshis: u64) -> Result<FundingTx> {
+        check_address_network(addr, self.network)?;
+        let funds_str = (satoshis as f64 / SATOSHI_DIVISOR).to_string();
+        let params = vec![json!(addr.to_string()), json!(funds_str)];
+        let utxo_txid: String = self.request("sendtoaddress", params)?;
+        // TODO rewrite Error types to avoid unwraps.
+        let utxo_txid = btc::TxId::from_hex(&utxo_txid).unwrap();
+        Ok(FundingTx::from(self.get_transaction(utxo_txid)?.unwrap()))
+    }
+
+    fn unspent_transactions(&self, addr: &btc::Address) -> Result<Vec<TxInfo>> {
+        check_address_network(addr, self.network)?;
+        let params = vec![json!(0), json!(9_999_999), json!([addr.to_string()])];
+        let unspent_txs: Vec<bitcoinrpc::UnspentTransactionInfo> =
+            self.request("listunspent", params)?;
+        let mut txs = Vec::new();
+        for info in unspent_txs {
+            let txid = btc::TxId::from_hex(&info.txid).unwrap();
+            let confirmations = Some(info.confirmations);
+            if let Some(raw_tx) = self.get_transaction(txid)? {
+                // The transactions are classified by the caller according to the payload
+                // prefix of its configuration.
+                txs.push(TxInfo {
+                    body: BitcoinTx::from(raw_tx),
+                    confirmations,
+                    block_hash: None,
+                    block_time: None,
+                });
+            }
+        }
+        Ok(txs)
+    }
+
+    fn estimate_fee(&self, conf_target: u16) -> Result<Option<u64>> {
+        let response: Value = self.request("estimatesmartfee", vec![json!(conf_target)])?;
+        Ok(fee_rate_from_estimate(&response))
+    }
+
+    fn get_block_count(&self) -> Result<u64> {
+        self.request("getblockcount", Vec::new())
+    }
+
+    fn is_block_in_best_chain(&self, block_hash: btc::BlockHash) -> Result<bool> {
+        let params = vec![json!(block_hash.to_string())];
+        match self.request::<Value>("getblockheader", params) {
+            Ok(header) => Ok(block_is_in_best_chain(&header)),
+            Err(bitcoinrpc::Error::NoInformation(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn config(&self) -> AnchoringRpcConfig {
+        self.rpc.clone()
+    }
+}
+
 impl<'a, T: BitcoinRelay + 'a> From<T> for Box<BitcoinRelay> {
     fn from(t: T) -> Self {
         Box::new(t) as Box<BitcoinRelay>
     }
 }
+
+/// Relay decorator that retries the calls failed due to the temporary `bitcoind` unavailability.
+///
+/// Only the transport errors and the errors of the node warming up are retried,
+/// other errors, e.g. `bad-txns-inputs-missingorspent`, are returned immediately.
+/// `send_to_address` is never retried, because it is not idempotent.
+#[derive(Debug)]
+pub struct RetryingRpc<T> {
+    inner: T,
+    config: RpcRetryConfig,
+}
+
+impl<T: BitcoinRelay> RetryingRpc<T> {
+    /// Wraps the given relay.
+    pub fn new(inner: T, config: RpcRetryConfig) -> RetryingRpc<T> {
+        RetryingRpc { inner, config }
+    }
+
+    /// Returns the wrapped relay.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    fn call<R, F>(&self, f: F) -> Result<R>
+    where
+        F: Fn(&T) -> Result<R>,
+    {
+        let mut delay = self.config.initial_delay;
+        let mut attempt = 1;
+        loop {
+            match f(&self.inner) {
+                Err(ref e) if attempt < self.config.max_attempts && is_retryable(e) => {
+                    warn!(
+                        "Bitcoind RPC call failed, attempt {} of {}: {}",
+                        attempt, self.config.max_attempts, e
+                    );
+                }
+                res => return res,
+            }
+            thread::sleep(Duration::from_millis(delay));
+            delay = cmp::min(delay * 2, self.config.max_delay);
+            attempt += 1;
+        }
+    }
+}
+
+impl<T: BitcoinRelay> BitcoinRelay for RetryingRpc<T> {
+    fn get_transaction(&self, txid: btc::TxId) -> Result<Option<BitcoinTx>> {
+        self.call(|relay| relay.get_transaction(txid))
+    }
+
+    fn get_transaction_info(&self, txid: btc::TxId) -> Result<Option<TxInfo>> {
+        self.call(|relay| relay.get_transaction_info(txid))
+    }
+
+    fn get_transaction_info_with_prev(
+        &self,
+        txid: btc::TxId,
+        prev_txid: btc::TxId,
+    ) -> Result<(Option<TxInfo>, Option<BitcoinTx>)> {
+        self.call(|relay| relay.get_transaction_info_with_prev(txid, prev_txid))
+    }
+
+    fn get_transactions_info(&self, txids: &[btc::TxId]) -> Result<Vec<Option<TxInfo>>> {
+        self.call(|relay| relay.get_transactions_info(txids))
+    }
+
+    fn watch_address(&self, addr: &btc::Address, rescan: bool) -> Result<()> {
+        self.call(|relay| relay.watch_address(addr, rescan))
+    }
+
+    fn send_transaction(&self, tx: BitcoinTx) -> Result<()> {
+        // The previous attempt may have reached the node even if its response has been lost.
+        match self.call(|relay| relay.send_transaction(tx.clone())) {
+            Err(ref e) if error_message_contains(e, "txn-already-in-mempool") => Ok(()),
+            res => res,
+        }
+    }
+
+    fn send_to_address(&self, addr: &btc::Address, satoshis: u64) -> Result<FundingTx> {
+        self.inner.send_to_address(addr, satoshis)
+    }
+
+    fn unspent_transactions(&self, addr: &btc::Address) -> Result<Vec<TxInfo>> {
+        self.call(|relay| relay.unspent_transactions(addr))
+    }
+
+    fn estimate_fee(&self, conf_target: u16) -> Result<Option<u64>> {
+        self.call(|relay| relay.estimate_fee(conf_target))
+    }
+
+    fn get_block_count(&self) -> Result<u64> {
+        self.call(|relay| relay.get_block_count())
+    }
+
+    fn is_block_in_best_chain(&self, block_hash: btc::BlockHash) -> Result<bool> {
+        self.call(|relay| relay.is_block_in_best_chain(block_hash))
+    }
+
+    fn config(&self) -> AnchoringRpcConfig {
+        self.inner.config()
+    }
+}
+
+/// Reason of the `sendrawtransaction` rejection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendTxRejection {
+    /// The transaction is already in the mempool or in the blockchain.
+    AlreadyKnown,
+    /// An input of the transaction is missing or is spent by another transaction
+    /// in the mempool or in the blockchain.
+    InputSpent,
+    /// The transaction is rejected for another reason, for example, because of
+    /// the insufficient fee, or the relay is unavailable.
+    Other,
+}
+
+/// Classifies the `sendrawtransaction` error by its message.
+pub fn send_tx_rejection(e: &Error) -> SendTxRejection {
+    let matches = |patterns: &[&str]| {
+        patterns
+            .iter()
+            .any(|pattern| error_message_contains(e, pattern))
+    };
+    if matches(&[
+        "txn-already-in-mempool",
+        "txn-already-known",
+        "already in block chain",
+    ]) {
+        SendTxRejection::AlreadyKnown
+    } else if matches(&[
+        "bad-txns-inputs-missingorspent",
+        "bad-txns-inputs-spent",
+        "missing-inputs",
+        "Missing inputs",
+        "txn-mempool-conflict",
+    ]) {
+        SendTxRejection::InputSpent
+    } else {
+        SendTxRejection::Other
+    }
+}
+
+/// Returns `true` if the `sendrawtransaction` error means that the transaction
+/// is already in the mempool or in the blockchain.
+pub fn is_tx_already_known(e: &Error) -> bool {
+    send_tx_rejection(e) == SendTxRejection::AlreadyKnown
+}
+
+fn is_retryable(e: &Error) -> bool {
+    match *e {
+        Error::Rpc(jsonrpc::Error::Client(_)) => true,
+        Error::Rpc(jsonrpc::Error::Rpc(ref e)) => {
+            e.pointer("/code").and_then(Value::as_i64) == Some(i64::from(RPC_IN_WARMUP))
+        }
+        // Connection failures and timeouts of `PooledRpcClient`.
+        Error::Other(ref e) => match e.kind() {
+            io::ErrorKind::TimedOut
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::UnexpectedEof => true,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Checks that the message of the given error contains the `pattern`.
+pub fn error_message_contains(e: &Error, pattern: &str) -> bool {
+    match *e {
+        Error::Rpc(jsonrpc::Error::Rpc(ref e)) => e.pointer("/message")
+            .and_then(Value::as_str)
+            .map_or(false, |message| message.contains(pattern)),
+        ref e => e.to_string().contains(pattern),
+    }
+}
@@ -0,0 +1,194 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Backends that sign the inputs of the anchoring transactions, so the handler
+//! never touches the private keys itself.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bitcoin::util::hash::Sha256dHash;
+use jsonrpc;
+use secp256k1::{Message, Secp256k1};
+use serde_json::{self, Value};
+
+use exonum::encoding::serialize::{encode_hex, FromHex};
+
+use details::btc;
+use details::btc::transactions::RawBitcoinTx;
+use details::btc::SighashType;
+use details::error::SignerError;
+use details::http::HttpConnectionPool;
+use details::rpc::RpcConnectionConfig;
+
+/// Input of the anchoring transaction to be signed.
+#[derive(Debug)]
+pub struct InputSigningRequest<'a> {
+    /// Unsigned anchoring transaction.
+    pub tx: &'a RawBitcoinTx,
+    /// Index of the input to be signed.
+    pub input: u32,
+    /// Transaction whose output is spent by the input.
+    pub prev_tx: &'a RawBitcoinTx,
+    /// Redeem script of the anchoring address the input is spent from.
+    pub redeem_script: &'a btc::RedeemScript,
+    /// Anchoring address the input is spent from.
+    pub address: &'a btc::Address,
+    /// Signature hash type of the signature.
+    pub sighash_type: SighashType,
+    /// Signature hash of the input for the given `sighash_type`.
+    pub sighash: Sha256dHash,
+}
+
+/// Backend that keeps the private keys of the anchoring addresses and signs
+/// the signature hashes of the anchoring transaction inputs.
+pub trait SignerBackend: fmt::Debug + Send {
+    /// Signs the `sighash` by the private key for the anchoring `address`
+    /// and returns the DER-encoded signature without the sighash type byte.
+    fn sign(&self, sighash: &Sha256dHash, address: &btc::Address) -> Result<Vec<u8>, SignerError>;
+
+    /// Same as `sign`, but the backend gets the whole transaction along with the spent
+    /// output. The backends that do not sign the bare signature hashes, like the hardware
+    /// wallets, override this method, by default it signs the `sighash` of the request.
+    fn sign_input(&self, request: &InputSigningRequest) -> Result<Vec<u8>, SignerError> {
+        self.sign(&request.sighash, request.address)
+    }
+
+    /// Returns the public key of the private key for the anchoring `address`
+    /// or `None` if the backend has no such key.
+    fn public_key(&self, address: &btc::Address) -> Result<Option<btc::PublicKey>, SignerError>;
+}
+
+/// Signer that uses the `private_keys` from the node configuration file.
+#[derive(Debug)]
+pub struct LocalSigner<'a> {
+    private_keys: &'a BTreeMap<String, btc::PrivateKey>,
+}
+
+impl<'a> LocalSigner<'a> {
+    /// Creates the signer for the given map of the anchoring addresses to the private keys.
+    pub fn new(private_keys: &'a BTreeMap<String, btc::PrivateKey>) -> LocalSigner<'a> {
+        LocalSigner { private_keys }
+    }
+}
+
+impl<'a> SignerBackend for LocalSigner<'a> {
+    fn sign(&self, sighash: &Sha256dHash, address: &btc::Address) -> Result<Vec<u8>, SignerError> {
+        let addr = address.to_string();
+        let priv_key = self.private_keys
+            .get(&addr)
+            .ok_or_else(|| SignerError::KeyNotFound(addr.clone()))?;
+        let context = Secp256k1::new();
+        let msg = Message::from_slice(&sighash[..]).unwrap();
+        let signature = context
+            .sign(&msg, priv_key.secret_key())
+            .unwrap()
+            .serialize_der(&context);
+        Ok(signature)
+    }
+
+    fn public_key(&self, address: &btc::Address) -> Result<Option<btc::PublicKey>, SignerError> {
+        Ok(self.private_keys
+            .get(&address.to_string())
+            .map(|priv_key| priv_key.public_key()))
+    }
+}
+
+/// Signer that requests the signatures from the external service by the JSON-RPC
+/// over HTTP, so the private keys are never stored by the node.
+///
+/// The service should support the following methods:
+///
+/// - `sign` with the anchoring address and the hex-encoded signature hash as the params,
+///   which returns the hex-encoded DER signature.
+/// - `public_key` with the anchoring address as the param, which returns the hex-encoded
+///   compressed public key or `null` if the service has no key for the address.
+///
+/// The public keys are cached, since the handler checks them at every block.
+#[derive(Debug)]
+pub struct ExternalSigner {
+    pool: HttpConnectionPool,
+    nonce: AtomicUsize,
+    public_keys: Mutex<HashMap<String, btc::PublicKey>>,
+}
+
+impl ExternalSigner {
+    /// Creates the signer for the service at the given `url`.
+    pub fn new(
+        url: &str,
+        username: Option<String>,
+        password: Option<String>,
+        connection: RpcConnectionConfig,
+    ) -> ExternalSigner {
+        ExternalSigner {
+            pool: HttpConnectionPool::new(url, username, password, connection),
+            nonce: AtomicUsize::new(0),
+            public_keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn request(&self, method: &str, params: Vec<Value>) -> Result<Value, SignerError> {
+        let request = jsonrpc::Request {
+            method: method.to_owned(),
+            params,
+            id: json!(self.nonce.fetch_add(1, Ordering::SeqCst) + 1),
+        };
+        let body = serde_json::to_vec(&request).map_err(|e| external_error(&e))?;
+        let http_response = self.pool.post(&body).map_err(|e| external_error(&e))?;
+        let response: jsonrpc::Response =
+            serde_json::from_slice(&http_response.body).map_err(|_| {
+                SignerError::External(format!(
+                    "Unexpected response, HTTP status={}",
+                    http_response.status
+                ))
+            })?;
+        if response.id != request.id {
+            return Err(SignerError::External("Response id mismatch".to_owned()));
+        }
+        response.into_result().map_err(|e| external_error(&e))
+    }
+}
+
+impl SignerBackend for ExternalSigner {
+    fn sign(&self, sighash: &Sha256dHash, address: &btc::Address) -> Result<Vec<u8>, SignerError> {
+        let params = vec![json!(address.to_string()), json!(encode_hex(&sighash[..]))];
+        match self.request("sign", params)? {
+            Value::String(ref signature) => Vec::<u8>::from_hex(signature)
+                .map_err(|_| SignerError::External(format!("Malformed signature {}", signature))),
+            Value::Null => Err(SignerError::KeyNotFound(address.to_string())),
+            other => Err(SignerError::External(format!("Malformed signature {}", other))),
+        }
+    }
+
+    fn public_key(&self, address: &btc::Address) -> Result<Option<btc::PublicKey>, SignerError> {
+        let addr = address.to_string();
+        if let Some(key) = self.public_keys.lock().unwrap().get(&addr) {
+            return Ok(Some(*key));
+        }
+        let key = match self.request("public_key", vec![json!(addr)])? {
+            Value::String(ref key) => btc::PublicKey::from_hex(key)
+                .map_err(|_| SignerError::External(format!("Malformed public key {}", key)))?,
+            Value::Null => return Ok(None),
+            other => return Err(SignerError::External(format!("Malformed public key {}", other))),
+        };
+        self.public_keys.lock().unwrap().insert(addr, key);
+        Ok(Some(key))
+    }
+}
+
+fn external_error<E: fmt::Display>(e: &E) -> SignerError {
+    SignerError::External(e.to_string())
+}
@@ -17,19 +17,29 @@ extern crate rand;
 use std::collections::HashMap;
 use std::str::FromStr;
 
-use bitcoin::blockdata::transaction::SigHashType;
+use bitcoin::blockdata::script::{Builder, Script};
+use bitcoin::blockdata::transaction::{SigHashType, TxIn, TxOut};
 use bitcoin::network::constants::Network;
+use bitcoin::util::hash::Sha256dHash;
 use serde_json;
 
 use exonum::crypto::Hash;
-use exonum::encoding::serialize::FromHex;
+use exonum::encoding::serialize::{encode_hex, FromHex};
 use exonum::encoding::Field;
 use exonum::helpers::Height;
 use exonum::storage::StorageValue;
 
+use blockchain::consensus_storage::AnchoringConfig;
 use details::btc;
-use details::btc::transactions::{AnchoringTx, BitcoinTx, FundingTx, RawBitcoinTx,
-                                 TransactionBuilder, TxKind};
+use details::btc::fee::{cpfp_fee, dust_threshold, estimate_vsize, tx_vsize, FeeStrategy};
+use details::btc::payload::PayloadError;
+use details::btc::psbt::{PartialSignature, Psbt};
+use details::btc::transactions::{tx_input_signature_hash, AnchoringTx, BitcoinTx, FundingTx,
+                                 RawBitcoinTx, TransactionBuilder, TxFromRaw, TxKind,
+                                 MAX_STANDARD_TX_VSIZE};
+use details::error::{AddrError, PsbtError, TxBuildError};
+use details::rpc::Result as RpcResult;
+use local_storage::AnchoringNodeConfig;
 
 pub fn redeem_script_testnet<'a, I: IntoIterator<Item = &'a btc::PublicKey>>(
     keys: I,
@@ -79,7 +89,8 @@ pub fn make_signatures(
     proposal
         .inputs()
         .map(|idx| {
-            let prev_tx = &prev_txs[idx as usize];
+            let prev_hash = proposal.input[idx as usize].prev_hash;
+            let prev_tx = prev_txs.iter().find(|tx| tx.txid() == prev_hash).unwrap();
             let signatures = priv_keys
                 .iter()
                 .map(|pk| proposal.sign_input(redeem_script, idx, prev_tx, pk))
@@ -426,6 +437,337 @@ fn test_anchoring_tx_sign() {
     }
 }
 
+// The P2SH-P2WSH example of BIP143 with the 6-of-6 multisig witness script.
+#[test]
+fn test_anyonecanpay_signature_hash_bip143() {
+    let tx = BitcoinTx::from_hex(
+        "010000000136641869ca081e70f394c6948e8af409e18b619df2ed74aa106c1ca29787b96e0100000000ff\
+         ffffff0200e9a435000000001976a914389ffce9cd9ae88dcc0631e88a821ffdbe9bfe2688acc0832f0500\
+         0000001976a9147480a33f950689af511e6e84c138dbbd3c3ee41588ac00000000",
+    ).unwrap();
+    let redeem_script = btc::RedeemScript::from_str(
+        "56210307b8ae49ac90a048e9b53357a2354b3334e9c8bee813ecb98e99a7e07e8c3ba32103b28f0c28bfab\
+         54554ae8c658ac5c3e0ce6e79ad336331f78c428dd43eea8449b21034b8113d703413d57761b8b9781957b\
+         8c0ac1dfe69f492580ca4195f50376ba4a21033400f6afecb833092a9a21cfdf1ed1376e58c5d1f47de746\
+         83123987e967a8f42103a6d48b1131e94ba04d9737d61acdaa1322008af9602b3b14862c07a1789aac1621\
+         02d8b661b0b3302ee2f162b09e07a55ad5dfbe673a9f01d9f0c19617681024306b56ae",
+    ).unwrap();
+    // Only the value of the spent output is committed from the previous transaction.
+    let mut prev_tx = tx.0.clone();
+    prev_tx.output[1].value = 987_654_321;
+
+    let sighash = tx_input_signature_hash(
+        &tx,
+        0,
+        &redeem_script,
+        &prev_tx,
+        btc::SighashType::AllPlusAnyoneCanPay,
+    );
+    assert_eq!(
+        encode_hex(&sighash[..]),
+        "2a67f03e63a6a422125878b40b82da593be8d4efaafe88ee528af6e5a9955c6e"
+    );
+}
+
+#[test]
+fn test_anchoring_tx_finalize_witness() {
+    let (pub_keys, priv_keys) = gen_anchoring_keys(4);
+    let redeem_script = redeem_script_testnet(&pub_keys, 3);
+    let addr = btc::Address::from_script(&redeem_script, Network::Testnet);
+
+    let funding_tx = FundingTx::from(RawBitcoinTx {
+        version: 2,
+        lock_time: 0,
+        input: vec![TxIn {
+            prev_hash: Sha256dHash::from_data(&[]),
+            prev_index: 0,
+            script_sig: Script::new(),
+            sequence: 0xFFFF_FFFF,
+            witness: Vec::default(),
+        }],
+        output: vec![TxOut {
+            value: 10_000,
+            script_pubkey: addr.script_pubkey(),
+        }],
+    });
+
+    let tx = TransactionBuilder::with_prev_tx(&funding_tx, 0)
+        .payload(Height(10), Hash::zero())
+        .fee(1000)
+        .send_to(addr.clone())
+        .into_transaction()
+        .unwrap();
+
+    // Only 3 of 4 validators sign the transaction.
+    let signatures = make_signatures(&redeem_script, &tx, &[&funding_tx.0], &priv_keys[0..3]);
+    for (input, signs) in &signatures {
+        for (id, signature) in signs.iter().enumerate() {
+            assert!(tx.verify_input(
+                &redeem_script,
+                *input,
+                &funding_tx,
+                &pub_keys[id],
+                signature.as_ref(),
+            ));
+        }
+    }
+
+    let signed_tx = tx.clone().finalize(&redeem_script, signatures);
+    // Signatures are placed into the witness, so the transaction id stays the same.
+    assert_eq!(signed_tx.id(), tx.id());
+    assert_eq!(signed_tx.input[0].script_sig, Script::new());
+    assert!(!signed_tx.input[0].witness.is_empty());
+    match TxKind::from(signed_tx.0.clone()) {
+        TxKind::Anchoring(anchoring_tx) => assert_eq!(anchoring_tx, signed_tx),
+        kind => panic!("Unexpected tx kind: {:?}", kind),
+    }
+}
+
+#[test]
+fn test_anchoring_tx_sign_multiple_inputs() {
+    let (pub_keys, priv_keys) = gen_anchoring_keys(4);
+    let redeem_script = redeem_script_testnet(&pub_keys, 3);
+    let addr = btc::Address::from_script(&redeem_script, Network::Testnet);
+
+    // The funds are spread across the outputs of two funding transactions.
+    let funding_txs = [6000, 4000]
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            FundingTx::from(RawBitcoinTx {
+                version: 2,
+                lock_time: 0,
+                input: vec![TxIn {
+                    prev_hash: Sha256dHash::from_data(&[i as u8]),
+                    prev_index: 0,
+                    script_sig: Script::new(),
+                    sequence: 0xFFFF_FFFF,
+                    witness: Vec::default(),
+                }],
+                output: vec![TxOut {
+                    value,
+                    script_pubkey: addr.script_pubkey(),
+                }],
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let tx = TransactionBuilder::with_prev_tx(&funding_txs[0], 0)
+        .add_funds(&funding_txs[1], 0)
+        .payload(Height(10), Hash::zero())
+        .fee(1000)
+        .send_to(addr.clone())
+        .into_transaction()
+        .unwrap();
+    assert_eq!(tx.inputs(), 0..2);
+    assert_eq!(tx.amount(), 9000);
+
+    // The previous transactions are looked up by the inputs, so their order does not matter.
+    let prev_txs = vec![funding_txs[1].0.clone(), funding_txs[0].0.clone()];
+    assert_eq!(tx.fee(&prev_txs), Ok(1000));
+    assert_eq!(
+        tx.fee(&prev_txs[..1]),
+        Err(TxBuildError::PrevTxNotFound { input: 0 })
+    );
+    assert_eq!(tx.find_prev_tx(0, &prev_txs), Some(&funding_txs[0].0));
+    assert_eq!(tx.find_prev_tx(1, &prev_txs), Some(&funding_txs[1].0));
+
+    // Each input has its own signature hash.
+    let sighashes = tx.inputs()
+        .map(|input| {
+            let prev_tx = tx.find_prev_tx(input, &prev_txs).unwrap();
+            tx.signature_hash(&redeem_script, input, prev_tx, btc::SighashType::All)
+        })
+        .collect::<Vec<_>>();
+    assert_ne!(sighashes[0], sighashes[1]);
+
+    let signatures = make_signatures(
+        &redeem_script,
+        &tx,
+        &[&funding_txs[1].0, &funding_txs[0].0],
+        &priv_keys,
+    );
+    assert_eq!(signatures.len(), 2);
+    for (input, signs) in &signatures {
+        let prev_tx = tx.find_prev_tx(*input, &prev_txs).unwrap();
+        let other_input = 1 - *input;
+        let other_prev_tx = tx.find_prev_tx(other_input, &prev_txs).unwrap();
+        assert_eq!(signs.len(), pub_keys.len());
+        for (id, signature) in signs.iter().enumerate() {
+            assert!(tx.verify_input(
+                &redeem_script,
+                *input,
+                prev_tx,
+                &pub_keys[id],
+                signature.as_ref(),
+            ));
+            // The signature of one input is not valid for the other one.
+            assert!(!tx.verify_input(
+                &redeem_script,
+                other_input,
+                other_prev_tx,
+                &pub_keys[id],
+                signature.as_ref(),
+            ));
+        }
+    }
+
+    let signed_tx = tx.clone().finalize(&redeem_script, signatures);
+    assert_eq!(signed_tx.id(), tx.id());
+    for input in &signed_tx.input {
+        assert_eq!(input.script_sig, Script::new());
+        assert!(!input.witness.is_empty());
+    }
+}
+
+#[test]
+fn test_anchoring_tx_psbt_round_trip() {
+    let (pub_keys, priv_keys) = gen_anchoring_keys(4);
+    let redeem_script = redeem_script_testnet(&pub_keys, 3);
+    let addr = btc::Address::from_script(&redeem_script, Network::Testnet);
+
+    let funding_tx = FundingTx::from(RawBitcoinTx {
+        version: 2,
+        lock_time: 0,
+        input: vec![TxIn {
+            prev_hash: Sha256dHash::from_data(&[]),
+            prev_index: 0,
+            script_sig: Script::new(),
+            sequence: 0xFFFF_FFFF,
+            witness: Vec::default(),
+        }],
+        output: vec![TxOut {
+            value: 10_000,
+            script_pubkey: addr.script_pubkey(),
+        }],
+    });
+    let prev_txs = [funding_tx.0.clone()];
+
+    let tx = TransactionBuilder::with_prev_tx(&funding_tx, 0)
+        .payload(Height(10), Hash::zero())
+        .fee(1000)
+        .send_to(addr.clone())
+        .into_transaction()
+        .unwrap();
+    let signatures = make_signatures(&redeem_script, &tx, &[&funding_tx.0], &priv_keys[0..3]);
+
+    // The signature of the first validator is already collected.
+    let collected = vec![
+        PartialSignature {
+            input: 0,
+            public_key: pub_keys[0],
+            signature: signatures[&0][0].clone(),
+        },
+    ];
+    let psbt = tx.to_psbt(&redeem_script, &prev_txs, btc::SighashType::All, &collected);
+    let mut psbt = Psbt::from_base64(&psbt).unwrap();
+    assert_eq!(psbt.partial_signature(0, &pub_keys[0]), Some(&signatures[&0][0][..]));
+
+    // The other validators sign the PSBT by the external tools in the reverse order.
+    psbt.add_partial_signature(0, &pub_keys[2], &signatures[&0][2]);
+    psbt.add_partial_signature(0, &pub_keys[1], &signatures[&0][1]);
+    let merged = tx.psbt_signatures(&psbt.to_base64(), &redeem_script, &prev_txs)
+        .unwrap();
+    assert_eq!(
+        merged.iter().map(|s| s.public_key).collect::<Vec<_>>(),
+        pub_keys[0..3].to_vec()
+    );
+
+    let mut merged_signatures = HashMap::new();
+    for signature in merged {
+        merged_signatures
+            .entry(signature.input)
+            .or_insert_with(Vec::new)
+            .push(signature.signature);
+    }
+    assert_eq!(merged_signatures, signatures);
+    let signed_tx = tx.clone().finalize(&redeem_script, merged_signatures);
+    assert_eq!(signed_tx, tx.clone().finalize(&redeem_script, signatures.clone()));
+    assert!(!signed_tx.input[0].witness.is_empty());
+
+    // The signature made by another key is rejected.
+    psbt.add_partial_signature(0, &pub_keys[3], &signatures[&0][1]);
+    assert_eq!(
+        tx.psbt_signatures(&psbt.to_base64(), &redeem_script, &prev_txs),
+        Err(PsbtError::InvalidSignature {
+            input: 0,
+            public_key: encode_hex(&pub_keys[3].serialize()[..]),
+        })
+    );
+
+    // The PSBT of another transaction is rejected.
+    let other_tx = TransactionBuilder::with_prev_tx(&funding_tx, 0)
+        .payload(Height(20), Hash::zero())
+        .fee(1000)
+        .send_to(addr)
+        .into_transaction()
+        .unwrap();
+    let other_psbt = other_tx.to_psbt(&redeem_script, &prev_txs, btc::SighashType::All, &[]);
+    assert_eq!(
+        tx.psbt_signatures(&other_psbt, &redeem_script, &prev_txs),
+        Err(PsbtError::TxMismatch)
+    );
+}
+
+#[test]
+fn test_tx_kind_any_signatures_count() {
+    let (pub_keys, priv_keys) = gen_anchoring_keys(4);
+    let redeem_script = redeem_script_testnet(&pub_keys, 3);
+    let addr = btc::Address::from_script(&redeem_script, Network::Testnet);
+
+    let funding_tx = FundingTx::from(RawBitcoinTx {
+        version: 2,
+        lock_time: 0,
+        input: vec![TxIn {
+            prev_hash: Sha256dHash::from_data(&[]),
+            prev_index: 0,
+            script_sig: Script::new(),
+            sequence: 0xFFFF_FFFF,
+            witness: Vec::default(),
+        }],
+        output: vec![TxOut {
+            value: 10_000,
+            script_pubkey: addr.script_pubkey(),
+        }],
+    });
+    let tx = TransactionBuilder::with_prev_tx(&funding_tx, 0)
+        .payload(Height(10), Hash::zero())
+        .fee(1000)
+        .send_to(addr.clone())
+        .into_transaction()
+        .unwrap();
+
+    // Exactly the threshold and more than the threshold of the signatures.
+    for signers_count in 3..5 {
+        let signatures = make_signatures(
+            &redeem_script,
+            &tx,
+            &[&funding_tx.0],
+            &priv_keys[0..signers_count],
+        );
+        let signed_tx = tx.clone().finalize(&redeem_script, signatures.clone());
+        assert_eq!(signed_tx.input[0].witness.len(), signers_count + 2);
+        match TxKind::from(signed_tx.0.clone()) {
+            TxKind::Anchoring(anchoring_tx) => assert_eq!(anchoring_tx, signed_tx),
+            kind => panic!("Unexpected tx kind: {:?}", kind),
+        }
+
+        // The signatures in the script sig are not inspected either.
+        let mut legacy_tx = tx.0.clone();
+        legacy_tx.input[0].script_sig = signatures[&0]
+            .iter()
+            .fold(Builder::new().push_int(0), |builder, signature| {
+                builder.push_slice(signature)
+            })
+            .push_slice(&redeem_script.as_ref().data())
+            .into_script();
+        match TxKind::from(legacy_tx.clone()) {
+            TxKind::Anchoring(anchoring_tx) => assert_eq!(anchoring_tx.0, legacy_tx),
+            kind => panic!("Unexpected tx kind: {:?}", kind),
+        }
+    }
+}
+
 #[test]
 fn test_anchoring_tx_output_address() {
     let tx = AnchoringTx::from_hex(
@@ -451,6 +793,98 @@ fn test_anchoring_tx_output_address() {
     );
 }
 
+#[test]
+fn test_address_bech32_from_script() {
+    let pub_keys = [
+        "03475ab0e9cfc6015927e662f6f8f088de12287cee1a3237aeb497d1763064690c",
+        "02a63948315dda66506faf4fecd54b085c08b13932a210fa5806e3691c69819aa0",
+        "0230cb2805476bf984d2236b56ff5da548dfe116daf2982608d898d9ecb3dceb49",
+        "036e4777c8d19ccaa67334491e777f221d37fd85d5786a4e5214b281cf0133d65e",
+    ].iter()
+        .map(|x| btc::PublicKey::from_hex(x).unwrap())
+        .collect::<Vec<_>>();
+    let redeem_script = redeem_script_testnet(&pub_keys, 3);
+
+    let addr = btc::Address::from_script(&redeem_script, Network::Testnet);
+    assert!(addr.to_string().starts_with("tb1q"));
+    assert!(addr.script_pubkey().is_v0_p2wsh());
+    assert_eq!(btc::Address::from_str(&addr.to_string()).unwrap(), addr);
+
+    let addr = btc::Address::from_script(&redeem_script, Network::Bitcoin);
+    assert!(addr.to_string().starts_with("bc1q"));
+    assert_eq!(btc::Address::from_str(&addr.to_string()).unwrap(), addr);
+}
+
+#[test]
+fn test_address_str_conversion() {
+    let addresses = [
+        "tb1qn5mmecjkj4us6uhr5tc453k96hrzcwr3l9d8fkc7fg8zwur50y4qfdclp7",
+        "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy",
+    ];
+    for addr_str in &addresses {
+        let addr = btc::Address::from_str(addr_str).unwrap();
+        assert_eq!(addr.to_string(), *addr_str);
+    }
+}
+
+#[test]
+fn test_address_network_check() {
+    let valid = [
+        ("tb1qn5mmecjkj4us6uhr5tc453k96hrzcwr3l9d8fkc7fg8zwur50y4qfdclp7", Network::Testnet),
+        ("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy", Network::Bitcoin),
+        ("2N1mHzwKTmjnC7JjqeGFBRKYE4WDTjTfop1", Network::Testnet),
+        ("2N1mHzwKTmjnC7JjqeGFBRKYE4WDTjTfop1", Network::Regtest),
+    ];
+    for &(addr_str, network) in &valid {
+        let addr = btc::Address::from_str_checked(addr_str, network).unwrap();
+        assert_eq!(addr.to_string(), addr_str);
+    }
+
+    let invalid = [
+        ("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy", Network::Regtest, Network::Bitcoin),
+        ("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy", Network::Testnet, Network::Bitcoin),
+        ("2N1mHzwKTmjnC7JjqeGFBRKYE4WDTjTfop1", Network::Bitcoin, Network::Testnet),
+        (
+            "tb1qn5mmecjkj4us6uhr5tc453k96hrzcwr3l9d8fkc7fg8zwur50y4qfdclp7",
+            Network::Regtest,
+            Network::Testnet,
+        ),
+    ];
+    for &(addr_str, expected, actual) in &invalid {
+        let err = btc::Address::from_str_checked(addr_str, expected).unwrap_err();
+        assert_eq!(
+            err,
+            AddrError::NetworkMismatch {
+                address: addr_str.to_owned(),
+                actual,
+                expected,
+            }
+        );
+    }
+
+    match btc::Address::from_str_checked("not an address", Network::Bitcoin) {
+        Err(AddrError::Parse { .. }) => {}
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn test_node_config_network_check() {
+    let (_, priv_key) = btc::gen_btc_keypair(Network::Bitcoin);
+    let mut node_cfg = AnchoringNodeConfig::default();
+    node_cfg
+        .private_keys
+        .insert("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy".to_owned(), priv_key);
+
+    assert!(node_cfg.check_network(Network::Bitcoin).is_ok());
+    let err = node_cfg.check_network(Network::Regtest).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Address 3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy is generated for the bitcoin network, \
+         but the regtest network is expected"
+    );
+}
+
 #[test]
 fn test_anchoring_tx_prev_chain() {
     let prev_tx = AnchoringTx::from_hex(
@@ -478,6 +912,376 @@ fn test_anchoring_tx_prev_chain() {
     assert_eq!(tx.payload().prev_tx_chain, Some(prev_tx.id()));
 }
 
+#[test]
+fn test_anchoring_tx_output_dust_threshold() {
+    let (pub_keys, _) = gen_anchoring_keys(4);
+    let redeem_script = redeem_script_testnet(&pub_keys, 3);
+    let fee = 1000;
+
+    let cases = [
+        (btc::Address::from_script(&redeem_script, Network::Testnet), 330),
+        (btc::Address::from_script(&redeem_script, Network::Bitcoin), 330),
+        (btc::Address::from("2N1mHzwKTmjnC7JjqeGFBRKYE4WDTjTfop1"), 540),
+    ];
+    for &(ref addr, threshold) in &cases {
+        assert_eq!(dust_threshold(&addr.script_pubkey()), threshold);
+
+        let build_tx = |funds: u64| {
+            let funding_tx = RawBitcoinTx {
+                version: 2,
+                lock_time: 0,
+                input: vec![TxIn {
+                    prev_hash: Sha256dHash::from_data(&[]),
+                    prev_index: 0,
+                    script_sig: Script::new(),
+                    sequence: 0xFFFF_FFFF,
+                    witness: Vec::new(),
+                }],
+                output: vec![TxOut {
+                    value: funds,
+                    script_pubkey: addr.script_pubkey(),
+                }],
+            };
+            TransactionBuilder::with_prev_tx(&funding_tx, 0)
+                .fee(fee)
+                .payload(Height::zero(), Hash::zero())
+                .send_to(addr.clone())
+                .into_transaction()
+        };
+
+        let tx = build_tx(fee + threshold).unwrap();
+        assert_eq!(tx.amount(), threshold);
+        assert_eq!(
+            build_tx(fee + threshold - 1).unwrap_err(),
+            TxBuildError::OutputBelowDust {
+                value: threshold - 1,
+                threshold,
+            }
+        );
+        assert_eq!(
+            build_tx(fee - 1).unwrap_err(),
+            TxBuildError::InsufficientFunds
+        );
+    }
+}
+
+#[test]
+fn test_anchoring_tx_fixed_output_value() {
+    let (pub_keys, _) = gen_anchoring_keys(4);
+    let redeem_script = redeem_script_testnet(&pub_keys, 3);
+    let addr = btc::Address::from_script(&redeem_script, Network::Testnet);
+    let change_addr = btc::Address::from("2N1mHzwKTmjnC7JjqeGFBRKYE4WDTjTfop1");
+    let fee = 1000;
+
+    let funding_tx = RawBitcoinTx {
+        version: 2,
+        lock_time: 0,
+        input: vec![TxIn {
+            prev_hash: Sha256dHash::from_data(&[]),
+            prev_index: 0,
+            script_sig: Script::new(),
+            sequence: 0xFFFF_FFFF,
+            witness: Vec::new(),
+        }],
+        output: vec![TxOut {
+            value: 10_000,
+            script_pubkey: addr.script_pubkey(),
+        }],
+    };
+    let build_tx = |value: u64| {
+        TransactionBuilder::with_prev_tx(&funding_tx, 0)
+            .fee(fee)
+            .payload(Height(10), Hash::zero())
+            .anchor_output(Some((value, change_addr.clone())))
+            .send_to(addr.clone())
+            .into_transaction()
+    };
+
+    // The rest of the funds goes to the change output after the data one.
+    let tx = build_tx(3000).unwrap();
+    assert_eq!(tx.output.len(), 3);
+    assert_eq!(tx.amount(), 3000);
+    assert_eq!(tx.output[2].value, 6000);
+    assert_eq!(tx.output[2].script_pubkey, change_addr.script_pubkey());
+    assert_eq!(tx.payload().block_height, Height(10));
+    match TxKind::from(tx.0.clone()) {
+        TxKind::Anchoring(parsed) => assert_eq!(parsed, tx),
+        other => panic!("Unexpected tx kind: {:?}", other),
+    }
+
+    assert_eq!(
+        build_tx(9001).unwrap_err(),
+        TxBuildError::AnchorOutputExceedsFunds {
+            value: 9001,
+            available: 9000,
+        }
+    );
+    assert_eq!(
+        build_tx(8500).unwrap_err(),
+        TxBuildError::ChangeBelowDust {
+            value: 500,
+            threshold: 540,
+        }
+    );
+    assert_eq!(
+        build_tx(300).unwrap_err(),
+        TxBuildError::OutputBelowDust {
+            value: 300,
+            threshold: 330,
+        }
+    );
+}
+
+#[test]
+fn test_anchoring_tx_replace_with_fee() {
+    let (pub_keys, _) = gen_anchoring_keys(4);
+    let redeem_script = redeem_script_testnet(&pub_keys, 3);
+    let addr = btc::Address::from_script(&redeem_script, Network::Testnet);
+    let change_addr = btc::Address::from("2N1mHzwKTmjnC7JjqeGFBRKYE4WDTjTfop1");
+
+    let funding_tx = RawBitcoinTx {
+        version: 2,
+        lock_time: 0,
+        input: vec![TxIn {
+            prev_hash: Sha256dHash::from_data(&[]),
+            prev_index: 0,
+            script_sig: Script::new(),
+            sequence: 0xFFFF_FFFF,
+            witness: Vec::new(),
+        }],
+        output: vec![TxOut {
+            value: 10_000,
+            script_pubkey: addr.script_pubkey(),
+        }],
+    };
+    let build_tx = |anchor_output: Option<(u64, btc::Address)>| {
+        TransactionBuilder::with_prev_tx(&funding_tx, 0)
+            .fee(1000)
+            .payload(Height(10), Hash::from_slice(&[1; 32]).unwrap())
+            .prev_tx_chain(Some(btc::TxId::from(Sha256dHash::from_data(&[2]))))
+            .anchor_output(anchor_output)
+            .send_to(addr.clone())
+            .into_transaction()
+            .unwrap()
+    };
+    let prev_txs = [funding_tx.clone()];
+
+    // The fee delta is taken from the anchoring output if there is no change.
+    let tx = build_tx(None);
+    assert_eq!(tx.fee(&prev_txs), Ok(1000));
+    let replacement = tx.replace_with_fee(&prev_txs, 1500).unwrap();
+    assert_eq!(replacement.fee(&prev_txs), Ok(1500));
+    assert_eq!(replacement.amount(), tx.amount() - 500);
+    assert_eq!(replacement.output[1].script_pubkey, tx.output[1].script_pubkey);
+    assert_eq!(replacement.payload(), tx.payload());
+    assert_eq!(replacement.input, tx.input);
+    assert_ne!(replacement.id(), tx.id());
+    // The txid differs only due to the fee.
+    let mut restored = replacement.clone();
+    restored.0.output[0].value = tx.amount();
+    assert_eq!(restored.id(), tx.id());
+
+    // Otherwise it is taken from the change output.
+    let tx = build_tx(Some((3000, change_addr.clone())));
+    let replacement = tx.replace_with_fee(&prev_txs, 2000).unwrap();
+    assert_eq!(replacement.amount(), 3000);
+    assert_eq!(replacement.output[2].value, tx.output[2].value - 1000);
+    assert_eq!(replacement.output[1].script_pubkey, tx.output[1].script_pubkey);
+
+    assert_eq!(
+        tx.replace_with_fee(&prev_txs, 1000).unwrap_err(),
+        TxBuildError::FeeNotIncreased {
+            fee: 1000,
+            old_fee: 1000,
+        }
+    );
+    assert_eq!(
+        tx.replace_with_fee(&prev_txs, 6500).unwrap_err(),
+        TxBuildError::ChangeBelowDust {
+            value: 500,
+            threshold: 540,
+        }
+    );
+
+    // The transaction without the payload is not replaced.
+    let mut tx = tx;
+    tx.0.output[1].script_pubkey = Script::new();
+    assert_eq!(
+        tx.replace_with_fee(&prev_txs, 2000).unwrap_err(),
+        TxBuildError::Payload(PayloadError::NotFound)
+    );
+}
+
+#[test]
+fn test_anchoring_tx_inputs_order() {
+    let (pub_keys, _) = gen_anchoring_keys(4);
+    let redeem_script = redeem_script_testnet(&pub_keys, 3);
+    let addr = btc::Address::from_script(&redeem_script, Network::Testnet);
+
+    let funding_txs = (0..4)
+        .map(|i| RawBitcoinTx {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                prev_hash: Sha256dHash::from_data(&[i]),
+                prev_index: 0,
+                script_sig: Script::new(),
+                sequence: 0xFFFF_FFFF,
+                witness: Vec::new(),
+            }],
+            output: vec![
+                TxOut {
+                    value: 3000,
+                    script_pubkey: addr.script_pubkey(),
+                },
+                TxOut {
+                    value: 4000,
+                    script_pubkey: addr.script_pubkey(),
+                },
+            ],
+        })
+        .collect::<Vec<_>>();
+    let funds = vec![
+        (&funding_txs[1], 1),
+        (&funding_txs[2], 0),
+        (&funding_txs[1], 0),
+        (&funding_txs[3], 1),
+    ];
+    let build_tx = |funds: &[(&RawBitcoinTx, u32)]| {
+        let mut builder = TransactionBuilder::with_prev_tx(&funding_txs[0], 0)
+            .fee(1000)
+            .payload(Height(10), Hash::zero())
+            .send_to(addr.clone());
+        for &(tx, out) in funds {
+            builder = builder.add_funds(tx, out);
+        }
+        builder.into_transaction().unwrap()
+    };
+
+    let tx = build_tx(&funds);
+    let reversed_funds = funds.iter().cloned().rev().collect::<Vec<_>>();
+    assert_eq!(build_tx(&reversed_funds).id(), tx.id());
+    // The input that links the anchoring chain keeps its position.
+    assert_eq!(tx.input[0].prev_hash, funding_txs[0].txid());
+    let funding_inputs = tx.input[1..]
+        .iter()
+        .map(|input| (input.prev_hash.be_hex_string(), input.prev_index))
+        .collect::<Vec<_>>();
+    let mut sorted_inputs = funding_inputs.clone();
+    sorted_inputs.sort();
+    assert_eq!(funding_inputs, sorted_inputs);
+}
+
+#[test]
+fn test_anchoring_tx_size_limit() {
+    // Simulates a large validator set whose witnesses make the transaction non-standard.
+    let (pub_keys, _) = gen_anchoring_keys(100);
+    let majority_count = ::majority_count(100) as usize;
+    let redeem_script = redeem_script_testnet(&pub_keys, majority_count as u8);
+    let addr = btc::Address::from_script(&redeem_script, Network::Testnet);
+
+    let funding_tx = RawBitcoinTx {
+        version: 2,
+        lock_time: 0,
+        input: vec![TxIn {
+            prev_hash: Sha256dHash::from_data(&[1]),
+            prev_index: 0,
+            script_sig: Script::new(),
+            sequence: 0xFFFF_FFFF,
+            witness: Vec::new(),
+        }],
+        output: (0..60)
+            .map(|_| TxOut {
+                value: 10_000,
+                script_pubkey: addr.script_pubkey(),
+            })
+            .collect(),
+    };
+    let build_tx = |inputs_count: u32, limit: u64| {
+        let mut builder = TransactionBuilder::with_prev_tx(&funding_tx, 0)
+            .fee(1000)
+            .payload(Height(10), Hash::zero())
+            .size_limit(&redeem_script, majority_count, limit)
+            .send_to(addr.clone());
+        for out in 1..inputs_count {
+            builder = builder.add_funds(&funding_tx, out);
+        }
+        builder.into_transaction()
+    };
+
+    let tx = build_tx(1, MAX_STANDARD_TX_VSIZE).unwrap();
+    let vsize = estimate_vsize(&tx.0, &redeem_script, majority_count);
+    assert!(vsize < MAX_STANDARD_TX_VSIZE);
+    assert_eq!(
+        build_tx(1, vsize - 1).unwrap_err(),
+        TxBuildError::TooLarge {
+            vsize,
+            limit: vsize - 1,
+        }
+    );
+
+    match build_tx(60, MAX_STANDARD_TX_VSIZE).unwrap_err() {
+        TxBuildError::TooLarge { vsize, limit } => {
+            assert!(vsize > MAX_STANDARD_TX_VSIZE);
+            assert_eq!(limit, MAX_STANDARD_TX_VSIZE);
+        }
+        e => panic!("Unexpected error: {}", e),
+    }
+}
+
+#[derive(Debug)]
+struct FixedFeeRate(u64);
+
+impl FeeStrategy for FixedFeeRate {
+    fn fee_per_kb(&self) -> RpcResult<u64> {
+        Ok(self.0)
+    }
+}
+
+#[test]
+fn test_cpfp_fee_raises_package_fee_rate() {
+    let (pub_keys, _) = gen_anchoring_keys(4);
+    let redeem_script = redeem_script_testnet(&pub_keys, 3);
+    let addr = btc::Address::from_script(&redeem_script, Network::Testnet);
+
+    // Parent pays 1 satoshi per virtual byte.
+    let parent = RawBitcoinTx {
+        version: 2,
+        lock_time: 0,
+        input: vec![TxIn {
+            prev_hash: Sha256dHash::from_data(&[]),
+            prev_index: 0,
+            script_sig: Script::new(),
+            sequence: 0xFFFF_FFFF,
+            witness: vec![vec![0; 72], vec![0; 33]],
+        }],
+        output: vec![TxOut {
+            value: 100_000,
+            script_pubkey: addr.script_pubkey(),
+        }],
+    };
+    let parent_vsize = tx_vsize(&parent);
+    let parent_fee = parent_vsize;
+
+    let child = TransactionBuilder::with_prev_tx(&parent, 0)
+        .fee(0)
+        .payload(Height::zero(), Hash::zero())
+        .send_to(addr.clone())
+        .into_transaction()
+        .unwrap();
+    let child_vsize = estimate_vsize(&child, &redeem_script, 3);
+
+    let strategy = FixedFeeRate(20_000);
+    let fee = cpfp_fee(&strategy, parent_fee, parent_vsize, child_vsize).unwrap();
+    let package_vsize = parent_vsize + child_vsize;
+    assert_eq!(parent_fee + fee, package_vsize * 20);
+    assert!(fee > child_vsize * 20);
+
+    // Parent pays enough for the whole package, so the child pays only for its own relay.
+    let fee = cpfp_fee(&FixedFeeRate(1000), 10 * parent_fee, parent_vsize, child_vsize).unwrap();
+    assert_eq!(fee, child_vsize);
+}
+
 #[test]
 fn test_tx_kind_funding() {
     let tx = BitcoinTx::from_hex(
@@ -525,6 +1329,62 @@ fn test_tx_kind_other() {
     }
 }
 
+#[test]
+fn test_tx_kind_payload_prefix() {
+    let funding_tx = FundingTx::from_hex(
+        "02000000000101bf38388e54b384527be79b3f073ed96e28dd90d2ec151ee89123652cf1fc35790100000000f\
+         effffff02f5fb690a000000001600140d2481bfc824b8d44f010ede3aa310986190c2aca08601000000000022\
+         0020c0276efb42fd5a690fc6c60a23bb2bc6a9e0562a4252c4004dfb662df83f0e9702473044022015dd0b7a3\
+         6ad6c95c9a0fc2329c40b67a95ae96c62475890887a77395d1ce2c5022034bb49c53ec8f9f985887023b85688\
+         2b13aa2966bc64e1be182eb71605c5d2ee01210360b8005275219721562b49cbd0acfc7e60f57123b2e84e9c8\
+         42b1e500c2e86e13fbd1300",
+    ).unwrap();
+    let make_tx = |prefix: &[u8]| {
+        TransactionBuilder::with_prev_tx(&funding_tx, 1)
+            .fee(1000)
+            .payload(Height(10), Hash::default())
+            .payload_prefix(prefix)
+            .send_to(btc::Address::from("2N1mHzwKTmjnC7JjqeGFBRKYE4WDTjTfop1"))
+            .into_transaction()
+            .unwrap()
+    };
+    let first_tx = make_tx(b"NETONE");
+    let second_tx = make_tx(b"NETTWO");
+
+    let mut first_cfg = AnchoringConfig::default();
+    first_cfg.payload_prefix = "NETONE".to_owned();
+    let mut second_cfg = AnchoringConfig::default();
+    second_cfg.payload_prefix = "NETTWO".to_owned();
+
+    // Each deployment recognizes only its own anchoring transactions.
+    match first_cfg.tx_kind(first_tx.clone()) {
+        TxKind::Anchoring(tx) => assert_eq!(tx.payload().block_height, Height(10)),
+        _ => panic!("Wrong tx kind!"),
+    }
+    match second_cfg.tx_kind(first_tx.clone()) {
+        TxKind::Other(_) => {}
+        _ => panic!("Wrong tx kind!"),
+    }
+    match second_cfg.tx_kind(second_tx.clone()) {
+        TxKind::Anchoring(_) => {}
+        _ => panic!("Wrong tx kind!"),
+    }
+    match first_cfg.tx_kind(second_tx.clone()) {
+        TxKind::Other(_) => {}
+        _ => panic!("Wrong tx kind!"),
+    }
+    // Transactions with a custom prefix are not anchoring ones for the default prefix.
+    match TxKind::from(first_tx.0.clone()) {
+        TxKind::Other(_) => {}
+        _ => panic!("Wrong tx kind!"),
+    }
+    // Though the anchoring transactions of any prefix are decoded.
+    assert_eq!(
+        AnchoringTx::from_raw(second_tx.0.clone()),
+        Some(second_tx.clone())
+    );
+}
+
 #[test]
 fn test_tx_verify_sighash_type_correct() {
     let (pub_keys, priv_keys) = gen_anchoring_keys(4);
@@ -587,6 +1447,15 @@ fn test_tx_verify_sighash_type_wrong() {
     assert!(tx.verify_input(&redeem_script, 0, &prev_tx, &pub_key, &btc_signature));
 }
 
+#[test]
+fn test_descriptor_checksum() {
+    assert_eq!(
+        btc::descriptor::checksum("raw(deadbeef)"),
+        Some("89f8spxm".to_owned())
+    );
+    assert_eq!(btc::descriptor::checksum("raw(deadbeef)\u{e9}"), None);
+}
+
 // rpc tests. Works through `rpc` by given env variables.
 // See the `anchoring_client` method on top of this file.
 #[cfg(feature = "rpc_tests")]
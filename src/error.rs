@@ -16,6 +16,7 @@ use std::io;
 
 use bitcoinrpc::Error as RpcError;
 pub use details::error::Error as InternalError;
+pub use details::error::{SignerError, TxBuildError};
 pub use handler::error::Error as HandlerError;
 
 /// Anchoring btc service Error type.
@@ -47,6 +48,18 @@ impl From<InternalError> for Error {
     }
 }
 
+impl From<TxBuildError> for Error {
+    fn from(e: TxBuildError) -> Self {
+        Error::Internal(InternalError::TxBuild(e))
+    }
+}
+
+impl From<SignerError> for Error {
+    fn from(e: SignerError) -> Self {
+        Error::Internal(InternalError::Signer(e))
+    }
+}
+
 impl From<HandlerError> for Error {
     fn from(e: HandlerError) -> Self {
         Error::Handler(e)
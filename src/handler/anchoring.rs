@@ -12,18 +12,28 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::cmp;
+
 use exonum::blockchain::{Schema, ServiceContext};
 use exonum::encoding::serialize::encode_hex;
 use exonum::helpers::Height;
+use exonum::storage::Snapshot;
 
-use blockchain::consensus_storage::AnchoringConfig;
-use blockchain::dto::{MsgAnchoringSignature, MsgAnchoringUpdateLatest};
+use blockchain::consensus_storage::{AnchoringConfig, FeeStrategyConfig};
+use blockchain::dto::{MsgAnchoringFeeRate, MsgAnchoringReplaceRequest, MsgAnchoringSignature,
+                      MsgAnchoringUpdateLatest};
 use blockchain::schema::AnchoringSchema;
 use details::btc;
+use details::btc::fee::{cpfp_fee, estimate_vsize, replacement_fee, tx_vsize, AgreedFeeRate,
+                        FeeStrategy, RpcEstimateFee, StaticFee};
 use details::btc::transactions::{AnchoringTx, RawBitcoinTx, TransactionBuilder};
+use details::error::{ConfigError, SignerError, TxBuildError};
+use details::rpc::{send_tx_rejection, SendTxRejection};
+use details::signer::InputSigningRequest;
 use error::Error as ServiceError;
+use handler::error::Error as HandlerError;
 
-use super::{collect_signatures, AnchoringHandler, LectKind, MultisigAddress};
+use super::{collect_signatures, AnchoringHandler, AnchoringPause, LectKind, MultisigAddress};
 
 #[doc(hidden)]
 impl AnchoringHandler {
@@ -35,9 +45,14 @@ impl AnchoringHandler {
         let multisig = self.multisig_address(cfg);
         trace!("Anchoring state, addr={}", multisig.addr.to_string());
 
-        if context.height().0 % self.node.check_lect_frequency == 0 {
+        if self.is_lect_check_height(context.height()) {
             // First of all we try to update our lect and actual configuration
             self.update_our_lect(&multisig, context)?;
+            self.update_our_fee_rate(cfg, context)?;
+            self.try_top_up_funding(&multisig, context)?;
+        }
+        if self.is_anchoring_paused(cfg, context.snapshot()) {
+            return Ok(());
         }
         // Now if we have anchoring tx proposal we must try to finalize it
         if let Some(proposal) = self.proposal_tx.clone() {
@@ -49,6 +64,55 @@ impl AnchoringHandler {
         Ok(())
     }
 
+    // Checks whether the anchoring is paused after its input has been spent externally.
+    // The pause ends once the given config adds a funding transaction or a new top-up
+    // is committed.
+    pub fn is_anchoring_paused(&mut self, cfg: &AnchoringConfig, snapshot: &Snapshot) -> bool {
+        let refueled = match self.anchoring_pause {
+            Some(ref pause) => AnchoringSchema::new(snapshot)
+                .funding_txs(cfg)
+                .iter()
+                .any(|tx| !pause.funding_txs.contains(&tx.id())),
+            None => return false,
+        };
+        if refueled {
+            info!("Anchoring is resumed by the new funding transaction");
+            self.anchoring_pause = None;
+        }
+        !refueled
+    }
+
+    // Sends the anchoring transaction to the bitcoin network. If its input has been spent
+    // outside of the anchoring chain, the anchoring is paused instead of repeating
+    // the doomed transaction.
+    pub fn send_anchoring_tx(
+        &mut self,
+        tx: &AnchoringTx,
+        cfg: &AnchoringConfig,
+        context: &ServiceContext,
+    ) -> Result<(), ServiceError> {
+        let e = match self.client().send_transaction(tx.clone().into()) {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+        match send_tx_rejection(&e) {
+            SendTxRejection::AlreadyKnown => Ok(()),
+            SendTxRejection::InputSpent => {
+                self.proposal_tx = None;
+                self.anchoring_pause = Some(AnchoringPause {
+                    rejected_tx: tx.id(),
+                    funding_txs: AnchoringSchema::new(context.snapshot())
+                        .funding_txs(cfg)
+                        .iter()
+                        .map(|tx| tx.id())
+                        .collect(),
+                });
+                Err(HandlerError::FundingUtxoSpent { txid: tx.id() }.into())
+            }
+            SendTxRejection::Other => Err(e.into()),
+        }
+    }
+
     pub fn try_create_proposal_tx(
         &mut self,
         multisig: &MultisigAddress,
@@ -63,12 +127,13 @@ impl AnchoringHandler {
             LectKind::Funding(_) => self.try_create_anchoring_tx_chain(multisig, None, context),
             LectKind::Anchoring(tx) => {
                 let anchored_height = tx.payload().block_height;
-                let latest_anchored_height =
-                    multisig.common.latest_anchoring_height(context.height());
-                if latest_anchored_height > anchored_height {
-                    return self.create_proposal_tx(&tx, multisig, latest_anchored_height, context);
+                let anchoring_height =
+                    self.anchoring_height(multisig.common, anchored_height, context);
+                if anchoring_height > anchored_height {
+                    return self.create_proposal_tx(&tx, multisig, anchoring_height, context);
                 }
-                Ok(())
+                self.try_rebroadcast_lect(&tx, multisig.common, context)?;
+                self.try_replace_unconfirmed_lect(&tx, multisig, context)
             }
             LectKind::None => {
                 warn!("Unable to reach consensus in the lect");
@@ -77,6 +142,26 @@ impl AnchoringHandler {
         }
     }
 
+    // Returns the height of the block to anchor next. It is the latest anchoring height
+    // of the schedule unless the higher height is agreed to be anchored out of it.
+    // The request is taken from the blockchain, so all the validators propose the same
+    // height. It is ignored once the anchored height reaches it, so the anchored heights
+    // keep growing and the schedule takes over once its next height is reached.
+    pub fn anchoring_height(
+        &self,
+        cfg: &AnchoringConfig,
+        anchored_height: Height,
+        context: &ServiceContext,
+    ) -> Height {
+        let scheduled_height = cfg.latest_anchoring_height(context.height());
+        let passed_height = cmp::max(scheduled_height, anchored_height);
+        let request = AnchoringSchema::new(context.snapshot()).agreed_anchor_request(cfg);
+        match request {
+            Some(height) if height > passed_height && height <= context.height() => height,
+            _ => scheduled_height,
+        }
+    }
+
     // Create first anchoring tx proposal from funding tx in AnchoringNodeConfig
     pub fn try_create_anchoring_tx_chain(
         &mut self,
@@ -85,21 +170,30 @@ impl AnchoringHandler {
         context: &ServiceContext,
     ) -> Result<(), ServiceError> {
         trace!("Create tx chain");
-        if let Some(funding_tx) = self.available_funding_tx(multisig)? {
+        if let Some(funding_tx) = self.available_funding_tx(multisig, context.snapshot())? {
             // Create anchoring proposal
             let height = multisig.common.latest_anchoring_height(context.height());
             let hash = Schema::new(context.snapshot())
                 .block_hashes_by_height()
                 .get(height.0)
                 .unwrap();
+            let table_root = AnchoringSchema::new(context.snapshot()).anchored_table_root(height);
 
             let out = funding_tx.find_out(&multisig.addr).unwrap();
-            let proposal = TransactionBuilder::with_prev_tx(&funding_tx, out)
-                .fee(multisig.common.fee)
+            let builder = TransactionBuilder::with_prev_tx(&funding_tx, out)
                 .payload(height, hash)
+                .payload_prefix(multisig.common.payload_prefix.as_bytes())
+                .table_root(table_root)
+                .anchor_output(multisig.common.anchor_output())
+                .size_limit(
+                    &multisig.redeem_script,
+                    multisig.common.majority_count() as usize,
+                    multisig.common.max_tx_vsize,
+                )
                 .prev_tx_chain(prev_tx_chain)
-                .send_to(multisig.addr.clone())
-                .into_transaction()?;
+                .send_to(multisig.addr.clone());
+            let fee = self.anchoring_fee(builder.clone(), multisig, context)?;
+            let proposal = builder.fee(fee).into_transaction()?;
 
             trace!("initial_proposal={:?}", proposal,);
 
@@ -122,24 +216,41 @@ impl AnchoringHandler {
             .block_hashes_by_height()
             .get(height.0)
             .unwrap();
+        let table_root = AnchoringSchema::new(context.snapshot()).anchored_table_root(height);
 
         let (proposal, prev_txs) = {
             let mut prev_txs = vec![lect.0.clone()];
 
             let mut builder = TransactionBuilder::with_prev_tx(lect, 0)
-                .fee(multisig.common.fee)
                 .payload(height, hash)
+                .payload_prefix(multisig.common.payload_prefix.as_bytes())
+                .table_root(table_root)
+                .anchor_output(multisig.common.anchor_output())
+                .size_limit(
+                    &multisig.redeem_script,
+                    multisig.common.majority_count() as usize,
+                    multisig.common.max_tx_vsize,
+                )
                 .send_to(multisig.addr.clone());
 
-            if let Some(funds) = self.available_funding_tx(multisig)? {
-                let out = funds.find_out(&multisig.addr).expect(
-                    "Funding tx has proper \
-                     multisig output",
-                );
-                builder = builder.add_funds(&funds, out);
-                prev_txs.push(funds.0);
+            match self.available_funding_tx(multisig, context.snapshot())? {
+                Some(funds) => {
+                    let out = funds.find_out(&multisig.addr).expect(
+                        "Funding tx has proper \
+                         multisig output",
+                    );
+                    builder = builder.add_funds(&funds, out);
+                    prev_txs.push(funds.0);
+                }
+                // The lect keeps only the anchoring output, the rest of its funds has been
+                // sent to the change address.
+                None if multisig.common.anchor_output().is_some() => {
+                    return Err(HandlerError::FundingTxNotAvailable.into());
+                }
+                None => {}
             }
-            (builder.into_transaction()?, prev_txs)
+            let fee = self.anchoring_fee(builder.clone(), multisig, context)?;
+            (builder.fee(fee).into_transaction()?, prev_txs)
         };
 
         trace!(
@@ -152,6 +263,326 @@ impl AnchoringHandler {
         self.sign_proposal_tx(proposal, &prev_txs, multisig, context)
     }
 
+    // Returns the fee strategy of the given config. The estimated fee rate is the one
+    // agreed by the validators, so all of them build the same proposal.
+    pub fn fee_strategy(
+        &self,
+        cfg: &AnchoringConfig,
+        context: &ServiceContext,
+    ) -> Result<Box<FeeStrategy>, ServiceError> {
+        match cfg.fee_strategy {
+            FeeStrategyConfig::Static => Ok(Box::new(StaticFee(cfg.fee))),
+            FeeStrategyConfig::EstimateSmartFee { .. } => {
+                let fee_rate = AnchoringSchema::new(context.snapshot())
+                    .agreed_fee_rate(cfg)
+                    .ok_or(HandlerError::FeeRateNotAgreed)?;
+                Ok(Box::new(AgreedFeeRate(fee_rate)))
+            }
+        }
+    }
+
+    // Estimates the fee rate by the `bitcoind` and commits it to the blockchain
+    // if it differs from the one committed by the validator before.
+    pub fn update_our_fee_rate(
+        &self,
+        cfg: &AnchoringConfig,
+        context: &ServiceContext,
+    ) -> Result<(), ServiceError> {
+        let conf_target = match cfg.fee_strategy {
+            FeeStrategyConfig::EstimateSmartFee { conf_target, .. } => conf_target,
+            FeeStrategyConfig::Static => return Ok(()),
+        };
+        let fee_per_kb = RpcEstimateFee::new(self.client(), conf_target).fee_per_kb()?;
+        let key = self.anchoring_key(cfg, context);
+        if AnchoringSchema::new(context.snapshot()).fee_rates().get(key) == Some(fee_per_kb) {
+            return Ok(());
+        }
+        let msg = MsgAnchoringFeeRate::new(
+            context.public_key(),
+            self.validator_id(context),
+            fee_per_kb,
+            context.secret_key(),
+        );
+        trace!("Update our fee rate, msg={:?}", msg);
+        context.transaction_sender().send(Box::new(msg))?;
+        Ok(())
+    }
+
+    // Checks that the funding transaction of the given config is known to the relay
+    // and has at least `required_confirmations`. The nodes without a relay skip the check
+    // as well as the ones with the `skip_funding_tx_check` option enabled.
+    pub fn check_funding_tx_confirmations(&self, cfg: &AnchoringConfig) -> Result<(), ConfigError> {
+        let (funding_tx, client) = match (cfg.funding_tx.as_ref(), self.client.as_ref()) {
+            (Some(tx), Some(client)) if !self.node.skip_funding_tx_check => (tx, client),
+            _ => return Ok(()),
+        };
+        let txid = funding_tx.id();
+        let info = client
+            .get_transaction_info(txid)
+            .map_err(|e| ConfigError::FundingTxCheckFailed {
+                txid: txid.to_string(),
+                reason: e.to_string(),
+            })?
+            .ok_or_else(|| ConfigError::FundingTxNotFound(txid.to_string()))?;
+
+        let confirmations = info.confirmations.unwrap_or(0);
+        let required = self.node.required_confirmations;
+        if confirmations < required {
+            return Err(ConfigError::FundingTxUnconfirmed {
+                txid: txid.to_string(),
+                confirmations,
+                required,
+            });
+        }
+        Ok(())
+    }
+
+    // Estimates the virtual size of the signed transaction from the given builder.
+    pub fn estimate_proposal_vsize(
+        &self,
+        builder: TransactionBuilder,
+        multisig: &MultisigAddress,
+    ) -> Result<u64, ServiceError> {
+        let draft = builder.fee(0).into_transaction()?;
+        Ok(estimate_vsize(
+            &draft.0,
+            &multisig.redeem_script,
+            multisig.common.majority_count() as usize,
+        ))
+    }
+
+    // Computes fee for the transaction from the given builder according to the fee strategy.
+    pub fn anchoring_fee(
+        &self,
+        builder: TransactionBuilder,
+        multisig: &MultisigAddress,
+        context: &ServiceContext,
+    ) -> Result<u64, ServiceError> {
+        let vsize = self.estimate_proposal_vsize(builder, multisig)?;
+        let fee = self.fee_strategy(multisig.common, context)?.fee_for_vsize(vsize)?;
+        trace!("Anchoring tx vsize={}, fee={}", vsize, fee);
+        Ok(fee)
+    }
+
+    // Sends the lect to the bitcoin network again every `rebroadcast_interval` blocks
+    // until it is confirmed, in case the `bitcoind` has dropped it from the mempool.
+    pub fn try_rebroadcast_lect(
+        &mut self,
+        lect: &AnchoringTx,
+        cfg: &AnchoringConfig,
+        context: &ServiceContext,
+    ) -> Result<(), ServiceError> {
+        let interval = match self.node.rebroadcast_interval {
+            Some(interval) => interval,
+            None => return Ok(()),
+        };
+        let since = match self.rebroadcasted_lect {
+            Some((txid, height)) if txid == lect.id() => height,
+            _ => {
+                self.rebroadcasted_lect = Some((lect.id(), context.height()));
+                return Ok(());
+            }
+        };
+        if context.height().0 < since.0 + interval {
+            return Ok(());
+        }
+        self.rebroadcasted_lect = Some((lect.id(), context.height()));
+
+        let confirmations = self.client().get_transaction_confirmations(lect.id())?;
+        if confirmations.unwrap_or(0) > 0 {
+            return Ok(());
+        }
+
+        trace!("Rebroadcast unconfirmed lect, txid={}", lect.id());
+        self.send_anchoring_tx(lect, cfg, context)
+    }
+
+    // Replaces the lect by the transaction with a higher fee once +2/3 validators have
+    // voted for it. At the lect check heights the validator votes for the replacement
+    // if the lect has not been confirmed for `rbf_unconfirmed_btc_blocks` bitcoin blocks
+    // since the validator has observed it.
+    pub fn try_replace_unconfirmed_lect(
+        &mut self,
+        lect: &AnchoringTx,
+        multisig: &MultisigAddress,
+        context: &ServiceContext,
+    ) -> Result<(), ServiceError> {
+        let threshold = match multisig.common.rbf_unconfirmed_btc_blocks {
+            Some(threshold) => threshold,
+            None => return Ok(()),
+        };
+        let (agreed, requested) = {
+            let schema = AnchoringSchema::new(context.snapshot());
+            let key = self.anchoring_key(multisig.common, context);
+            (
+                schema.replacement_agreed(multisig.common, &lect.id()),
+                schema.replacement_requests().get(key) == Some(lect.id()),
+            )
+        };
+        if agreed {
+            if self.replaced_lect == Some(lect.id()) {
+                return Ok(());
+            }
+            self.replaced_lect = Some(lect.id());
+            return self.create_replacement_tx(lect, multisig, context);
+        }
+        if requested || !self.is_lect_check_height(context.height()) {
+            return Ok(());
+        }
+
+        let block_count = self.client().get_block_count()?;
+        let since = match self.unconfirmed_lect {
+            Some((txid, since)) if txid == lect.id() => since,
+            _ => {
+                self.unconfirmed_lect = Some((lect.id(), block_count));
+                return Ok(());
+            }
+        };
+        if block_count < since + threshold {
+            return Ok(());
+        }
+        let confirmations = self.client().get_transaction_confirmations(lect.id())?;
+        if confirmations.unwrap_or(0) > 0 {
+            return Ok(());
+        }
+
+        warn!(
+            "Anchoring tx has not been confirmed for {} bitcoin blocks, txid={}",
+            block_count - since,
+            lect.id()
+        );
+        let msg = MsgAnchoringReplaceRequest::new(
+            context.public_key(),
+            self.validator_id(context),
+            lect.clone(),
+            context.secret_key(),
+        );
+        trace!("Request lect replacement, msg={:?}", msg);
+        context.transaction_sender().send(Box::new(msg))?;
+        Ok(())
+    }
+
+    // Creates proposal that spends the same outputs as the given lect and anchors
+    // the same block, but pays a higher fee.
+    pub fn create_replacement_tx(
+        &mut self,
+        lect: &AnchoringTx,
+        multisig: &MultisigAddress,
+        context: &ServiceContext,
+    ) -> Result<(), ServiceError> {
+        // Validators build the identical replacement, so the spent transactions are taken
+        // from the consensus state only.
+        let mut prev_txs = Vec::new();
+        {
+            let schema = AnchoringSchema::new(context.snapshot());
+            let known_txs = schema.known_txs();
+            for (input, txin) in lect.input.iter().enumerate() {
+                let txid = btc::TxId::from(txin.prev_hash);
+                let prev_tx = match schema.find_funding_tx(multisig.common, &txid) {
+                    Some(tx) => tx.0,
+                    None => known_txs
+                        .get(&txid)
+                        .ok_or(TxBuildError::PrevTxNotFound { input: input as u32 })?
+                        .0,
+                };
+                prev_txs.push(prev_tx);
+            }
+        }
+
+        let payload = lect.payload();
+        let mut builder = TransactionBuilder::with_prev_tx(&prev_txs[0], lect.input[0].prev_index)
+            .payload(payload.block_height, payload.block_hash)
+            .payload_prefix(multisig.common.payload_prefix.as_bytes())
+            .table_root(payload.table_root)
+            .anchor_output(multisig.common.anchor_output())
+            .size_limit(
+                &multisig.redeem_script,
+                multisig.common.majority_count() as usize,
+                multisig.common.max_tx_vsize,
+            )
+            .prev_tx_chain(payload.prev_tx_chain)
+            .send_to(multisig.addr.clone());
+        for (prev_tx, input) in prev_txs.iter().zip(lect.input.iter()).skip(1) {
+            builder = builder.add_funds(prev_tx, input.prev_index);
+        }
+
+        let old_fee = lect.fee(&prev_txs)?;
+        let vsize = self.estimate_proposal_vsize(builder.clone(), multisig)?;
+        let estimated_fee = self.anchoring_fee(builder, multisig, context)?;
+        let fee = replacement_fee(old_fee, estimated_fee, vsize);
+
+        // The replacement keeps the payload of the lect, so its proofs stay valid.
+        let proposal = lect.replace_with_fee(&prev_txs, fee)?;
+        trace!(
+            "replacement={:?}, replaced_txid={}, old_fee={}, fee={}",
+            proposal,
+            lect.id(),
+            old_fee,
+            fee
+        );
+        self.sign_proposal_tx(proposal, &prev_txs, multisig, context)
+    }
+
+    // Creates proposal that spends the multisig output of the given unconfirmed `parent`
+    // and pays the fee that raises the fee rate of both transactions to the estimated one.
+    pub fn create_cpfp_tx(
+        &self,
+        parent: &RawBitcoinTx,
+        parent_fee: u64,
+        multisig: &MultisigAddress,
+        height: Height,
+        context: &ServiceContext,
+    ) -> Result<Option<AnchoringTx>, ServiceError> {
+        let script_pubkey = multisig.addr.script_pubkey();
+        let out = match parent
+            .output
+            .iter()
+            .position(|output| output.script_pubkey == script_pubkey)
+        {
+            Some(out) => out as u32,
+            None => {
+                warn!(
+                    "Parent tx={} has no multisig output",
+                    btc::TxId::from(parent.txid())
+                );
+                return Ok(None);
+            }
+        };
+        let hash = Schema::new(context.snapshot())
+            .block_hashes_by_height()
+            .get(height.0)
+            .unwrap();
+        let table_root = AnchoringSchema::new(context.snapshot()).anchored_table_root(height);
+
+        let builder = TransactionBuilder::with_prev_tx(parent, out)
+            .payload(height, hash)
+            .payload_prefix(multisig.common.payload_prefix.as_bytes())
+            .table_root(table_root)
+            .anchor_output(multisig.common.anchor_output())
+            .size_limit(
+                &multisig.redeem_script,
+                multisig.common.majority_count() as usize,
+                multisig.common.max_tx_vsize,
+            )
+            .send_to(multisig.addr.clone());
+        let child_vsize = self.estimate_proposal_vsize(builder.clone(), multisig)?;
+        let fee = cpfp_fee(
+            self.fee_strategy(multisig.common, context)?.as_ref(),
+            parent_fee,
+            tx_vsize(parent),
+            child_vsize,
+        )?;
+        let proposal = builder.fee(fee).into_transaction()?;
+        trace!(
+            "cpfp_child={:?}, parent_txid={}, parent_fee={}, fee={}",
+            proposal,
+            btc::TxId::from(parent.txid()),
+            parent_fee,
+            fee
+        );
+        Ok(Some(proposal))
+    }
+
     pub fn sign_proposal_tx(
         &mut self,
         proposal: AnchoringTx,
@@ -159,20 +590,40 @@ impl AnchoringHandler {
         multisig: &MultisigAddress,
         context: &ServiceContext,
     ) -> Result<(), ServiceError> {
+        // The inputs are spent from the address of the config, the `multisig.addr`
+        // is the address of the funds output that differs during the transition.
+        let signing_addr = multisig.common.redeem_script().1;
+        let sighash_type = multisig.common.sighash_type;
         for input in proposal.inputs() {
-            let prev_tx = &prev_txs[input as usize];
-            let signature =
-                proposal.sign_input(&multisig.redeem_script, input, prev_tx, &multisig.priv_key);
-
-            debug_assert_eq!(proposal.input[input as usize].prev_hash, prev_tx.txid());
+            let prev_tx = proposal
+                .find_prev_tx(input, prev_txs)
+                .expect("Previous transaction of the proposal input is not found");
+            let request = InputSigningRequest {
+                tx: &proposal,
+                input,
+                prev_tx,
+                redeem_script: &multisig.redeem_script,
+                address: &signing_addr,
+                sighash_type,
+                sighash: proposal.signature_hash(
+                    &multisig.redeem_script,
+                    input,
+                    prev_tx,
+                    sighash_type,
+                ),
+            };
+            let mut signature = self.sign_input(&request)?;
+            signature.push(sighash_type.as_u8());
 
-            debug_assert!(proposal.verify_input(
+            if !proposal.verify_input(
                 &multisig.redeem_script,
                 input,
                 prev_tx,
                 self.anchoring_key(multisig.common, context),
-                &signature
-            ));
+                &signature,
+            ) {
+                return Err(SignerError::InvalidSignature(input).into());
+            }
 
             let sign_msg = MsgAnchoringSignature::new(
                 context.public_key(),
@@ -194,12 +645,13 @@ impl AnchoringHandler {
         Ok(())
     }
 
+    // Returns the finalized transaction if the signatures of the proposal are collected.
     pub fn try_finalize_proposal_tx(
         &mut self,
         proposal: AnchoringTx,
         multisig: &MultisigAddress,
         context: &ServiceContext,
-    ) -> Result<(), ServiceError> {
+    ) -> Result<Option<AnchoringTx>, ServiceError> {
         trace!("Try finalize proposal tx");
         let txid = proposal.id();
 
@@ -212,7 +664,7 @@ impl AnchoringHandler {
                 proposal_height
             );
             self.proposal_tx = None;
-            return Ok(());
+            return Ok(None);
         }
 
         let collected_signatures = {
@@ -224,7 +676,7 @@ impl AnchoringHandler {
             let new_lect = proposal.finalize(&multisig.redeem_script, signatures);
             // Send transaction if it needs
             if self.client().get_transaction(new_lect.id())?.is_none() {
-                self.client().send_transaction(new_lect.clone().into())?;
+                self.send_anchoring_tx(&new_lect, multisig.common, context)?;
                 trace!("Sent signed_tx={:#?}, to={}", new_lect, multisig.addr,);
             }
 
@@ -251,14 +703,15 @@ impl AnchoringHandler {
             let lect_msg = MsgAnchoringUpdateLatest::new(
                 context.public_key(),
                 self.validator_id(context),
-                new_lect.into(),
+                new_lect.clone().into(),
                 lects_count,
                 context.secret_key(),
             );
             context.transaction_sender().send(Box::new(lect_msg))?;
+            Ok(Some(new_lect))
         } else {
             warn!("Insufficient signatures for proposal={:#?}", proposal);
+            Ok(None)
         }
-        Ok(())
     }
 }
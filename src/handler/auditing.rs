@@ -30,7 +30,7 @@ impl AnchoringHandler {
         state: &ServiceContext,
     ) -> Result<(), ServiceError> {
         trace!("Auditing state");
-        if state.height().0 % self.node.check_lect_frequency == 0 {
+        if self.is_lect_check_height(state.height()) {
             let r = match self.collect_lects(state)? {
                 LectKind::Funding(tx) => self.check_funding_lect(tx, state),
                 LectKind::Anchoring(tx) => self.check_anchoring_lect(&tx),
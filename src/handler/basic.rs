@@ -14,35 +14,154 @@
 
 use std::collections::HashSet;
 use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
 use exonum::blockchain::ServiceContext;
+use exonum::crypto::CryptoHash;
 use exonum::helpers::{Height, ValidatorId};
 use exonum::storage::Snapshot;
 
 use blockchain::consensus_storage::AnchoringConfig;
-use blockchain::dto::MsgAnchoringUpdateLatest;
+use blockchain::dto::{MsgAnchoringTopUp, MsgAnchoringUpdateLatest};
 use blockchain::schema::AnchoringSchema;
 use details::btc;
 use details::btc::transactions::{AnchoringTx, BitcoinTx, FundingTx, TxKind};
+use details::error::SignerError;
+use details::hwi::HwiSigner;
 use details::rpc::BitcoinRelay;
+use details::signer::{ExternalSigner, InputSigningRequest, LocalSigner, SignerBackend};
 use error::Error as ServiceError;
 use handler::error::Error as HandlerError;
-use local_storage::AnchoringNodeConfig;
+use local_storage::{AnchoringNodeConfig, SignerConfig};
+use zmq_listener::BitcoindEvents;
 
-use super::{AnchoringHandler, AnchoringState, LectKind, MultisigAddress};
+use super::{AnchoringHandler, AnchoringPause, AnchoringState, AnchoringWatchdog, LectKind,
+            MultisigAddress, SigningStatus, TransitionEvent, TransitionProgress};
 
 impl AnchoringHandler {
     #[doc(hidden)]
     pub fn new(client: Option<Box<BitcoinRelay>>, node: AnchoringNodeConfig) -> AnchoringHandler {
+        let stall_budget = node.anchor_stall_budget.map(Duration::from_secs);
+        let signer = match node.signer {
+            SignerConfig::Local => None,
+            SignerConfig::External {
+                ref url,
+                ref username,
+                ref password,
+            } => {
+                let signer = ExternalSigner::new(
+                    url,
+                    username.clone(),
+                    password.clone(),
+                    node.rpc_connection,
+                );
+                Some(Box::new(signer) as Box<SignerBackend>)
+            }
+            SignerConfig::Hwi(ref config) => {
+                let signer = HwiSigner::new(config.clone())
+                    .unwrap_or_else(|e| panic!("Invalid hardware wallet config: {}", e));
+                Some(Box::new(signer) as Box<SignerBackend>)
+            }
+        };
         AnchoringHandler {
             client,
             node,
             proposal_tx: None,
             known_addresses: HashSet::new(),
             errors_sink: None,
+            unconfirmed_lect: None,
+            replaced_lect: None,
+            rebroadcasted_lect: None,
+            bitcoind_events: BitcoindEvents::new(),
+            lect_check_requested: false,
+            signing_status: SigningStatus::Unknown,
+            transition_stage: None,
+            transition_events: Vec::new(),
+            transition_progress: TransitionProgress::new(),
+            watchdog: AnchoringWatchdog::new(stall_budget, Instant::now()),
+            anchoring_pause: None,
+            signer,
+            accepted_configs: HashSet::new(),
         }
     }
 
+    /// Returns whether the node can sign the anchoring transactions according to
+    /// the latest check of its private keys against the actual anchoring configuration.
+    pub fn signing_status(&self) -> SigningStatus {
+        self.signing_status
+    }
+
+    /// Returns the pause of the anchoring if the input of the latest anchoring transaction
+    /// has been spent outside of the anchoring chain and the configuration is not refueled
+    /// since then.
+    pub fn anchoring_pause(&self) -> Option<&AnchoringPause> {
+        self.anchoring_pause.as_ref()
+    }
+
+    /// Replaces the signing backend from the node configuration by the given `signer`.
+    pub fn set_signer(&mut self, signer: Box<SignerBackend>) {
+        self.signer = Some(signer);
+    }
+
+    /// Signs the input of the anchoring transaction by the private key for the anchoring
+    /// address, the signing backend of the node is used, or the `private_keys`
+    /// if there is no backend.
+    #[doc(hidden)]
+    pub fn sign_input(&self, request: &InputSigningRequest) -> Result<Vec<u8>, SignerError> {
+        match self.signer {
+            Some(ref signer) => signer.sign_input(request),
+            None => LocalSigner::new(&self.node.private_keys).sign_input(request),
+        }
+    }
+
+    /// Same as `sign_input` but returns the public key for the anchoring `address`.
+    #[doc(hidden)]
+    pub fn signer_public_key(
+        &self,
+        address: &btc::Address,
+    ) -> Result<Option<btc::PublicKey>, SignerError> {
+        match self.signer {
+            Some(ref signer) => signer.public_key(address),
+            None => LocalSigner::new(&self.node.private_keys).public_key(address),
+        }
+    }
+
+    /// Checks that the node has the private key for the anchoring key of the validator
+    /// with the given `validator_id` in the `cfg`.
+    ///
+    /// The status is logged once it changes, so the missing key is reported on the first
+    /// block after the node start or the configuration change.
+    #[doc(hidden)]
+    pub fn check_signing_status(
+        &mut self,
+        cfg: &AnchoringConfig,
+        validator_id: ValidatorId,
+    ) -> SigningStatus {
+        let addr = cfg.redeem_script().1;
+        let key = cfg.anchoring_keys.get(validator_id.0 as usize);
+        let signer_key = self.signer_public_key(&addr).unwrap_or_else(|e| {
+            warn!("Unable to get the signing key for address={}: {}", addr, e);
+            None
+        });
+        let status = match (key, signer_key) {
+            (Some(key), Some(ref signer_key)) if signer_key == key => SigningStatus::CanSign,
+            _ => SigningStatus::CannotSign,
+        };
+        if status != self.signing_status {
+            if status == SigningStatus::CanSign {
+                info!("Node can sign anchoring transactions, address={}", addr);
+            } else {
+                error!(
+                    "Node cannot sign anchoring transactions, the private key for the anchoring \
+                     key of the validator={} is missing in the signer, address={}",
+                    validator_id, addr
+                );
+            }
+        }
+        self.signing_status = status;
+        status
+    }
+
     #[doc(hidden)]
     pub fn validator_id(&self, context: &ServiceContext) -> ValidatorId {
         context
@@ -71,18 +190,31 @@ impl AnchoringHandler {
             .as_ref()
     }
 
+    /// Checks that the anchoring configuration is compatible with the node, i.e. the addresses
+    /// of the private keys belong to its network and the node accepts its parameters.
+    ///
+    /// The accepted configurations are remembered, so each one is checked once.
+    #[doc(hidden)]
+    pub fn check_config(&mut self, cfg: &AnchoringConfig) -> Result<(), HandlerError> {
+        let hash = cfg.hash();
+        if self.accepted_configs.contains(&hash) {
+            return Ok(());
+        }
+        self.node
+            .check_network(cfg.network)
+            .map_err(HandlerError::IncompatibleNodeConfig)?;
+        cfg.check_frequency(self.node.min_anchoring_frequency)
+            .and_then(|_| cfg.check_anchor_output())
+            .map_err(HandlerError::ConfigRejected)?;
+        self.accepted_configs.insert(hash);
+        Ok(())
+    }
+
     #[doc(hidden)]
     pub fn multisig_address<'a>(&self, common: &'a AnchoringConfig) -> MultisigAddress<'a> {
         let (redeem_script, addr) = common.redeem_script();
-        let addr_str = addr.to_string();
-        let priv_key = self.node
-            .private_keys
-            .get(&addr_str)
-            .expect(&format!("Expected private key for address={}", addr_str))
-            .clone();
         MultisigAddress {
             common,
-            priv_key,
             redeem_script,
             addr,
         }
@@ -155,7 +287,13 @@ impl AnchoringHandler {
         // Ensure that bitcoind watching for the current addr
         self.import_address(&actual_addr)?;
 
-        if state.validator_id().is_none() {
+        if state.validator_id().is_none() || self.node.observer_only {
+            self.signing_status = SigningStatus::Auditor;
+            return Ok(AnchoringState::Auditing { cfg: actual });
+        }
+        // The node that cannot sign follows the anchoring chain like an auditor.
+        let validator_id = self.validator_id(state);
+        if self.check_signing_status(&actual, validator_id) == SigningStatus::CannotSign {
             return Ok(AnchoringState::Auditing { cfg: actual });
         }
 
@@ -168,7 +306,7 @@ impl AnchoringHandler {
         } else {
             let prev_cfg = anchoring_schema.previous_anchoring_config().unwrap();
             let is_recovering = if let Some(prev_lect) = anchoring_schema.collect_lects(&prev_cfg) {
-                match TxKind::from(prev_lect) {
+                match prev_cfg.tx_kind(prev_lect) {
                     TxKind::FundingTx(_) => prev_cfg.redeem_script().1 != actual_addr,
                     TxKind::Anchoring(tx) => tx.script_pubkey() != &actual_addr.script_pubkey(),
                     TxKind::Other(tx) => panic!("Incorrect lect found={:#?}", tx),
@@ -194,11 +332,17 @@ impl AnchoringHandler {
             // Ensure that bitcoind watching for following addr.
             self.import_address(&following_addr)?;
 
-            match TxKind::from(actual_lect) {
+            match actual.tx_kind(actual_lect) {
                 TxKind::Anchoring(lect) => {
                     let lect_script_pubkey = lect.script_pubkey().clone();
                     if lect_script_pubkey == following_addr.script_pubkey() {
                         let confirmations = self.client().get_transaction_confirmations(lect.id())?;
+                        if is_enough_confirmations(&following, confirmations) {
+                            self.emit_transition_event(TransitionEvent::TransferConfirmed {
+                                to: following_addr,
+                                txid: lect.id(),
+                            });
+                        }
                         // Lect now is transition transaction
                         AnchoringState::Waiting {
                             lect: lect.into(),
@@ -221,7 +365,7 @@ impl AnchoringHandler {
                 TxKind::Other(tx) => panic!("Incorrect lect found={:#?}", tx),
             }
         } else {
-            match TxKind::from(actual_lect) {
+            match actual.tx_kind(actual_lect) {
                 TxKind::FundingTx(tx) => {
                     if tx.find_out(&actual_addr).is_some() {
                         trace!("Checking funding_tx={:?}, txid={}", tx, tx.id());
@@ -264,6 +408,10 @@ impl AnchoringHandler {
                             };
                             return Ok(state);
                         }
+                        self.emit_transition_event(TransitionEvent::TransferConfirmed {
+                            to: actual_addr,
+                            txid: actual_lect.id(),
+                        });
                     }
 
                     AnchoringState::Anchoring { cfg: actual }
@@ -276,6 +424,20 @@ impl AnchoringHandler {
 
     #[doc(hidden)]
     pub fn after_commit(&mut self, state: &ServiceContext) -> Result<(), ServiceError> {
+        self.lect_check_requested = self.bitcoind_events.take();
+        self.watchdog.check(Instant::now());
+        // The anchoring stops until the node accepts both configurations.
+        let (actual, following) = {
+            let schema = AnchoringSchema::new(state.snapshot());
+            (
+                schema.actual_anchoring_config(),
+                schema.following_anchoring_config(),
+            )
+        };
+        self.check_config(&actual)?;
+        if let Some(following) = following {
+            self.check_config(&following)?;
+        }
         match self.current_state(state)? {
             AnchoringState::Anchoring { cfg } => self.handle_anchoring_state(&cfg, state),
             AnchoringState::Transition { from, to } => {
@@ -294,6 +456,13 @@ impl AnchoringHandler {
         }
     }
 
+    /// Returns `true` if the lect should be checked at the given height, either
+    /// according to the `check_lect_frequency` or after the `bitcoind` notification.
+    #[doc(hidden)]
+    pub fn is_lect_check_height(&self, height: Height) -> bool {
+        height.0 % self.node.check_lect_frequency == 0 || self.lect_check_requested
+    }
+
     #[doc(hidden)]
     pub fn collect_lects_for_validator(
         &self,
@@ -320,7 +489,7 @@ impl AnchoringHandler {
         }
 
         if count >= ::majority_count(validators_count as u8) {
-            match TxKind::from(our_lect) {
+            match anchoring_cfg.tx_kind(our_lect) {
                 TxKind::Anchoring(tx) => LectKind::Anchoring(tx),
                 TxKind::FundingTx(tx) => LectKind::Funding(tx),
                 TxKind::Other(tx) => panic!("Found incorrect lect transaction, content={:#?}", tx),
@@ -335,7 +504,7 @@ impl AnchoringHandler {
         let anchoring_schema = AnchoringSchema::new(state.snapshot());
         let actual_cfg = anchoring_schema.actual_anchoring_config();
         let kind = if let Some(lect) = anchoring_schema.collect_lects(&actual_cfg) {
-            match TxKind::from(lect) {
+            match actual_cfg.tx_kind(lect) {
                 TxKind::Anchoring(tx) => LectKind::Anchoring(tx),
                 TxKind::FundingTx(tx) => LectKind::Funding(tx),
                 TxKind::Other(tx) => {
@@ -356,17 +525,34 @@ impl AnchoringHandler {
     /// We list unspent transaction by 'listunspent' and search among
     /// them only one that prev_hash is exists in our `lects` or it equals first `funding_tx`
     /// if all `lects` have disappeared.
+    /// Unknown transactions should have at least `required_confirmations`.
     pub fn find_lect(
         &self,
         multisig: &MultisigAddress,
         state: &ServiceContext,
     ) -> Result<Option<BitcoinTx>, ServiceError> {
-        let lects = self.client()
-            .unspent_transactions(&multisig.addr)?
-            .into_iter()
-            .map(|tx| tx.body);
-        for lect in lects {
+        let key = self.anchoring_key(multisig.common, state);
+        let unspent_txs = self.client().unspent_transactions(&multisig.addr)?;
+        for info in unspent_txs {
+            let lect = info.body;
+            let is_known = AnchoringSchema::new(state.snapshot())
+                .find_lect_position(key, &lect.id())
+                .is_some();
+            let confirmations = info.confirmations.unwrap_or(0);
+            if !is_known && confirmations < self.node.required_confirmations {
+                trace!(
+                    "Insufficient confirmations for the lect candidate, txid={}, confirmations={}",
+                    lect.id(),
+                    confirmations
+                );
+                continue;
+            }
             if self.transaction_is_lect(&lect, multisig, state)? {
+                if confirmations > 0 {
+                    if let TxKind::Anchoring(tx) = multisig.common.tx_kind(lect.clone()) {
+                        self.watchdog.anchor_confirmed(tx.id(), Instant::now());
+                    }
+                }
                 return Ok(Some(lect));
             }
         }
@@ -383,14 +569,21 @@ impl AnchoringHandler {
         trace!("Update our lect");
         if let Some(lect) = self.find_lect(multisig, state)? {
             // New lect with different signatures set.
-            let (our_lect, lects_count) = {
+            let (our_lect, lects_count, rejected) = {
                 let schema = AnchoringSchema::new(state.snapshot());
                 let our_lect = schema.lect(key);
                 let count = schema.lects(key).len();
-                (our_lect, count)
+                // The rejected conflicting lect is sent again only once +2/3 validators
+                // have reported it.
+                let txid = lect.id();
+                let rejected = schema.lect_conflicts(key).contains(&txid)
+                    && !schema.conflicting_lect_agreed(&txid);
+                (our_lect, count, rejected)
             };
 
-            if Some(&lect) != our_lect.as_ref() {
+            if rejected {
+                trace!("Our lect={} conflicts with the agreed one", lect.id());
+            } else if Some(&lect) != our_lect.as_ref() {
                 self.send_updated_lect(&lect, lects_count, state);
             }
 
@@ -400,37 +593,147 @@ impl AnchoringHandler {
         }
     }
 
+    /// Returns the largest unspent funding transaction from the given `multisig` configuration
+    /// or the top-ups committed to the blockchain.
     #[doc(hidden)]
     pub fn available_funding_tx(
         &self,
         multisig: &MultisigAddress,
+        snapshot: &Snapshot,
     ) -> Result<Option<FundingTx>, ServiceError> {
-        let funding_tx = multisig.common.funding_tx();
         // Do not need to check funding_tx to the different address.
-        if funding_tx.find_out(&multisig.addr).is_none() {
+        let funding_txs = AnchoringSchema::new(snapshot)
+            .funding_txs(multisig.common)
+            .into_iter()
+            .filter_map(|tx| tx.find_out(&multisig.addr).map(|out| (tx, out)))
+            .collect::<Vec<_>>();
+        if funding_txs.is_empty() {
             return Ok(None);
         }
 
         trace!(
-            "Checking funding_tx={:?}, addr={} availability",
-            funding_tx,
+            "Checking funding_txs={:?}, addr={} availability",
+            funding_txs,
             multisig.addr
         );
-        if let Some(info) = self.client()
-            .unspent_transactions(&multisig.addr)?
-            .iter()
-            .find(|tx| tx.body.0 == funding_tx.0)
-        {
+        let unspent_txs = self.client().unspent_transactions(&multisig.addr)?;
+        let available = funding_txs
+            .into_iter()
+            .filter_map(|(funding_tx, out)| {
+                unspent_txs
+                    .iter()
+                    .find(|info| info.body.0 == funding_tx.0)
+                    .map(|info| (funding_tx, out, info.confirmations))
+            })
+            .collect::<Vec<_>>();
+        let available_count = available.len();
+        let available = available
+            .into_iter()
+            .max_by_key(|&(ref funding_tx, out, _)| funding_tx.output[out as usize].value);
+
+        if let Some((funding_tx, _, confirmations)) = available {
             trace!(
                 "available_funding_tx={:?}, confirmations={:?}",
                 funding_tx,
-                info.confirmations
+                confirmations
             );
-            return Ok(Some(funding_tx.clone()));
+            // With the change address every anchoring transaction consumes the whole
+            // funding transaction, so the anchoring stops once they run out.
+            if multisig.common.change_address.is_some() && available_count == 1 {
+                warn!(
+                    "The last unspent funding tx={} is being consumed, fund the anchoring \
+                     address {} to continue anchoring",
+                    funding_tx.id(),
+                    multisig.addr
+                );
+            }
+            return Ok(Some(funding_tx));
         }
         Ok(None)
     }
 
+    /// Returns the total value of the unspent outputs to the given `multisig` address
+    /// in satoshis, including the unconfirmed ones.
+    ///
+    /// Operators can compare it with the dust threshold and the fee to refuel the
+    /// address before the anchoring stalls.
+    pub fn spendable_balance(&self, multisig: &MultisigAddress) -> Result<u64, ServiceError> {
+        let script_pubkey = multisig.addr.script_pubkey();
+        let balance = self.client()
+            .unspent_transactions(&multisig.addr)?
+            .iter()
+            .flat_map(|info| info.body.output.iter())
+            .filter(|output| output.script_pubkey == script_pubkey)
+            .map(|output| output.value)
+            .sum();
+        Ok(balance)
+    }
+
+    /// Sends the funding transaction to the `multisig` address if its spendable balance
+    /// is below the `funding_top_up.min_balance` and the spending limit allows it.
+    ///
+    /// The validators take turns by the lect check rounds, so only one of them
+    /// tops up the address at the given height.
+    #[doc(hidden)]
+    pub fn try_top_up_funding(
+        &mut self,
+        multisig: &MultisigAddress,
+        context: &ServiceContext,
+    ) -> Result<(), ServiceError> {
+        let top_up = match self.node.funding_top_up {
+            Some(top_up) => top_up,
+            None => return Ok(()),
+        };
+        if !self.is_top_up_turn(context) {
+            return Ok(());
+        }
+        let balance = self.spendable_balance(multisig)?;
+        if balance >= top_up.min_balance {
+            return Ok(());
+        }
+
+        // The spending is counted by the top-ups of all validators committed to the blockchain
+        // within the period, so it is kept across the restarts of the node.
+        let since = Height((context.height().0 + 1).saturating_sub(top_up.period));
+        let spent = AnchoringSchema::new(context.snapshot()).top_ups_amount_since(since);
+        if spent + top_up.amount > top_up.max_spend_per_period {
+            warn!(
+                "Funding top-up is suppressed by the spending limit, addr={}, balance={}, \
+                 spent={}, max_spend_per_period={}",
+                multisig.addr, balance, spent, top_up.max_spend_per_period
+            );
+            return Ok(());
+        }
+
+        let funding_tx = self.client()
+            .send_to_address(&multisig.addr, top_up.amount)?;
+        info!(
+            "TOP-UP ====== txid={}, addr={}, amount={}, balance={}",
+            funding_tx.id(),
+            multisig.addr,
+            top_up.amount,
+            balance
+        );
+
+        let top_up_msg = MsgAnchoringTopUp::new(
+            context.public_key(),
+            self.validator_id(context),
+            BitcoinTx::from(funding_tx),
+            context.secret_key(),
+        );
+        context.transaction_sender().send(Box::new(top_up_msg))?;
+        Ok(())
+    }
+
+    /// Returns `true` if this validator should top up the anchoring address at the current
+    /// height. The turn passes to the next validator every `check_lect_frequency` blocks.
+    #[doc(hidden)]
+    pub fn is_top_up_turn(&self, context: &ServiceContext) -> bool {
+        let validators_count = context.actual_configuration().validator_keys.len() as u64;
+        let round = context.height().0 / self.node.check_lect_frequency;
+        round % validators_count == u64::from(self.validator_id(context).0)
+    }
+
     #[doc(hidden)]
     fn transaction_is_lect(
         &self,
@@ -446,7 +749,7 @@ impl AnchoringHandler {
             return Ok(true);
         }
 
-        let kind = TxKind::from(lect.clone());
+        let kind = multisig.common.tx_kind(lect.clone());
         match kind {
             TxKind::FundingTx(tx) => {
                 let genesis_cfg = schema.genesis_anchoring_config();
@@ -466,7 +769,7 @@ impl AnchoringHandler {
 
                 trace!("Check prev lect={:?}", prev_lect);
 
-                let lect_height = match TxKind::from(prev_lect) {
+                let lect_height = match multisig.common.tx_kind(prev_lect) {
                     TxKind::FundingTx(_) => Height::zero(),
                     TxKind::Anchoring(tx) => tx.payload().block_height,
                     TxKind::Other(_) => return Ok(false),
@@ -526,7 +829,7 @@ where
     let actual_lect_script_pubkey = actual_lect.script_pubkey();
 
     if let Some(prev_lect) = schema.known_txs().get(&prev_lect_id) {
-        match TxKind::from(prev_lect) {
+        match schema.actual_anchoring_config().tx_kind(prev_lect) {
             TxKind::Anchoring(prev_lect) => {
                 let prev_lect_script_pubkey = prev_lect.script_pubkey();
                 prev_lect_script_pubkey != actual_lect_script_pubkey
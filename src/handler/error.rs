@@ -14,7 +14,9 @@
 
 use exonum::helpers::Height;
 
+use details::btc;
 use details::btc::transactions::BitcoinTx;
+use details::error::{AddrError, ConfigError};
 
 #[derive(Debug, PartialEq, Display, Fail)]
 pub enum Error {
@@ -22,4 +24,16 @@ pub enum Error {
     IncorrectLect { reason: String, tx: BitcoinTx },
     #[display(fmt = "Suitable lect not found for height={}", height)]
     LectNotFound { height: Height },
+    #[display(fmt = "Funding UTXO externally spent, the anchoring tx={} is rejected and \
+                     the anchoring is paused until refueled", txid)]
+    FundingUtxoSpent { txid: btc::TxId },
+    #[display(fmt = "Fee rate of the anchoring transactions is not agreed by +2/3 validators")]
+    FeeRateNotAgreed,
+    #[display(fmt = "No unspent funding tx left to pay for the anchoring tx, the change output \
+                     of the previous one is sent to the change address")]
+    FundingTxNotAvailable,
+    #[display(fmt = "Anchoring node configuration is incompatible with the actual one: {}", _0)]
+    IncompatibleNodeConfig(AddrError),
+    #[display(fmt = "Anchoring configuration is rejected by the node: {}", _0)]
+    ConfigRejected(ConfigError),
 }
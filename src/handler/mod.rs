@@ -19,17 +19,25 @@ mod auditing;
 mod basic;
 pub mod error;
 mod transition;
+mod watchdog;
 
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc, Mutex};
+
+use exonum::crypto::Hash;
+use exonum::helpers::Height;
 
 use blockchain::consensus_storage::AnchoringConfig;
 use blockchain::dto::MsgAnchoringSignature;
 use details::btc;
 use details::btc::transactions::{AnchoringTx, BitcoinTx, FundingTx};
 use details::rpc::BitcoinRelay;
+use details::signer::SignerBackend;
 use local_storage::AnchoringNodeConfig;
+use zmq_listener::BitcoindEvents;
+
+pub use self::watchdog::{AnchoringWatchdog, WatchdogStatus};
 
 /// Internal anchoring service handler. Can be used to manage the service.
 #[derive(Debug)]
@@ -44,13 +52,152 @@ pub struct AnchoringHandler {
     pub errors_sink: Option<mpsc::Sender<error::Error>>,
     #[doc(hidden)]
     pub known_addresses: HashSet<String>,
+    #[doc(hidden)]
+    pub unconfirmed_lect: Option<(btc::TxId, u64)>,
+    #[doc(hidden)]
+    pub replaced_lect: Option<btc::TxId>,
+    #[doc(hidden)]
+    pub rebroadcasted_lect: Option<(btc::TxId, Height)>,
+    #[doc(hidden)]
+    pub bitcoind_events: BitcoindEvents,
+    #[doc(hidden)]
+    pub lect_check_requested: bool,
+    #[doc(hidden)]
+    pub signing_status: SigningStatus,
+    #[doc(hidden)]
+    pub transition_stage: Option<(btc::Address, u8)>,
+    #[doc(hidden)]
+    pub transition_events: Vec<TransitionEvent>,
+    #[doc(hidden)]
+    pub transition_progress: TransitionProgress,
+    #[doc(hidden)]
+    pub watchdog: AnchoringWatchdog,
+    #[doc(hidden)]
+    pub anchoring_pause: Option<AnchoringPause>,
+    #[doc(hidden)]
+    pub signer: Option<Box<SignerBackend>>,
+    #[doc(hidden)]
+    pub accepted_configs: HashSet<Hash>,
+}
+
+/// Anchoring that is paused because an input of the anchoring transaction has been
+/// spent outside of the anchoring chain, for example, by an operator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnchoringPause {
+    /// Id of the rejected anchoring transaction.
+    pub rejected_tx: btc::TxId,
+    /// Funding transactions of the actual configuration and the committed top-ups
+    /// at the moment of the pause. The anchoring is resumed once another funding
+    /// transaction is added by the configuration or a top-up.
+    pub funding_txs: Vec<btc::TxId>,
+}
+
+/// Ability of the node to sign the anchoring transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SigningStatus {
+    /// The node has not checked its keys against the anchoring configuration yet.
+    Unknown,
+    /// The node is not a validator or it is configured as an observer only.
+    Auditor,
+    /// The node has the private key for its anchoring key in the actual configuration.
+    CanSign,
+    /// The node lacks the private key for its anchoring key in the actual configuration,
+    /// so it follows the anchoring chain without signing.
+    CannotSign,
+}
+
+/// Stage of the transition of the anchoring chain to the address of the following
+/// configuration, the stages are passed in the order of the variants.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "event")]
+pub enum TransitionEvent {
+    /// The following configuration changes the anchoring address.
+    Started {
+        /// Actual anchoring address.
+        from: btc::Address,
+        /// Anchoring address of the following configuration.
+        to: btc::Address,
+    },
+    /// The transfer transaction is built, and its signing is started.
+    TransferBuilt {
+        /// Anchoring address of the following configuration.
+        to: btc::Address,
+        /// Id of the transfer transaction.
+        txid: btc::TxId,
+    },
+    /// Signatures of the transfer transaction are collected, and it is sent
+    /// to the bitcoin network.
+    SignaturesCollected {
+        /// Anchoring address of the following configuration.
+        to: btc::Address,
+        /// Id of the transfer transaction.
+        txid: btc::TxId,
+    },
+    /// The transfer transaction has got enough confirmations.
+    TransferConfirmed {
+        /// Anchoring address of the following configuration.
+        to: btc::Address,
+        /// Id of the transfer transaction.
+        txid: btc::TxId,
+    },
+}
+
+impl TransitionEvent {
+    /// Returns the anchoring address of the following configuration.
+    pub fn to(&self) -> &btc::Address {
+        match *self {
+            TransitionEvent::Started { ref to, .. }
+            | TransitionEvent::TransferBuilt { ref to, .. }
+            | TransitionEvent::SignaturesCollected { ref to, .. }
+            | TransitionEvent::TransferConfirmed { ref to, .. } => to,
+        }
+    }
+
+    fn stage(&self) -> u8 {
+        match *self {
+            TransitionEvent::Started { .. } => 0,
+            TransitionEvent::TransferBuilt { .. } => 1,
+            TransitionEvent::SignaturesCollected { .. } => 2,
+            TransitionEvent::TransferConfirmed { .. } => 3,
+        }
+    }
+}
+
+/// Transfer transaction to the address of the following configuration that is signed
+/// by the node.
+///
+/// The transaction is shared between the handler that builds it and the public API
+/// that counts its signatures committed to the blockchain.
+#[derive(Debug, Clone, Default)]
+pub struct TransitionProgress {
+    transfer: Arc<Mutex<Option<(btc::Address, btc::TxId)>>>,
+}
+
+impl TransitionProgress {
+    /// Creates the progress without the transfer transaction.
+    pub fn new() -> TransitionProgress {
+        TransitionProgress::default()
+    }
+
+    /// Remembers the normalized txid of the transfer transaction to the given address.
+    pub fn set_transfer(&self, to: btc::Address, ntxid: btc::TxId) {
+        *self.transfer.lock().unwrap() = Some((to, ntxid));
+    }
+
+    /// Returns the normalized txid of the transfer transaction to the given address.
+    pub fn transfer(&self, to: &btc::Address) -> Option<btc::TxId> {
+        match *self.transfer.lock().unwrap() {
+            Some((ref addr, ntxid)) if addr == to => Some(ntxid),
+            _ => None,
+        }
+    }
 }
 
 #[doc(hidden)]
 #[derive(Debug)]
 pub struct MultisigAddress<'a> {
     pub common: &'a AnchoringConfig,
-    pub priv_key: btc::PrivateKey,
     pub addr: btc::Address,
     pub redeem_script: btc::RedeemScript,
 }
@@ -87,6 +234,12 @@ pub enum LectKind {
     None,
 }
 
+/// Returns `true` if the block at the given `height` should be anchored
+/// according to the anchoring `cfg`.
+pub fn is_anchoring_height(height: Height, cfg: &AnchoringConfig) -> bool {
+    cfg.latest_anchoring_height(height) == height
+}
+
 #[doc(hidden)]
 /// The function extracts signatures from messages and order them by inputs.
 pub fn collect_signatures<I>(
@@ -105,6 +258,14 @@ where
     for msg in msgs {
         let input = msg.input();
         let validator = msg.validator().0 as usize;
+        // Signatures of the different types cannot be combined into the valid witness.
+        if btc::SighashType::of_signature(msg.signature()) != Some(common.sighash_type) {
+            warn!(
+                "Signature with unexpected sighash type, input={}, validator={}",
+                input, validator
+            );
+            continue;
+        }
 
         let signatures_by_input = signatures.get_mut(&input).unwrap();
         signatures_by_input[validator] = Some(msg.signature().to_vec());
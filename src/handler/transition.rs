@@ -19,7 +19,7 @@ use blockchain::schema::AnchoringSchema;
 use details::btc::transactions::BitcoinTx;
 use error::Error as ServiceError;
 
-use super::{AnchoringHandler, LectKind, MultisigAddress};
+use super::{AnchoringHandler, LectKind, MultisigAddress, TransitionEvent};
 
 #[doc(hidden)]
 impl AnchoringHandler {
@@ -39,16 +39,26 @@ impl AnchoringHandler {
             multisig.addr,
             to
         );
+        self.emit_transition_event(TransitionEvent::Started {
+            from: from.redeem_script().1,
+            to: multisig.addr.clone(),
+        });
 
         // Similar we update lect each n blocks
-        if state.height().0 % self.node.check_lect_frequency == 0 {
+        if self.is_lect_check_height(state.height()) {
             // First of all we try to update our lect and actual configuration
             self.update_our_lect(&multisig, state)?;
+            self.update_our_fee_rate(multisig.common, state)?;
         }
 
         // Now if we have anchoring tx proposal we must try to finalize it
         if let Some(proposal) = self.proposal_tx.clone() {
-            self.try_finalize_proposal_tx(proposal, &multisig, state)?;
+            if let Some(lect) = self.try_finalize_proposal_tx(proposal, &multisig, state)? {
+                self.emit_transition_event(TransitionEvent::SignaturesCollected {
+                    to: multisig.addr.clone(),
+                    txid: lect.id(),
+                });
+            }
         } else {
             // Or try to create proposal
             match self.collect_lects_for_validator(
@@ -67,6 +77,17 @@ impl AnchoringHandler {
                     if confirmations >= multisig.common.utxo_confirmations {
                         let height = multisig.common.latest_anchoring_height(state.height());
                         self.create_proposal_tx(&lect, &multisig, height, state)?;
+                        let txid = self.proposal_tx
+                            .as_ref()
+                            .map(|proposal| (proposal.id(), proposal.nid()));
+                        if let Some((txid, ntxid)) = txid {
+                            self.transition_progress
+                                .set_transfer(multisig.addr.clone(), ntxid);
+                            self.emit_transition_event(TransitionEvent::TransferBuilt {
+                                to: multisig.addr.clone(),
+                                txid,
+                            });
+                        }
                     } else {
                         warn!(
                             "Insufficient confirmations for create transition transaction, \
@@ -84,6 +105,21 @@ impl AnchoringHandler {
         Ok(())
     }
 
+    /// Queues the event for the subscribers if the transition to its address
+    /// has not passed its stage yet.
+    pub fn emit_transition_event(&mut self, event: TransitionEvent) {
+        let stage = event.stage();
+        let is_passed = match self.transition_stage {
+            Some((ref to, passed_stage)) => to == event.to() && passed_stage >= stage,
+            None => false,
+        };
+        if !is_passed {
+            info!("Transition event={:?}", event);
+            self.transition_stage = Some((event.to().clone(), stage));
+            self.transition_events.push(event);
+        }
+    }
+
     pub fn handle_waiting_state(
         &mut self,
         lect: BitcoinTx,
@@ -109,9 +145,10 @@ impl AnchoringHandler {
     ) -> Result<(), ServiceError> {
         let multisig: MultisigAddress = self.multisig_address(actual_cfg);
 
-        if state.height().0 % self.node.check_lect_frequency == 0 {
+        if self.is_lect_check_height(state.height()) {
             // First of all we try to update our lect and actual configuration
             self.update_our_lect(&multisig, state)?;
+            self.update_our_fee_rate(multisig.common, state)?;
         }
 
         trace!(
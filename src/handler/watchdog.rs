@@ -0,0 +1,122 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detection of the anchoring that has silently stopped.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use details::btc;
+
+/// Watchdog that tracks the wall-clock time since the latest confirmed anchor.
+///
+/// The watchdog is shared between the handler that reports the confirmed anchors
+/// and the public API that serves its status. The current time is passed explicitly,
+/// so the callers decide which clock is used.
+#[derive(Debug, Clone)]
+pub struct AnchoringWatchdog {
+    budget: Option<Duration>,
+    state: Arc<Mutex<WatchdogState>>,
+}
+
+#[derive(Debug)]
+struct WatchdogState {
+    last_anchor: Option<btc::TxId>,
+    since: Instant,
+    // Number of the whole budgets elapsed at the time of the latest alert.
+    alerted: u32,
+}
+
+/// Status of the anchoring watchdog.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchdogStatus {
+    /// Whether no new anchor has been confirmed within the time budget.
+    pub is_stalled: bool,
+    /// Number of seconds since the latest confirmed anchor or since the node start
+    /// if no anchor has been confirmed yet.
+    pub elapsed: u64,
+    /// Time budget in seconds, the anchoring is never reported as stalled without it.
+    pub budget: Option<u64>,
+    /// Id of the latest confirmed anchoring transaction seen by the node.
+    pub last_anchor: Option<btc::TxId>,
+}
+
+impl AnchoringWatchdog {
+    /// Creates the watchdog with the given time `budget` that starts counting from `now`.
+    pub fn new(budget: Option<Duration>, now: Instant) -> AnchoringWatchdog {
+        AnchoringWatchdog {
+            budget,
+            state: Arc::new(Mutex::new(WatchdogState {
+                last_anchor: None,
+                since: now,
+                alerted: 0,
+            })),
+        }
+    }
+
+    /// Remembers that the anchoring transaction with the given `txid` is confirmed.
+    /// The elapsed time is reset only by the anchor that differs from the latest one.
+    pub fn anchor_confirmed(&self, txid: btc::TxId, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        if state.last_anchor == Some(txid) {
+            return;
+        }
+        if state.alerted > 0 {
+            info!("Anchoring is resumed by the confirmed anchor txid={}", txid);
+        }
+        state.last_anchor = Some(txid);
+        state.since = now;
+        state.alerted = 0;
+    }
+
+    /// Returns the status of the watchdog at the given moment.
+    pub fn status(&self, now: Instant) -> WatchdogStatus {
+        let state = self.state.lock().unwrap();
+        let elapsed = now.duration_since(state.since);
+        WatchdogStatus {
+            is_stalled: self.budget.map_or(false, |budget| elapsed > budget),
+            elapsed: elapsed.as_secs(),
+            budget: self.budget.map(|budget| budget.as_secs()),
+            last_anchor: state.last_anchor,
+        }
+    }
+
+    /// Logs the stalled anchoring once per every elapsed budget, the first alert
+    /// is a warning and the following ones are errors.
+    pub fn check(&self, now: Instant) {
+        let budget = match self.budget {
+            Some(ref budget) if budget.as_secs() > 0 => budget.as_secs(),
+            _ => return,
+        };
+        let mut state = self.state.lock().unwrap();
+        let elapsed = now.duration_since(state.since).as_secs();
+        let budgets = (elapsed / budget) as u32;
+        if budgets <= state.alerted {
+            return;
+        }
+        state.alerted = budgets;
+        if budgets == 1 {
+            warn!(
+                "No anchor has been confirmed for {} seconds, last_anchor={:?}",
+                elapsed, state.last_anchor
+            );
+        } else {
+            error!(
+                "Anchoring is stalled, no anchor has been confirmed for {} seconds, \
+                 last_anchor={:?}",
+                elapsed, state.last_anchor
+            );
+        }
+    }
+}
@@ -57,12 +57,14 @@
 
 #![deny(missing_docs, missing_debug_implementations)]
 
+extern crate base64;
 extern crate bitcoin;
 extern crate btc_transaction_utils;
 extern crate byteorder;
 #[macro_use]
 extern crate display_derive;
 extern crate exonum_bitcoinrpc as bitcoinrpc;
+extern crate exonum_jsonrpc as jsonrpc;
 extern crate failure;
 #[macro_use]
 extern crate failure_derive;
@@ -70,11 +72,14 @@ extern crate failure_derive;
 extern crate log;
 extern crate secp256k1;
 extern crate serde;
+extern crate serde_cbor;
 #[macro_use]
 extern crate serde_derive;
 #[macro_use]
 extern crate serde_json;
 extern crate toml;
+extern crate ws;
+extern crate zmq;
 
 #[macro_use]
 extern crate exonum;
@@ -100,8 +105,9 @@ pub mod local_storage;
 pub mod observer;
 #[doc(hidden)]
 pub mod service;
+pub mod zmq_listener;
 
-pub use blockchain::consensus_storage::AnchoringConfig;
+pub use blockchain::consensus_storage::{AnchoredTable, AnchoringConfig, FeeStrategyConfig};
 pub use cmd::AnchoringServiceFactory as ServiceFactory;
 pub use details::btc::{gen_btc_keypair, gen_btc_keypair_with_rng, Network as BitcoinNetwork};
 pub use details::rpc::{AnchoringRpcConfig, BitcoinRelay, RpcClient};
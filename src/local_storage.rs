@@ -17,23 +17,149 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::BTreeMap;
 use std::default::Default;
 
+use api::subscriptions::WebSocketConfig;
+use api::RateLimitConfig;
 use details::btc;
-use details::rpc::AnchoringRpcConfig;
-use observer::AnchoringObserverConfig;
+use details::electrum::ElectrumConfig;
+use details::error::AddrError;
+use details::hwi::HwiConfig;
+use details::rpc::{AnchoringRpcConfig, RpcConnectionConfig, RpcRetryConfig};
+use observer::{AnchoringObserverConfig, Milliseconds};
+use zmq_listener::ZmqListenerConfig;
 
 /// Private part of anchoring service configuration stored on a local machine.
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct AnchoringNodeConfig {
-    /// Rpc configuration. Must exist if node is validator.
+    /// Rpc configuration. Must exist if node is validator and the `electrum` is not set.
     /// Otherwise node can only check `lect` payload without any checks with `bitcoind`.
     pub rpc: Option<AnchoringRpcConfig>,
+    /// Retries of the failed rpc calls.
+    #[serde(default)]
+    pub rpc_retry: RpcRetryConfig,
+    /// Timeouts and the connection pool of the rpc client.
+    #[serde(default)]
+    pub rpc_connection: RpcConnectionConfig,
+    /// Electrum server that is used instead of the `bitcoind` if the value is present.
+    /// The connection timeouts are taken from `rpc_connection`.
+    #[serde(default)]
+    pub electrum: Option<ElectrumConfig>,
     #[serde(serialize_with = "serialize_map_to_vec", deserialize_with = "deserialize_vec_to_map")]
     /// Set of private keys for each anchoring address.
     pub private_keys: BTreeMap<String, btc::PrivateKey>,
+    /// Backend that signs the anchoring transactions, the `private_keys` are used
+    /// by default.
+    #[serde(default)]
+    pub signer: SignerConfig,
     /// Frequency of lect check in blocks.
     pub check_lect_frequency: u64,
+    /// Number of confirmations after which the transaction observed in the bitcoin network
+    /// is accepted as the new lect. Transactions finalized by the node itself are accepted
+    /// immediately.
+    #[serde(default = "default_required_confirmations")]
+    pub required_confirmations: u64,
+    /// Minimal anchoring frequency that the node accepts. The node refuses to anchor
+    /// with the configuration of a lower frequency.
+    #[serde(default = "default_min_anchoring_frequency")]
+    pub min_anchoring_frequency: u64,
     /// Anchoring observer config.
     pub observer: AnchoringObserverConfig,
+    /// If this option is enabled, the node never signs the anchoring transactions and only
+    /// follows the anchoring chain by the observer, which is launched regardless of the
+    /// `observer.enabled` value. The private keys are not required in this mode.
+    #[serde(default)]
+    pub observer_only: bool,
+    /// If this option is enabled, the node does not check at startup that the initial
+    /// funding transaction has at least `required_confirmations`. The check is useful
+    /// for the validators, but it may be skipped by the offline and observer nodes.
+    #[serde(default)]
+    pub skip_funding_tx_check: bool,
+    /// Origins that are allowed to make cross-origin requests to the public API.
+    #[serde(default)]
+    pub cors_allowed_origins: Vec<String>,
+    /// Rate limits of the public API routes, the requests are not limited by default.
+    #[serde(default)]
+    pub api_rate_limits: RateLimitConfig,
+    /// Average time between the Exonum blocks used by the public API
+    /// to estimate the time until the next anchoring.
+    #[serde(default = "default_average_block_time")]
+    pub average_block_time: Milliseconds,
+    /// Maximal number of blocks since the latest anchored block after which
+    /// the health check reports that the service is down. The doubled anchoring
+    /// frequency is used if the value is absent.
+    #[serde(default)]
+    pub max_anchoring_lag: Option<u64>,
+    /// Maximal number of seconds since the latest confirmed anchor after which
+    /// the anchoring is reported as stalled. The watchdog is disabled if the value is absent.
+    #[serde(default)]
+    pub anchor_stall_budget: Option<u64>,
+    /// Number of blocks after which the unconfirmed lect is sent to the `bitcoind` again
+    /// in case it has been dropped from the mempool. Rebroadcast is disabled
+    /// if the value is absent.
+    #[serde(default)]
+    pub rebroadcast_interval: Option<u64>,
+    /// WebSocket server that pushes the committed lects.
+    /// The server is not launched if the value is absent.
+    #[serde(default)]
+    pub websocket: Option<WebSocketConfig>,
+    /// Listener of the `bitcoind` ZMQ notifications.
+    /// The lect is checked only every `check_lect_frequency` blocks if the value is absent.
+    #[serde(default)]
+    pub zmq: Option<ZmqListenerConfig>,
+    /// Automatic top-up of the anchoring address from the wallet of the `bitcoind` node.
+    /// The top-up is disabled if the value is absent.
+    #[serde(default)]
+    pub funding_top_up: Option<FundingTopUpConfig>,
+}
+
+/// Automatic top-up of the anchoring address.
+///
+/// If the spendable balance of the actual anchoring address falls below `min_balance`,
+/// the node sends `amount` satoshis to it by the `sendtoaddress` call, so the wallet
+/// of the `bitcoind` node should have enough funds. The resulting funding transaction
+/// is recorded in the blockchain and is spent by the anchoring transactions along with
+/// the funding transactions of the anchoring configuration.
+///
+/// The validators take turns to check the balance, one per `check_lect_frequency` blocks,
+/// so the address is topped up by a single validator at a time. The spending limit
+/// counts the top-ups of all validators committed to the blockchain within the `period`.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FundingTopUpConfig {
+    /// Spendable balance of the anchoring address in satoshis below which the top-up is made.
+    pub min_balance: u64,
+    /// Value of the top-up funding transaction in satoshis.
+    pub amount: u64,
+    /// Maximal total value of the top-ups within the `period` in satoshis.
+    pub max_spend_per_period: u64,
+    /// Length of the spending period in blocks.
+    pub period: u64,
+}
+
+/// Backend that signs the inputs of the anchoring transactions.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum SignerConfig {
+    /// Private keys from the `private_keys` of the node configuration file.
+    Local,
+    /// External signer that keeps the private keys itself and is requested
+    /// by the JSON-RPC over HTTP. The connection timeouts are taken from `rpc_connection`.
+    External {
+        /// Url of the signer, e.g. `http://127.0.0.1:8500`.
+        url: String,
+        /// Username of the HTTP basic authentication.
+        #[serde(default)]
+        username: Option<String>,
+        /// Password of the HTTP basic authentication.
+        #[serde(default)]
+        password: Option<String>,
+    },
+    /// Hardware wallet that is accessed by the `hwi` command line tool.
+    Hwi(HwiConfig),
+}
+
+impl Default for SignerConfig {
+    fn default() -> SignerConfig {
+        SignerConfig::Local
+    }
 }
 
 impl AnchoringNodeConfig {
@@ -44,19 +170,56 @@ impl AnchoringNodeConfig {
             ..Default::default()
         }
     }
+
+    /// Checks that the addresses of the private keys belong to the given `network`.
+    pub fn check_network(&self, network: btc::Network) -> Result<(), AddrError> {
+        for address in self.private_keys.keys() {
+            btc::Address::from_str_checked(address, network)?;
+        }
+        Ok(())
+    }
 }
 
 impl Default for AnchoringNodeConfig {
     fn default() -> AnchoringNodeConfig {
         AnchoringNodeConfig {
             rpc: None,
+            rpc_retry: RpcRetryConfig::default(),
+            rpc_connection: RpcConnectionConfig::default(),
+            electrum: None,
             observer: AnchoringObserverConfig::default(),
+            observer_only: false,
+            skip_funding_tx_check: false,
             private_keys: BTreeMap::new(),
+            signer: SignerConfig::default(),
             check_lect_frequency: 30,
+            required_confirmations: default_required_confirmations(),
+            min_anchoring_frequency: default_min_anchoring_frequency(),
+            cors_allowed_origins: Vec::new(),
+            api_rate_limits: RateLimitConfig::default(),
+            average_block_time: default_average_block_time(),
+            max_anchoring_lag: None,
+            anchor_stall_budget: None,
+            rebroadcast_interval: None,
+            websocket: None,
+            zmq: None,
+            funding_top_up: None,
         }
     }
 }
 
+fn default_required_confirmations() -> u64 {
+    6
+}
+
+fn default_min_anchoring_frequency() -> u64 {
+    10
+}
+
+fn default_average_block_time() -> Milliseconds {
+    1000
+}
+
 /// The structure for storing the anchoring address and private key. The structure is needed to
 /// convert data from the toml-file into memory.
 #[derive(Deserialize, Serialize)]
@@ -22,8 +22,10 @@ use exonum::storage::Fork;
 
 use blockchain::consensus_storage::AnchoringConfig;
 use blockchain::schema::AnchoringSchema;
+use details::btc;
 use details::btc::transactions::{AnchoringTx, BitcoinTx, TxKind};
-use details::rpc::{AnchoringRpcConfig, BitcoinRelay, RpcClient};
+use details::rpc::{AnchoringRpcConfig, BitcoinRelay, PooledRpcClient, RetryingRpc,
+                   RpcConnectionConfig, RpcRetryConfig};
 use error::Error as ServiceError;
 
 /// Type alias for milliseconds.
@@ -31,6 +33,10 @@ pub type Milliseconds = u64;
 /// Type alias for block height.
 pub type Height = u64;
 
+/// Default maximum depth of the bitcoin blockchain reorganization that the observer
+/// rolls back by itself.
+pub const DEFAULT_MAX_REORG_DEPTH: u64 = 100;
+
 /// Anchoring observer configuration.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct AnchoringObserverConfig {
@@ -38,6 +44,10 @@ pub struct AnchoringObserverConfig {
     pub check_interval: Milliseconds,
     /// If this option enabled observer thread will launch with in the public API handler.
     pub enabled: bool,
+    /// Maximum depth of the bitcoin blockchain reorganization in blocks that the observer
+    /// rolls back by itself. The deeper reorganizations require the manual intervention.
+    #[serde(default = "default_max_reorg_depth")]
+    pub max_reorg_depth: u64,
 }
 
 impl Default for AnchoringObserverConfig {
@@ -45,6 +55,7 @@ impl Default for AnchoringObserverConfig {
         AnchoringObserverConfig {
             check_interval: 10_000,
             enabled: false,
+            max_reorg_depth: DEFAULT_MAX_REORG_DEPTH,
         }
     }
 }
@@ -56,19 +67,27 @@ pub struct AnchoringChainObserver {
     blockchain: Blockchain,
     client: Box<BitcoinRelay>,
     check_interval: Milliseconds,
+    max_reorg_depth: u64,
 }
 
 impl AnchoringChainObserver {
-    /// Constructs observer for the given `blockchain`.
+    /// Constructs observer for the given `blockchain`, the `rpc` node belongs to
+    /// the given `network`.
     pub fn new(
         blockchain: Blockchain,
         rpc: AnchoringRpcConfig,
+        network: btc::Network,
+        connection: RpcConnectionConfig,
         observer: &AnchoringObserverConfig,
     ) -> AnchoringChainObserver {
         AnchoringChainObserver {
             blockchain,
-            client: Box::new(RpcClient::from(rpc)),
+            client: Box::new(RetryingRpc::new(
+                PooledRpcClient::new(rpc, network, connection),
+                RpcRetryConfig::default(),
+            )),
             check_interval: observer.check_interval,
+            max_reorg_depth: observer.max_reorg_depth,
         }
     }
 
@@ -82,9 +101,17 @@ impl AnchoringChainObserver {
             blockchain,
             client,
             check_interval,
+            max_reorg_depth: DEFAULT_MAX_REORG_DEPTH,
         }
     }
 
+    /// Sets the maximum depth of the bitcoin blockchain reorganization that the observer
+    /// rolls back by itself.
+    pub fn with_max_reorg_depth(mut self, max_reorg_depth: u64) -> AnchoringChainObserver {
+        self.max_reorg_depth = max_reorg_depth;
+        self
+    }
+
     /// Runs observer in infinity loop.
     pub fn run(&mut self) -> Result<(), ServiceError> {
         info!(
@@ -105,23 +132,35 @@ impl AnchoringChainObserver {
 
     /// Tries to get `lect` for the current anchoring configuration and retrospectively adds
     /// all previously unknown anchoring transactions.
+    ///
+    /// The transactions whose bitcoin blocks have been orphaned by a reorganization
+    /// are removed from the chain beforehand, so they are added again only once
+    /// they are confirmed in the best chain. If the reorganization is deeper than
+    /// `max_reorg_depth`, the observer neither removes nor adds transactions and marks
+    /// that the manual intervention is required until the reorganization is resolved,
+    /// see `AnchoringSchema::observer_reorg_depth`.
+    ///
+    /// The heights of the bitcoin blocks that contain the added transactions are derived
+    /// from the number of their confirmations and the height of the bitcoin blockchain.
     pub fn check_anchoring_chain(&mut self) -> Result<(), ServiceError> {
         let mut fork = self.blockchain.fork();
         if !self.is_blockchain_inited(&fork) {
             return Ok(());
         }
 
-        let cfg = AnchoringSchema::new(&fork).actual_anchoring_config();
-        if let Some(lect) = self.find_lect(&fork, &cfg)? {
-            if !self.lect_payload_is_correct(&fork, &lect) {
-                error!("Received lect with incorrect payload, content={:#?}", lect);
-                return Ok(());
+        self.fill_anchoring_tx_chain_heights(&mut fork);
+        if self.revert_orphaned_txs(&mut fork)? {
+            let cfg = AnchoringSchema::new(&fork).actual_anchoring_config();
+            if let Some(lect) = self.find_lect(&fork, &cfg)? {
+                if self.lect_payload_is_correct(&fork, &lect) {
+                    self.update_anchoring_chain(&mut fork, &cfg, lect)?;
+                } else {
+                    error!("Received lect with incorrect payload, content={:#?}", lect);
+                }
             }
-
-            self.update_anchoring_chain(&mut fork, &cfg, lect)?;
-            let patch = fork.into_patch();
-            self.blockchain.merge(patch).unwrap(); // FIXME remove unwrap.
         }
+        let patch = fork.into_patch();
+        self.blockchain.merge(patch).unwrap(); // FIXME remove unwrap.
         Ok(())
     }
 
@@ -141,7 +180,9 @@ impl AnchoringChainObserver {
         actual_cfg: &AnchoringConfig,
         mut lect: AnchoringTx,
     ) -> Result<(), ServiceError> {
+        let bitcoin_height = self.client.get_block_count()?;
         let mut anchoring_schema = AnchoringSchema::new(fork);
+        let mut added_heights = Vec::new();
 
         loop {
             let payload = lect.payload();
@@ -151,36 +192,152 @@ impl AnchoringChainObserver {
             // checking chain.
             if let Some(other_lect) = anchoring_schema.anchoring_tx_chain().get(&height) {
                 if other_lect == lect {
-                    return Ok(());
+                    break;
                 }
             }
 
-            let confirmations = self.client.get_transaction_confirmations(lect.id())?;
-            if confirmations.as_ref() >= Some(&actual_cfg.utxo_confirmations) {
+            let prev_txid = payload.prev_tx_chain.unwrap_or_else(|| lect.prev_hash());
+            let (info, prev_tx) = self.client
+                .get_transaction_info_with_prev(lect.id(), prev_txid)?;
+            let confirmations = info.as_ref().and_then(|info| info.confirmations);
+            if confirmations >= Some(actual_cfg.utxo_confirmations) {
                 trace!(
                     "Adds transaction to chain, height={}, content={:#?}",
                     payload.block_height,
                     lect
                 );
 
+                if !anchoring_schema.anchoring_tx_chain().contains(&height) {
+                    added_heights.push(height);
+                }
                 anchoring_schema
                     .anchoring_tx_chain_mut()
                     .put(&height, lect.clone());
+                match info.as_ref().and_then(|info| info.block_time) {
+                    Some(block_time) => anchoring_schema
+                        .anchoring_tx_btc_times_mut()
+                        .put(&height, block_time),
+                    None => anchoring_schema.anchoring_tx_btc_times_mut().remove(&height),
+                }
+                match info.and_then(|info| info.block_hash) {
+                    Some(block_hash) => anchoring_schema
+                        .anchoring_tx_block_hashes_mut()
+                        .put(&height, block_hash),
+                    None => anchoring_schema
+                        .anchoring_tx_block_hashes_mut()
+                        .remove(&height),
+                }
+                let btc_height = match confirmations {
+                    Some(confirmations) if confirmations > 0 => {
+                        Some((bitcoin_height + 1).saturating_sub(confirmations))
+                    }
+                    _ => None,
+                };
+                anchoring_schema.set_anchoring_tx_btc_height(height, btc_height);
             }
 
-            let prev_txid = payload.prev_tx_chain.unwrap_or_else(|| lect.prev_hash());
-            if let Some(prev_tx) = self.client.get_transaction(prev_txid)? {
-                lect = match TxKind::from(prev_tx) {
+            if let Some(prev_tx) = prev_tx {
+                lect = match actual_cfg.tx_kind(prev_tx) {
                     TxKind::Anchoring(lect) => lect,
-                    TxKind::FundingTx(_) => return Ok(()),
+                    TxKind::FundingTx(_) => break,
                     TxKind::Other(tx) => {
                         panic!("Found incorrect lect transaction, content={:#?}", tx)
                     }
                 }
             } else {
-                return Ok(());
+                break;
             }
         }
+
+        // The chain is walked from the latest transaction, while the heights are kept
+        // in the ascending order.
+        anchoring_schema
+            .anchoring_tx_chain_heights_mut()
+            .extend(added_heights.into_iter().rev());
+        Ok(())
+    }
+
+    // The heights of the chain written before they were tracked are filled once.
+    fn fill_anchoring_tx_chain_heights(&self, fork: &mut Fork) {
+        let mut anchoring_schema = AnchoringSchema::new(fork);
+        if anchoring_schema.anchoring_tx_chain_heights().is_empty() {
+            let heights = anchoring_schema
+                .anchoring_tx_chain()
+                .keys()
+                .collect::<Vec<_>>();
+            anchoring_schema
+                .anchoring_tx_chain_heights_mut()
+                .extend(heights);
+        }
+    }
+
+    /// Removes the latest transactions from the anchoring chain if the bitcoin blocks
+    /// that contain them are no longer in the best chain.
+    ///
+    /// The check stops at the first transaction whose block is still in the best chain
+    /// or is unknown, i.e. the transaction was added before the block hashes were tracked.
+    ///
+    /// Returns `false` if the reorganization is deeper than `max_reorg_depth`, in this case
+    /// nothing is removed. The depth is estimated by the number of blocks in the best chain
+    /// since the height of the orphaned block.
+    fn revert_orphaned_txs(&self, fork: &mut Fork) -> Result<bool, ServiceError> {
+        let mut anchoring_schema = AnchoringSchema::new(fork);
+        let chain_len = anchoring_schema.anchoring_tx_chain_heights().len();
+        let mut orphaned = Vec::new();
+        let mut bitcoin_height = None;
+        for index in (0..chain_len).rev() {
+            let height = anchoring_schema
+                .anchoring_tx_chain_heights()
+                .get(index)
+                .unwrap();
+            let block_hash = match anchoring_schema.anchoring_tx_block_hashes().get(&height) {
+                Some(block_hash) => block_hash,
+                None => break,
+            };
+            if self.client.is_block_in_best_chain(block_hash)? {
+                break;
+            }
+            orphaned.push((height, block_hash));
+
+            let btc_height = match anchoring_schema.anchoring_tx_btc_heights().get(&height) {
+                Some(btc_height) => btc_height,
+                None => continue,
+            };
+            let bitcoin_height = match bitcoin_height {
+                Some(bitcoin_height) => bitcoin_height,
+                None => {
+                    let count = self.client.get_block_count()?;
+                    bitcoin_height = Some(count);
+                    count
+                }
+            };
+            let depth = (bitcoin_height + 1).saturating_sub(btc_height);
+            if depth > self.max_reorg_depth {
+                error!(
+                    "Bitcoin block {} at height={} is orphaned by the reorganization of depth={} \
+                     that exceeds the limit={}, manual intervention is required",
+                    block_hash, btc_height, depth, self.max_reorg_depth
+                );
+                anchoring_schema.observer_reorg_depth_mut().set(depth);
+                return Ok(false);
+            }
+        }
+        anchoring_schema.observer_reorg_depth_mut().remove();
+
+        for (height, block_hash) in orphaned {
+            warn!(
+                "Bitcoin block {} is orphaned, removes anchoring transaction for height={}",
+                block_hash, height
+            );
+            anchoring_schema.anchoring_tx_chain_mut().remove(&height);
+            anchoring_schema
+                .anchoring_tx_block_hashes_mut()
+                .remove(&height);
+            anchoring_schema.set_anchoring_tx_btc_height(height, None);
+            anchoring_schema.anchoring_tx_btc_times_mut().remove(&height);
+            anchoring_schema.anchoring_tx_chain_heights_mut().pop();
+        }
+        Ok(true)
     }
 
     fn find_lect(
@@ -195,7 +352,7 @@ impl AnchoringChainObserver {
         let unspent_txs: Vec<_> = self.client.unspent_transactions(&actual_addr)?;
         for tx in unspent_txs {
             if self.transaction_is_lect(fork, actual_cfg, &tx.body)? {
-                if let TxKind::Anchoring(lect) = TxKind::from(tx.body) {
+                if let TxKind::Anchoring(lect) = actual_cfg.tx_kind(tx.body) {
                     return Ok(Some(lect));
                 }
             }
@@ -234,3 +391,7 @@ impl AnchoringChainObserver {
         len > 0
     }
 }
+
+fn default_max_reorg_depth() -> u64 {
+    DEFAULT_MAX_REORG_DEPTH
+}
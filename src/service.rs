@@ -17,31 +17,33 @@ use std::sync::{Arc, Mutex};
 use std::thread;
 
 use iron::prelude::IronResult;
-use iron::{Handler, Request, Response};
+use iron::{Chain, Handler, Request, Response};
 use rand::{thread_rng, Rng};
-use router::Router;
 use serde_json;
 use serde_json::value::Value;
 
-use exonum::api::Api;
 use exonum::blockchain::{ApiContext, Blockchain, Schema as CoreSchema, Service, ServiceContext,
                          Transaction};
 use exonum::crypto::Hash;
 use exonum::encoding::Error as StreamStructError;
+use exonum::helpers::Height;
 use exonum::messages::RawTransaction;
 use exonum::storage::{Fork, Snapshot};
 
-use api::PublicApi;
+use api::subscriptions::{LectsNotifier, LectsServer};
+use api::{PrivateApi, PublicApi};
 use blockchain::consensus_storage::AnchoringConfig;
 use blockchain::dto;
 use blockchain::schema::AnchoringSchema;
 use details::btc;
-use details::rpc::{BitcoinRelay, RpcClient};
+use details::electrum::ElectrumRelay;
+use details::rpc::{BitcoinRelay, PooledRpcClient, RetryingRpc, RpcRetryConfig};
 use error::Error as ServiceError;
 use handler::error::Error as HandlerError;
-use handler::AnchoringHandler;
+use handler::{AnchoringHandler, AnchoringWatchdog, TransitionProgress};
 use local_storage::AnchoringNodeConfig;
 use observer::AnchoringChainObserver;
+use zmq_listener::{ZmqListener, ZmqListenerHandle};
 
 /// Anchoring service id.
 pub const ANCHORING_SERVICE_ID: u16 = 3;
@@ -53,16 +55,15 @@ pub const ANCHORING_SERVICE_NAME: &str = "btc_anchoring";
 pub struct AnchoringService {
     genesis: AnchoringConfig,
     handler: Arc<Mutex<AnchoringHandler>>,
+    notifier: LectsNotifier,
+    zmq_listener: Option<ZmqListenerHandle>,
 }
 
 impl AnchoringService {
     /// Creates a new service instance with the given `consensus` and `local` configurations.
     pub fn new(consensus: AnchoringConfig, local: AnchoringNodeConfig) -> AnchoringService {
-        let client = local.rpc.clone().map(RpcClient::from).map(Into::into);
-        AnchoringService {
-            genesis: consensus,
-            handler: Arc::new(Mutex::new(AnchoringHandler::new(client, local))),
-        }
+        let client = bitcoin_relay(&local, consensus.network, Some(local.rpc_retry));
+        AnchoringService::with_handler(consensus, AnchoringHandler::new(client, local))
     }
 
     #[doc(hidden)]
@@ -71,9 +72,33 @@ impl AnchoringService {
         genesis: AnchoringConfig,
         local_cfg: AnchoringNodeConfig,
     ) -> AnchoringService {
+        AnchoringService::with_handler(genesis, AnchoringHandler::new(Some(client), local_cfg))
+    }
+
+    fn with_handler(genesis: AnchoringConfig, handler: AnchoringHandler) -> AnchoringService {
+        // The estimated fee rate is agreed by the validators after the start,
+        // so the funding is checked against the largest fee it may result in.
+        if let Err(e) = genesis.check_funding_amount(genesis.max_anchoring_fee()) {
+            panic!("Anchoring configuration is rejected by the node: {}", e);
+        }
+        if let Err(e) = handler.check_funding_tx_confirmations(&genesis) {
+            panic!("Funding transaction is rejected by the node: {}", e);
+        }
+        // Notifications are useful only for the validators that have the `bitcoind` client.
+        // The payload prefix is kept by the following configurations.
+        let zmq_listener = match (handler.node.zmq.clone(), handler.client.is_some()) {
+            (Some(config), true) => Some(ZmqListener::spawn(
+                config,
+                genesis.payload_prefix.as_bytes(),
+                handler.bitcoind_events.clone(),
+            )),
+            _ => None,
+        };
         AnchoringService {
             genesis,
-            handler: Arc::new(Mutex::new(AnchoringHandler::new(Some(client), local_cfg))),
+            handler: Arc::new(Mutex::new(handler)),
+            notifier: LectsNotifier::new(),
+            zmq_listener,
         }
     }
 
@@ -81,6 +106,11 @@ impl AnchoringService {
     pub fn handler(&self) -> Arc<Mutex<AnchoringHandler>> {
         Arc::clone(&self.handler)
     }
+
+    /// Returns the notifier of the WebSocket subscribers.
+    pub fn notifier(&self) -> LectsNotifier {
+        self.notifier.clone()
+    }
 }
 
 impl Service for AnchoringService {
@@ -118,14 +148,25 @@ impl Service for AnchoringService {
             .block_hashes_by_height()
             .last()
             .expect("An attempt to invoke execute during the genesis block initialization.");
-        AnchoringSchema::new(fork)
-            .anchored_blocks_mut()
-            .push(block_header_hash)
+        let height = Height(CoreSchema::new(&fork).block_hashes_by_height().len());
+        let mut schema = AnchoringSchema::new(fork);
+        schema.anchored_blocks_mut().push(block_header_hash);
+        schema.add_anchored_table_root(height.previous());
+        // Keeps the funding transactions that are added by the configuration changes.
+        let cfg = schema.actual_anchoring_config();
+        schema.add_funding_txs(&cfg, height);
+        schema.prune_anchored_blocks(&cfg);
     }
 
     fn after_commit(&self, state: &ServiceContext) {
+        self.notifier.notify(state.snapshot());
+
         let mut handler = self.handler.lock().unwrap();
-        match handler.after_commit(state) {
+        let result = handler.after_commit(state);
+        for event in handler.transition_events.drain(..) {
+            self.notifier.notify_transition(&event);
+        }
+        match result {
             Err(ServiceError::Handler(e @ HandlerError::IncorrectLect { .. })) => {
                 panic!("A critical error occurred: {}", e)
             }
@@ -149,9 +190,27 @@ impl Service for AnchoringService {
     /// See [`PublicApi`](api/struct.PublicApi.html) for details.
     fn public_api_handler(&self, context: &ApiContext) -> Option<Box<Handler>> {
         let handler = self.handler.lock().unwrap();
-        let router = PublicApiHandler::new(context.blockchain(), &handler.node);
+        let router = PublicApiHandler::new(
+            context.blockchain(),
+            &handler.node,
+            self.genesis.network,
+            &handler.watchdog,
+            &handler.transition_progress,
+            &self.notifier,
+        );
         Some(Box::new(router))
     }
+
+    /// Private API implementation.
+    /// See [`PrivateApi`](api/struct.PrivateApi.html) for details.
+    fn private_api_handler(&self, context: &ApiContext) -> Option<Box<Handler>> {
+        let api = PrivateApi::new(context.blockchain().clone()).with_node_channel(
+            context.node_channel().clone(),
+            *context.public_key(),
+            context.secret_key().clone(),
+        );
+        Some(Box::new(api.handler()))
+    }
 }
 
 /// Generates testnet configuration by given rpc for given nodes amount
@@ -215,26 +274,76 @@ pub fn gen_anchoring_testnet_config(
     gen_anchoring_testnet_config_with_rng(client, network, count, total_funds, &mut rng)
 }
 
-/// Helper class that combines `Router` for public api with the observer thread.
+/// Creates the relay to the given bitcoin `network` from the node `config`, the Electrum
+/// server is preferred to the `bitcoind` if both are set. The failed calls of the relay
+/// are repeated according to the `retry` config if the value is present.
+fn bitcoin_relay(
+    config: &AnchoringNodeConfig,
+    network: btc::Network,
+    retry: Option<RpcRetryConfig>,
+) -> Option<Box<BitcoinRelay>> {
+    if let Some(ref electrum) = config.electrum {
+        let relay = ElectrumRelay::new(electrum.clone(), network, config.rpc_connection);
+        Some(with_retries(relay, retry))
+    } else {
+        config.rpc.clone().map(|rpc| {
+            let relay = PooledRpcClient::new(rpc, network, config.rpc_connection);
+            with_retries(relay, retry)
+        })
+    }
+}
+
+fn with_retries<T: BitcoinRelay>(relay: T, retry: Option<RpcRetryConfig>) -> Box<BitcoinRelay> {
+    match retry {
+        Some(retry) => RetryingRpc::new(relay, retry).into(),
+        None => relay.into(),
+    }
+}
+
+/// Helper class that combines handler for public api with the observer thread.
 struct PublicApiHandler {
-    router: Router,
+    handler: Chain,
     observer: Option<thread::JoinHandle<()>>,
+    websocket: Option<(LectsNotifier, thread::JoinHandle<()>)>,
 }
 
 impl PublicApiHandler {
     /// Creates public api handler instance for the given `blockchain`
-    /// and anchoring node `config`.
-    pub fn new(blockchain: &Blockchain, config: &AnchoringNodeConfig) -> PublicApiHandler {
-        let mut router = Router::new();
-        let api = PublicApi {
-            blockchain: blockchain.clone(),
-        };
-        api.wire(&mut router);
+    /// and anchoring node `config`, the relay connects to the bitcoin node
+    /// of the given `network`.
+    pub fn new(
+        blockchain: &Blockchain,
+        config: &AnchoringNodeConfig,
+        network: btc::Network,
+        watchdog: &AnchoringWatchdog,
+        transition_progress: &TransitionProgress,
+        notifier: &LectsNotifier,
+    ) -> PublicApiHandler {
+        let mut api = PublicApi::with_cors(blockchain.clone(), config.cors_allowed_origins.clone())
+            .with_average_block_time(config.average_block_time)
+            .with_rate_limits(config.api_rate_limits.clone())
+            .with_watchdog(watchdog.clone())
+            .with_transition_progress(transition_progress.clone());
+        if let Some(max_anchoring_lag) = config.max_anchoring_lag {
+            api = api.with_max_anchoring_lag(max_anchoring_lag);
+        }
+        if let Some(relay) = bitcoin_relay(config, network, None) {
+            api = api.with_relay(relay);
+        }
+        let observer_enabled = config.observer.enabled || config.observer_only;
+        if observer_enabled {
+            api = api.with_observer();
+        }
+        let handler = api.handler();
 
-        let observer = if config.observer.enabled {
-            let rpc_cfg = config.rpc.clone().expect("Rpc config is not set");
-            let mut observer =
-                AnchoringChainObserver::new(blockchain.clone(), rpc_cfg, &config.observer);
+        let observer = if observer_enabled {
+            let relay = bitcoin_relay(config, network, Some(RpcRetryConfig::default()))
+                .expect("Neither rpc nor electrum config is set");
+            let mut observer = AnchoringChainObserver::new_with_client(
+                blockchain.clone(),
+                relay,
+                config.observer.check_interval,
+            ).with_max_reorg_depth(config.observer.max_reorg_depth);
 
             Some(thread::spawn(move || {
                 observer.run().unwrap();
@@ -243,18 +352,44 @@ impl PublicApiHandler {
             None
         };
 
-        PublicApiHandler { router, observer }
+        // The node keeps working without the subscriptions if the address is unavailable.
+        let websocket = config.websocket.as_ref().and_then(|ws_cfg| {
+            let server = match LectsServer::bind(notifier, ws_cfg) {
+                Ok(server) => server,
+                Err(e) => {
+                    error!(
+                        "Unable to bind WebSocket to {}, subscriptions are disabled: {}",
+                        ws_cfg.listen_address, e
+                    );
+                    return None;
+                }
+            };
+            let thread = thread::spawn(move || {
+                server.run().unwrap();
+            });
+            Some((notifier.clone(), thread))
+        });
+
+        PublicApiHandler {
+            handler,
+            observer,
+            websocket,
+        }
     }
 }
 
 impl Handler for PublicApiHandler {
     fn handle(&self, request: &mut Request) -> IronResult<Response> {
-        self.router.handle(request)
+        self.handler.handle(request)
     }
 }
 
 impl Drop for PublicApiHandler {
     fn drop(&mut self) {
+        if let Some((notifier, websocket_thread)) = self.websocket.take() {
+            notifier.shutdown();
+            websocket_thread.join().unwrap()
+        }
         if let Some(observer_thread) = self.observer.take() {
             observer_thread.join().unwrap()
         }
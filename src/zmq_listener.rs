@@ -0,0 +1,200 @@
+// Copyright 2017 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Listener of the `bitcoind` ZMQ notifications.
+//!
+//! The anchoring handler checks its lect every `check_lect_frequency` blocks. If the listener
+//! is enabled, the lect is also checked after the `bitcoind` reports a new block or
+//! an anchoring transaction in the mempool.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, sleep};
+use std::time::Duration;
+
+use bitcoin::network::serialize::deserialize;
+use zmq;
+
+use details::btc::transactions::{RawBitcoinTx, TxKind};
+use observer::Milliseconds;
+
+/// Topic of the `zmqpubhashblock` notifications.
+pub const HASHBLOCK_TOPIC: &str = "hashblock";
+/// Topic of the `zmqpubrawtx` notifications.
+pub const RAWTX_TOPIC: &str = "rawtx";
+
+/// Timeout of the socket reading after which the listener checks whether it has been stopped.
+const RECV_TIMEOUT: i32 = 100;
+
+/// Configuration of the `bitcoind` ZMQ notifications listener.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ZmqListenerConfig {
+    /// Addresses of the `zmqpubhashblock` and `zmqpubrawtx` sockets of the `bitcoind`,
+    /// for example `tcp://127.0.0.1:28332`.
+    pub endpoints: Vec<String>,
+    /// An interval between the reconnection attempts.
+    pub reconnect_interval: Milliseconds,
+}
+
+/// Flag that is raised when the `bitcoind` reports a new block or an anchoring transaction.
+#[derive(Debug, Clone, Default)]
+pub struct BitcoindEvents {
+    pending: Arc<AtomicBool>,
+}
+
+impl BitcoindEvents {
+    /// Creates the events flag that is not raised.
+    pub fn new() -> BitcoindEvents {
+        BitcoindEvents::default()
+    }
+
+    /// Raises the flag.
+    pub fn notify(&self) {
+        self.pending.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if the flag is raised.
+    pub fn is_pending(&self) -> bool {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    /// Returns `true` if the flag is raised and drops it.
+    pub fn take(&self) -> bool {
+        self.pending.swap(false, Ordering::SeqCst)
+    }
+}
+
+/// Listener of the `bitcoind` ZMQ notifications.
+#[derive(Debug)]
+pub struct ZmqListener {
+    config: ZmqListenerConfig,
+    payload_prefix: Vec<u8>,
+    events: BitcoindEvents,
+    stopped: Arc<AtomicBool>,
+}
+
+/// Handle of the listener running in a separate thread. The listener is stopped on drop.
+#[derive(Debug)]
+pub struct ZmqListenerHandle {
+    stopped: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl ZmqListener {
+    /// Creates listener that raises the given `events` flag. Only the anchoring transactions
+    /// whose payloads are tagged by the given `payload_prefix` raise the flag.
+    pub fn new(
+        config: ZmqListenerConfig,
+        payload_prefix: &[u8],
+        events: BitcoindEvents,
+    ) -> ZmqListener {
+        ZmqListener {
+            config,
+            payload_prefix: payload_prefix.to_vec(),
+            events,
+            stopped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Runs the listener in a separate thread.
+    pub fn spawn(
+        config: ZmqListenerConfig,
+        payload_prefix: &[u8],
+        events: BitcoindEvents,
+    ) -> ZmqListenerHandle {
+        let listener = ZmqListener::new(config, payload_prefix, events);
+        let stopped = Arc::clone(&listener.stopped);
+        let thread = thread::spawn(move || listener.run());
+        ZmqListenerHandle {
+            stopped,
+            thread: Some(thread),
+        }
+    }
+
+    /// Listens to the notifications until the listener is stopped.
+    ///
+    /// The socket is recreated after any error.
+    pub fn run(&self) {
+        info!(
+            "Launching bitcoind notifications listener, endpoints={:?}",
+            self.config.endpoints
+        );
+        let context = zmq::Context::new();
+        let interval = Duration::from_millis(self.config.reconnect_interval);
+        while !self.is_stopped() {
+            if let Err(e) = self.listen(&context) {
+                warn!("An error in the bitcoind notifications listener occurred: {}", e);
+                sleep(interval);
+            }
+        }
+    }
+
+    /// Handles the multipart notification message.
+    pub fn handle_notification(&self, parts: &[Vec<u8>]) {
+        if parts.len() < 2 {
+            warn!("Received malformed bitcoind notification");
+            return;
+        }
+        let (topic, body) = (&parts[0], &parts[1]);
+        if topic.as_slice() == HASHBLOCK_TOPIC.as_bytes() {
+            trace!("Received new bitcoin block notification");
+            self.events.notify();
+        } else if topic.as_slice() == RAWTX_TOPIC.as_bytes() {
+            let tx = match deserialize::<RawBitcoinTx>(body) {
+                Ok(tx) => tx,
+                Err(e) => {
+                    warn!("Received malformed bitcoin transaction: {}", e);
+                    return;
+                }
+            };
+            if let TxKind::Anchoring(tx) = TxKind::with_prefix(tx, &self.payload_prefix) {
+                trace!("Received anchoring transaction notification, txid={}", tx.id());
+                self.events.notify();
+            }
+        }
+    }
+
+    fn listen(&self, context: &zmq::Context) -> zmq::Result<()> {
+        let socket = context.socket(zmq::SUB)?;
+        socket.set_rcvtimeo(RECV_TIMEOUT)?;
+        socket.set_reconnect_ivl(self.config.reconnect_interval as i32)?;
+        socket.set_subscribe(HASHBLOCK_TOPIC.as_bytes())?;
+        socket.set_subscribe(RAWTX_TOPIC.as_bytes())?;
+        for endpoint in &self.config.endpoints {
+            socket.connect(endpoint)?;
+        }
+
+        while !self.is_stopped() {
+            match socket.recv_multipart(0) {
+                Ok(parts) => self.handle_notification(&parts),
+                Err(zmq::Error::EAGAIN) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for ZmqListenerHandle {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            thread.join().unwrap();
+        }
+    }
+}
@@ -30,24 +30,57 @@ extern crate secp256k1;
 extern crate serde;
 #[macro_use]
 extern crate serde_json;
+extern crate tempdir;
+extern crate zmq;
 
 #[macro_use]
 pub mod testkit_extras;
 
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::mem;
+use std::net::{TcpListener, TcpStream};
 use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use bitcoin::blockdata::script::Script;
+use bitcoin::util::hash::Sha256dHash;
+use tempdir::TempDir;
 
-use exonum::blockchain::Transaction;
+use exonum::blockchain::{Schema, Transaction, TransactionErrorType};
 use exonum::crypto::{CryptoHash, Hash};
-use exonum::encoding::serialize::FromHex;
+use exonum::encoding::serialize::{encode_hex, FromHex};
 use exonum::helpers::{Height, ValidatorId};
 
-use exonum_btc_anchoring::blockchain::dto::{MsgAnchoringSignature, MsgAnchoringUpdateLatest};
-use exonum_btc_anchoring::details::btc::transactions::{verify_tx_input, AnchoringTx, FundingTx,
-                                                       TransactionBuilder};
+use exonum_btc_anchoring::blockchain::{AnchoringSchema, Error as ValidateError};
+use exonum_btc_anchoring::blockchain::dto::{MsgAnchoringSignature, MsgAnchoringTopUp,
+                                            MsgAnchoringUpdateLatest};
+use exonum_btc_anchoring::details::btc;
+use exonum_btc_anchoring::details::btc::fee::estimate_vsize;
+use exonum_btc_anchoring::details::btc::payload::DEFAULT_PAYLOAD_PREFIX;
+use exonum_btc_anchoring::details::btc::psbt::Psbt;
+use exonum_btc_anchoring::details::btc::transactions::{verify_tx_input, AnchoringTx, BitcoinTx,
+                                                       FundingTx, RawBitcoinTx, TransactionBuilder};
+use exonum_btc_anchoring::details::electrum::{script_hash, ElectrumConfig, ElectrumRelay};
+use exonum_btc_anchoring::details::error::ConfigError;
+use exonum_btc_anchoring::details::hwi::{HwiConfig, HwiSigner};
+use exonum_btc_anchoring::details::rpc::{AnchoringRpcConfig, BitcoinRelay, PooledRpcClient,
+                                         RetryingRpc, RpcConnectionConfig, RpcRetryConfig,
+                                         RPC_IN_WARMUP, SATOSHI_DIVISOR};
+use exonum_btc_anchoring::details::signer::{ExternalSigner, LocalSigner, SignerBackend};
+use exonum_btc_anchoring::error::{HandlerError, SignerError};
+use exonum_btc_anchoring::handler::{AnchoringWatchdog, SigningStatus};
+use exonum_btc_anchoring::local_storage::FundingTopUpConfig;
+use exonum_btc_anchoring::zmq_listener::{BitcoindEvents, ZmqListener, ZmqListenerConfig,
+                                         HASHBLOCK_TOPIC};
+use exonum_btc_anchoring::{gen_btc_keypair, AnchoredTable, AnchoringService, BitcoinNetwork,
+                           FeeStrategyConfig, ANCHORING_SERVICE_ID};
 use testkit_extras::helpers::*;
-use testkit_extras::AnchoringTestKit;
+use testkit_extras::{gen_sandbox_anchoring_config, AnchoringTestKit, TestClient, TestRequests,
+                     CHECK_LECT_FREQUENCY};
 
 // We anchor first block
 // problems: None
@@ -55,7 +88,460 @@ use testkit_extras::AnchoringTestKit;
 #[test]
 fn test_anchoring_first_block_simple() {
     let mut testkit = AnchoringTestKit::default();
+    assert_eq!(testkit.handler().signing_status(), SigningStatus::Unknown);
     anchor_first_block(&mut testkit);
+    assert_eq!(testkit.handler().signing_status(), SigningStatus::CanSign);
+}
+
+// Signing backend that signs by the given private keys and records the requests.
+#[derive(Debug)]
+struct RecordingSigner {
+    private_keys: BTreeMap<String, btc::PrivateKey>,
+    requests: Arc<Mutex<Vec<(Sha256dHash, btc::Address)>>>,
+}
+
+impl SignerBackend for RecordingSigner {
+    fn sign(&self, sighash: &Sha256dHash, address: &btc::Address) -> Result<Vec<u8>, SignerError> {
+        self.requests
+            .lock()
+            .unwrap()
+            .push((*sighash, address.clone()));
+        LocalSigner::new(&self.private_keys).sign(sighash, address)
+    }
+
+    fn public_key(&self, address: &btc::Address) -> Result<Option<btc::PublicKey>, SignerError> {
+        LocalSigner::new(&self.private_keys).public_key(address)
+    }
+}
+
+// We anchor first block by the external signer
+// problems: The node configuration has no private keys
+// result: success, the signer is requested to sign the sighash of the proposal input
+#[test]
+fn test_anchoring_first_block_external_signer() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_addr = testkit.current_addr();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    {
+        let mut handler = testkit.handler();
+        let private_keys = mem::replace(&mut handler.node.private_keys, BTreeMap::new());
+        handler.set_signer(Box::new(RecordingSigner {
+            private_keys,
+            requests: requests.clone(),
+        }));
+    }
+
+    anchor_first_block(&mut testkit);
+    assert_eq!(testkit.handler().signing_status(), SigningStatus::CanSign);
+
+    let anchored_tx = testkit.latest_anchored_tx();
+    let sighash = anchored_tx.signature_hash(
+        &testkit.current_redeem_script(),
+        0,
+        &testkit.current_funding_tx(),
+        btc::SighashType::All,
+    );
+    assert_eq!(*requests.lock().unwrap(), vec![(sighash, anchoring_addr)]);
+}
+
+// The validator signs the anchoring transaction by the hardware wallet
+// problems: None
+// result: the signature of the wallet is accepted and the transaction is sent
+#[cfg(unix)]
+#[test]
+fn test_anchoring_first_block_hwi_signer() {
+    let mut testkit = AnchoringTestKit::default();
+    let requests = testkit.requests();
+    let anchoring_addr = testkit.current_addr();
+    let dir = TempDir::new("hwi").unwrap();
+    let args_path = dir.path().join("args");
+    let response_path = dir.path().join("response");
+    let script_path = dir.path().join("hwi.sh");
+    write!(
+        File::create(&script_path).unwrap(),
+        "echo \"$@\" > {}\ncat {}\n",
+        args_path.display(),
+        response_path.display()
+    ).unwrap();
+
+    let priv_key = {
+        let mut handler = testkit.handler();
+        let private_keys = mem::replace(&mut handler.node.private_keys, BTreeMap::new());
+        let priv_key = private_keys[&anchoring_addr.to_string()].clone();
+        let config = HwiConfig {
+            command: "sh".to_owned(),
+            fingerprint: "8038ecd5".to_owned(),
+            derivation_path: "m/48'/1'/0'/2'/0/0".to_owned(),
+            public_key: priv_key.public_key(),
+            args: vec![script_path.to_str().unwrap().to_owned()],
+        };
+        handler.set_signer(Box::new(HwiSigner::new(config).unwrap()));
+        priv_key
+    };
+
+    requests.expect(vec![
+        confirmations_request(&testkit.current_funding_tx(), 50),
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&testkit.current_funding_tx(), &anchoring_addr, 50)
+            ]
+        },
+        get_transaction_request(&testkit.current_funding_tx()),
+    ]);
+
+    let hash = testkit.last_block_hash();
+    let (proposal, signatures) =
+        testkit.gen_anchoring_tx_with_signatures(Height::zero(), hash, &[], None, &anchoring_addr);
+    let anchored_tx = testkit.latest_anchored_tx();
+    // The wallet returns the PSBT with the signature of the validator.
+    let signature = proposal.sign_input(
+        &testkit.current_redeem_script(),
+        0,
+        &testkit.current_funding_tx(),
+        &priv_key,
+    );
+    let mut psbt = Psbt::new(proposal.0.clone());
+    psbt.add_partial_signature(0, &priv_key.public_key(), &signature);
+    let response = json!({ "psbt": psbt.to_base64() });
+    write!(File::create(&response_path).unwrap(), "{}", response).unwrap();
+    testkit.create_block();
+
+    assert!(testkit.is_tx_in_pool(&signatures[0].hash()));
+    let mut args = String::new();
+    File::open(&args_path)
+        .unwrap()
+        .read_to_string(&mut args)
+        .unwrap();
+    assert!(args.starts_with("--fingerprint 8038ecd5 signtx "));
+
+    requests.expect(vec![
+        confirmations_request(&testkit.current_funding_tx(), 50),
+        request! {
+            method: "getrawtransaction",
+            params: [&anchored_tx.id(), 0],
+            error: RpcError::NoInformation("Unable to find tx".to_string())
+        },
+        request! {
+            method: "sendrawtransaction",
+            params: [anchored_tx],
+            response: anchored_tx
+        },
+    ]);
+    testkit.create_block_with_transactions(signatures);
+    assert_eq!(testkit.handler().signing_status(), SigningStatus::CanSign);
+}
+
+// The node starts with the funding transaction that is not yet mined
+// problems: The funding tx has fewer confirmations than required
+// result: panic
+#[test]
+#[should_panic(expected = "Funding transaction is rejected by the node")]
+fn test_anchoring_startup_funding_tx_unconfirmed() {
+    let mut client = TestClient::default();
+    let (common, mut nodes) = gen_sandbox_anchoring_config(&mut client);
+    nodes[0].required_confirmations = 6;
+
+    let funding_tx = common.funding_tx.clone().unwrap();
+    client.requests().expect(vec![confirmations_request(&funding_tx, 0)]);
+    AnchoringService::new_with_client(Box::new(client), common, nodes[0].clone());
+}
+
+// The anchoring configuration has the frequency below the minimum of the node
+// problems: The configuration is rejected by the node
+// result: the anchoring is stopped with the handler error on each block
+#[test]
+fn test_anchoring_config_rejected_by_node() {
+    let mut testkit = AnchoringTestKit::with_config(|cfg| cfg.frequency = 5);
+    for _ in 0..2 {
+        testkit.create_block();
+        assert_eq!(
+            testkit.take_handler_errors(),
+            vec![
+                HandlerError::ConfigRejected(ConfigError::FrequencyTooLow {
+                    frequency: 5,
+                    min_frequency: 10,
+                }),
+            ]
+        );
+    }
+    assert!(testkit.handler().accepted_configs.is_empty());
+}
+
+// The observer node starts without the funding transaction check
+// problems: None
+// result: success without the rpc requests
+#[test]
+fn test_anchoring_startup_funding_tx_check_skipped() {
+    let mut client = TestClient::default();
+    let (common, mut nodes) = gen_sandbox_anchoring_config(&mut client);
+    let mut node = nodes.remove(0);
+    node.private_keys.clear();
+    node.observer_only = true;
+    node.skip_funding_tx_check = true;
+
+    AnchoringService::new_with_client(Box::new(client), common, node);
+}
+
+// The funding UTXO is spent by an operator before the first anchoring transaction is sent
+// problems: bitcoind rejects the anchoring transaction as a double spend
+// result: the anchoring is paused until the configuration is refueled
+#[test]
+fn test_anchoring_funding_utxo_externally_spent() {
+    let mut testkit = AnchoringTestKit::default();
+    let requests = testkit.requests();
+    let funding_tx = testkit.current_funding_tx();
+    let anchoring_addr = testkit.current_addr();
+
+    requests.expect(vec![
+        confirmations_request(&funding_tx, 50),
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&funding_tx, &anchoring_addr, 50)
+            ]
+        },
+        get_transaction_request(&funding_tx),
+    ]);
+    let hash = testkit.last_block_hash();
+    let (_, signatures) =
+        testkit.gen_anchoring_tx_with_signatures(Height::zero(), hash, &[], None, &anchoring_addr);
+    let anchored_tx = testkit.latest_anchored_tx();
+    testkit.create_block();
+
+    requests.expect(vec![
+        confirmations_request(&funding_tx, 50),
+        request! {
+            method: "getrawtransaction",
+            params: [&anchored_tx.id(), 0],
+            error: RpcError::NoInformation("Unable to find tx".to_string())
+        },
+        request! {
+            method: "sendrawtransaction",
+            params: [anchored_tx],
+            error: bitcoind_error(-25, "bad-txns-inputs-missingorspent")
+        },
+    ]);
+    testkit.create_block_with_transactions(signatures);
+
+    assert_eq!(
+        testkit.take_handler_errors(),
+        vec![HandlerError::FundingUtxoSpent { txid: anchored_tx.id() }]
+    );
+    let pause = testkit.handler().anchoring_pause().cloned().unwrap();
+    assert_eq!(pause.rejected_tx, anchored_tx.id());
+    assert_eq!(pause.funding_txs, vec![funding_tx.id()]);
+
+    // The doomed transaction is neither finalized nor sent again.
+    requests.expect(vec![confirmations_request(&funding_tx, 50)]);
+    testkit.create_block();
+    assert!(testkit.handler().anchoring_pause().is_some());
+
+    // The anchoring is resumed by the configuration with a new funding transaction.
+    let mut cfg = testkit.current_cfg();
+    assert!(
+        testkit
+            .handler()
+            .is_anchoring_paused(&cfg, &testkit.snapshot())
+    );
+    cfg.additional_funding_txs.push(FundingTx::from(RawBitcoinTx {
+        version: 1,
+        lock_time: 0,
+        input: Vec::new(),
+        output: Vec::new(),
+    }));
+    assert!(
+        !testkit
+            .handler()
+            .is_anchoring_paused(&cfg, &testkit.snapshot())
+    );
+    assert!(testkit.handler().anchoring_pause().is_none());
+}
+
+// We anchor first block along with the root hash of the anchored blocks table
+// problems: None
+// result: success with the payload that commits the table root at the anchored height
+#[test]
+fn test_anchoring_first_block_table_root() {
+    let mut testkit = AnchoringTestKit::with_config(|cfg| {
+        cfg.anchored_table = Some(AnchoredTable {
+            service_id: ANCHORING_SERVICE_ID,
+            table_idx: 0,
+        });
+    });
+    anchor_first_block(&mut testkit);
+
+    let payload = testkit.latest_anchored_tx().payload();
+    let schema = AnchoringSchema::new(testkit.snapshot());
+    assert_eq!(payload.block_height, Height::zero());
+    assert!(payload.table_root.is_some());
+    assert_eq!(
+        payload.table_root,
+        schema.anchored_table_roots().get(&payload.block_height.0)
+    );
+    assert_eq!(
+        payload.table_root,
+        schema.anchored_table_root(payload.block_height)
+    );
+    assert_eq!(lects_count(&testkit, ValidatorId(0)), 2);
+}
+
+// We anchor first block with the fee rate estimated by the validators
+// problems: None
+// result: success with the fee proportional to the transaction vsize
+// at the median of the committed rates
+#[test]
+fn test_anchoring_first_block_estimated_fee() {
+    let mut testkit = AnchoringTestKit::with_config(|cfg| {
+        cfg.fee_strategy = FeeStrategyConfig::EstimateSmartFee {
+            conf_target: 6,
+            max_fee_rate: 10_000,
+        }
+    });
+    let requests = testkit.requests();
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+
+    requests.expect(vec![
+        confirmations_request(&funding_tx, 50),
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&funding_tx, &anchoring_addr, 50)
+            ]
+        },
+        get_transaction_request(&funding_tx),
+    ]);
+    let fee_rates = [3000, 1000, 2000]
+        .iter()
+        .enumerate()
+        .map(|(id, rate)| gen_service_tx_fee_rate(&testkit, ValidatorId(id as u16), *rate))
+        .map(Box::<Transaction>::from)
+        .collect::<Vec<_>>();
+    testkit.create_block_with_transactions(fee_rates);
+
+    let cfg = testkit.current_cfg();
+    let schema = AnchoringSchema::new(testkit.snapshot());
+    assert_eq!(schema.agreed_fee_rate(&cfg), Some(2000));
+    // The agreed rate is limited by the maximal one.
+    let mut capped_cfg = cfg.clone();
+    capped_cfg.fee_strategy = FeeStrategyConfig::EstimateSmartFee {
+        conf_target: 6,
+        max_fee_rate: 1500,
+    };
+    assert_eq!(schema.agreed_fee_rate(&capped_cfg), Some(1500));
+
+    let proposal = testkit.handler().proposal_tx.clone().unwrap();
+    let vsize = estimate_vsize(
+        &proposal.0,
+        &testkit.current_redeem_script(),
+        cfg.majority_count() as usize,
+    );
+    let out = funding_tx.find_out(&anchoring_addr).unwrap();
+    let funds = funding_tx.0.output[out as usize].value;
+    assert_eq!(funds - proposal.amount(), vsize * 2000 / 1000);
+}
+
+// We try to anchor first block before +2/3 validators estimate the fee rate
+// problems: the fee rate is not agreed
+// result: the proposal is not created
+#[test]
+fn test_anchoring_fee_rate_not_agreed() {
+    let mut testkit = AnchoringTestKit::with_config(|cfg| {
+        cfg.fee_strategy = FeeStrategyConfig::EstimateSmartFee {
+            conf_target: 6,
+            max_fee_rate: 10_000,
+        }
+    });
+    let requests = testkit.requests();
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+
+    requests.expect(vec![
+        confirmations_request(&funding_tx, 50),
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&funding_tx, &anchoring_addr, 50)
+            ]
+        },
+        get_transaction_request(&funding_tx),
+    ]);
+    let fee_rates = (0..2)
+        .map(|id| gen_service_tx_fee_rate(&testkit, ValidatorId(id), 1000))
+        .map(Box::<Transaction>::from)
+        .collect::<Vec<_>>();
+    testkit.create_block_with_transactions(fee_rates);
+
+    assert!(testkit.handler().proposal_tx.is_none());
+    assert_eq!(
+        testkit.take_handler_errors(),
+        vec![HandlerError::FeeRateNotAgreed]
+    );
+}
+
+// The validator estimates the fee rate by its `bitcoind` at the lect check height
+// problems: None
+// result: the estimated rate is sent if it differs from the committed one
+#[test]
+fn test_anchoring_update_our_fee_rate() {
+    let mut testkit = AnchoringTestKit::with_config(|cfg| {
+        cfg.fee_strategy = FeeStrategyConfig::EstimateSmartFee {
+            conf_target: 6,
+            max_fee_rate: 10_000,
+        }
+    });
+    let requests = testkit.requests();
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+
+    requests.expect(vec![
+        confirmations_request(&funding_tx, 50),
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&funding_tx, &anchoring_addr, 50)
+            ]
+        },
+        get_transaction_request(&funding_tx),
+    ]);
+    let fee_rates = (0..3)
+        .map(|id| gen_service_tx_fee_rate(&testkit, ValidatorId(id), 2000))
+        .map(Box::<Transaction>::from)
+        .collect::<Vec<_>>();
+    testkit.create_block_with_transactions(fee_rates);
+    assert!(testkit.handler().proposal_tx.is_some());
+
+    let height = testkit.next_check_lect_height();
+    testkit.create_blocks_until(height);
+    requests.expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: []
+        },
+        request! {
+            method: "estimatesmartfee",
+            params: [6],
+            response: {
+                "feerate": 0.00004,
+                "blocks": 6
+            }
+        },
+    ]);
+    testkit.create_block();
+
+    let fee_rate_tx = gen_service_tx_fee_rate(&testkit, ValidatorId(0), 4000);
+    assert!(testkit.is_tx_in_pool(&fee_rate_tx.hash()));
+    testkit.create_block();
+    let cfg = testkit.current_cfg();
+    let schema = AnchoringSchema::new(testkit.snapshot());
+    assert_eq!(schema.fee_rates().get(&cfg.anchoring_keys[0]), Some(4000));
 }
 
 // We wait until `funding_tx` have got enough confirmations.
@@ -67,42 +553,810 @@ fn test_anchoring_funding_tx_waiting() {
     let requests = testkit.requests();
     let funding_tx = testkit.current_funding_tx();
 
-    requests.expect(vec![confirmations_request(&funding_tx, 0)]);
+    requests.expect(vec![confirmations_request(&funding_tx, 0)]);
+    testkit.create_block();
+    // Resend funding_tx if we lost it
+    requests.expect(resend_raw_transaction_requests(&funding_tx));
+    testkit.create_block();
+
+    requests.expect(vec![confirmations_request(&funding_tx, 0)]);
+    testkit.create_block();
+}
+
+// We anchor the first block with the change address set in the anchoring config
+// problems: None
+// result: the anchoring output keeps the dust threshold and the rest of the funds
+// goes to the change address
+#[test]
+fn test_anchoring_first_block_change_address() {
+    let change_addr = btc::Address::from("2N1mHzwKTmjnC7JjqeGFBRKYE4WDTjTfop1");
+    let mut testkit = {
+        let change_addr = change_addr.clone();
+        AnchoringTestKit::with_config(move |cfg| cfg.change_address = Some(change_addr))
+    };
+    let requests = testkit.requests();
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+
+    requests.expect(vec![
+        confirmations_request(&funding_tx, 50),
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&funding_tx, &anchoring_addr, 50)
+            ]
+        },
+        get_transaction_request(&funding_tx),
+    ]);
+    testkit.create_block();
+
+    let proposal = testkit.handler().proposal_tx.clone().unwrap();
+    let cfg = testkit.current_cfg();
+    let out = funding_tx.find_out(&anchoring_addr).unwrap();
+    let funds = funding_tx.0.output[out as usize].value;
+    assert_eq!(proposal.output.len(), 3);
+    assert_eq!(proposal.output[0].script_pubkey, anchoring_addr.script_pubkey());
+    assert_eq!(proposal.amount(), cfg.anchor_output().unwrap().0);
+    assert_eq!(proposal.output[2].script_pubkey, change_addr.script_pubkey());
+    assert_eq!(proposal.output[2].value, funds - cfg.fee - proposal.amount());
+}
+
+// Anchors two blocks with the change address set in the anchoring config, each of them
+// spends its own funding transaction. Returns the anchoring transactions.
+fn anchor_two_blocks_with_change_address(
+    anchor_output_value: Option<u64>,
+) -> (AnchoringTestKit, AnchoringTx, AnchoringTx) {
+    let change_addr = btc::Address::from("2N1mHzwKTmjnC7JjqeGFBRKYE4WDTjTfop1");
+    let mut testkit = {
+        let change_addr = change_addr.clone();
+        AnchoringTestKit::with_config(move |cfg| {
+            let addr = cfg.redeem_script().1;
+            let mut raw = cfg.funding_tx().0.clone();
+            let out = cfg.funding_tx().find_out(&addr).unwrap() as usize;
+            raw.output[out].value += 1;
+            cfg.additional_funding_txs = vec![FundingTx::from(raw)];
+            cfg.anchor_output_value = anchor_output_value;
+            cfg.change_address = Some(change_addr);
+        })
+    };
+    let requests = testkit.requests();
+    let anchoring_addr = testkit.current_addr();
+    let next_funding_tx = testkit.current_cfg().additional_funding_txs[0].clone();
+    let anchor_value = testkit.current_cfg().anchor_output().unwrap().0;
+
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+    let first_tx = testkit.latest_anchored_tx();
+    assert_eq!(first_tx.amount(), anchor_value);
+    assert_eq!(first_tx.output[2].script_pubkey, change_addr.script_pubkey());
+
+    let height = testkit.next_anchoring_height();
+    testkit.create_blocks_until(height);
+    requests.expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&first_tx, &anchoring_addr, 1),
+                listunspent_entry(&next_funding_tx, &anchoring_addr, 50)
+            ]
+        },
+        get_transaction_request(&first_tx),
+        get_transaction_request(&next_funding_tx),
+    ]);
+    testkit.create_block();
+
+    let last_block_hash = testkit.last_block_hash();
+    let (proposal, signatures) = testkit.gen_anchoring_tx_with_signatures(
+        Height(10),
+        last_block_hash,
+        &[next_funding_tx.clone()],
+        None,
+        &anchoring_addr,
+    );
+    assert_eq!(testkit.handler().proposal_tx.as_ref(), Some(&proposal));
+    let second_tx = testkit.latest_anchored_tx();
+    requests.expect(vec![get_transaction_request(&second_tx)]);
+    testkit.create_block_with_transactions(signatures);
+
+    // The lect keeps only the anchoring output, so the whole funding transaction
+    // goes to the change output except for the fee.
+    let funds = next_funding_tx
+        .find_out(&anchoring_addr)
+        .map(|out| next_funding_tx.0.output[out as usize].value)
+        .unwrap();
+    assert_eq!(second_tx.input.len(), 2);
+    assert_eq!(second_tx.amount(), anchor_value);
+    assert_eq!(second_tx.output[0].script_pubkey, anchoring_addr.script_pubkey());
+    assert_eq!(second_tx.output[2].script_pubkey, change_addr.script_pubkey());
+    assert_eq!(second_tx.output[2].value, funds - 1000);
+    (testkit, first_tx, second_tx)
+}
+
+// We anchor two blocks with the fixed anchoring output value
+// problems: the rest of the funds goes to the change address, so the lect alone cannot pay
+// for the next anchoring transaction
+// result: the second anchoring transaction spends the lect along with the next funding tx
+#[test]
+fn test_anchoring_second_block_anchor_output_value() {
+    let (_, _, second_tx) = anchor_two_blocks_with_change_address(Some(2000));
+    assert_eq!(second_tx.amount(), 2000);
+}
+
+// We anchor two blocks with the change address set in the anchoring config
+// problems: the anchoring output keeps only the dust threshold
+// result: every anchoring transaction consumes the whole funding tx
+#[test]
+fn test_anchoring_second_block_change_address() {
+    let (testkit, first_tx, second_tx) = anchor_two_blocks_with_change_address(None);
+    let cfg = testkit.current_cfg();
+    let dust = cfg.anchor_output().unwrap().0;
+    assert_eq!(first_tx.amount(), dust);
+    assert_eq!(second_tx.amount(), dust);
+    assert_eq!(btc::TxId::from(second_tx.input[0].prev_hash), first_tx.id());
+}
+
+// We anchor the next block with the fixed anchoring output value, but all funding txs
+// have been spent
+// problems: the lect alone cannot pay for the next anchoring transaction
+// result: the proposal is not created and the error is reported
+#[test]
+fn test_anchoring_anchor_output_value_without_funding() {
+    let change_addr = btc::Address::from("2N1mHzwKTmjnC7JjqeGFBRKYE4WDTjTfop1");
+    let mut testkit = AnchoringTestKit::with_config(move |cfg| {
+        cfg.anchor_output_value = Some(2000);
+        cfg.change_address = Some(change_addr);
+    });
+    let requests = testkit.requests();
+    let anchoring_addr = testkit.current_addr();
+
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+    let first_tx = testkit.latest_anchored_tx();
+
+    let height = testkit.next_anchoring_height();
+    testkit.create_blocks_until(height);
+    requests.expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&first_tx, &anchoring_addr, 1)
+            ]
+        },
+        get_transaction_request(&first_tx),
+    ]);
+    testkit.create_block();
+
+    assert!(testkit.handler().proposal_tx.is_none());
+    assert_eq!(
+        testkit.take_handler_errors(),
+        vec![HandlerError::FundingTxNotAvailable]
+    );
+}
+
+// We start the validator node without the private key for the anchoring address
+// problems: the node cannot sign the anchoring transactions
+// result: the node reports that it cannot sign and follows the chain as an auditor
+#[test]
+fn test_anchoring_private_key_missing() {
+    let mut testkit = AnchoringTestKit::default();
+    testkit.handler().node.private_keys.clear();
+
+    testkit.create_block();
+    assert_eq!(testkit.handler().signing_status(), SigningStatus::CannotSign);
+    assert!(testkit.handler().proposal_tx.is_none());
+    assert!(testkit.take_handler_errors().is_empty());
+
+    testkit.create_block();
+    assert_eq!(testkit.handler().signing_status(), SigningStatus::CannotSign);
+}
+
+// We start the validator node with the private key for another anchoring key
+// problems: the node cannot sign the anchoring transactions
+// result: the node reports that it cannot sign and follows the chain as an auditor
+#[test]
+fn test_anchoring_private_key_mismatch() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_addr = testkit.current_addr();
+    let (_, priv_key) = gen_btc_keypair(BitcoinNetwork::Testnet);
+    testkit.handler().add_private_key(&anchoring_addr, priv_key);
+
+    testkit.create_block();
+    assert_eq!(testkit.handler().signing_status(), SigningStatus::CannotSign);
+    assert!(testkit.handler().proposal_tx.is_none());
+    assert!(testkit.take_handler_errors().is_empty());
+}
+
+// We choose the funding transaction when the config contains several ones.
+// problems: funding transactions are spent one by one
+// result: the largest unspent funding transaction is used
+#[test]
+fn test_anchoring_additional_funding_txs_fallback() {
+    let testkit = AnchoringTestKit::default();
+    let requests = testkit.requests();
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+    let out = funding_tx.find_out(&anchoring_addr).unwrap() as usize;
+
+    let gen_funding_tx = |value: u64| {
+        let mut raw = funding_tx.0.clone();
+        raw.output[out].value = value;
+        FundingTx::from(raw)
+    };
+    let funds = funding_tx.0.output[out].value;
+    let large_tx = gen_funding_tx(funds * 2);
+    let small_tx = gen_funding_tx(funds / 2);
+
+    let mut cfg = testkit.current_cfg();
+    cfg.additional_funding_txs = vec![small_tx.clone(), large_tx.clone()];
+    let handler = testkit.handler();
+    let multisig = handler.multisig_address(&cfg);
+
+    let mut unspent_txs = vec![funding_tx.clone(), small_tx.clone(), large_tx.clone()];
+    for expected_tx in &[large_tx.clone(), funding_tx.clone(), small_tx.clone()] {
+        let entries = unspent_txs
+            .iter()
+            .map(|tx| listunspent_entry(tx, &anchoring_addr, 50))
+            .collect::<Vec<_>>();
+        let mut expected_requests = vec![request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: entries
+        }];
+        expected_requests.extend(unspent_txs.iter().map(|tx| get_transaction_request(tx)));
+        requests.expect(expected_requests);
+
+        assert_eq!(
+            handler
+                .available_funding_tx(&multisig, &testkit.snapshot())
+                .unwrap()
+                .as_ref(),
+            Some(expected_tx)
+        );
+        // The chosen funding transaction is spent by the anchoring chain.
+        unspent_txs.retain(|tx| tx != expected_tx);
+    }
+
+    requests.expect(vec![request! {
+        method: "listunspent",
+        params: [0, 9_999_999, [&anchoring_addr]],
+        response: []
+    }]);
+    assert_eq!(
+        handler
+            .available_funding_tx(&multisig, &testkit.snapshot())
+            .unwrap(),
+        None
+    );
+}
+
+// We anchor first block and receive lect
+// problems: None
+// result: success
+#[test]
+fn test_anchoring_update_lect_normal() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+}
+
+// Publishes the block hash until the listener raises the events flag.
+fn publish_block_hash(publisher: &zmq::Socket, events: &BitcoindEvents) -> bool {
+    let block_hash = [0_u8; 32];
+    let sequence = [0_u8; 4];
+    for _ in 0..100 {
+        publisher
+            .send_multipart(&[HASHBLOCK_TOPIC.as_bytes(), &block_hash, &sequence], 0)
+            .unwrap();
+        thread::sleep(Duration::from_millis(50));
+        if events.is_pending() {
+            return true;
+        }
+    }
+    false
+}
+
+// We anchor first block and receive the bitcoind notification before the lect check height
+// problems: None
+// result: lect is updated in the next block
+#[test]
+fn test_anchoring_update_lect_after_bitcoind_notification() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    assert!(testkit.height().next() < testkit.next_check_lect_height());
+
+    let context = zmq::Context::new();
+    let publisher = context.socket(zmq::PUB).unwrap();
+    publisher.bind("tcp://127.0.0.1:*").unwrap();
+    let endpoint = publisher.get_last_endpoint().unwrap().unwrap();
+
+    let events = testkit.handler().bitcoind_events.clone();
+    let config = ZmqListenerConfig {
+        endpoints: vec![endpoint],
+        reconnect_interval: 100,
+    };
+    let listener = ZmqListener::spawn(config, DEFAULT_PAYLOAD_PREFIX.as_bytes(), events.clone());
+    assert!(publish_block_hash(&publisher, &events));
+    drop(listener);
+
+    let anchored_tx = testkit.latest_anchored_tx();
+    let anchoring_addr = testkit.current_addr();
+    testkit.requests().expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&anchored_tx, &anchoring_addr, 0),
+            ]
+        },
+        request! {
+            method: "getrawtransaction",
+            params: [&anchored_tx.id(), 0],
+            response: &anchored_tx
+        },
+    ]);
+    testkit.create_block();
+    assert!(!events.is_pending());
+}
+
+// We receive the bitcoind notification after the publisher has been restarted
+// problems: None
+// result: listener reconnects and raises the events flag
+#[test]
+fn test_anchoring_bitcoind_notifications_reconnect() {
+    let context = zmq::Context::new();
+    let publisher = context.socket(zmq::PUB).unwrap();
+    publisher.bind("tcp://127.0.0.1:*").unwrap();
+    let endpoint = publisher.get_last_endpoint().unwrap().unwrap();
+
+    let events = BitcoindEvents::new();
+    let config = ZmqListenerConfig {
+        endpoints: vec![endpoint.clone()],
+        reconnect_interval: 100,
+    };
+    let _listener = ZmqListener::spawn(config, DEFAULT_PAYLOAD_PREFIX.as_bytes(), events.clone());
+    assert!(publish_block_hash(&publisher, &events));
+    assert!(events.take());
+
+    drop(publisher);
+    let publisher = context.socket(zmq::PUB).unwrap();
+    publisher.bind(&endpoint).unwrap();
+    assert!(publish_block_hash(&publisher, &events));
+}
+
+// We anchor first block and receive lect with different but correct signatures
+// problems: lect with a different signature set
+// result: success with a new lect
+#[test]
+fn test_anchoring_update_lect_different() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block_lect_different(&mut testkit);
+}
+
+// We anchor first block and receive lect with different signatures that is confirmed slowly
+// problems: lect candidate has insufficient confirmations
+// result: lect is updated only after it reaches the required confirmations
+#[test]
+fn test_anchoring_update_lect_required_confirmations() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    testkit.handler().node.required_confirmations = 2;
+    let height = testkit.next_check_lect_height();
+    testkit.create_blocks_until(height);
+
+    let other_lect = {
+        let anchored_tx = testkit.latest_anchored_tx();
+        let other_signatures = testkit
+            .latest_anchored_tx_signatures()
+            .iter()
+            .filter(|tx| tx.validator() != ValidatorId(0))
+            .cloned()
+            .collect::<Vec<_>>();
+        testkit.finalize_tx(anchored_tx, other_signatures)
+    };
+    let lect_tx: Box<Transaction> =
+        gen_service_tx_lect(&testkit, ValidatorId(0), &other_lect, 2).into();
+
+    let anchoring_addr = testkit.current_addr();
+    for confirmations in 0..3 {
+        testkit.requests().expect(vec![
+            request! {
+                method: "listunspent",
+                params: [0, 9_999_999, [&anchoring_addr]],
+                response: [
+                    listunspent_entry(&other_lect, &anchoring_addr, confirmations)
+                ]
+            },
+            get_transaction_request(&other_lect),
+        ]);
+        if confirmations > 0 {
+            testkit.handler().bitcoind_events.notify();
+        }
+        testkit.create_block();
+        assert_eq!(testkit.is_tx_in_pool(&lect_tx.hash()), confirmations >= 2);
+    }
+}
+
+// We anchor first block and lose anchoring transaction
+// problems: anchoring transaction is lost
+// result: we have lost anchoring transaction
+#[test]
+fn test_anchoring_first_block_lect_lost() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block_lect_lost(&mut testkit);
+}
+
+// We replace the anchoring transaction that has not been confirmed for a long time
+// problems: anchoring transaction is stuck in the mempool
+// result: success with a higher fee replacement of the same block
+#[test]
+fn test_anchoring_replace_unconfirmed_lect() {
+    let mut testkit = AnchoringTestKit::with_config(|cfg| {
+        cfg.rbf_unconfirmed_btc_blocks = Some(2);
+        // The replacement must happen before the next anchoring height.
+        cfg.frequency = 30;
+    });
+    let requests = testkit.requests();
+    anchor_first_block(&mut testkit);
+
+    let anchored_tx = testkit.latest_anchored_tx();
+    let anchoring_addr = testkit.current_addr();
+    let lect_requests = || {
+        vec![
+            request! {
+                method: "listunspent",
+                params: [0, 9_999_999, [&anchoring_addr]],
+                response: [
+                    listunspent_entry(&anchored_tx, &anchoring_addr, 0),
+                ]
+            },
+            request! {
+                method: "getrawtransaction",
+                params: [&anchored_tx.id(), 0],
+                response: &anchored_tx
+            },
+        ]
+    };
+
+    // Handler starts to track the unconfirmed lect.
+    let height = testkit.next_check_lect_height();
+    testkit.create_blocks_until(height);
+    let mut expected = lect_requests();
+    expected.push(get_block_count_request(100));
+    requests.expect(expected);
+    testkit.create_block();
+
+    // The lect has not been confirmed for enough bitcoin blocks, so the handler votes
+    // for its replacement.
+    let height = testkit.next_check_lect_height();
+    testkit.create_blocks_until(height);
+    let mut expected = lect_requests();
+    expected.push(get_block_count_request(102));
+    expected.push(confirmations_request(&anchored_tx, 0));
+    requests.expect(expected);
+    testkit.create_block();
+
+    let our_request = gen_service_tx_replace_request(&testkit, ValidatorId(0), &anchored_tx);
+    assert!(testkit.is_tx_in_pool(&our_request.hash()));
+    assert!(testkit.handler().proposal_tx.is_none());
+
+    // The replacement is built once +2/3 validators have voted for it.
+    let replace_requests = (0..3)
+        .map(|id| gen_service_tx_replace_request(&testkit, ValidatorId(id), &anchored_tx))
+        .map(Box::<Transaction>::from)
+        .collect::<Vec<_>>();
+    testkit.create_block_with_transactions(replace_requests);
+    let cfg = testkit.current_cfg();
+    assert!(AnchoringSchema::new(testkit.snapshot()).replacement_agreed(&cfg, &anchored_tx.id()));
+
+    let replacement = testkit.handler().proposal_tx.clone().unwrap();
+    assert_ne!(replacement.id(), anchored_tx.id());
+    assert_eq!(replacement.payload(), anchored_tx.payload());
+    assert_eq!(replacement.input.len(), anchored_tx.input.len());
+    assert_eq!(replacement.input[0].prev_hash, anchored_tx.input[0].prev_hash);
+    assert_eq!(replacement.input[0].prev_index, anchored_tx.input[0].prev_index);
+    assert_eq!(replacement.input[0].sequence, 0xFFFF_FFFD);
+    assert_eq!(replacement.script_pubkey(), anchored_tx.script_pubkey());
+    assert!(replacement.amount() < anchored_tx.amount());
+}
+
+// We send the unconfirmed anchoring transaction to the bitcoin network again
+// problems: bitcoind forgets the anchoring transaction
+// result: the same raw transaction is sent again
+#[test]
+fn test_anchoring_rebroadcast_unconfirmed_lect() {
+    let mut testkit = AnchoringTestKit::default();
+    let requests = testkit.requests();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    let anchored_tx = testkit.latest_anchored_tx();
+    testkit.handler().node.rebroadcast_interval = Some(2);
+    // Handler starts to track the unconfirmed lect.
+    testkit.create_block();
+    testkit.create_block();
+
+    requests.expect(resend_raw_transaction_requests(&anchored_tx));
+    testkit.create_block();
+    assert!(testkit.take_handler_errors().is_empty());
+    assert!(testkit.handler().proposal_tx.is_none());
+}
+
+// We track the time since the latest confirmed anchor by the mock clock
+// problems: no new anchor is confirmed within the time budget
+// result: the watchdog reports the stalled anchoring until the next anchor is confirmed
+#[test]
+fn test_anchoring_watchdog_stalled() {
+    let mut testkit = AnchoringTestKit::default();
+    let start = Instant::now();
+    let watchdog = AnchoringWatchdog::new(Some(Duration::from_secs(60)), start);
+    testkit.handler().watchdog = watchdog.clone();
+
+    anchor_first_block(&mut testkit);
+    // The node finds the confirmed lect.
+    let height = testkit.next_check_lect_height();
+    testkit.create_blocks_until(height);
+    let anchored_tx = testkit.latest_anchored_tx();
+    let anchoring_addr = testkit.current_addr();
+    testkit.requests().expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&anchored_tx, &anchoring_addr, 1),
+            ]
+        },
+        request! {
+            method: "getrawtransaction",
+            params: [&anchored_tx.id(), 0],
+            response: &anchored_tx
+        },
+    ]);
+    let confirmed_at = Instant::now();
+    testkit.create_block();
+
+    let status = watchdog.status(confirmed_at + Duration::from_secs(30));
+    assert!(!status.is_stalled);
+    assert_eq!(status.budget, Some(60));
+    assert_eq!(status.last_anchor, Some(anchored_tx.id()));
+
+    // The same anchor does not reset the elapsed time.
+    watchdog.anchor_confirmed(anchored_tx.id(), confirmed_at + Duration::from_secs(30));
+    let status = watchdog.status(confirmed_at + Duration::from_secs(61));
+    assert!(status.is_stalled);
+    assert!(status.elapsed >= 60);
+
+    let next_txid = btc::TxId::from_slice(Hash::zero().as_ref()).unwrap();
+    watchdog.anchor_confirmed(next_txid, confirmed_at + Duration::from_secs(90));
+    let status = watchdog.status(confirmed_at + Duration::from_secs(100));
+    assert!(!status.is_stalled);
+    assert_eq!(status.elapsed, 10);
+    assert_eq!(status.last_anchor, Some(next_txid));
+}
+
+// We top up the anchoring address which balance is below the threshold
+// problems: anchoring address is nearly exhausted
+// result: success with the funding tx recorded in the blockchain and available
+// for the anchoring, the next top-up is suppressed by the spending limit
+#[test]
+fn test_anchoring_funding_top_up_below_threshold() {
+    // The blocks are not anchored during the test.
+    let mut testkit = AnchoringTestKit::with_config(|cfg| cfg.frequency = 1000);
+    let requests = testkit.requests();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    let anchored_tx = testkit.latest_anchored_tx();
+    let anchoring_addr = testkit.current_addr();
+    let top_up_tx = {
+        let mut raw = testkit.current_funding_tx().0;
+        raw.lock_time = 1;
+        FundingTx::from(raw)
+    };
+    let amount = top_up_tx.output[top_up_tx.find_out(&anchoring_addr).unwrap() as usize].value;
+    testkit.handler().node.funding_top_up = Some(FundingTopUpConfig {
+        min_balance: anchored_tx.amount() + 1,
+        amount,
+        max_spend_per_period: amount,
+        period: 1000,
+    });
+
+    create_blocks_until_top_up_turn(&mut testkit, ValidatorId(0));
+    requests.expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [listunspent_entry(&anchored_tx, &anchoring_addr, 1)]
+        },
+        get_transaction_request(&anchored_tx),
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [listunspent_entry(&anchored_tx, &anchoring_addr, 1)]
+        },
+        get_transaction_request(&anchored_tx),
+        request! {
+            method: "sendtoaddress",
+            params: [&anchoring_addr, (amount as f64 / SATOSHI_DIVISOR).to_string()],
+            response: &top_up_tx.id()
+        },
+        get_transaction_request(&top_up_tx),
+    ]);
     testkit.create_block();
-    // Resend funding_tx if we lost it
-    requests.expect(resend_raw_transaction_requests(&funding_tx));
+    assert!(testkit.take_handler_errors().is_empty());
+
+    let top_up_msg = {
+        let keypair = testkit.network().validators()[0].service_keypair();
+        MsgAnchoringTopUp::new(
+            keypair.0,
+            ValidatorId(0),
+            BitcoinTx::from(top_up_tx.clone()),
+            keypair.1,
+        )
+    };
+    assert!(testkit.is_tx_in_pool(&top_up_msg.hash()));
     testkit.create_block();
+    let schema = AnchoringSchema::new(testkit.snapshot());
+    assert_eq!(
+        schema.top_up_funding_txs().get(&top_up_tx.id()),
+        Some(BitcoinTx::from(top_up_tx.clone()))
+    );
 
-    requests.expect(vec![confirmations_request(&funding_tx, 0)]);
+    // The committed top-up is spent by the next anchoring transaction.
+    let cfg = testkit.current_cfg();
+    requests.expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&anchored_tx, &anchoring_addr, 1),
+                listunspent_entry(&top_up_tx, &anchoring_addr, 1)
+            ]
+        },
+        get_transaction_request(&anchored_tx),
+        get_transaction_request(&top_up_tx),
+    ]);
+    {
+        let handler = testkit.handler();
+        let multisig = handler.multisig_address(&cfg);
+        assert_eq!(
+            handler
+                .available_funding_tx(&multisig, &testkit.snapshot())
+                .unwrap(),
+            Some(top_up_tx.clone())
+        );
+    }
+
+    // The balance is still low, but the spending limit is exhausted.
+    create_blocks_until_top_up_turn(&mut testkit, ValidatorId(0));
+    requests.expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [listunspent_entry(&anchored_tx, &anchoring_addr, 1)]
+        },
+        get_transaction_request(&anchored_tx),
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [listunspent_entry(&anchored_tx, &anchoring_addr, 1)]
+        },
+        get_transaction_request(&anchored_tx),
+    ]);
     testkit.create_block();
+    assert!(testkit.take_handler_errors().is_empty());
+    let schema = AnchoringSchema::new(testkit.snapshot());
+    assert_eq!(schema.top_up_history().len(), 1);
+    assert_eq!(schema.top_ups_amount_since(Height::zero()), amount);
 }
 
-// We anchor first block and receive lect
-// problems: None
-// result: success
+// We top up the anchoring address during the full cycle of the validators turns
+// problems: anchoring address is nearly exhausted
+// result: the node tops up the address only at its own turn, so the validators
+// do not send the top-ups at the same height
 #[test]
-fn test_anchoring_update_lect_normal() {
-    let mut testkit = AnchoringTestKit::default();
+fn test_anchoring_funding_top_up_validators_turns() {
+    // The blocks are not anchored during the test.
+    let mut testkit = AnchoringTestKit::with_config(|cfg| cfg.frequency = 1000);
+    let requests = testkit.requests();
     anchor_first_block(&mut testkit);
     anchor_first_block_lect_normal(&mut testkit);
-}
 
-// We anchor first block and receive lect with different but correct signatures
-// problems: lect with a different signature set
-// result: success with a new lect
-#[test]
-fn test_anchoring_update_lect_different() {
-    let mut testkit = AnchoringTestKit::default();
-    anchor_first_block_lect_different(&mut testkit);
+    let anchored_tx = testkit.latest_anchored_tx();
+    let anchoring_addr = testkit.current_addr();
+    let top_up_tx = {
+        let mut raw = testkit.current_funding_tx().0;
+        raw.lock_time = 1;
+        FundingTx::from(raw)
+    };
+    let amount = 10_000;
+    testkit.handler().node.funding_top_up = Some(FundingTopUpConfig {
+        min_balance: anchored_tx.amount() + 1,
+        amount,
+        max_spend_per_period: amount * 10,
+        period: 1000,
+    });
+
+    let validators_count = testkit.network().validators().len() as u64;
+    for _ in 0..validators_count {
+        let height = testkit.next_check_lect_height();
+        testkit.create_blocks_until(height);
+
+        let mut expected_requests = vec![
+            request! {
+                method: "listunspent",
+                params: [0, 9_999_999, [&anchoring_addr]],
+                response: [listunspent_entry(&anchored_tx, &anchoring_addr, 1)]
+            },
+            get_transaction_request(&anchored_tx),
+        ];
+        let round = height.next().0 / CHECK_LECT_FREQUENCY;
+        if round % validators_count == 0 {
+            expected_requests.extend(vec![
+                request! {
+                    method: "listunspent",
+                    params: [0, 9_999_999, [&anchoring_addr]],
+                    response: [listunspent_entry(&anchored_tx, &anchoring_addr, 1)]
+                },
+                get_transaction_request(&anchored_tx),
+                request! {
+                    method: "sendtoaddress",
+                    params: [&anchoring_addr, (amount as f64 / SATOSHI_DIVISOR).to_string()],
+                    response: &top_up_tx.id()
+                },
+                get_transaction_request(&top_up_tx),
+            ]);
+        }
+        requests.expect(expected_requests);
+        testkit.create_block();
+        assert!(testkit.take_handler_errors().is_empty());
+    }
+
+    let schema = AnchoringSchema::new(testkit.snapshot());
+    assert_eq!(schema.top_up_history().len(), 1);
 }
 
-// We anchor first block and lose anchoring transaction
-// problems: anchoring transaction is lost
-// result: we have lost anchoring transaction
+// We do not top up the anchoring address which balance is above the threshold
+// problems: none
+// result: no funding tx is sent
 #[test]
-fn test_anchoring_first_block_lect_lost() {
-    let mut testkit = AnchoringTestKit::default();
-    anchor_first_block_lect_lost(&mut testkit);
+fn test_anchoring_funding_top_up_above_threshold() {
+    // The blocks are not anchored during the test.
+    let mut testkit = AnchoringTestKit::with_config(|cfg| cfg.frequency = 1000);
+    let requests = testkit.requests();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    let anchored_tx = testkit.latest_anchored_tx();
+    let anchoring_addr = testkit.current_addr();
+    testkit.handler().node.funding_top_up = Some(FundingTopUpConfig {
+        min_balance: anchored_tx.amount(),
+        amount: 10_000,
+        max_spend_per_period: 100_000,
+        period: 1000,
+    });
+
+    create_blocks_until_top_up_turn(&mut testkit, ValidatorId(0));
+    requests.expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [listunspent_entry(&anchored_tx, &anchoring_addr, 1)]
+        },
+        get_transaction_request(&anchored_tx),
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [listunspent_entry(&anchored_tx, &anchoring_addr, 1)]
+        },
+        get_transaction_request(&anchored_tx),
+    ]);
+    testkit.create_block();
+    assert!(testkit.take_handler_errors().is_empty());
+    let schema = AnchoringSchema::new(testkit.snapshot());
+    assert_eq!(schema.top_up_history().len(), 0);
 }
 
 // We anchor second block after successfully anchored first
@@ -576,6 +1830,121 @@ fn test_anchoring_lect_funding_tx() {
     assert_eq!(lects_after[2], tx.0);
 }
 
+// We received the older lect for the lects count that has already been passed
+// problems: the update is replayed or delayed
+// result: we reject it
+#[test]
+fn test_anchoring_lect_stale() {
+    let mut testkit = AnchoringTestKit::default();
+
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    let tx = testkit.current_funding_tx();
+    let msg_lect = gen_service_tx_lect(&testkit, ValidatorId(0), &tx, 1);
+    let lects_before = dump_lects(&testkit, ValidatorId(0));
+    assert_eq!(lects_before.len(), 2);
+    // Commit `msg_lect` into blockchain
+    testkit.create_block_with_transactions(txvec![msg_lect.clone()]);
+    // Ensure that service rejects it
+    let lects_after = dump_lects(&testkit, ValidatorId(0));
+    assert_eq!(lects_before, lects_after);
+
+    let result = Schema::new(testkit.snapshot())
+        .transaction_results()
+        .get(&msg_lect.hash())
+        .unwrap();
+    assert_eq!(
+        result.unwrap_err().error_type(),
+        TransactionErrorType::Code(ValidateError::LectStale.as_code())
+    );
+}
+
+// We received the replace request for the transaction that is not the lect
+// problems: the request is outdated or malicious
+// result: we reject it
+#[test]
+fn test_anchoring_replace_request_non_lect() {
+    let mut testkit = AnchoringTestKit::default();
+
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    let tx = TransactionBuilder::with_prev_tx(&testkit.latest_anchored_tx(), 0)
+        .fee(1000)
+        .payload(Height(10), Hash::zero())
+        .send_to(testkit.current_addr())
+        .into_transaction()
+        .unwrap();
+    let msg = gen_service_tx_replace_request(&testkit, ValidatorId(0), &tx);
+    testkit.create_block_with_transactions(txvec![msg.clone()]);
+
+    let cfg = testkit.current_cfg();
+    let schema = AnchoringSchema::new(testkit.snapshot());
+    assert_eq!(schema.replacement_requests().get(&cfg.anchoring_keys[0]), None);
+    let result = Schema::new(testkit.snapshot())
+        .transaction_results()
+        .get(&msg.hash())
+        .unwrap();
+    assert_eq!(
+        result.unwrap_err().error_type(),
+        TransactionErrorType::Code(ValidateError::ReplacementOfNonLect.as_code())
+    );
+}
+
+// We received the anchor requests of the validators for the different heights
+// problems: the request for the uncommitted block
+// result: we reject it, the anchoring transaction for the highest height requested
+// by +2/3 validators is proposed
+#[test]
+fn test_anchoring_anchor_request() {
+    let mut testkit = AnchoringTestKit::default();
+    let requests = testkit.requests();
+    anchor_first_block(&mut testkit);
+
+    let cfg = testkit.current_cfg();
+    let msg_unknown = gen_service_tx_anchor_request(&testkit, ValidatorId(3), Height(100));
+    let msgs = txvec![
+        gen_service_tx_anchor_request(&testkit, ValidatorId(0), Height(3)),
+        gen_service_tx_anchor_request(&testkit, ValidatorId(1), Height(2)),
+        msg_unknown.clone(),
+    ];
+    testkit.create_block_with_transactions(msgs);
+
+    let schema = AnchoringSchema::new(testkit.snapshot());
+    assert_eq!(schema.anchor_requests().get(&cfg.anchoring_keys[0]), Some(3));
+    assert_eq!(schema.anchor_requests().get(&cfg.anchoring_keys[3]), None);
+    assert_eq!(schema.agreed_anchor_request(&cfg), None);
+    let result = Schema::new(testkit.snapshot())
+        .transaction_results()
+        .get(&msg_unknown.hash())
+        .unwrap();
+    assert_eq!(
+        result.unwrap_err().error_type(),
+        TransactionErrorType::Code(ValidateError::AnchorRequestOfUnknownBlock.as_code())
+    );
+
+    let anchoring_addr = testkit.current_addr();
+    let anchored_tx = testkit.latest_anchored_tx();
+    requests.expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&anchored_tx, &anchoring_addr, 1)
+            ]
+        },
+        get_transaction_request(&anchored_tx),
+    ]);
+    let msg = gen_service_tx_anchor_request(&testkit, ValidatorId(2), Height(4));
+    testkit.create_block_with_transactions(txvec![msg]);
+
+    let schema = AnchoringSchema::new(testkit.snapshot());
+    assert_eq!(schema.agreed_anchor_request(&cfg), Some(Height(2)));
+    let proposal = testkit.handler().proposal_tx.clone().unwrap();
+    assert_eq!(proposal.payload().block_height, Height(2));
+}
+
 // We received correct lect with the incorrect funding_tx
 // problems: None
 // result: we ignore it
@@ -862,3 +2231,492 @@ fn test_anchoring_signature_unknown_output_address() {
     let signs_after = dump_signatures(&testkit, &tx.id());
     assert_eq!(signs_before, signs_after);
 }
+
+fn bitcoind_error(code: i32, message: &str) -> RpcError {
+    RpcError::Rpc(JsonRpcError::Rpc(json!({
+        "code": code,
+        "message": message,
+    })))
+}
+
+fn retrying_client(max_attempts: u32) -> (RetryingRpc<TestClient>, TestRequests) {
+    let client = TestClient::default();
+    let requests = client.requests();
+    let config = RpcRetryConfig {
+        max_attempts,
+        initial_delay: 0,
+        max_delay: 0,
+    };
+    (RetryingRpc::new(client, config), requests)
+}
+
+// We request the fee estimate while the bitcoind is warming up
+// problems: first two calls fail
+// result: the third call succeeds
+#[test]
+fn test_anchoring_rpc_retry_until_success() {
+    let (relay, requests) = retrying_client(3);
+    requests.expect(vec![
+        request! {
+            method: "estimatesmartfee",
+            params: [2],
+            error: bitcoind_error(RPC_IN_WARMUP, "Loading block index...")
+        },
+        request! {
+            method: "estimatesmartfee",
+            params: [2],
+            error: bitcoind_error(RPC_IN_WARMUP, "Verifying blocks...")
+        },
+        request! {
+            method: "estimatesmartfee",
+            params: [2],
+            response: { "feerate": 0.000_100_00, "blocks": 2 }
+        },
+    ]);
+    assert_eq!(relay.estimate_fee(2).unwrap(), Some(10_000));
+}
+
+// We request the fee estimate while the bitcoind is warming up
+// problems: all attempts fail
+// result: the error of the last attempt is returned
+#[test]
+fn test_anchoring_rpc_retry_attempts_exceeded() {
+    let (relay, requests) = retrying_client(2);
+    requests.expect(vec![
+        request! {
+            method: "estimatesmartfee",
+            params: [2],
+            error: bitcoind_error(RPC_IN_WARMUP, "Loading block index...")
+        },
+        request! {
+            method: "estimatesmartfee",
+            params: [2],
+            error: bitcoind_error(RPC_IN_WARMUP, "Loading block index...")
+        },
+    ]);
+    assert!(relay.estimate_fee(2).is_err());
+}
+
+// We send the transaction that spends the missing inputs
+// problems: bitcoind rejects the transaction
+// result: the error is returned without retries
+#[test]
+fn test_anchoring_rpc_retry_fatal_error() {
+    let (relay, requests) = retrying_client(3);
+    let tx = BitcoinTx::from(RawBitcoinTx {
+        version: 1,
+        lock_time: 0,
+        input: Vec::new(),
+        output: Vec::new(),
+    });
+    requests.expect(vec![request! {
+        method: "sendrawtransaction",
+        params: [&tx],
+        error: bitcoind_error(-25, "bad-txns-inputs-missingorspent")
+    }]);
+    assert!(relay.send_transaction(tx.clone()).is_err());
+
+    requests.expect(vec![request! {
+        method: "sendrawtransaction",
+        params: [&tx],
+        error: bitcoind_error(-26, "txn-already-in-mempool")
+    }]);
+    relay.send_transaction(tx).unwrap();
+}
+
+fn pooled_client(addr: &str, read_timeout: u64) -> PooledRpcClient {
+    let rpc = AnchoringRpcConfig {
+        host: format!("http://{}", addr),
+        username: Some("user".to_owned()),
+        password: Some("password".to_owned()),
+    };
+    let connection = RpcConnectionConfig {
+        connect_timeout: 1_000,
+        read_timeout,
+        pool_size: 1,
+    };
+    PooledRpcClient::new(rpc, BitcoinNetwork::Testnet, connection)
+}
+
+// Reads the body of the json rpc request.
+fn read_rpc_request(stream: &TcpStream) -> serde_json::Value {
+    let mut reader = BufReader::new(stream);
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim().to_lowercase();
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with("content-length:") {
+            content_length = line["content-length:".len()..].trim().parse().unwrap();
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+// Responds to the json rpc request with the given `response` body.
+fn write_rpc_response(stream: &TcpStream, response: &serde_json::Value) {
+    let response = response.to_string();
+    let mut writer = stream;
+    write!(
+        writer,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        response.len(),
+        response
+    ).unwrap();
+}
+
+// Reads the json rpc request and responds with the given `result`.
+fn respond_to_rpc_request(stream: &TcpStream, result: u64) {
+    let request = read_rpc_request(stream);
+    let response = json!({ "result": result, "error": null, "id": request["id"] });
+    write_rpc_response(stream, &response);
+}
+
+// We request the block count twice
+// problems: None
+// result: both calls are served by the single pooled connection
+#[test]
+fn test_anchoring_rpc_pooled_connection_reuse() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        respond_to_rpc_request(&stream, 100);
+        respond_to_rpc_request(&stream, 101);
+    });
+
+    let client = pooled_client(&addr, 5_000);
+    assert_eq!(client.get_block_count().unwrap(), 100);
+    assert_eq!(client.get_block_count().unwrap(), 101);
+    server.join().unwrap();
+}
+
+// We request the info of the anchoring tx along with its previous tx
+// problems: bitcoind responds to the batch in the reverse order
+// result: both calls are sent in the single http request and the responses are matched by ids
+#[test]
+fn test_anchoring_rpc_pooled_batch_request() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    let anchored_tx = testkit.latest_anchored_tx();
+    let funding_tx = testkit.current_funding_tx();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let tx_info = confirmations_request(&anchored_tx, 5).response.unwrap();
+    let funding_tx_hex = funding_tx.to_hex();
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let request = read_rpc_request(&stream);
+        let response = json!([
+            { "result": funding_tx_hex, "error": null, "id": request[1]["id"] },
+            { "result": tx_info, "error": null, "id": request[0]["id"] }
+        ]);
+        write_rpc_response(&stream, &response);
+        request
+    });
+
+    let client = pooled_client(&addr, 5_000);
+    let (info, prev_tx) = client
+        .get_transaction_info_with_prev(anchored_tx.id(), funding_tx.id())
+        .unwrap();
+    let info = info.unwrap();
+    assert_eq!(info.body, BitcoinTx::from(anchored_tx.clone()));
+    assert_eq!(info.confirmations, Some(5));
+    assert_eq!(prev_tx, Some(BitcoinTx::from(funding_tx.clone())));
+
+    let request = server.join().unwrap();
+    let calls = request.as_array().unwrap();
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0]["method"], "getrawtransaction");
+    assert_eq!(calls[0]["params"], json!([anchored_tx.id(), 1]));
+    assert_eq!(calls[1]["method"], "getrawtransaction");
+    assert_eq!(calls[1]["params"], json!([funding_tx.id(), 0]));
+}
+
+// We sign the sighash by the external signer
+// problems: None
+// result: the signer is requested by the json rpc and its public key is cached
+#[test]
+fn test_anchoring_external_signer_requests() {
+    let (pub_key, priv_key) = gen_btc_keypair(BitcoinNetwork::Testnet);
+    let address = btc::Address::from("2N1mHzwKTmjnC7JjqeGFBRKYE4WDTjTfop1");
+    let sighash = Sha256dHash::from_data(&[1, 2, 3]);
+    let mut private_keys = BTreeMap::new();
+    private_keys.insert(address.to_string(), priv_key);
+    let signature = LocalSigner::new(&private_keys)
+        .sign(&sighash, &address)
+        .unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let results = [json!(encode_hex(&signature)), json!(pub_key)];
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut requests = Vec::new();
+        for result in &results {
+            let request = read_rpc_request(&stream);
+            let response = json!({ "result": result, "error": null, "id": request["id"] });
+            write_rpc_response(&stream, &response);
+            requests.push(request);
+        }
+        requests
+    });
+
+    let connection = RpcConnectionConfig {
+        connect_timeout: 1_000,
+        read_timeout: 5_000,
+        pool_size: 1,
+    };
+    let signer = ExternalSigner::new(&format!("http://{}", addr), None, None, connection);
+    assert_eq!(signer.sign(&sighash, &address).unwrap(), signature);
+    assert_eq!(signer.public_key(&address).unwrap(), Some(pub_key));
+    assert_eq!(signer.public_key(&address).unwrap(), Some(pub_key));
+
+    let requests = server.join().unwrap();
+    assert_eq!(requests[0]["method"], "sign");
+    assert_eq!(
+        requests[0]["params"],
+        json!([address.to_string(), encode_hex(&sighash[..])])
+    );
+    assert_eq!(requests[1]["method"], "public_key");
+    assert_eq!(requests[1]["params"], json!([address.to_string()]));
+}
+
+// We request the block count from the bitcoind that accepts the connections
+// problems: bitcoind never responds
+// result: every attempt fails by the read timeout instead of hanging and is retried
+#[test]
+fn test_anchoring_rpc_read_timeout() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    // The accepted connections are kept open without responses.
+    let server = thread::spawn(move || {
+        listener
+            .incoming()
+            .take(2)
+            .map(|stream| stream.unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    let config = RpcRetryConfig {
+        max_attempts: 2,
+        initial_delay: 0,
+        max_delay: 0,
+    };
+    let relay = RetryingRpc::new(pooled_client(&addr, 200), config);
+    let start = Instant::now();
+    assert!(relay.get_block_count().is_err());
+    assert!(start.elapsed() < Duration::from_secs(5));
+    // Both attempts have reached the server.
+    assert_eq!(server.join().unwrap().len(), 2);
+}
+
+// We pass the mainnet address to the relays of the testnet node
+// problems: the address belongs to another network
+// result: the calls fail before reaching the node
+#[test]
+fn test_anchoring_relay_rejects_address_of_another_network() {
+    let mainnet_addr = btc::Address::from("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy");
+    // Nothing listens on the port, so the calls would fail differently after the check.
+    let client = pooled_client("127.0.0.1:1", 200);
+    for e in vec![
+        client.watch_address(&mainnet_addr, false).unwrap_err(),
+        client.unspent_transactions(&mainnet_addr).unwrap_err(),
+        client.send_to_address(&mainnet_addr, 1000).unwrap_err(),
+        electrum_relay("127.0.0.1:1")
+            .unspent_transactions(&mainnet_addr)
+            .unwrap_err(),
+    ] {
+        assert!(
+            e.to_string().contains("does not belong to the testnet network"),
+            "{}",
+            e
+        );
+    }
+}
+
+fn electrum_relay(addr: &str) -> ElectrumRelay {
+    let config = ElectrumConfig {
+        address: addr.to_owned(),
+    };
+    let connection = RpcConnectionConfig {
+        connect_timeout: 1_000,
+        read_timeout: 5_000,
+        pool_size: 1,
+    };
+    ElectrumRelay::new(config, BitcoinNetwork::Testnet, connection)
+}
+
+// Serves the line-delimited json rpc requests of the Electrum relay by the given
+// `(method, response)` pairs in order and returns the params of the requests.
+fn electrum_server(
+    responses: Vec<(&'static str, serde_json::Value)>,
+) -> (String, thread::JoinHandle<Vec<serde_json::Value>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(&stream);
+        let mut writer = &stream;
+        // The relay should skip the notifications of the subscriptions.
+        writeln!(
+            writer,
+            "{}",
+            json!({ "jsonrpc": "2.0", "method": "blockchain.headers.subscribe", "params": [] })
+        ).unwrap();
+        responses
+            .into_iter()
+            .map(|(method, mut response)| {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let request: serde_json::Value = serde_json::from_str(&line).unwrap();
+                assert_eq!(request["method"], method);
+                response["jsonrpc"] = json!("2.0");
+                response["id"] = request["id"].clone();
+                writeln!(writer, "{}", response).unwrap();
+                request["params"].clone()
+            })
+            .collect()
+    });
+    (addr, server)
+}
+
+// We compute the Electrum script hash of the P2PKH script from the protocol docs
+// problems: None
+// result: the hash is the reversed sha256 of the script
+#[test]
+fn test_anchoring_electrum_script_hash() {
+    let script = Script::from(
+        Vec::<u8>::from_hex("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac").unwrap(),
+    );
+    assert_eq!(
+        script_hash(&script),
+        "8b01df4e368ea28f8dc0423bcf7a4923e3a12d307c875e47a0cfbf90b5c39161"
+    );
+}
+
+// We list the unspent transactions of the anchoring address through the Electrum server
+// problems: None
+// result: the funding tx is found with the confirmations counted from the best height
+#[test]
+fn test_anchoring_electrum_unspent_transactions() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    let funding_tx = testkit.current_funding_tx();
+    let addr = testkit.current_addr();
+
+    let (server_addr, server) = electrum_server(vec![
+        (
+            "blockchain.scripthash.listunspent",
+            json!({ "result": [
+                { "tx_hash": funding_tx.id(), "tx_pos": 0, "height": 100, "value": 10_000 }
+            ] }),
+        ),
+        (
+            "blockchain.headers.subscribe",
+            json!({ "result": { "height": 104, "hex": "" } }),
+        ),
+        (
+            "blockchain.transaction.get",
+            json!({ "result": funding_tx.to_hex() }),
+        ),
+    ]);
+
+    let relay = electrum_relay(&server_addr);
+    let unspent_txs = relay.unspent_transactions(&addr).unwrap();
+    assert_eq!(unspent_txs.len(), 1);
+    assert_eq!(unspent_txs[0].body, BitcoinTx::from(funding_tx.clone()));
+    assert_eq!(unspent_txs[0].confirmations, Some(5));
+
+    let params = server.join().unwrap();
+    assert_eq!(params[0], json!([script_hash(&addr.script_pubkey())]));
+    assert_eq!(params[2], json!([funding_tx.id()]));
+}
+
+// We request the confirmations of the anchoring tx through the Electrum server
+// problems: the tx is unknown at first, then it is in the mempool
+// result: the confirmations are absent until the tx is in a block
+#[test]
+fn test_anchoring_electrum_transaction_confirmations() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    let tx = testkit.latest_anchored_tx();
+    let tx_hex = tx.to_hex();
+
+    let (server_addr, server) = electrum_server(vec![
+        (
+            "blockchain.transaction.get",
+            json!({ "error": {
+                "code": 2,
+                "message": "daemon error: No such mempool or blockchain transaction"
+            } }),
+        ),
+        ("blockchain.transaction.get", json!({ "result": tx_hex })),
+        (
+            "blockchain.scripthash.get_history",
+            json!({ "result": [{ "tx_hash": tx.id(), "height": 0 }] }),
+        ),
+        ("blockchain.transaction.get", json!({ "result": tx_hex })),
+        (
+            "blockchain.scripthash.get_history",
+            json!({ "result": [
+                { "tx_hash": tx.prev_hash(), "height": 90 },
+                { "tx_hash": tx.id(), "height": 101 }
+            ] }),
+        ),
+        (
+            "blockchain.headers.subscribe",
+            json!({ "result": { "height": 110, "hex": "" } }),
+        ),
+    ]);
+
+    let relay = electrum_relay(&server_addr);
+    assert!(relay.get_transaction_info(tx.id()).unwrap().is_none());
+    assert_eq!(relay.get_transaction_confirmations(tx.id()).unwrap(), None);
+    assert_eq!(
+        relay.get_transaction_confirmations(tx.id()).unwrap(),
+        Some(10)
+    );
+
+    let params = server.join().unwrap();
+    assert_eq!(params[2], json!([script_hash(&tx.output[0].script_pubkey)]));
+}
+
+// We send the anchoring tx through the Electrum server twice
+// problems: the second broadcast is rejected because the tx is already in the mempool
+// result: both sends succeed
+#[test]
+fn test_anchoring_electrum_send_transaction() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    let tx = BitcoinTx::from(testkit.latest_anchored_tx());
+
+    let (server_addr, server) = electrum_server(vec![
+        (
+            "blockchain.transaction.broadcast",
+            json!({ "result": tx.id() }),
+        ),
+        (
+            "blockchain.transaction.broadcast",
+            json!({ "error": { "code": 1, "message": "txn-already-in-mempool" } }),
+        ),
+    ]);
+
+    let config = RpcRetryConfig {
+        max_attempts: 2,
+        initial_delay: 0,
+        max_delay: 0,
+    };
+    let relay = RetryingRpc::new(electrum_relay(&server_addr), config);
+    relay.send_transaction(tx.clone()).unwrap();
+    relay.send_transaction(tx.clone()).unwrap();
+
+    let params = server.join().unwrap();
+    assert_eq!(params, vec![json!([tx.to_hex()]); 2]);
+}
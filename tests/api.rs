@@ -21,6 +21,9 @@ extern crate exonum_btc_anchoring;
 extern crate exonum_testkit;
 #[macro_use]
 extern crate failure;
+extern crate futures;
+extern crate iron;
+extern crate iron_test;
 extern crate libc;
 #[macro_use]
 extern crate log;
@@ -29,25 +32,59 @@ extern crate pretty_assertions;
 extern crate rand;
 extern crate secp256k1;
 extern crate serde;
+extern crate serde_cbor;
 #[macro_use]
 extern crate serde_json;
+extern crate ws;
 
 #[macro_use]
 pub mod testkit_extras;
 
-use exonum::blockchain::{Blockchain, StoredConfiguration};
-use exonum::crypto::{CryptoHash, Hash};
-use exonum::encoding::serialize::FromHex;
+use std::str::FromStr;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use bitcoin::blockdata::script::Script;
+use exonum::blockchain::{Blockchain, Schema as CoreSchema, StoredConfiguration, Transaction};
+use exonum::crypto::{gen_keypair, hash, CryptoHash, Hash, PublicKey};
+use exonum::encoding::serialize::{encode_hex, FromHex};
 use exonum::helpers::{Height, ValidatorId};
 use exonum::messages::Message;
+use exonum::node::ApiSender;
+use exonum::storage::MemoryDB;
 use exonum_testkit::{ApiKind, TestKitApi};
 
-use exonum_btc_anchoring::api::{AnchoredBlockHeaderProof, AnchoringInfo, LectInfo};
-use exonum_btc_anchoring::blockchain::dto::MsgAnchoringUpdateLatest;
+use iron::headers::{qitem, Accept, AccessControlAllowOrigin, CacheControl, CacheDirective,
+                    ContentType, ETag, EntityTag, Headers, IfNoneMatch, Origin};
+use iron::status;
+
+use exonum_btc_anchoring::api::subscriptions::{LectsNotifier, LectsServer, WebSocketConfig,
+                                                LECTS_RESOURCE};
+use exonum_btc_anchoring::api::{response_schema, validate_schema, verify_anchor_depth,
+                                verify_anchored_block_header_proof, AnchorInterval,
+                                AnchorNowRequest, AnchorToVerify, AnchorVerification,
+                                AnchoredBlockHeaderProof, AnchoredBlockHeaderProofs,
+                                AnchoredBlocksInfo, AnchoringChainExport, AnchoringInfo,
+                                AnchoringPsbt, AnchoringTxKind, AnchoringTxPreview,
+                                CollectedSignatures, ConfigHistoryEntry, FundingHistoryEntry,
+                                FundingTxInfo, HealthChecks, HealthState, HeightMapEntry,
+                                ImportedSignatures, InputSignature, LectAgreement, LectConflictInfo,
+                                LectInfo, LectsInfo, NextAnchorEta, NotReadyReason, ObserverStatus,
+                                PayloadInfo, PrivateApi, ProofError, ProofHeights, PruningInfo,
+                                PsbtImportRequest, PublicApi, RateLimit, RateLimitConfig, Readiness,
+                                RedeemScripts, ScheduledAnchor, SchemaError, TransitionStatus,
+                                TxClass, TxClassification, TxToClassify, ValidatorInfo,
+                                ValidatorLectDepth, MAX_PROOF_HEIGHTS_COUNT, REQUEST_ID_HEADER};
+use exonum_btc_anchoring::blockchain::dto::{MsgAnchoringTopUp, MsgAnchoringUpdateLatest};
+use exonum_btc_anchoring::blockchain::AnchoringSchema;
 use exonum_btc_anchoring::details::btc;
-use exonum_btc_anchoring::details::btc::transactions::{AnchoringTx, BitcoinTx};
+use exonum_btc_anchoring::details::btc::psbt::Psbt;
+use exonum_btc_anchoring::details::btc::transactions::{AnchoringTx, BitcoinTx, FundingTx,
+                                                       RawBitcoinTx, TransactionBuilder};
+use exonum_btc_anchoring::details::btc::HexValueEx;
 use exonum_btc_anchoring::observer::AnchoringChainObserver;
-use exonum_btc_anchoring::{ANCHORING_SERVICE_ID, ANCHORING_SERVICE_NAME};
+use exonum_btc_anchoring::{AnchoringConfig, ANCHORING_SERVICE_ID, ANCHORING_SERVICE_NAME};
 use testkit_extras::helpers::*;
 use testkit_extras::{AnchoringTestKit, TestClient};
 
@@ -56,13 +93,75 @@ trait AnchoringApi {
 
     fn current_lect_of_validator(&self, id: usize) -> LectInfo;
 
+    fn lect_agreement(&self) -> LectAgreement;
+
+    fn validators(&self) -> Vec<ValidatorInfo>;
+
+    fn lects_of_validator(&self, id: usize, from: u64, count: u64) -> LectsInfo;
+
+    fn actual_funding_tx(&self) -> Option<FundingTxInfo>;
+
+    fn actual_funding_txs(&self) -> Vec<FundingTxInfo>;
+
+    fn funding_history(&self) -> Vec<FundingHistoryEntry>;
+
+    fn actual_config(&self) -> AnchoringConfig;
+
+    fn following_config(&self) -> Option<AnchoringConfig>;
+
+    fn config_history(&self) -> Vec<ConfigHistoryEntry>;
+
     fn actual_address(&self) -> btc::Address;
 
     fn following_address(&self) -> Option<btc::Address>;
 
+    fn redeem_scripts(&self) -> RedeemScripts;
+
     fn nearest_lect(&self, height: u64) -> Option<AnchoringTx>;
 
+    fn anchor_for_block(&self, block_hash: &Hash) -> Option<AnchoringTx>;
+
+    fn anchored_blocks(&self, from: u64, count: u64) -> AnchoredBlocksInfo;
+
+    fn height_map(&self, from: u64, count: u64) -> Vec<HeightMapEntry>;
+
+    fn anchor_intervals(&self, count: u64) -> Vec<AnchorInterval>;
+
+    fn lect_by_txid(&self, txid: &btc::TxId) -> Option<AnchoringInfo>;
+
+    fn payload_by_txid(&self, txid: &btc::TxId) -> PayloadInfo;
+
+    fn raw_tx(&self, txid: &btc::TxId) -> String;
+
+    fn prev_anchor(&self, txid: &btc::TxId) -> Option<AnchoringInfo>;
+
+    fn verify_anchor(&self, tx: &RawBitcoinTx) -> AnchorVerification;
+
+    fn classify_tx(&self, tx: &RawBitcoinTx) -> TxClassification;
+
+    fn next_anchoring_tx_preview(&self) -> Option<AnchoringTxPreview>;
+
+    fn time_to_next_anchor(&self) -> NextAnchorEta;
+
     fn anchored_block_header_proof(&self, height: u64) -> AnchoredBlockHeaderProof;
+
+    fn anchored_block_header_proof_range(&self, from: u64, to: u64) -> AnchoredBlockHeaderProof;
+
+    fn anchored_block_header_proofs(&self, heights: &[u64]) -> AnchoredBlockHeaderProofs;
+
+    fn signatures(&self, txid: &btc::TxId) -> CollectedSignatures;
+
+    fn anchor_now(&self, request: &AnchorNowRequest) -> ScheduledAnchor;
+
+    fn observer_status(&self) -> ObserverStatus;
+
+    fn psbt(&self, txid: &btc::TxId) -> AnchoringPsbt;
+
+    fn import_psbt(&self, request: &PsbtImportRequest) -> ImportedSignatures;
+
+    fn export_chain(&self) -> AnchoringChainExport;
+
+    fn response_schema(&self) -> serde_json::Value;
 }
 
 impl AnchoringApi for TestKitApi {
@@ -77,6 +176,66 @@ impl AnchoringApi for TestKitApi {
         )
     }
 
+    fn lect_agreement(&self) -> LectAgreement {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            "/v1/actual_lect/agreement",
+        )
+    }
+
+    fn validators(&self) -> Vec<ValidatorInfo> {
+        self.get(ApiKind::Service(ANCHORING_SERVICE_NAME), "/v1/validators")
+    }
+
+    fn lects_of_validator(&self, id: usize, from: u64, count: u64) -> LectsInfo {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            &format!("/v1/lects/{}?from={}&count={}", id, from, count),
+        )
+    }
+
+    fn actual_funding_tx(&self) -> Option<FundingTxInfo> {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            "/v1/funding_tx/actual",
+        )
+    }
+
+    fn actual_funding_txs(&self) -> Vec<FundingTxInfo> {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            "/v1/funding_txs/actual",
+        )
+    }
+
+    fn funding_history(&self) -> Vec<FundingHistoryEntry> {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            "/v1/funding/history",
+        )
+    }
+
+    fn actual_config(&self) -> AnchoringConfig {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            "/v1/config/actual",
+        )
+    }
+
+    fn following_config(&self) -> Option<AnchoringConfig> {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            "/v1/config/following",
+        )
+    }
+
+    fn config_history(&self) -> Vec<ConfigHistoryEntry> {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            "/v1/config/history",
+        )
+    }
+
     fn actual_address(&self) -> btc::Address {
         self.get(
             ApiKind::Service(ANCHORING_SERVICE_NAME),
@@ -91,6 +250,13 @@ impl AnchoringApi for TestKitApi {
         )
     }
 
+    fn redeem_scripts(&self) -> RedeemScripts {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            "/v1/redeem_script",
+        )
+    }
+
     fn nearest_lect(&self, height: u64) -> Option<AnchoringTx> {
         self.get(
             ApiKind::Service(ANCHORING_SERVICE_NAME),
@@ -98,24 +264,183 @@ impl AnchoringApi for TestKitApi {
         )
     }
 
+    fn anchor_for_block(&self, block_hash: &Hash) -> Option<AnchoringTx> {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            &format!("/v1/anchor_for_block/{}", block_hash),
+        )
+    }
+
+    fn anchored_blocks(&self, from: u64, count: u64) -> AnchoredBlocksInfo {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            &format!("/v1/anchored_blocks?from={}&count={}", from, count),
+        )
+    }
+
+    fn height_map(&self, from: u64, count: u64) -> Vec<HeightMapEntry> {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            &format!("/v1/height_map?from={}&count={}", from, count),
+        )
+    }
+
+    fn anchor_intervals(&self, count: u64) -> Vec<AnchorInterval> {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            &format!("/v1/anchor_intervals?count={}", count),
+        )
+    }
+
+    fn lect_by_txid(&self, txid: &btc::TxId) -> Option<AnchoringInfo> {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            &format!("/v1/lect_by_txid/{}", txid),
+        )
+    }
+
+    fn payload_by_txid(&self, txid: &btc::TxId) -> PayloadInfo {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            &format!("/v1/payload/{}", txid),
+        )
+    }
+
+    fn raw_tx(&self, txid: &btc::TxId) -> String {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            &format!("/v1/tx/{}/raw", txid),
+        )
+    }
+
+    fn prev_anchor(&self, txid: &btc::TxId) -> Option<AnchoringInfo> {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            &format!("/v1/prev_anchor/{}", txid),
+        )
+    }
+
+    fn verify_anchor(&self, tx: &RawBitcoinTx) -> AnchorVerification {
+        self.post(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            "/v1/verify_anchor",
+            &AnchorToVerify { tx: tx.to_hex() },
+        )
+    }
+
+    fn classify_tx(&self, tx: &RawBitcoinTx) -> TxClassification {
+        self.post(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            "/v1/classify",
+            &TxToClassify { tx: tx.to_hex() },
+        )
+    }
+
+    fn next_anchoring_tx_preview(&self) -> Option<AnchoringTxPreview> {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            "/v1/next_anchoring_tx",
+        )
+    }
+
+    fn time_to_next_anchor(&self) -> NextAnchorEta {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            "/v1/next_anchor_eta",
+        )
+    }
+
     fn anchored_block_header_proof(&self, height: u64) -> AnchoredBlockHeaderProof {
         self.get(
             ApiKind::Service(ANCHORING_SERVICE_NAME),
             &format!("/v1/block_header_proof/{}", height),
         )
     }
+
+    fn anchored_block_header_proof_range(&self, from: u64, to: u64) -> AnchoredBlockHeaderProof {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            &format!("/v1/block_header_proof?from={}&to={}", from, to),
+        )
+    }
+
+    fn anchored_block_header_proofs(&self, heights: &[u64]) -> AnchoredBlockHeaderProofs {
+        self.post(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            "/v1/block_header_proofs",
+            &ProofHeights {
+                heights: heights.to_vec(),
+            },
+        )
+    }
+
+    fn signatures(&self, txid: &btc::TxId) -> CollectedSignatures {
+        self.get_private(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            &format!("/v1/signatures/{}", txid),
+        )
+    }
+
+    fn anchor_now(&self, request: &AnchorNowRequest) -> ScheduledAnchor {
+        self.post_private(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            "/v1/anchor_now",
+            request,
+        )
+    }
+
+    fn psbt(&self, txid: &btc::TxId) -> AnchoringPsbt {
+        self.get_private(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            &format!("/v1/psbt/{}", txid),
+        )
+    }
+
+    fn import_psbt(&self, request: &PsbtImportRequest) -> ImportedSignatures {
+        self.post_private(ApiKind::Service(ANCHORING_SERVICE_NAME), "/v1/psbt", request)
+    }
+
+    fn observer_status(&self) -> ObserverStatus {
+        self.get(
+            ApiKind::Service(ANCHORING_SERVICE_NAME),
+            "/v1/observer/status",
+        )
+    }
+
+    fn export_chain(&self) -> AnchoringChainExport {
+        self.get(ApiKind::Service(ANCHORING_SERVICE_NAME), "/v1/export")
+    }
+
+    fn response_schema(&self) -> serde_json::Value {
+        self.get(ApiKind::Service(ANCHORING_SERVICE_NAME), "/v1/schema")
+    }
 }
 
 trait ValidateProof {
     type Output;
 
-    fn validate(self, actual_config: &StoredConfiguration) -> Result<Self::Output, failure::Error>;
+    fn validate_range(
+        self,
+        actual_config: &StoredConfiguration,
+    ) -> Result<Vec<Self::Output>, failure::Error>;
+
+    fn validate(self, actual_config: &StoredConfiguration) -> Result<Self::Output, failure::Error>
+    where
+        Self: Sized,
+    {
+        let mut values = self.validate_range(actual_config)?;
+        ensure!(values.len() == 1, "Invalid values count");
+        Ok(values.remove(0))
+    }
 }
 
 impl ValidateProof for AnchoredBlockHeaderProof {
     type Output = (u64, Hash);
 
-    fn validate(self, actual_config: &StoredConfiguration) -> Result<Self::Output, failure::Error> {
+    fn validate_range(
+        self,
+        actual_config: &StoredConfiguration,
+    ) -> Result<Vec<Self::Output>, failure::Error> {
         // Checks precommits.
         for precommit in self.latest_authorized_block.precommits {
             let validator_id = precommit.validator().0 as usize;
@@ -158,8 +483,10 @@ impl ValidateProof for AnchoredBlockHeaderProof {
                 self.latest_authorized_block.block.height().0,
             )
             .map_err(|e| format_err!("An error occurred {:?}", e))?;
-        ensure!(values.len() == 1, "Invalid values count");
-        Ok((values[0].0, *values[0].1))
+        Ok(values
+            .into_iter()
+            .map(|(height, hash)| (height, *hash))
+            .collect())
     }
 }
 
@@ -176,18 +503,164 @@ fn test_api_public_common() {
         .collect::<Vec<_>>();
 
     let api = testkit.api();
-    let anchoring_info = AnchoringInfo::from(lects[0].tx());
+    let anchoring_info = AnchoringInfo::from(testkit.latest_anchored_tx());
     assert_eq!(api.actual_lect(), Some(anchoring_info));
     // Check validators lects
     for (id, lect) in lects.iter().enumerate() {
         let lect_info = LectInfo {
             hash: Message::hash(lect),
-            content: AnchoringInfo::from(lect.tx()),
+            content: AnchoringInfo::from(testkit.latest_anchored_tx()),
         };
         assert_eq!(api.current_lect_of_validator(id), lect_info);
     }
 }
 
+// Tries to get the lects history for validators.
+#[test]
+fn test_api_public_lects_of_validator() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+
+    let api = testkit.api();
+    // The funding transaction is followed by the anchoring one.
+    let contents = vec![
+        AnchoringInfo::from(testkit.current_funding_tx()),
+        AnchoringInfo::from(testkit.latest_anchored_tx()),
+    ];
+    for id in 0..4 {
+        let info = api.lects_of_validator(id, 0, 10);
+        assert_eq!(info.total_count, 2);
+        assert_eq!(
+            info.lects
+                .into_iter()
+                .map(|lect_info| lect_info.content)
+                .collect::<Vec<_>>(),
+            contents
+        );
+
+        let info = api.lects_of_validator(id, 1, 10);
+        assert_eq!(info.total_count, 2);
+        assert_eq!(info.lects.len(), 1);
+        assert_eq!(info.lects[0], api.current_lect_of_validator(id));
+
+        let info = api.lects_of_validator(id, 0, 1);
+        assert_eq!(info.lects.len(), 1);
+    }
+}
+
+// Tries to get lects history from nonexistent validator id.
+// result: Panic
+#[test]
+#[should_panic(expected = "Unknown validator id")]
+fn test_api_public_lects_of_nonexistent_validator() {
+    let testkit = AnchoringTestKit::default();
+    let api = testkit.api();
+    api.lects_of_validator(100, 0, 10);
+}
+
+// Tries to get the actual funding transaction before and after it has been spent.
+#[test]
+fn test_api_public_actual_funding_tx() {
+    let mut testkit = AnchoringTestKit::default();
+    let funding_tx = testkit.current_funding_tx();
+
+    let info = testkit.api().actual_funding_tx().unwrap();
+    assert_eq!(info.content.txid, funding_tx.id());
+    assert_eq!(info.content.kind, AnchoringTxKind::Funding);
+    assert_eq!(info.content.payload, None);
+    assert!(!info.spent);
+
+    anchor_first_block(&mut testkit);
+
+    let info = testkit.api().actual_funding_tx().unwrap();
+    assert_eq!(info.content.txid, funding_tx.id());
+    assert!(info.spent);
+}
+
+// Tries to get all funding transactions before and after the first one has been spent.
+#[test]
+fn test_api_public_actual_funding_txs() {
+    let mut testkit = AnchoringTestKit::default();
+    let funding_tx = testkit.current_funding_tx();
+
+    let infos = testkit.api().actual_funding_txs();
+    assert_eq!(infos.len(), 1);
+    assert_eq!(infos[0].content.txid, funding_tx.id());
+    assert!(!infos[0].spent);
+
+    anchor_first_block(&mut testkit);
+
+    let infos = testkit.api().actual_funding_txs();
+    assert_eq!(infos.len(), 1);
+    assert!(infos[0].spent);
+
+    let schema = AnchoringSchema::new(testkit.snapshot());
+    assert_eq!(
+        schema.spent_funding_txs().get(&funding_tx.id()),
+        Some(testkit.latest_anchored_tx().id())
+    );
+}
+
+// Tries to get the funding history after the initial funding transaction has been spent
+// and the address has been topped up.
+// result: Both transactions are in the history, only the initial one is spent
+#[test]
+fn test_api_public_funding_history() {
+    let mut testkit = AnchoringTestKit::default();
+    let funding_tx = testkit.current_funding_tx();
+    let anchoring_addr = testkit.current_addr();
+    let out = funding_tx.find_out(&anchoring_addr).unwrap() as usize;
+    let amount = funding_tx.output[out].value;
+
+    let history = testkit.api().funding_history();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].txid, funding_tx.id());
+    assert!(!history[0].spent);
+
+    anchor_first_block(&mut testkit);
+    let anchored_tx = testkit.latest_anchored_tx();
+
+    let top_up_tx = {
+        let mut raw = funding_tx.0.clone();
+        raw.output[out].value = amount * 2;
+        FundingTx::from(raw)
+    };
+    let top_up_msg = {
+        let keypair = testkit.network().validators()[0].service_keypair();
+        MsgAnchoringTopUp::new(
+            keypair.0,
+            ValidatorId(0),
+            BitcoinTx::from(top_up_tx.clone()),
+            keypair.1,
+        )
+    };
+    let top_up_height = testkit.height().next();
+    testkit.create_block_with_transactions(vec![Box::<Transaction>::from(top_up_msg)]);
+
+    let history = testkit.api().funding_history();
+    assert_eq!(
+        history,
+        vec![
+            FundingHistoryEntry {
+                txid: funding_tx.id(),
+                amount,
+                height: 0,
+                spent: true,
+                spent_by: Some(anchored_tx.id()),
+                spent_at_height: Some(0),
+            },
+            FundingHistoryEntry {
+                txid: top_up_tx.id(),
+                amount: amount * 2,
+                height: top_up_height.0,
+                spent: false,
+                spent_by: None,
+                spent_at_height: None,
+            },
+        ]
+    );
+}
+
 // Tries to get LECT from nonexistent validator id.
 // result: Panic
 #[test]
@@ -198,6 +671,83 @@ fn test_api_public_get_lect_nonexistent_validator() {
     api.current_lect_of_validator(100);
 }
 
+// Tries to get lects when the validators agreed on a transaction that is neither
+// anchoring nor funding.
+// result: Panic
+#[test]
+#[should_panic(expected = "Malformed lect")]
+fn test_api_public_get_malformed_lect() {
+    let mut testkit = AnchoringTestKit::default();
+
+    let lect_tx = BitcoinTx::from_hex(
+        "020000000152f2e44424d6cc16ce29566b54468084d1d15329b28e\
+         8fc7cb9d9d783b8a76d3010000006b4830450221009e5ae44ba558\
+         6e4aadb9e1bc5369cc9fe9f16c12ff94454ac90414f1c5a3df9002\
+         20794b24afab7501ba12ea504853a31359d718c2a7ff6dd2688e95\
+         c5bc6634ce39012102f81d4470a303a508bf03de893223c89360a5\
+         d093e3095560b71de245aaf45d57feffffff028096980000000000\
+         17a914dcfbafb4c432a24dd4b268570d26d7841a20fbbd87e7cc39\
+         0a000000001976a914b3203ee5a42f8f524d14397ef10b84277f78\
+         4b4a88acd81d1100",
+    ).unwrap();
+    {
+        let anchoring_keys = testkit.current_cfg().anchoring_keys;
+        let blockchain = testkit.blockchain_mut();
+        let mut fork = blockchain.fork();
+        {
+            let mut schema = AnchoringSchema::new(&mut fork);
+            for key in &anchoring_keys {
+                schema.add_lect(key, lect_tx.clone(), Hash::zero());
+            }
+        }
+        blockchain.merge(fork.into_patch()).unwrap();
+    }
+
+    let api = testkit.api();
+    api.actual_lect();
+}
+
+// Tries to get lects when the validators agreed on an anchoring transaction
+// with the payload of the unknown version.
+// result: Panic
+#[test]
+#[should_panic(expected = "Unknown payload version=2")]
+fn test_api_public_get_lect_unknown_payload_version() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+    let out = funding_tx.find_out(&anchoring_addr).unwrap();
+
+    let mut lect_tx: RawBitcoinTx = TransactionBuilder::with_prev_tx(&funding_tx, out)
+        .payload(Height(1234), hash(&[]))
+        .send_to(anchoring_addr.clone())
+        .fee(1000)
+        .into_transaction()
+        .unwrap()
+        .into();
+    // Same payload with the version byte set to 2
+    lect_tx.output[1].script_pubkey = Script::from_hex(
+        "6a3045584f4e554d0200d204000000000000e3b0c44298fc1c14\
+         9afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+    ).unwrap();
+    let lect_tx = BitcoinTx::from(lect_tx);
+    {
+        let anchoring_keys = testkit.current_cfg().anchoring_keys;
+        let blockchain = testkit.blockchain_mut();
+        let mut fork = blockchain.fork();
+        {
+            let mut schema = AnchoringSchema::new(&mut fork);
+            for key in &anchoring_keys {
+                schema.add_lect(key, lect_tx.clone(), Hash::zero());
+            }
+        }
+        blockchain.merge(fork.into_patch()).unwrap();
+    }
+
+    let api = testkit.api();
+    api.actual_lect();
+}
+
 // Tries to get current LECT when there is no agreed [or consensus] LECT.
 // result: Returns null
 #[test]
@@ -234,7 +784,185 @@ fn test_api_public_get_lect_unavailable() {
     assert_eq!(api.actual_lect(), None);
 }
 
-// Tries to get actual anchoring address.
+// Lists the anchoring keys of the validators when one of them has not reported a lect.
+// result: The validator is shown without lect
+#[test]
+fn test_api_validators() {
+    let mut testkit = AnchoringTestKit::default();
+    let cfg = testkit.current_cfg();
+    assert_eq!(cfg.anchoring_keys.len(), 4);
+    {
+        let blockchain = testkit.blockchain_mut();
+        let mut fork = blockchain.fork();
+        AnchoringSchema::new(&mut fork)
+            .lects_mut(&cfg.anchoring_keys[3])
+            .clear();
+        blockchain.merge(fork.into_patch()).unwrap();
+    }
+
+    let funding_txid = testkit.current_funding_tx().id();
+    let validators = testkit.api().validators();
+    assert_eq!(validators.len(), 4);
+    for (id, info) in validators.iter().enumerate().take(3) {
+        assert_eq!(info.id, id as u32);
+        assert_eq!(info.public_key, cfg.anchoring_keys[id]);
+        assert!(info.has_lect);
+        assert_eq!(info.lect_txid, Some(funding_txid));
+    }
+    assert_eq!(
+        validators[3],
+        ValidatorInfo {
+            id: 3,
+            public_key: cfg.anchoring_keys[3],
+            has_lect: false,
+            lect_txid: None,
+        }
+    );
+}
+
+// Tries to get the lects agreement when two validators report another lect.
+// result: Lects are grouped by their txids
+#[test]
+fn test_api_public_get_lect_agreement() {
+    let mut testkit = AnchoringTestKit::default();
+
+    let lect_tx = BitcoinTx::from_hex(
+        "020000000152f2e44424d6cc16ce29566b54468084d1d15329b28e\
+         8fc7cb9d9d783b8a76d3010000006b4830450221009e5ae44ba558\
+         6e4aadb9e1bc5369cc9fe9f16c12ff94454ac90414f1c5a3df9002\
+         20794b24afab7501ba12ea504853a31359d718c2a7ff6dd2688e95\
+         c5bc6634ce39012102f81d4470a303a508bf03de893223c89360a5\
+         d093e3095560b71de245aaf45d57feffffff028096980000000000\
+         17a914dcfbafb4c432a24dd4b268570d26d7841a20fbbd87e7cc39\
+         0a000000001976a914b3203ee5a42f8f524d14397ef10b84277f78\
+         4b4a88acd81d1100",
+    ).unwrap();
+    let lects = (0..2)
+        .map(|id| {
+            let validator_id = ValidatorId(id);
+            let keypair = testkit.validator(validator_id).service_keypair();
+            MsgAnchoringUpdateLatest::new(
+                keypair.0,
+                validator_id,
+                lect_tx.clone(),
+                lects_count(&testkit, validator_id),
+                keypair.1,
+            )
+        })
+        .collect::<Vec<_>>();
+    force_commit_lects(&mut testkit, lects);
+
+    let cfg = testkit.current_cfg();
+    let funding_txid = testkit.current_funding_tx().id();
+    let others = (2..cfg.anchoring_keys.len() as u32).collect::<Vec<_>>();
+
+    let agreement = testkit.api().lect_agreement();
+    assert_eq!(agreement.majority_count, cfg.majority_count());
+    assert_eq!(agreement.lects.len(), 2);
+    assert_eq!(agreement.lects[&lect_tx.id()], vec![0, 1]);
+    assert_eq!(agreement.lects[&funding_txid], others);
+    assert!(agreement.conflicts.is_empty());
+}
+
+// A validator reports the lect that anchors the same block as its agreed lect
+// by another transaction with the lower fee.
+// result: The lect is rejected and the conflict is shown in the lects agreement
+#[test]
+fn test_api_public_get_lect_agreement_conflict() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+
+    let agreed_tx = testkit.latest_anchored_tx();
+    let conflicting_tx = {
+        let funding_tx = testkit.current_funding_tx();
+        let out = funding_tx.find_out(&testkit.current_addr()).unwrap();
+        TransactionBuilder::with_prev_tx(&funding_tx, out)
+            .payload(Height::zero(), testkit.block_hash_on_height(Height::zero()))
+            .send_to(testkit.current_addr())
+            .fee(500)
+            .into_transaction()
+            .unwrap()
+    };
+    assert_eq!(agreed_tx.payload().block_height, Height::zero());
+    assert_ne!(agreed_tx.id(), conflicting_tx.id());
+
+    let count = lects_count(&testkit, ValidatorId(0));
+    let lect_msg = gen_service_tx_lect(&testkit, ValidatorId(0), &conflicting_tx, count);
+    let msg_hash = Message::hash(&lect_msg);
+    testkit.create_block_with_transactions(vec![Box::<Transaction>::from(lect_msg)]);
+    assert_eq!(lects_count(&testkit, ValidatorId(0)), count);
+
+    let agreement = testkit.api().lect_agreement();
+    assert_eq!(agreement.lects.len(), 1);
+    assert_eq!(agreement.lects[&agreed_tx.id()], vec![0, 1, 2, 3]);
+    assert_eq!(
+        agreement.conflicts,
+        vec![LectConflictInfo {
+            validator: 0,
+            height: 0,
+            agreed_txid: agreed_tx.id(),
+            conflicting_txid: conflicting_tx.id(),
+            msg_hash,
+        }]
+    );
+
+    // The repeated conflicting lect is recorded only once.
+    let lect_msg = gen_service_tx_lect(&testkit, ValidatorId(0), &conflicting_tx, count);
+    testkit.create_block_with_transactions(vec![Box::<Transaction>::from(lect_msg)]);
+    let agreement = testkit.api().lect_agreement();
+    assert_eq!(agreement.conflicts.len(), 1);
+    assert_eq!(agreement.conflicts[0].msg_hash, msg_hash);
+}
+
+// The validators report the lect that anchors the same block as their agreed lect
+// by another transaction, e.g. after the reorganization of the bitcoin blockchain.
+// result: The conflicting lect replaces the agreed one once +2/3 validators have reported it
+#[test]
+fn test_api_public_get_lect_agreement_conflict_reorg() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+
+    let agreed_tx = testkit.latest_anchored_tx();
+    let conflicting_tx = {
+        let funding_tx = testkit.current_funding_tx();
+        let out = funding_tx.find_out(&testkit.current_addr()).unwrap();
+        TransactionBuilder::with_prev_tx(&funding_tx, out)
+            .payload(Height::zero(), testkit.block_hash_on_height(Height::zero()))
+            .send_to(testkit.current_addr())
+            .fee(500)
+            .into_transaction()
+            .unwrap()
+    };
+    let count = lects_count(&testkit, ValidatorId(0));
+    let report = |testkit: &mut AnchoringTestKit, id: u16| {
+        let count = lects_count(testkit, ValidatorId(id));
+        let lect_msg = gen_service_tx_lect(testkit, ValidatorId(id), &conflicting_tx, count);
+        testkit.create_block_with_transactions(vec![Box::<Transaction>::from(lect_msg)]);
+    };
+
+    // The conflicting lect is rejected until +2/3 validators have reported it.
+    report(&mut testkit, 0);
+    report(&mut testkit, 1);
+    assert_eq!(lects_count(&testkit, ValidatorId(0)), count);
+    assert_eq!(lects_count(&testkit, ValidatorId(1)), count);
+    assert_eq!(testkit.api().lect_agreement().conflicts.len(), 2);
+
+    report(&mut testkit, 2);
+    assert_eq!(lects_count(&testkit, ValidatorId(2)), count + 1);
+    let agreement = testkit.api().lect_agreement();
+    assert_eq!(agreement.lects[&conflicting_tx.id()], vec![2]);
+    assert_eq!(agreement.conflicts.len(), 2);
+
+    // The validators that have been rejected before switch to the conflicting lect as well.
+    report(&mut testkit, 0);
+    report(&mut testkit, 1);
+    let agreement = testkit.api().lect_agreement();
+    assert_eq!(agreement.lects[&conflicting_tx.id()], vec![0, 1, 2]);
+    assert_eq!(agreement.lects[&agreed_tx.id()], vec![3]);
+    assert!(agreement.conflicts.is_empty());
+}
+
+// Tries to get actual anchoring address.
 #[test]
 fn test_api_public_get_current_address() {
     let testkit = AnchoringTestKit::default();
@@ -242,6 +970,64 @@ fn test_api_public_get_current_address() {
     assert_eq!(api.actual_address(), testkit.current_addr());
 }
 
+// Tries to get actual anchoring configuration.
+#[test]
+fn test_api_public_get_actual_config() {
+    let testkit = AnchoringTestKit::default();
+    let api = testkit.api();
+    assert_eq!(api.actual_config(), testkit.current_cfg());
+
+    let (redeem_script, address) = testkit.current_cfg().redeem_script();
+    let value: serde_json::Value = api.get(
+        ApiKind::Service(ANCHORING_SERVICE_NAME),
+        "/v1/config/actual",
+    );
+    assert_eq!(value["redeem_script"], json!(redeem_script.to_string()));
+    assert_eq!(value["address"], json!(address.to_string()));
+    assert_eq!(value["majority_count"], json!(3));
+    assert_eq!(value["frequency"], json!(testkit.current_cfg().frequency));
+    // Only the public part of the configuration is exposed.
+    let mut fields = value
+        .as_object()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect::<Vec<_>>();
+    fields.sort();
+    assert_eq!(
+        fields,
+        vec![
+            "address",
+            "anchoring_keys",
+            "fee",
+            "frequency",
+            "funding_tx",
+            "majority_count",
+            "network",
+            "redeem_script",
+            "utxo_confirmations",
+        ]
+    );
+}
+
+// Tries to get the transition status when there is no following configuration.
+#[test]
+fn test_api_public_transition_status_stable() {
+    let testkit = AnchoringTestKit::default();
+    let status: TransitionStatus = testkit
+        .api()
+        .get(ApiKind::Service(ANCHORING_SERVICE_NAME), "/v1/transition");
+    assert_eq!(
+        status,
+        TransitionStatus {
+            in_progress: false,
+            actual_address: testkit.current_addr(),
+            following_address: None,
+            collected_signatures: 0,
+        }
+    );
+}
+
 // Tries to get following address.
 #[test]
 fn test_api_public_get_following_address_existent() {
@@ -269,6 +1055,148 @@ fn test_api_public_get_following_address_existent() {
     assert_eq!(api.following_address(), Some(following_addr));
 }
 
+// Tries to get the redeem scripts of the actual and following addresses.
+// result: the hex-encoded scripts hash to the corresponding addresses.
+#[test]
+fn test_api_public_get_redeem_scripts() {
+    let mut testkit = AnchoringTestKit::default();
+    let requests = testkit.requests();
+    let api = testkit.api();
+    let network = testkit.current_cfg().network;
+
+    let value: serde_json::Value = api.get(
+        ApiKind::Service(ANCHORING_SERVICE_NAME),
+        "/v1/redeem_script",
+    );
+    let actual_script = btc::RedeemScript::from_str(value["actual"].as_str().unwrap()).unwrap();
+    assert_eq!(
+        btc::Address::from_script(&actual_script, network),
+        api.actual_address()
+    );
+    assert_eq!(value["following"], serde_json::Value::Null);
+
+    let (cfg_proposal, following_cfg) =
+        gen_following_cfg_exclude_validator(&mut testkit, Height(10));
+    let following_addr = following_cfg.redeem_script().1;
+
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    requests.expect(vec![
+        request! {
+            method: "importaddress",
+            params: [&following_addr, "multisig", false, false]
+        },
+        confirmations_request(&testkit.latest_anchored_tx(), 0),
+    ]);
+    testkit.commit_configuration_change(cfg_proposal);
+    testkit.create_block();
+
+    let redeem_scripts = api.redeem_scripts();
+    assert_eq!(redeem_scripts.actual, actual_script);
+    let following_script = redeem_scripts.following.unwrap();
+    assert_eq!(
+        btc::Address::from_script(&following_script, network),
+        following_addr
+    );
+    assert_eq!(api.following_address(), Some(following_addr));
+}
+
+// Tries to get following configuration.
+#[test]
+fn test_api_public_get_following_config_existent() {
+    let mut testkit = AnchoringTestKit::default();
+    let requests = testkit.requests();
+    let api = testkit.api();
+
+    let (cfg_proposal, following_cfg) =
+        gen_following_cfg_exclude_validator(&mut testkit, Height(10));
+    let (following_redeem_script, following_addr) = following_cfg.redeem_script();
+
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    requests.expect(vec![
+        request! {
+            method: "importaddress",
+            params: [&following_addr, "multisig", false, false]
+        },
+        confirmations_request(&testkit.latest_anchored_tx(), 0),
+    ]);
+    testkit.commit_configuration_change(cfg_proposal);
+    testkit.create_block();
+
+    assert_eq!(api.following_config(), Some(following_cfg));
+
+    let value: serde_json::Value = api.get(
+        ApiKind::Service(ANCHORING_SERVICE_NAME),
+        "/v1/config/following",
+    );
+    assert_eq!(
+        value["redeem_script"],
+        json!(following_redeem_script.to_string())
+    );
+    assert_eq!(value["address"], json!(following_addr.to_string()));
+    assert_eq!(value["actual_from"], json!(10));
+}
+
+// Tries to get the following configuration which does not exist.
+// result: Returns null
+#[test]
+fn test_api_public_get_following_config_nonexistent() {
+    let testkit = AnchoringTestKit::default();
+    let api = testkit.api();
+    assert_eq!(api.following_config(), None);
+}
+
+// Applies two configuration changes with the different fees and schedules the third one.
+// result: The history contains the genesis and the applied configs with their activation heights
+#[test]
+fn test_api_public_config_history() {
+    let mut testkit = AnchoringTestKit::new_observer();
+    let mut expected_history = vec![(0, testkit.current_cfg())];
+    assert_eq!(
+        testkit
+            .api()
+            .config_history()
+            .into_iter()
+            .map(|entry| (entry.actual_from, entry.config))
+            .collect::<Vec<_>>(),
+        expected_history
+    );
+
+    let propose_fee = |testkit: &mut AnchoringTestKit, fee: u64, actual_from: Height| {
+        let mut cfg_proposal = testkit.configuration_change_proposal();
+        cfg_proposal.set_actual_from(actual_from);
+        let mut service_cfg: AnchoringConfig =
+            cfg_proposal.service_config(ANCHORING_SERVICE_NAME);
+        service_cfg.fee = fee;
+        cfg_proposal.set_service_config(ANCHORING_SERVICE_NAME, service_cfg.clone());
+        testkit.commit_configuration_change(cfg_proposal);
+        service_cfg
+    };
+
+    for &(fee, actual_from) in &[(2_000, Height(3)), (3_000, Height(6))] {
+        let cfg = propose_fee(&mut testkit, fee, actual_from);
+        testkit.create_blocks_until(actual_from);
+        expected_history.push((actual_from.0, cfg));
+    }
+    // The scheduled configuration is not in the history until it takes effect.
+    propose_fee(&mut testkit, 4_000, Height(10));
+    testkit.create_block();
+    assert!(testkit.api().following_config().is_some());
+
+    let history = testkit.api().config_history();
+    assert_eq!(
+        history
+            .into_iter()
+            .map(|entry| (entry.actual_from, entry.config))
+            .collect::<Vec<_>>(),
+        expected_history
+    );
+    assert_eq!(testkit.current_cfg().fee, 3_000);
+}
+
 // Tries to get the following address which does not exist.
 // result: Returns null
 #[test]
@@ -309,6 +1237,7 @@ fn test_api_anchoring_observer_normal() {
             ]
         },
         get_transaction_request(&second_anchored_tx),
+        get_block_count_request(1_000),
         confirmations_request(&second_anchored_tx, 100),
         get_transaction_request(&first_anchored_tx),
         confirmations_request(&first_anchored_tx, 200),
@@ -318,6 +1247,22 @@ fn test_api_anchoring_observer_normal() {
 
     let api = testkit.api();
 
+    // Checks the heights of the bitcoin blocks that contain the anchoring transactions.
+    assert_eq!(
+        api.height_map(800, 200),
+        vec![
+            HeightMapEntry {
+                btc_height: 801,
+                exonum_height: 0,
+                txid: first_anchored_tx.id(),
+            },
+            HeightMapEntry {
+                btc_height: 901,
+                exonum_height: 10,
+                txid: second_anchored_tx.id(),
+            },
+        ]
+    );
     // Checks that `first_anchored_tx` anchors the block at height 0.
     assert_eq!(api.nearest_lect(0), Some(first_anchored_tx));
     // Checks that closest anchoring transaction for height 1 is
@@ -327,23 +1272,2327 @@ fn test_api_anchoring_observer_normal() {
     assert_eq!(api.nearest_lect(11), None);
 }
 
-// Tries to get a proof of existence for an anchored block.
+// Tries to find the anchoring transactions by the hashes of the anchored blocks.
+// result: The block is resolved to the anchor of the nearest anchored block at or after it.
 #[test]
-fn test_api_anchored_block_header_proof() {
+fn test_api_anchor_for_block() {
     let mut testkit = AnchoringTestKit::default();
-    let cfg = testkit.actual_configuration();
+    let anchoring_addr = testkit.current_addr();
+
     anchor_first_block(&mut testkit);
-    // Checks proof for the genesis block.
-    let genesis_block_proof = testkit.api().anchored_block_header_proof(0);
-    let value = genesis_block_proof.validate(&cfg).unwrap();
-    assert_eq!(value.0, 0);
-    assert_eq!(value.1, testkit.block_hash_on_height(Height(0)));
+    anchor_first_block_lect_normal(&mut testkit);
+    let first_anchored_tx = testkit.latest_anchored_tx();
+    anchor_second_block_normal(&mut testkit);
+    let second_anchored_tx = testkit.latest_anchored_tx();
+
+    let client = TestClient::default();
+    let requests = client.requests();
+    let mut observer = AnchoringChainObserver::new_with_client(
+        testkit.blockchain_mut().clone(),
+        Box::new(client),
+        0,
+    );
+    requests.expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&second_anchored_tx, &anchoring_addr, 10)
+            ]
+        },
+        get_transaction_request(&second_anchored_tx),
+        get_block_count_request(1_000),
+        confirmations_request(&second_anchored_tx, 100),
+        get_transaction_request(&first_anchored_tx),
+        confirmations_request(&first_anchored_tx, 200),
+        get_transaction_request(&testkit.current_funding_tx()),
+    ]);
+    observer.check_anchoring_chain().unwrap();
+
+    let block_hashes = [0, 5, 10, 11]
+        .iter()
+        .map(|&height| testkit.block_hash_on_height(Height(height)))
+        .collect::<Vec<_>>();
+    let api = testkit.api();
+    assert_eq!(
+        api.anchor_for_block(&block_hashes[0]),
+        Some(first_anchored_tx)
+    );
+    assert_eq!(
+        api.anchor_for_block(&block_hashes[1]),
+        Some(second_anchored_tx.clone())
+    );
+    assert_eq!(
+        api.anchor_for_block(&block_hashes[2]),
+        Some(second_anchored_tx)
+    );
+    assert_eq!(api.anchor_for_block(&block_hashes[3]), None);
+
+    let api = PublicApi::new(testkit.blockchain_mut().clone());
+    let handler = api.handler();
+    let requests = [
+        (hash(&[1, 2, 3]).to_string(), status::NotFound),
+        ("0123".to_owned(), status::BadRequest),
+    ];
+    for &(ref block_hash, expected_status) in &requests {
+        let error = iron_test::request::get(
+            &format!("http://localhost:3000/v1/anchor_for_block/{}", block_hash),
+            Headers::new(),
+            &handler,
+        ).unwrap_err();
+        assert_eq!(error.response.status, Some(expected_status));
+    }
+}
+
+// Testing the observer when the bitcoin blockchain reorganization orphans the latest anchor.
+// result: The observer retreats to the prior anchor that is still in the best chain
+#[test]
+fn test_api_anchoring_observer_reorg() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_addr = testkit.current_addr();
 
+    anchor_first_block(&mut testkit);
     anchor_first_block_lect_normal(&mut testkit);
+    let first_anchored_tx = testkit.latest_anchored_tx();
     anchor_second_block_normal(&mut testkit);
-    // Checks proof for the second block.
-    let second_block_proof = testkit.api().anchored_block_header_proof(10);
-    let value = second_block_proof.validate(&cfg).unwrap();
-    assert_eq!(value.0, 10);
-    assert_eq!(value.1, testkit.block_hash_on_height(Height(10)));
+    let second_anchored_tx = testkit.latest_anchored_tx();
+
+    let first_block_hash = btc::BlockHash::from_slice(&[1; 32]).unwrap();
+    let second_block_hash = btc::BlockHash::from_slice(&[2; 32]).unwrap();
+
+    let client = TestClient::default();
+    let requests = client.requests();
+    let mut observer = AnchoringChainObserver::new_with_client(
+        testkit.blockchain_mut().clone(),
+        Box::new(client),
+        0,
+    );
+    requests.expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&second_anchored_tx, &anchoring_addr, 100)
+            ]
+        },
+        get_transaction_request(&second_anchored_tx),
+        get_block_count_request(1_000),
+        confirmations_in_block_request(&second_anchored_tx, 100, &second_block_hash),
+        get_transaction_request(&first_anchored_tx),
+        confirmations_in_block_request(&first_anchored_tx, 200, &first_block_hash),
+        get_transaction_request(&testkit.current_funding_tx()),
+    ]);
+    observer.check_anchoring_chain().unwrap();
+    assert_eq!(
+        testkit.api().nearest_lect(1),
+        Some(second_anchored_tx.clone())
+    );
+    assert_eq!(anchoring_tx_chain_heights(&testkit), vec![0, 10]);
+
+    // The block with the second anchoring transaction is orphaned,
+    // and the transaction returns to the mempool.
+    requests.expect(vec![
+        get_block_header_request(&second_block_hash, -1),
+        get_block_count_request(1_000),
+        get_block_header_request(&first_block_hash, 200),
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&second_anchored_tx, &anchoring_addr, 0)
+            ]
+        },
+        get_transaction_request(&second_anchored_tx),
+        get_block_count_request(1_000),
+        confirmations_request(&second_anchored_tx, 0),
+        get_transaction_request(&first_anchored_tx),
+    ]);
+    observer.check_anchoring_chain().unwrap();
+
+    let api = testkit.api();
+    assert_eq!(api.nearest_lect(0), Some(first_anchored_tx));
+    assert_eq!(api.nearest_lect(1), None);
+    assert_eq!(anchoring_tx_chain_heights(&testkit), vec![0]);
+}
+
+// Testing the observer when the bitcoin blockchain reorganization is deeper than the limit.
+// problems: The observer should not roll back the anchoring chain by itself
+// result: The observer keeps the chain and reports that the manual intervention is required
+// until the orphaned block returns to the best chain
+#[test]
+fn test_api_anchoring_observer_deep_reorg() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_addr = testkit.current_addr();
+
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+    let first_anchored_tx = testkit.latest_anchored_tx();
+    anchor_second_block_normal(&mut testkit);
+    let second_anchored_tx = testkit.latest_anchored_tx();
+
+    let first_block_hash = btc::BlockHash::from_slice(&[1; 32]).unwrap();
+    let second_block_hash = btc::BlockHash::from_slice(&[2; 32]).unwrap();
+
+    let client = TestClient::default();
+    let requests = client.requests();
+    let mut observer = AnchoringChainObserver::new_with_client(
+        testkit.blockchain_mut().clone(),
+        Box::new(client),
+        0,
+    ).with_max_reorg_depth(50);
+    requests.expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&second_anchored_tx, &anchoring_addr, 100)
+            ]
+        },
+        get_transaction_request(&second_anchored_tx),
+        get_block_count_request(1_000),
+        confirmations_in_block_request(&second_anchored_tx, 100, &second_block_hash),
+        get_transaction_request(&first_anchored_tx),
+        confirmations_in_block_request(&first_anchored_tx, 200, &first_block_hash),
+        get_transaction_request(&testkit.current_funding_tx()),
+    ]);
+    observer.check_anchoring_chain().unwrap();
+    assert_eq!(
+        testkit.api().observer_status(),
+        ObserverStatus {
+            manual_intervention_required: false,
+            reorg_depth: None,
+        }
+    );
+
+    // The block with the second anchoring transaction that is 100 blocks deep is orphaned.
+    requests.expect(vec![
+        get_block_header_request(&second_block_hash, -1),
+        get_block_count_request(1_000),
+    ]);
+    observer.check_anchoring_chain().unwrap();
+
+    let api = testkit.api();
+    assert_eq!(api.nearest_lect(1), Some(second_anchored_tx.clone()));
+    assert_eq!(
+        api.observer_status(),
+        ObserverStatus {
+            manual_intervention_required: true,
+            reorg_depth: Some(100),
+        }
+    );
+
+    // The observer does not follow the chain until the block returns to the best chain.
+    requests.expect(vec![
+        get_block_header_request(&second_block_hash, -1),
+        get_block_count_request(1_000),
+    ]);
+    observer.check_anchoring_chain().unwrap();
+    assert!(testkit.api().observer_status().manual_intervention_required);
+
+    requests.expect(vec![
+        get_block_header_request(&second_block_hash, 100),
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&second_anchored_tx, &anchoring_addr, 100)
+            ]
+        },
+        get_transaction_request(&second_anchored_tx),
+        get_block_count_request(1_000),
+    ]);
+    observer.check_anchoring_chain().unwrap();
+
+    let api = testkit.api();
+    assert_eq!(api.nearest_lect(1), Some(second_anchored_tx));
+    assert_eq!(
+        api.observer_status(),
+        ObserverStatus {
+            manual_intervention_required: false,
+            reorg_depth: None,
+        }
+    );
+}
+
+// Testing the observer only node that follows the anchoring chain without private keys.
+#[test]
+fn test_api_anchoring_observer_only_node() {
+    let mut testkit = AnchoringTestKit::new_observer();
+    assert!(testkit.handler().node.private_keys.is_empty());
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+    let out = funding_tx.find_out(&anchoring_addr).unwrap();
+
+    let anchored_tx = TransactionBuilder::with_prev_tx(&funding_tx, out)
+        .payload(Height(0), testkit.block_hash_on_height(Height(0)))
+        .send_to(anchoring_addr.clone())
+        .fee(1000)
+        .into_transaction()
+        .unwrap();
+    // Validators agree on the anchoring transaction.
+    let lects = (0..4)
+        .map(|idx| gen_service_tx_lect(&testkit, ValidatorId(idx), &anchored_tx, 1))
+        .map(Box::<Transaction>::from)
+        .collect::<Vec<_>>();
+    testkit.create_block_with_transactions(lects);
+    assert!(testkit.take_handler_errors().is_empty());
+
+    let client = TestClient::default();
+    let requests = client.requests();
+    let mut observer = AnchoringChainObserver::new_with_client(
+        testkit.blockchain_mut().clone(),
+        Box::new(client),
+        0,
+    );
+    requests.expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&anchored_tx, &anchoring_addr, 100)
+            ]
+        },
+        get_transaction_request(&anchored_tx),
+        get_block_count_request(1_000),
+        confirmations_request(&anchored_tx, 100),
+        get_transaction_request(&funding_tx),
+    ]);
+    observer.check_anchoring_chain().unwrap();
+
+    let api = testkit.api();
+    assert_eq!(api.nearest_lect(0), Some(anchored_tx));
+    assert_eq!(api.nearest_lect(1), None);
+}
+
+// Tries to get the mapping from the bitcoin heights to the anchored heights.
+// result: The bitcoin heights without anchors and outside of the range are skipped
+#[test]
+fn test_api_height_map() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+    let out = funding_tx.find_out(&anchoring_addr).unwrap();
+
+    // Anchored heights along with the heights of the bitcoin blocks.
+    let heights = [(0, 500), (10, 500), (20, 503), (30, 510)];
+    let chain = heights
+        .iter()
+        .map(|&(height, _)| {
+            let tx = TransactionBuilder::with_prev_tx(&funding_tx, out)
+                .payload(Height(height), Hash::zero())
+                .send_to(anchoring_addr.clone())
+                .fee(1000)
+                .into_transaction()
+                .unwrap();
+            (height, tx)
+        })
+        .collect::<Vec<_>>();
+    put_anchoring_tx_chain(&mut testkit, &chain);
+    {
+        let blockchain = testkit.blockchain_mut();
+        let mut fork = blockchain.fork();
+        {
+            let mut schema = AnchoringSchema::new(&mut fork);
+            for &(height, btc_height) in &heights {
+                schema.set_anchoring_tx_btc_height(height, Some(btc_height));
+            }
+        }
+        blockchain.merge(fork.into_patch()).unwrap();
+    }
+
+    let entry = |idx: usize| HeightMapEntry {
+        btc_height: heights[idx].1,
+        exonum_height: heights[idx].0,
+        txid: chain[idx].1.id(),
+    };
+    let api = testkit.api();
+    assert_eq!(api.height_map(0, 1000), (0..4).map(&entry).collect::<Vec<_>>());
+    assert_eq!(api.height_map(500, 4), vec![entry(0), entry(1), entry(2)]);
+    assert_eq!(api.height_map(501, 9), vec![entry(2)]);
+    assert_eq!(api.height_map(504, 6), Vec::new());
+
+    // The anchoring transactions are moved to the other bitcoin blocks or orphaned.
+    {
+        let blockchain = testkit.blockchain_mut();
+        let mut fork = blockchain.fork();
+        {
+            let mut schema = AnchoringSchema::new(&mut fork);
+            schema.set_anchoring_tx_btc_height(20, Some(505));
+            schema.set_anchoring_tx_btc_height(30, None);
+        }
+        blockchain.merge(fork.into_patch()).unwrap();
+    }
+    let moved_entry = HeightMapEntry {
+        btc_height: 505,
+        ..entry(2)
+    };
+    assert_eq!(
+        api.height_map(500, 1000),
+        vec![entry(0), entry(1), moved_entry]
+    );
+    assert_eq!(api.height_map(503, 2), Vec::new());
+}
+
+// Tries to get the intervals between the latest anchors.
+// result: The deltas are computed from the previous anchor, even if it is not requested
+#[test]
+fn test_api_anchor_intervals() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+    let out = funding_tx.find_out(&anchoring_addr).unwrap();
+
+    let api = testkit.api();
+    assert_eq!(api.anchor_intervals(10), Vec::new());
+
+    // Anchored heights along with the timestamps of the bitcoin blocks, the last one is unknown.
+    let anchors = [
+        (0, Some(1_500_000_000)),
+        (10, Some(1_500_000_600)),
+        (20, Some(1_500_002_400)),
+        (40, Some(1_500_002_300)),
+        (50, None),
+    ];
+    let chain = anchors
+        .iter()
+        .map(|&(height, _)| {
+            let tx = TransactionBuilder::with_prev_tx(&funding_tx, out)
+                .payload(Height(height), Hash::zero())
+                .send_to(anchoring_addr.clone())
+                .fee(1000)
+                .into_transaction()
+                .unwrap();
+            (height, tx)
+        })
+        .collect::<Vec<_>>();
+    put_anchoring_tx_chain(&mut testkit, &chain);
+    {
+        let blockchain = testkit.blockchain_mut();
+        let mut fork = blockchain.fork();
+        {
+            let mut schema = AnchoringSchema::new(&mut fork);
+            let mut btc_times = schema.anchoring_tx_btc_times_mut();
+            for &(height, btc_time) in &anchors {
+                if let Some(btc_time) = btc_time {
+                    btc_times.put(&height, btc_time);
+                }
+            }
+        }
+        blockchain.merge(fork.into_patch()).unwrap();
+    }
+
+    let interval = |idx: usize, height_delta, btc_time_delta| AnchorInterval {
+        exonum_height: anchors[idx].0,
+        txid: chain[idx].1.id(),
+        btc_time: anchors[idx].1,
+        height_delta,
+        btc_time_delta,
+    };
+    let intervals = vec![
+        interval(0, None, None),
+        interval(1, Some(10), Some(600)),
+        interval(2, Some(10), Some(1800)),
+        interval(3, Some(20), Some(-100)),
+        interval(4, Some(10), None),
+    ];
+    assert_eq!(api.anchor_intervals(1000), intervals);
+    assert_eq!(api.anchor_intervals(2), intervals[3..].to_vec());
+    assert_eq!(api.anchor_intervals(0), Vec::new());
+}
+
+// Tries to get a proof of existence for an anchored block.
+#[test]
+fn test_api_anchored_block_header_proof() {
+    let mut testkit = AnchoringTestKit::default();
+    let cfg = testkit.actual_configuration();
+    anchor_first_block(&mut testkit);
+    // Checks proof for the genesis block.
+    let genesis_block_proof = testkit.api().anchored_block_header_proof(0);
+    let value = genesis_block_proof.validate(&cfg).unwrap();
+    assert_eq!(value.0, 0);
+    assert_eq!(value.1, testkit.block_hash_on_height(Height(0)));
+
+    anchor_first_block_lect_normal(&mut testkit);
+    anchor_second_block_normal(&mut testkit);
+    // Checks proof for the second block.
+    let second_block_proof = testkit.api().anchored_block_header_proof(10);
+    let value = second_block_proof.validate(&cfg).unwrap();
+    assert_eq!(value.0, 10);
+    assert_eq!(value.1, testkit.block_hash_on_height(Height(10)));
+}
+
+// Validates the actual responses against the JSON Schema of the public API.
+// result: The responses match the schema, the unknown properties are reported
+#[test]
+fn test_api_response_schema() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    let api = testkit.api();
+    let schema = api.response_schema();
+    assert_eq!(schema, response_schema());
+    assert_eq!(schema["definitions"]["LectInfo"]["additionalProperties"], json!(false));
+
+    let get = |endpoint: &str| -> serde_json::Value {
+        api.get(ApiKind::Service(ANCHORING_SERVICE_NAME), endpoint)
+    };
+    validate_schema(&schema, "AnchoringInfo", &get("/v1/actual_lect/")).unwrap();
+    let mut lect = get("/v1/actual_lect/0");
+    validate_schema(&schema, "LectInfo", &lect).unwrap();
+    let proof = get("/v1/block_header_proof/0");
+    validate_schema(&schema, "AnchoredBlockHeaderProof", &proof).unwrap();
+
+    // The response with a property that is absent in the schema is treated as a drift.
+    lect["content"]["fee"] = json!(1000);
+    assert_eq!(
+        validate_schema(&schema, "LectInfo", &lect),
+        Err(SchemaError::UnknownProperty {
+            path: "#/content".to_owned(),
+            property: "fee".to_owned(),
+        })
+    );
+    lect["content"]["txid"] = json!(1);
+    assert!(validate_schema(&schema, "LectInfo", &lect).is_err());
+    assert_eq!(
+        validate_schema(&schema, "Lect", &lect),
+        Err(SchemaError::UnknownDefinition("Lect".to_owned()))
+    );
+}
+
+// Requests the proof of existence for an anchored block in CBOR.
+// result: The binary proof deserializes into the same proof as the JSON one
+#[test]
+fn test_api_anchored_block_header_proof_cbor() {
+    let mut testkit = AnchoringTestKit::default();
+    let cfg = testkit.actual_configuration();
+    anchor_first_block(&mut testkit);
+
+    let handler = PublicApi::new(testkit.blockchain_mut().clone()).handler();
+    let url = "http://localhost:3000/v1/block_header_proof/0";
+    let response = iron_test::request::get(url, Headers::new(), &handler).unwrap();
+    let body = iron_test::response::extract_body_to_string(response);
+    let json_proof: AnchoredBlockHeaderProof = serde_json::from_str(&body).unwrap();
+
+    let mut headers = Headers::new();
+    headers.set(Accept(vec![qitem("application/cbor".parse().unwrap())]));
+    let response = iron_test::request::get(url, headers, &handler).unwrap();
+    assert_eq!(
+        response.headers.get::<ContentType>(),
+        Some(&ContentType("application/cbor".parse().unwrap()))
+    );
+    let body = iron_test::response::extract_body_to_bytes(response);
+    let cbor_proof: AnchoredBlockHeaderProof = serde_cbor::from_slice(&body).unwrap();
+
+    let json_value = serde_json::to_value(&json_proof).unwrap();
+    assert_eq!(serde_json::to_value(&cbor_proof).unwrap(), json_value);
+    // The encoding is deterministic.
+    assert_eq!(body, serde_cbor::to_vec(&json_value).unwrap());
+    let value = cbor_proof.validate(&cfg).unwrap();
+    assert_eq!(value, (0, testkit.block_hash_on_height(Height(0))));
+}
+
+// Tries to get a proof for the block that has not been anchored yet.
+#[test]
+fn test_api_anchored_block_header_proof_not_enough_blocks() {
+    let mut testkit = AnchoringTestKit::default();
+    let api = PublicApi::new(testkit.blockchain_mut().clone());
+    let handler = api.handler();
+    for height in &[0, 1] {
+        let error = iron_test::request::get(
+            &format!("http://localhost:3000/v1/block_header_proof/{}", height),
+            Headers::new(),
+            &handler,
+        ).unwrap_err();
+        assert_eq!(error.response.status, Some(status::NotFound));
+    }
+}
+
+// Tries to get the proofs for the blocks after the old hashes of the anchored blocks are pruned.
+// result: The proofs above the cutoff are valid, while the pruned ones are not found.
+#[test]
+fn test_api_anchored_block_header_proof_pruned() {
+    let mut testkit = AnchoringTestKit::with_config(|cfg| {
+        cfg.anchored_blocks_retention = Some(5);
+    });
+    let cfg = testkit.actual_configuration();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+    // The genesis block is the chain tip, so nothing is pruned.
+    let api = PublicApi::new(testkit.blockchain_mut().clone());
+    assert_eq!(
+        api.pruning_info().unwrap(),
+        PruningInfo {
+            cutoff: 0,
+            retention: Some(5),
+        }
+    );
+
+    anchor_second_block_normal(&mut testkit);
+    let api = PublicApi::new(testkit.blockchain_mut().clone());
+    let cutoff = api.pruning_info().unwrap().cutoff;
+    assert!(cutoff > 0 && cutoff <= 10, "cutoff={}", cutoff);
+
+    let proof = testkit.api().anchored_block_header_proof(10);
+    let value = proof.validate(&cfg).unwrap();
+    assert_eq!(value, (10, testkit.block_hash_on_height(Height(10))));
+    let proof = testkit.api().anchored_block_header_proof(cutoff);
+    let value = proof.validate(&cfg).unwrap();
+    assert_eq!(value, (cutoff, testkit.block_hash_on_height(Height(cutoff))));
+
+    let handler = api.handler();
+    for height in &[0, cutoff - 1] {
+        let error = iron_test::request::get(
+            &format!("http://localhost:3000/v1/block_header_proof/{}", height),
+            Headers::new(),
+            &handler,
+        ).unwrap_err();
+        assert_eq!(error.response.status, Some(status::NotFound));
+        let body = iron_test::response::extract_body_to_string(error.response);
+        assert!(body.contains("is pruned"), "{}", body);
+    }
+    assert!(api.anchored_block_header_proof_range(cutoff - 1, cutoff + 1).is_err());
+    assert!(api.anchored_block_header_proofs(&[0, 10]).is_err());
+}
+
+fn anchoring_tx_chain_heights(testkit: &AnchoringTestKit) -> Vec<u64> {
+    let schema = AnchoringSchema::new(testkit.snapshot());
+    let heights = schema.anchoring_tx_chain_heights();
+    heights.iter().collect()
+}
+
+// Puts the given anchoring transactions into the chain as if they were found by the observer.
+fn put_anchoring_tx_chain(testkit: &mut AnchoringTestKit, txs: &[(u64, AnchoringTx)]) {
+    let blockchain = testkit.blockchain_mut();
+    let mut fork = blockchain.fork();
+    {
+        let mut schema = AnchoringSchema::new(&mut fork);
+        for &(height, ref tx) in txs {
+            schema.anchoring_tx_chain_mut().put(&height, tx.clone());
+            schema.anchoring_tx_chain_heights_mut().push(height);
+        }
+    }
+    blockchain.merge(fork.into_patch()).unwrap();
+}
+
+fn consensus_keys(cfg: &StoredConfiguration) -> Vec<PublicKey> {
+    cfg.validator_keys
+        .iter()
+        .map(|keys| keys.consensus_key)
+        .collect()
+}
+
+// Verifies a proof for an anchored block with the library helper.
+#[test]
+fn test_api_verify_anchored_block_header_proof() {
+    let mut testkit = AnchoringTestKit::default();
+    let keys = consensus_keys(&testkit.actual_configuration());
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+    anchor_second_block_normal(&mut testkit);
+
+    let genesis_hash = testkit.block_hash_on_height(Height(0));
+    let proof = testkit.api().anchored_block_header_proof(0);
+    assert_eq!(
+        verify_anchored_block_header_proof(&proof, 0, genesis_hash, &keys),
+        Ok(())
+    );
+
+    let second_hash = testkit.block_hash_on_height(Height(10));
+    let proof = testkit.api().anchored_block_header_proof(10);
+    assert_eq!(
+        verify_anchored_block_header_proof(&proof, 10, second_hash, &keys),
+        Ok(())
+    );
+}
+
+// Verifies tampered proofs with the library helper.
+// result: Error
+#[test]
+fn test_api_verify_anchored_block_header_proof_tampered() {
+    let mut testkit = AnchoringTestKit::default();
+    let keys = consensus_keys(&testkit.actual_configuration());
+    anchor_first_block(&mut testkit);
+    let genesis_hash = testkit.block_hash_on_height(Height(0));
+
+    // Unexpected block hash.
+    let proof = testkit.api().anchored_block_header_proof(0);
+    assert_eq!(
+        verify_anchored_block_header_proof(&proof, 0, hash(&[1, 2, 3]), &keys),
+        Err(ProofError::HashMismatch(0))
+    );
+    // Block at another height.
+    assert_eq!(
+        verify_anchored_block_header_proof(&proof, 1, genesis_hash, &keys),
+        Err(ProofError::MissingHeader(1))
+    );
+    // Precommits of the unknown validators.
+    let fake_keys = (0..keys.len())
+        .map(|_| gen_keypair().0)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        verify_anchored_block_header_proof(&proof, 0, genesis_hash, &fake_keys),
+        Err(ProofError::InvalidSignature(0))
+    );
+    // Block without the majority of precommits.
+    let mut truncated_proof = testkit.api().anchored_block_header_proof(0);
+    truncated_proof.latest_authorized_block.precommits.truncate(1);
+    assert_eq!(
+        verify_anchored_block_header_proof(&truncated_proof, 0, genesis_hash, &keys),
+        Err(ProofError::NotEnoughPrecommits {
+            actual: 1,
+            required: keys.len() * 2 / 3 + 1,
+        })
+    );
+    // Table proof for another table of the service.
+    let mut mixed_proof = testkit.api().anchored_block_header_proof(0);
+    mixed_proof.to_table =
+        CoreSchema::new(testkit.snapshot()).get_proof_to_service_table(ANCHORING_SERVICE_ID, 1);
+    assert_eq!(
+        verify_anchored_block_header_proof(&mixed_proof, 0, genesis_hash, &keys),
+        Err(ProofError::MissingTable)
+    );
+}
+
+// Tries to find the nearest lect for heights that fall into the gaps between anchored blocks.
+#[test]
+fn test_api_nearest_lect_with_gaps() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+    let out = funding_tx.find_out(&anchoring_addr).unwrap();
+
+    let chain = [0, 20, 40, 100]
+        .iter()
+        .map(|&height| {
+            let tx = TransactionBuilder::with_prev_tx(&funding_tx, out)
+                .payload(Height(height), Hash::zero())
+                .send_to(anchoring_addr.clone())
+                .fee(1000)
+                .into_transaction()
+                .unwrap();
+            (height, tx)
+        })
+        .collect::<Vec<_>>();
+    put_anchoring_tx_chain(&mut testkit, &chain);
+
+    let api = testkit.api();
+    assert_eq!(api.nearest_lect(0), Some(chain[0].1.clone()));
+    assert_eq!(api.nearest_lect(1), Some(chain[1].1.clone()));
+    assert_eq!(api.nearest_lect(20), Some(chain[1].1.clone()));
+    assert_eq!(api.nearest_lect(21), Some(chain[2].1.clone()));
+    assert_eq!(api.nearest_lect(64), Some(chain[3].1.clone()));
+    assert_eq!(api.nearest_lect(101), None);
+}
+
+// Enumerates the anchoring chain with gaps page by page.
+#[test]
+fn test_api_anchored_blocks_paging() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+    let out = funding_tx.find_out(&anchoring_addr).unwrap();
+
+    let chain = [0, 20, 40, 100]
+        .iter()
+        .map(|&height| {
+            let tx = TransactionBuilder::with_prev_tx(&funding_tx, out)
+                .payload(Height(height), Hash::zero())
+                .send_to(anchoring_addr.clone())
+                .fee(1000)
+                .into_transaction()
+                .unwrap();
+            (height, tx)
+        })
+        .collect::<Vec<_>>();
+    put_anchoring_tx_chain(&mut testkit, &chain);
+
+    let expected_page = |range: ::std::ops::Range<usize>| {
+        chain[range]
+            .iter()
+            .map(|&(height, ref tx)| (height, AnchoringInfo::from(tx.clone())))
+            .collect::<Vec<_>>()
+    };
+
+    let api = testkit.api();
+    let info = api.anchored_blocks(0, 2);
+    assert_eq!(info.total_count, 4);
+    assert_eq!(info.blocks, expected_page(0..2));
+
+    let info = api.anchored_blocks(21, 10);
+    assert_eq!(info.total_count, 4);
+    assert_eq!(info.blocks, expected_page(2..4));
+
+    let info = api.anchored_blocks(101, 10);
+    assert_eq!(info.total_count, 4);
+    assert!(info.blocks.is_empty());
+}
+
+// Looks up the known anchoring and funding transactions by their txids.
+#[test]
+fn test_api_lect_by_txid() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+    let out = funding_tx.find_out(&anchoring_addr).unwrap();
+
+    let block_hash = hash(&[1, 2, 3]);
+    let anchoring_tx = TransactionBuilder::with_prev_tx(&funding_tx, out)
+        .payload(Height(10), block_hash)
+        .send_to(anchoring_addr.clone())
+        .fee(1000)
+        .into_transaction()
+        .unwrap();
+    {
+        let anchoring_key = testkit.current_cfg().anchoring_keys[0];
+        let blockchain = testkit.blockchain_mut();
+        let mut fork = blockchain.fork();
+        {
+            let mut schema = AnchoringSchema::new(&mut fork);
+            schema.add_lect(&anchoring_key, anchoring_tx.clone(), Hash::zero());
+        }
+        blockchain.merge(fork.into_patch()).unwrap();
+    }
+
+    let api = testkit.api();
+    let info = api.lect_by_txid(&anchoring_tx.id()).unwrap();
+    assert_eq!(info.kind, AnchoringTxKind::Anchoring);
+    assert_eq!(info.payload.unwrap().block_hash, block_hash);
+
+    let info = api.lect_by_txid(&funding_tx.id()).unwrap();
+    assert_eq!(info.kind, AnchoringTxKind::Funding);
+    assert_eq!(info.payload, None);
+
+    let unknown_txid = btc::TxId::from_slice(&[0; 32]).unwrap();
+    assert_eq!(api.lect_by_txid(&unknown_txid), None);
+}
+
+// Tries to traverse the chain of three anchoring transactions backwards.
+// result: Each transaction links to the previous one, the first one has no previous anchor
+#[test]
+fn test_api_prev_anchor() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+    let out = funding_tx.find_out(&anchoring_addr).unwrap();
+
+    let mut anchoring_txs: Vec<AnchoringTx> = Vec::new();
+    for height in 0..3 {
+        let builder = match anchoring_txs.last() {
+            Some(prev_tx) => TransactionBuilder::with_prev_tx(prev_tx, 0),
+            None => TransactionBuilder::with_prev_tx(&funding_tx, out),
+        };
+        let tx = builder
+            .payload(Height(height * 10), hash(&[height as u8]))
+            .send_to(anchoring_addr.clone())
+            .fee(1000)
+            .into_transaction()
+            .unwrap();
+        anchoring_txs.push(tx);
+    }
+    {
+        let anchoring_key = testkit.current_cfg().anchoring_keys[0];
+        let blockchain = testkit.blockchain_mut();
+        let mut fork = blockchain.fork();
+        {
+            let mut schema = AnchoringSchema::new(&mut fork);
+            for tx in &anchoring_txs {
+                schema.add_lect(&anchoring_key, tx.clone(), Hash::zero());
+            }
+        }
+        blockchain.merge(fork.into_patch()).unwrap();
+    }
+
+    let api = testkit.api();
+    for pair in anchoring_txs.windows(2) {
+        let info = api.prev_anchor(&pair[1].id()).unwrap();
+        assert_eq!(info.txid, pair[0].id());
+        assert_eq!(info.kind, AnchoringTxKind::Anchoring);
+        assert_eq!(info.payload, Some(pair[0].payload()));
+    }
+    assert_eq!(api.prev_anchor(&anchoring_txs[0].id()), None);
+}
+
+// Decodes the payload of the anchoring transaction by its txid.
+#[test]
+fn test_api_payload_by_txid() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+    anchor_second_block_normal(&mut testkit);
+
+    let anchored_tx = testkit.latest_anchored_tx();
+    let payload = testkit.api().payload_by_txid(&anchored_tx.id());
+    assert_eq!(payload.block_height, Height(10));
+    assert_eq!(payload.block_hash, testkit.block_hash_on_height(Height(10)));
+    assert_eq!(payload.prev_txid, None);
+
+    // Funding and unknown transactions have no payload.
+    let api = PublicApi::new(testkit.blockchain_mut().clone());
+    let handler = api.handler();
+    let funding_txid = testkit.current_funding_tx().id();
+    let unknown_txid = btc::TxId::from_slice(&[0; 32]).unwrap();
+    for txid in &[funding_txid, unknown_txid] {
+        let error = iron_test::request::get(
+            &format!("http://localhost:3000/v1/payload/{}", txid),
+            Headers::new(),
+            &handler,
+        ).unwrap_err();
+        assert_eq!(error.response.status, Some(status::NotFound));
+    }
+}
+
+// Tries to get the raw hex of the known and unknown transactions.
+// result: Returns hex of the anchoring transaction or 404 for the unknown one
+#[test]
+fn test_api_raw_tx() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    let anchored_tx = testkit.latest_anchored_tx();
+    let hex = testkit.api().raw_tx(&anchored_tx.id());
+    let tx = BitcoinTx::from_hex(&hex).unwrap();
+    assert_eq!(tx.id(), anchored_tx.id());
+
+    let api = PublicApi::new(testkit.blockchain_mut().clone());
+    let unknown_txid = btc::TxId::from_slice(&[0; 32]).unwrap();
+    let error = iron_test::request::get(
+        &format!("http://localhost:3000/v1/tx/{}/raw", unknown_txid),
+        Headers::new(),
+        &api.handler(),
+    ).unwrap_err();
+    assert_eq!(error.response.status, Some(status::NotFound));
+}
+
+// Tries to verify the anchoring transaction of the second anchored block.
+// result: The transaction is a valid anchor
+#[test]
+fn test_api_verify_anchor_valid() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+    anchor_second_block_normal(&mut testkit);
+
+    let anchored_tx = testkit.latest_anchored_tx();
+    let verification = testkit.api().verify_anchor(&anchored_tx);
+    let block_hash = testkit.block_hash_on_height(Height(10));
+    assert_eq!(
+        verification,
+        AnchorVerification {
+            txid: anchored_tx.id(),
+            payload: Some(PayloadInfo {
+                block_hash,
+                block_height: Height(10),
+                prev_txid: None,
+                table_root: None,
+            }),
+            expected_block_hash: Some(block_hash),
+            valid: true,
+        }
+    );
+}
+
+// Tries to verify the anchoring transaction with the tampered block hash in the payload.
+// result: The transaction is not a valid anchor
+#[test]
+fn test_api_verify_anchor_tampered_payload() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+    let out = funding_tx.find_out(&anchoring_addr).unwrap();
+
+    let tampered_tx = TransactionBuilder::with_prev_tx(&funding_tx, out)
+        .payload(Height(0), hash(&[1, 2, 3]))
+        .send_to(anchoring_addr)
+        .fee(1000)
+        .into_transaction()
+        .unwrap();
+    let verification = testkit.api().verify_anchor(&tampered_tx);
+    assert_eq!(
+        verification.payload.map(|payload| payload.block_hash),
+        Some(hash(&[1, 2, 3]))
+    );
+    assert_eq!(
+        verification.expected_block_hash,
+        Some(testkit.block_hash_on_height(Height(0)))
+    );
+    assert!(!verification.valid);
+}
+
+// Tries to verify the funding transaction as an anchor.
+// result: The transaction has no payload and is not a valid anchor
+#[test]
+fn test_api_verify_anchor_not_anchoring_tx() {
+    let testkit = AnchoringTestKit::default();
+    let funding_tx = testkit.current_funding_tx();
+
+    let verification = testkit.api().verify_anchor(&funding_tx);
+    assert_eq!(
+        verification,
+        AnchorVerification {
+            txid: funding_tx.id(),
+            payload: None,
+            expected_block_hash: None,
+            valid: false,
+        }
+    );
+}
+
+// Classifies the anchoring transaction that has not been sent to the bitcoin network.
+// result: The transaction is an anchoring one with the decoded payload
+#[test]
+fn test_api_classify_tx_anchoring() {
+    let testkit = AnchoringTestKit::default();
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+    let out = funding_tx.find_out(&anchoring_addr).unwrap();
+
+    let anchoring_tx = TransactionBuilder::with_prev_tx(&funding_tx, out)
+        .payload(Height(0), hash(&[1, 2, 3]))
+        .send_to(anchoring_addr)
+        .fee(1000)
+        .into_transaction()
+        .unwrap();
+    assert_eq!(
+        testkit.api().classify_tx(&anchoring_tx),
+        TxClassification {
+            txid: anchoring_tx.id(),
+            kind: TxClass::Anchoring,
+            payload: Some(PayloadInfo {
+                block_hash: hash(&[1, 2, 3]),
+                block_height: Height(0),
+                prev_txid: None,
+                table_root: None,
+            }),
+        }
+    );
+}
+
+// Classifies the funding transaction of the genesis configuration.
+// result: The transaction is a funding one without payload
+#[test]
+fn test_api_classify_tx_funding() {
+    let testkit = AnchoringTestKit::default();
+    let funding_tx = testkit.current_funding_tx();
+
+    assert_eq!(
+        testkit.api().classify_tx(&funding_tx),
+        TxClassification {
+            txid: funding_tx.id(),
+            kind: TxClass::Funding,
+            payload: None,
+        }
+    );
+}
+
+// Classifies the transaction without payload and segwit script outputs
+// and tries to classify the malformed one.
+// result: The first transaction is classified as other, the second one is rejected
+#[test]
+fn test_api_classify_tx_other() {
+    let mut testkit = AnchoringTestKit::default();
+    let other_tx = {
+        let mut raw = testkit.current_funding_tx().0.clone();
+        for output in &mut raw.output {
+            output.script_pubkey = Script::new();
+        }
+        BitcoinTx::from(raw)
+    };
+
+    assert_eq!(
+        testkit.api().classify_tx(&other_tx),
+        TxClassification {
+            txid: other_tx.id(),
+            kind: TxClass::Other,
+            payload: None,
+        }
+    );
+
+    let api = PublicApi::new(testkit.blockchain_mut().clone());
+    assert!(api.classify_tx("deadbeef".to_owned()).is_err());
+}
+
+// Tries to preview the next anchoring transaction.
+#[test]
+fn test_api_next_anchoring_tx_preview() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    // The latest anchoring height is already anchored.
+    assert_eq!(testkit.api().next_anchoring_tx_preview(), None);
+
+    let requests = testkit.requests();
+    let anchoring_addr = testkit.current_addr();
+    let height = testkit.next_anchoring_height();
+    testkit.create_blocks_until(height);
+    requests.expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&testkit.latest_anchored_tx(), &anchoring_addr, 1)
+            ]
+        },
+        get_transaction_request(&testkit.latest_anchored_tx()),
+    ]);
+    testkit.create_block();
+
+    let preview = testkit.api().next_anchoring_tx_preview().unwrap();
+    let proposal = testkit.handler().proposal_tx.clone().unwrap();
+    assert_eq!(preview.payload.block_height, Height(10));
+    assert_eq!(
+        preview.payload.block_hash,
+        testkit.block_hash_on_height(Height(10))
+    );
+    assert_eq!(preview.txid, proposal.id());
+    assert_eq!(preview.inputs, vec![testkit.latest_anchored_tx().id()]);
+    assert_eq!(preview.address, anchoring_addr);
+    assert_eq!(preview.fee, testkit.current_cfg().fee);
+}
+
+// Tries to estimate the time until the next anchoring height.
+#[test]
+fn test_api_time_to_next_anchor() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    // Just before the next anchoring height.
+    testkit.create_blocks_until(Height(9));
+    let eta = testkit.api().time_to_next_anchor();
+    assert_eq!(eta.height, Height(9));
+    assert_eq!(eta.next_anchoring_height, Height(10));
+    assert_eq!(eta.blocks_remaining, 1);
+    assert_eq!(eta.seconds_remaining, 1);
+
+    // Just after the anchoring height.
+    let anchoring_addr = testkit.current_addr();
+    testkit.requests().expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&testkit.latest_anchored_tx(), &anchoring_addr, 1)
+            ]
+        },
+        get_transaction_request(&testkit.latest_anchored_tx()),
+    ]);
+    testkit.create_block();
+    let eta = testkit.api().time_to_next_anchor();
+    assert_eq!(eta.height, Height(10));
+    assert_eq!(eta.next_anchoring_height, Height(20));
+    assert_eq!(eta.blocks_remaining, 10);
+    assert_eq!(eta.seconds_remaining, 10);
+}
+
+// Tries to get a single proof of existence for the range of blocks.
+#[test]
+fn test_api_anchored_block_header_proof_range() {
+    let mut testkit = AnchoringTestKit::default();
+    let cfg = testkit.actual_configuration();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    let proof = testkit.api().anchored_block_header_proof_range(1, 5);
+    let values = proof.validate_range(&cfg).unwrap();
+    assert_eq!(values.len(), 4);
+    for (height, hash) in values {
+        assert_eq!(hash, testkit.block_hash_on_height(Height(height)));
+    }
+}
+
+// Tries to get proofs for the anchored and the never anchored heights.
+// result: The proof for the anchored height and the `NotFound` error for the other one
+#[test]
+fn test_api_anchored_block_header_proof_never_anchored_height() {
+    let mut testkit = AnchoringTestKit::default();
+    let cfg = testkit.actual_configuration();
+    anchor_first_block(&mut testkit);
+
+    let latest_height = testkit.height().0;
+    let proof = testkit.api().anchored_block_header_proof(latest_height - 1);
+    let value = proof.validate(&cfg).unwrap();
+    assert_eq!(value.0, latest_height - 1);
+
+    let api = PublicApi::new(testkit.blockchain_mut().clone());
+    let handler = api.handler();
+    let urls = [
+        format!("/v1/block_header_proof/{}", latest_height + 1),
+        format!("/v1/block_header_proof?from=0&to={}", latest_height + 2),
+    ];
+    for url in &urls {
+        let error = iron_test::request::get(
+            &format!("http://localhost:3000{}", url),
+            Headers::new(),
+            &handler,
+        ).unwrap_err();
+        assert_eq!(error.response.status, Some(status::NotFound));
+    }
+}
+
+// Tries to get the proofs of existence for the blocks at the discrete heights.
+// result: Each of the proofs is valid on its own
+#[test]
+fn test_api_anchored_block_header_proofs() {
+    let mut testkit = AnchoringTestKit::default();
+    let keys = consensus_keys(&testkit.actual_configuration());
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+    anchor_second_block_normal(&mut testkit);
+
+    let heights = [10, 0, 3];
+    let proofs = testkit.api().anchored_block_header_proofs(&heights);
+    assert_eq!(proofs.to_block_headers.len(), heights.len());
+    let proofs = proofs.into_proofs();
+    for (&height, (proof_height, proof)) in heights.iter().zip(proofs) {
+        assert_eq!(proof_height, height);
+        let hash = testkit.block_hash_on_height(Height(height));
+        assert_eq!(
+            verify_anchored_block_header_proof(&proof, height, hash, &keys),
+            Ok(())
+        );
+    }
+}
+
+// Tries to get the proofs along with the depths of the anchors from the bitcoind.
+// result: The depth of the nearest anchor is reported and cached
+#[test]
+fn test_api_anchored_block_header_proof_anchor_depth() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+    let first_anchored_tx = testkit.latest_anchored_tx();
+    anchor_second_block_normal(&mut testkit);
+    let second_anchored_tx = testkit.latest_anchored_tx();
+    let chain = vec![first_anchored_tx, second_anchored_tx];
+    put_anchoring_tx_chain(&mut testkit, &[(0, chain[0].clone()), (10, chain[1].clone())]);
+
+    let client = TestClient::default();
+    let requests = client.requests();
+    let api = PublicApi::new(testkit.blockchain_mut().clone()).with_relay(Box::new(client));
+    requests.expect(vec![confirmations_request(&chain[1], 100)]);
+
+    let proof = api.anchored_block_header_proof(3).unwrap();
+    let depth = proof.anchor_depth.as_ref().unwrap();
+    assert_eq!(depth.txid, chain[1].id());
+    assert_eq!(depth.confirmations, Some(100));
+    assert_eq!(verify_anchor_depth(&proof, 100), Ok(100));
+    assert_eq!(
+        verify_anchor_depth(&proof, 101),
+        Err(ProofError::NotEnoughConfirmations {
+            actual: 100,
+            required: 101,
+        })
+    );
+    // Checks that the depth is served from the cache.
+    let proof = api.anchored_block_header_proof_range(1, 11).unwrap();
+    assert_eq!(proof.anchor_depth.unwrap().confirmations, Some(100));
+
+    requests.expect(vec![confirmations_request(&chain[0], 101)]);
+    let proofs = api.anchored_block_header_proofs(&[0, 10]).unwrap();
+    let depths = proofs
+        .into_proofs()
+        .into_iter()
+        .map(|(_, proof)| proof.anchor_depth.unwrap())
+        .map(|depth| (depth.txid, depth.confirmations))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        depths,
+        vec![(chain[0].id(), Some(101)), (chain[1].id(), Some(100))]
+    );
+}
+
+// Tries to get the proof when the bitcoind is unavailable.
+// result: The depth of the anchor is unknown and it is not cached
+#[test]
+fn test_api_anchored_block_header_proof_anchor_depth_unknown() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+    let lect = testkit.latest_anchored_tx();
+    put_anchoring_tx_chain(&mut testkit, &[(0, lect.clone())]);
+
+    let client = TestClient::default();
+    let requests = client.requests();
+    let api = PublicApi::new(testkit.blockchain_mut().clone()).with_relay(Box::new(client));
+    requests.expect(vec![request! {
+        method: "getrawtransaction",
+        params: [&lect.id(), 1],
+        error: RpcError::Rpc(JsonRpcError::Rpc(json!({
+            "code": -28,
+            "message": "Loading block index...",
+        })))
+    }]);
+
+    let proof = api.anchored_block_header_proof(0).unwrap();
+    let depth = proof.anchor_depth.as_ref().unwrap();
+    assert_eq!(depth.txid, lect.id());
+    assert_eq!(depth.confirmations, None);
+    assert_eq!(
+        verify_anchor_depth(&proof, 1),
+        Err(ProofError::UnknownAnchorDepth)
+    );
+
+    requests.expect(vec![confirmations_request(&lect, 6)]);
+    let proof = api.anchored_block_header_proof(0).unwrap();
+    assert_eq!(verify_anchor_depth(&proof, 6), Ok(6));
+
+    // Checks that the blocks after the latest anchor are not proved by any anchor.
+    let latest_height = testkit.height().0;
+    let proof = testkit.api().anchored_block_header_proof(latest_height - 1);
+    assert_eq!(proof.anchor_depth, None);
+    assert_eq!(verify_anchor_depth(&proof, 0), Err(ProofError::NotAnchored));
+}
+
+// Tries to export the whole anchoring chain.
+// result: Exported entries match the schema and anchor the corresponding block hashes
+#[test]
+fn test_api_export_chain() {
+    let mut testkit = AnchoringTestKit::default();
+    let api = testkit.api();
+    assert!(api.export_chain().entries.is_empty());
+
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+    let first_anchored_tx = testkit.latest_anchored_tx();
+    anchor_second_block_normal(&mut testkit);
+    let second_anchored_tx = testkit.latest_anchored_tx();
+    // The anchoring chain is filled by the observer.
+    put_anchoring_tx_chain(
+        &mut testkit,
+        &[(0, first_anchored_tx), (10, second_anchored_tx)],
+    );
+
+    let export = api.export_chain();
+    assert_eq!(export.config, testkit.current_cfg());
+
+    let schema = AnchoringSchema::new(testkit.snapshot());
+    let chain = schema.anchoring_tx_chain().iter().collect::<Vec<_>>();
+    assert_eq!(chain.len(), 2);
+    assert_eq!(export.entries.len(), chain.len());
+    for (entry, (height, tx)) in export.entries.iter().zip(chain) {
+        assert_eq!(entry.height, height);
+        assert_eq!(entry.tx, tx);
+
+        let payload = entry.tx.payload();
+        assert_eq!(payload.block_height, Height(entry.height));
+        assert_eq!(
+            payload.block_hash,
+            testkit.block_hash_on_height(Height(entry.height))
+        );
+    }
+}
+
+// Tries to collect the diagnostics of the service with the anchored blocks.
+// result: All sections are present and the private keys are not exposed
+#[test]
+fn test_api_diagnostics() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+    let lect = testkit.latest_anchored_tx();
+    put_anchoring_tx_chain(&mut testkit, &[(0, lect.clone())]);
+    let anchoring_addr = testkit.current_addr();
+
+    let client = TestClient::default();
+    let requests = client.requests();
+    let api = PublicApi::new(testkit.blockchain_mut().clone()).with_relay(Box::new(client));
+    requests.expect(vec![
+        confirmations_request(&lect, 3),
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [listunspent_entry(&lect, &anchoring_addr, 3)]
+        },
+        get_transaction_request(&lect),
+    ]);
+
+    let diagnostics = api.diagnostics().unwrap();
+    let cfg = testkit.current_cfg();
+    assert_eq!(diagnostics.height, testkit.height());
+    assert_eq!(diagnostics.actual_config.address, anchoring_addr);
+    assert_eq!(
+        diagnostics.actual_config.keys_count,
+        cfg.anchoring_keys.len() as u64
+    );
+    assert_eq!(
+        diagnostics.actual_config.funding_txs,
+        vec![testkit.current_funding_tx().id()]
+    );
+    assert_eq!(diagnostics.following_config, None);
+    assert_eq!(diagnostics.actual_lect.unwrap().txid, lect.id());
+    assert_eq!(
+        diagnostics.actual_lect_confirmations.unwrap().confirmations,
+        Some(3)
+    );
+    assert_eq!(diagnostics.funding_balance.unwrap().total, lect.amount());
+    assert!(!diagnostics.transition.in_progress);
+    assert_eq!(diagnostics.chain_length, 1);
+    assert_eq!(diagnostics.latest_anchored_heights, vec![0]);
+    assert!(diagnostics.errors.is_empty());
+
+    // The relay responses are cached, so the endpoint does not call the bitcoind again.
+    let response = iron_test::request::get(
+        "http://localhost:3000/v1/diagnostics",
+        Headers::new(),
+        &api.handler(),
+    ).unwrap();
+    let body = iron_test::response::extract_body_to_string(response);
+    let value: serde_json::Value = serde_json::from_str(&body).unwrap();
+    for section in &[
+        "height",
+        "actual_config",
+        "following_config",
+        "actual_lect",
+        "actual_lect_confirmations",
+        "funding_balance",
+        "transition",
+        "chain_length",
+        "latest_anchored_heights",
+        "errors",
+    ] {
+        assert!(value.get(section).is_some(), "Section {} is absent", section);
+    }
+    assert!(!body.contains("private"));
+    for priv_key in testkit.priv_keys(&anchoring_addr) {
+        assert!(!body.contains(&priv_key.to_string()));
+    }
+}
+
+// Tries to get the batch proofs for the invalid sets of heights.
+// result: `BadRequest` for the wrong number of heights and `NotFound` for the never anchored one
+#[test]
+fn test_api_anchored_block_header_proofs_invalid_heights() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    let latest_height = testkit.height().0;
+
+    let handler = PublicApi::new(testkit.blockchain_mut().clone()).handler();
+    let cases = vec![
+        (vec![], status::BadRequest),
+        (vec![0; MAX_PROOF_HEIGHTS_COUNT + 1], status::BadRequest),
+        (vec![0, latest_height + 1], status::NotFound),
+    ];
+    for (heights, expected_status) in cases {
+        let mut headers = Headers::new();
+        headers.set(ContentType::json());
+        let body = serde_json::to_string(&ProofHeights { heights }).unwrap();
+        let error = iron_test::request::post(
+            "http://localhost:3000/v1/block_header_proofs",
+            headers,
+            &body,
+            &handler,
+        ).unwrap_err();
+        assert_eq!(error.response.status, Some(expected_status));
+    }
+}
+
+// Inspects the signatures of the anchoring transaction signed by a single validator.
+// result: The signatures are available on the private API only
+#[test]
+fn test_api_private_signatures() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block_without_other_signatures(&mut testkit);
+
+    let tx = testkit.latest_anchored_tx();
+    let signatures = testkit.latest_anchored_tx_signatures();
+    let cfg = testkit.current_cfg();
+
+    let info = testkit.api().signatures(&tx.id());
+    assert_eq!(info.tx.map(|tx| tx.id()), Some(tx.id()));
+    assert_eq!(info.majority_count, cfg.majority_count());
+    assert_eq!(info.remaining, cfg.majority_count() - 1);
+    assert_eq!(info.validators.len(), 1);
+    let validator = &info.validators[0];
+    let msg = &signatures[validator.validator as usize];
+    assert_eq!(
+        validator.inputs,
+        vec![InputSignature {
+            input: 0,
+            signature: encode_hex(msg.signature()),
+        }]
+    );
+
+    let handler = PublicApi::new(testkit.blockchain_mut().clone()).handler();
+    let error = iron_test::request::get(
+        &format!("http://localhost:3000/v1/signatures/{}", tx.id()),
+        Headers::new(),
+        &handler,
+    ).unwrap_err();
+    assert_eq!(error.response.status, Some(status::NotFound));
+}
+
+// The node without the anchoring key exports the anchoring transaction as PSBT
+// and imports its signature made by the external tools.
+// result: The node sends the imported signature to the anchoring round
+#[test]
+fn test_api_private_psbt() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_addr = testkit.current_addr();
+    testkit.handler().node.private_keys.clear();
+
+    let hash = testkit.last_block_hash();
+    let (proposal, mut signatures) =
+        testkit.gen_anchoring_tx_with_signatures(Height::zero(), hash, &[], None, &anchoring_addr);
+    let msgs = testkit.latest_anchored_tx_signatures();
+    let own_signature = signatures.remove(0);
+    testkit.create_block_with_transactions(signatures.drain(0..2));
+
+    let cfg = testkit.current_cfg();
+    let exported = testkit.api().psbt(&proposal.nid());
+    assert_eq!(exported.tx, proposal);
+    let mut psbt = Psbt::from_base64(&exported.psbt).unwrap();
+    assert_eq!(psbt.partial_signature(0, &cfg.anchoring_keys[0]), None);
+    for id in 1..3 {
+        assert_eq!(
+            psbt.partial_signature(0, &cfg.anchoring_keys[id]),
+            Some(msgs[id].signature())
+        );
+    }
+
+    psbt.add_partial_signature(0, &cfg.anchoring_keys[0], msgs[0].signature());
+    let request = PsbtImportRequest {
+        psbt: psbt.to_base64(),
+    };
+    let imported = testkit.api().import_psbt(&request);
+    assert_eq!(
+        imported,
+        ImportedSignatures {
+            validator: 0,
+            inputs: vec![0],
+        }
+    );
+    testkit.poll_events();
+    assert!(testkit.is_tx_in_pool(&own_signature.hash()));
+}
+
+// Requests the anchoring of the latest block before the next anchoring height.
+// result: The node sends the anchor request and the anchoring transaction for the latest
+// block is built once +2/3 validators request it
+#[test]
+fn test_api_private_anchor_now() {
+    let mut testkit = AnchoringTestKit::default();
+    let requests = testkit.requests();
+    anchor_first_block(&mut testkit);
+
+    let height = testkit.height();
+    assert!(height < testkit.next_anchoring_height());
+    let anchor = testkit.api().anchor_now(&AnchorNowRequest::default());
+    assert_eq!(
+        anchor,
+        ScheduledAnchor {
+            height: height.0,
+            anchored_height: Some(0),
+        }
+    );
+
+    let msgs = (0..3)
+        .map(|idx| gen_service_tx_anchor_request(&testkit, ValidatorId(idx), height))
+        .map(Box::<Transaction>::from)
+        .collect::<Vec<_>>();
+    testkit.poll_events();
+    assert!(testkit.is_tx_in_pool(&msgs[0].hash()));
+
+    let anchoring_addr = testkit.current_addr();
+    requests.expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&testkit.latest_anchored_tx(), &anchoring_addr, 1)
+            ]
+        },
+        get_transaction_request(&testkit.latest_anchored_tx()),
+    ]);
+    testkit.create_block_with_transactions(msgs);
+
+    let block_hash = testkit.block_hash_on_height(height);
+    let (tx, signatures) =
+        testkit.gen_anchoring_tx_with_signatures(height, block_hash, &[], None, &anchoring_addr);
+    assert!(testkit.is_tx_in_pool(&signatures[0].hash()));
+    assert_eq!(tx.payload().block_height, height);
+
+    let anchored_tx = testkit.latest_anchored_tx();
+    requests.expect(vec![get_transaction_request(&anchored_tx)]);
+    testkit.create_block_with_transactions(signatures);
+
+    let txs = (0..4)
+        .map(|idx| gen_service_tx_lect(&testkit, ValidatorId(idx), &anchored_tx, 2))
+        .map(Box::<Transaction>::from)
+        .collect::<Vec<_>>();
+    requests.expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&anchored_tx, &anchoring_addr, 100)
+            ]
+        },
+        get_transaction_request(&anchored_tx),
+    ]);
+    testkit.create_block_with_transactions(txs);
+
+    // The block that is already anchored cannot be requested.
+    let api = PrivateApi::new(testkit.blockchain_mut().clone());
+    let request = AnchorNowRequest {
+        height: Some(height.0),
+    };
+    assert!(api.anchor_now(&request).is_err());
+}
+
+// Reads the state from the snapshot that was taken before the new blocks are committed.
+#[test]
+fn test_api_snapshot_is_consistent() {
+    let mut testkit = AnchoringTestKit::default();
+    let api = PublicApi::new(testkit.blockchain_mut().clone());
+    let snapshot = api.snapshot();
+    let height = snapshot.time_to_next_anchor().unwrap().height;
+    let lect = snapshot.actual_lect().unwrap();
+
+    anchor_first_block(&mut testkit);
+
+    assert_eq!(snapshot.time_to_next_anchor().unwrap().height, height);
+    assert_eq!(snapshot.actual_lect().unwrap(), lect);
+    assert!(api.time_to_next_anchor().unwrap().height > height);
+    assert_ne!(api.actual_lect().unwrap(), lect);
+}
+
+// Tries to get a proof of existence for the inverted range of blocks.
+// result: Panic
+#[test]
+#[should_panic(expected = "Invalid height range")]
+fn test_api_anchored_block_header_proof_inverted_range() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    testkit.api().anchored_block_header_proof_range(2, 1);
+}
+
+// Sends requests with and without the correlation id.
+// result: The given id is echoed back, otherwise a new one is generated
+#[test]
+fn test_api_request_id_round_trip() {
+    let mut testkit = AnchoringTestKit::default();
+    let handler = PublicApi::new(testkit.blockchain_mut().clone()).handler();
+    let request_id = |headers: &Headers| {
+        headers
+            .get_raw(REQUEST_ID_HEADER)
+            .map(|value| String::from_utf8(value[0].clone()).unwrap())
+    };
+
+    let mut headers = Headers::new();
+    headers.set_raw(REQUEST_ID_HEADER, vec![b"test-request-42".to_vec()]);
+    let response =
+        iron_test::request::get("http://localhost:3000/v1/actual_lect/", headers, &handler)
+            .unwrap();
+    assert_eq!(
+        request_id(&response.headers),
+        Some("test-request-42".to_owned())
+    );
+
+    // The id is echoed back for the failed requests too.
+    let mut headers = Headers::new();
+    headers.set_raw(REQUEST_ID_HEADER, vec![b"test-request-43".to_vec()]);
+    let error = iron_test::request::get(
+        "http://localhost:3000/v1/block_header_proof/100",
+        headers,
+        &handler,
+    ).unwrap_err();
+    assert_eq!(error.response.status, Some(status::NotFound));
+    assert_eq!(
+        request_id(&error.response.headers),
+        Some("test-request-43".to_owned())
+    );
+
+    // Missing and malformed ids are replaced by the generated ones.
+    let response =
+        iron_test::request::get("http://localhost:3000/v1/actual_lect/", Headers::new(), &handler)
+            .unwrap();
+    let generated_id = request_id(&response.headers).unwrap();
+    assert!(!generated_id.is_empty());
+
+    let mut headers = Headers::new();
+    headers.set_raw(REQUEST_ID_HEADER, vec![b"bad\nid".to_vec()]);
+    let response =
+        iron_test::request::get("http://localhost:3000/v1/actual_lect/", headers, &handler)
+            .unwrap();
+    let generated_id = request_id(&response.headers).unwrap();
+    assert_ne!(generated_id, "bad\nid");
+}
+
+// Sends a CORS preflight request from the allowed and the unknown origins.
+#[test]
+fn test_api_cors_preflight() {
+    let mut testkit = AnchoringTestKit::default();
+    let api = PublicApi::with_cors(
+        testkit.blockchain_mut().clone(),
+        vec!["http://example.com".to_owned()],
+    );
+    let handler = api.handler();
+
+    let mut headers = Headers::new();
+    headers.set(Origin::new("http", "example.com", None));
+    let response =
+        iron_test::request::options("http://localhost:3000/v1/actual_lect/", headers, &handler)
+            .unwrap();
+    assert_eq!(response.status, Some(status::Ok));
+    assert_eq!(
+        response.headers.get::<AccessControlAllowOrigin>(),
+        Some(&AccessControlAllowOrigin::Value(
+            "http://example.com".to_owned()
+        ))
+    );
+
+    let mut headers = Headers::new();
+    headers.set(Origin::new("http", "unknown.com", None));
+    let response =
+        iron_test::request::get("http://localhost:3000/v1/actual_lect/", headers, &handler)
+            .unwrap();
+    assert_eq!(response.status, Some(status::Ok));
+    assert_eq!(response.headers.get::<AccessControlAllowOrigin>(), None);
+}
+
+// Repeats the requests of the cacheable resources with the received entity tags.
+// result: `304 Not Modified` until the lects of the validators are changed
+#[test]
+fn test_api_etag_not_modified() {
+    let mut testkit = AnchoringTestKit::default();
+    let handler = PublicApi::new(testkit.blockchain_mut().clone()).handler();
+    let get = |url: &str, etag: Option<&EntityTag>| {
+        let mut headers = Headers::new();
+        if let Some(etag) = etag {
+            headers.set(IfNoneMatch::Items(vec![etag.clone()]));
+        }
+        iron_test::request::get(&format!("http://localhost:3000{}", url), headers, &handler)
+            .unwrap()
+    };
+    let etag = |response: &iron::Response| response.headers.get::<ETag>().unwrap().0.clone();
+
+    let response = get("/v1/address/actual", None);
+    assert_eq!(response.status, Some(status::Ok));
+    assert_eq!(
+        response.headers.get::<CacheControl>(),
+        Some(&CacheControl(vec![CacheDirective::NoCache]))
+    );
+    let address_etag = etag(&response);
+    let response = get("/v1/address/actual", Some(&address_etag));
+    assert_eq!(response.status, Some(status::NotModified));
+    assert_eq!(etag(&response), address_etag);
+
+    let lect_etag = etag(&get("/v1/actual_lect/", None));
+    assert_ne!(lect_etag, address_etag);
+    assert_eq!(
+        get("/v1/actual_lect/", Some(&lect_etag)).status,
+        Some(status::NotModified)
+    );
+
+    anchor_first_block(&mut testkit);
+    let response = get("/v1/actual_lect/", Some(&lect_etag));
+    assert_eq!(response.status, Some(status::Ok));
+    assert_ne!(etag(&response), lect_etag);
+    assert_eq!(
+        get("/v1/address/actual", Some(&address_etag)).status,
+        Some(status::NotModified)
+    );
+}
+
+// Scrapes the health metrics after the first anchored block.
+#[test]
+fn test_api_metrics() {
+    let mut testkit = AnchoringTestKit::default();
+    let api = PublicApi::new(testkit.blockchain_mut().clone());
+    let handler = api.handler();
+    let scrape = || {
+        let response =
+            iron_test::request::get("http://localhost:3000/v1/metrics", Headers::new(), &handler)
+                .unwrap();
+        assert_eq!(response.status, Some(status::Ok));
+        iron_test::response::extract_body_to_string(response)
+    };
+
+    let validators_count = testkit.current_cfg().anchoring_keys.len();
+    let metrics = scrape();
+    assert!(metrics.contains(&format!(
+        "anchoring_lect_agreed_validators {}\n",
+        validators_count
+    )));
+    assert!(metrics.contains("anchoring_transition_in_progress 0\n"));
+    assert!(!metrics.contains("anchoring_anchored_height"));
+
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    let metrics = scrape();
+    assert!(metrics.contains(&format!(
+        "anchoring_lect_agreed_validators {}\n",
+        validators_count
+    )));
+    assert!(metrics.contains("# TYPE anchoring_anchored_height gauge\n"));
+    assert!(metrics.contains("anchoring_anchored_height 0\n"));
+    assert!(metrics.contains(&format!(
+        "anchoring_blocks_since_last_anchor {}\n",
+        testkit.height().0
+    )));
+    assert!(!metrics.contains("anchoring_lect_btc_height"));
+
+    let lect = testkit.latest_anchored_tx();
+    put_anchoring_tx_chain(&mut testkit, &[(0, lect)]);
+    {
+        let blockchain = testkit.blockchain_mut();
+        let mut fork = blockchain.fork();
+        AnchoringSchema::new(&mut fork).set_anchoring_tx_btc_height(0, Some(500));
+        blockchain.merge(fork.into_patch()).unwrap();
+    }
+    let metrics = scrape();
+    assert!(metrics.contains("anchoring_lect_btc_height 500\n"));
+}
+
+struct LectsSubscriber {
+    out: ws::Sender,
+    opened: mpsc::Sender<()>,
+    received: mpsc::Sender<String>,
+}
+
+impl ws::Handler for LectsSubscriber {
+    fn on_open(&mut self, _: ws::Handshake) -> ws::Result<()> {
+        self.opened.send(()).unwrap();
+        Ok(())
+    }
+
+    fn on_message(&mut self, msg: ws::Message) -> ws::Result<()> {
+        self.received.send(msg.into_text()?).unwrap();
+        self.out.close(ws::CloseCode::Normal)
+    }
+}
+
+// Subscribes to the lects via WebSocket and commits a new lect.
+// result: Subscriber receives the committed lect
+#[test]
+fn test_api_websocket_lects() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+    let out = funding_tx.find_out(&anchoring_addr).unwrap();
+
+    let notifier = LectsNotifier::new();
+    let config = WebSocketConfig {
+        listen_address: "127.0.0.1:0".parse().unwrap(),
+        out_buffer_capacity: 64 * 1024,
+    };
+    let server = LectsServer::bind(&notifier, &config).unwrap();
+    let url = format!("ws://{}{}", server.local_addr().unwrap(), LECTS_RESOURCE);
+    let server_thread = thread::spawn(move || server.run().unwrap());
+    notifier.notify(testkit.snapshot());
+
+    let (opened_tx, opened_rx) = mpsc::channel();
+    let (received_tx, received_rx) = mpsc::channel();
+    let client_thread = thread::spawn(move || {
+        ws::connect(url, |out| LectsSubscriber {
+            out,
+            opened: opened_tx.clone(),
+            received: received_tx.clone(),
+        }).unwrap();
+    });
+    opened_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+
+    let block_hash = hash(&[1, 2, 3]);
+    let anchoring_tx = TransactionBuilder::with_prev_tx(&funding_tx, out)
+        .payload(Height(10), block_hash)
+        .send_to(anchoring_addr.clone())
+        .fee(1000)
+        .into_transaction()
+        .unwrap();
+    let validator_id = ValidatorId(0);
+    let keypair = testkit.validator(validator_id).service_keypair();
+    let lect = MsgAnchoringUpdateLatest::new(
+        keypair.0,
+        validator_id,
+        anchoring_tx.clone().into(),
+        lects_count(&testkit, validator_id),
+        keypair.1,
+    );
+    force_commit_lects(&mut testkit, vec![lect.clone()]);
+    notifier.notify(testkit.snapshot());
+
+    let msg = received_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    let info: LectInfo = serde_json::from_str(&msg).unwrap();
+    assert_eq!(info.hash, Message::hash(&lect));
+    assert_eq!(info.content.txid, anchoring_tx.id());
+    assert_eq!(info.content.payload.unwrap().block_hash, block_hash);
+
+    client_thread.join().unwrap();
+    notifier.shutdown();
+    server_thread.join().unwrap();
+}
+
+// Tries to get the sync status when the lect is deeply confirmed.
+#[test]
+fn test_api_sync_status_confirmed() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+    let lect = testkit.latest_anchored_tx();
+
+    let client = TestClient::default();
+    let requests = client.requests();
+    let api = PublicApi::new(testkit.blockchain_mut().clone()).with_relay(Box::new(client));
+    requests.expect(vec![
+        request! {
+            method: "getblockcount",
+            params: [],
+            response: 1_200
+        },
+        confirmations_request(&lect, 100),
+    ]);
+
+    let status = api.sync_status().unwrap();
+    assert_eq!(status.bitcoin_height, 1_200);
+    assert_eq!(status.lect_txid, Some(lect.id()));
+    assert_eq!(status.confirmations, 100);
+    assert_eq!(status.lect_height, Some(1_101));
+    assert!(!status.stale);
+    // The repeated request is served from the cache.
+    assert_eq!(api.sync_status().unwrap(), status);
+}
+
+// Tries to get the confirmations of the actual lect reported by the bitcoind.
+// result: The value is cached until the lect changes
+#[test]
+fn test_api_actual_lect_confirmations() {
+    let mut testkit = AnchoringTestKit::default();
+    let funding_tx = testkit.current_funding_tx();
+
+    let client = TestClient::default();
+    let requests = client.requests();
+    let api = PublicApi::new(testkit.blockchain_mut().clone()).with_relay(Box::new(client));
+    requests.expect(vec![confirmations_request(&funding_tx, 42)]);
+
+    let confirmations = api.actual_lect_confirmations().unwrap().unwrap();
+    assert_eq!(confirmations.txid, funding_tx.id());
+    assert_eq!(confirmations.confirmations, Some(42));
+    assert_eq!(api.actual_lect_confirmations().unwrap(), Some(confirmations));
+
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+    let lect = testkit.latest_anchored_tx();
+    requests.expect(vec![request! {
+        method: "getrawtransaction",
+        params: [&lect.id(), 1],
+        error: RpcError::NoInformation("Unable to find tx".to_string())
+    }]);
+
+    let confirmations = api.actual_lect_confirmations().unwrap().unwrap();
+    assert_eq!(confirmations.txid, lect.id());
+    assert_eq!(confirmations.confirmations, None);
+}
+
+// Requests the confirmation depths of the lects when the validators disagree.
+// problems: The validators report the different lects.
+// result: The depths are looked up once per distinct lect.
+#[test]
+fn test_api_validator_lect_depths() {
+    let mut testkit = AnchoringTestKit::default();
+    let funding_tx = testkit.current_funding_tx();
+    anchor_first_block(&mut testkit);
+    let anchored_tx = testkit.latest_anchored_tx();
+
+    // The last two validators have lost the anchored transaction.
+    let lects = (2..4)
+        .map(|idx| gen_service_tx_lect(&testkit, ValidatorId(idx), &funding_tx, 2))
+        .collect::<Vec<_>>();
+    force_commit_lects(&mut testkit, lects);
+
+    let client = TestClient::default();
+    let requests = client.requests();
+    let api = PublicApi::new(testkit.blockchain_mut().clone()).with_relay(Box::new(client));
+    requests.expect(vec![
+        confirmations_request(&anchored_tx, 3),
+        confirmations_request(&funding_tx, 120),
+    ]);
+
+    let depths = api.validator_lect_depths().unwrap();
+    let expected = (0..4)
+        .map(|id| {
+            let (txid, confirmations) = if id < 2 {
+                (anchored_tx.id(), 3)
+            } else {
+                (funding_tx.id(), 120)
+            };
+            ValidatorLectDepth {
+                id,
+                txid,
+                confirmations: Some(confirmations),
+            }
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(depths, expected);
+}
+
+// Tries to get the sync status when the lect is not yet included in a bitcoin block.
+#[test]
+fn test_api_sync_status_unconfirmed() {
+    let mut testkit = AnchoringTestKit::default();
+    let funding_tx = testkit.current_funding_tx();
+
+    let client = TestClient::default();
+    let requests = client.requests();
+    let api = PublicApi::new(testkit.blockchain_mut().clone()).with_relay(Box::new(client));
+    requests.expect(vec![
+        request! {
+            method: "getblockcount",
+            params: [],
+            response: 1_200
+        },
+        confirmations_request(&funding_tx, 0),
+    ]);
+
+    let status = api.sync_status().unwrap();
+    assert_eq!(status.lect_txid, Some(funding_tx.id()));
+    assert_eq!(status.confirmations, 0);
+    assert_eq!(status.lect_height, None);
+    assert!(!status.stale);
+}
+
+// Tries to get the balance of the anchoring address with two unspent outputs.
+#[test]
+fn test_api_anchoring_balance() {
+    let mut testkit = AnchoringTestKit::default();
+    let anchoring_addr = testkit.current_addr();
+    let funding_tx = testkit.current_funding_tx();
+    let out = funding_tx.find_out(&anchoring_addr).unwrap();
+    let other_tx = TransactionBuilder::with_prev_tx(&funding_tx, out)
+        .payload(Height(0), testkit.block_hash_on_height(Height(0)))
+        .send_to(anchoring_addr.clone())
+        .fee(1000)
+        .into_transaction()
+        .unwrap();
+
+    let client = TestClient::default();
+    let requests = client.requests();
+    let api = PublicApi::new(testkit.blockchain_mut().clone()).with_relay(Box::new(client));
+    requests.expect(vec![
+        request! {
+            method: "listunspent",
+            params: [0, 9_999_999, [&anchoring_addr]],
+            response: [
+                listunspent_entry(&funding_tx, &anchoring_addr, 100),
+                listunspent_entry(&other_tx, &anchoring_addr, 1)
+            ]
+        },
+        get_transaction_request(&funding_tx),
+        get_transaction_request(&other_tx),
+    ]);
+
+    let expected_total = funding_tx.output[out as usize].value + other_tx.amount();
+    let balance = api.anchoring_balance().unwrap();
+    assert_eq!(balance.addresses, vec![anchoring_addr]);
+    assert_eq!(balance.total, expected_total);
+    assert_eq!(balance.utxo_count, 2);
+    assert!(!balance.cached);
+
+    // The second request is served from the cache without calling the bitcoind.
+    let cached_balance = api.anchoring_balance().unwrap();
+    assert_eq!(cached_balance.total, expected_total);
+    assert!(cached_balance.cached);
+}
+
+// Tries to get the overall state from the individual health checks.
+#[test]
+fn test_api_health_state() {
+    let checks = HealthChecks {
+        snapshot: true,
+        rpc: Some(true),
+        funding: true,
+        anchoring_lag: 5,
+        lagging: false,
+    };
+    assert_eq!(checks.state(), HealthState::Ok);
+    assert_eq!(
+        HealthChecks {
+            rpc: None,
+            ..checks.clone()
+        }.state(),
+        HealthState::Ok
+    );
+    assert_eq!(
+        HealthChecks {
+            rpc: Some(false),
+            ..checks.clone()
+        }.state(),
+        HealthState::Degraded
+    );
+    assert_eq!(
+        HealthChecks {
+            funding: false,
+            ..checks.clone()
+        }.state(),
+        HealthState::Degraded
+    );
+    assert_eq!(
+        HealthChecks {
+            lagging: true,
+            ..checks.clone()
+        }.state(),
+        HealthState::Down
+    );
+    assert_eq!(
+        HealthChecks {
+            snapshot: false,
+            ..checks.clone()
+        }.state(),
+        HealthState::Down
+    );
+}
+
+// Tries to get the health of the service with the different bitcoind states and lag thresholds.
+#[test]
+fn test_api_health() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    let get_health = |api: &PublicApi| {
+        iron_test::request::get("http://localhost:3000/v1/health", Headers::new(), &api.handler())
+            .unwrap()
+            .status
+    };
+    let block_count_request = || {
+        request! {
+            method: "getblockcount",
+            params: [],
+            response: 1_200
+        }
+    };
+
+    let client = TestClient::default();
+    let requests = client.requests();
+    let api = PublicApi::new(testkit.blockchain_mut().clone()).with_relay(Box::new(client));
+    requests.expect(vec![block_count_request()]);
+    let health = api.health().unwrap();
+    assert_eq!(health.status, HealthState::Ok);
+    assert_eq!(health.checks.rpc, Some(true));
+    assert!(health.checks.funding);
+    assert_eq!(health.checks.anchoring_lag, testkit.height().0);
+    requests.expect(vec![block_count_request()]);
+    assert_eq!(get_health(&api), Some(status::Ok));
+
+    // The bitcoind is unreachable.
+    let unavailable = || {
+        request! {
+            method: "getblockcount",
+            params: [],
+            error: RpcError::NoInformation("Connection refused".to_string())
+        }
+    };
+    requests.expect(vec![unavailable()]);
+    let health = api.health().unwrap();
+    assert_eq!(health.status, HealthState::Degraded);
+    assert_eq!(health.checks.rpc, Some(false));
+    requests.expect(vec![unavailable()]);
+    assert_eq!(get_health(&api), Some(status::Ok));
+
+    // The anchoring lags behind.
+    let api = PublicApi::new(testkit.blockchain_mut().clone()).with_max_anchoring_lag(1);
+    let health = api.health().unwrap();
+    assert_eq!(health.status, HealthState::Down);
+    assert!(health.checks.lagging);
+    assert_eq!(get_health(&api), Some(status::ServiceUnavailable));
+}
+
+// Tries to get the health of the node before the genesis block is created.
+// result: The snapshot check fails and the service is down
+#[test]
+fn test_api_health_without_genesis_block() {
+    let (service_public_key, service_secret_key) = gen_keypair();
+    let api_sender = ApiSender::new(futures::sync::mpsc::channel(1).0);
+    let blockchain = Blockchain::new(
+        MemoryDB::new(),
+        Vec::new(),
+        service_public_key,
+        service_secret_key,
+        api_sender,
+    );
+    let api = PublicApi::new(blockchain);
+
+    let health = api.health().unwrap();
+    assert_eq!(health.status, HealthState::Down);
+    assert!(!health.checks.snapshot);
+    assert_eq!(health.checks.anchoring_lag, 0);
+
+    let readiness = api.readiness().unwrap();
+    assert!(!readiness.ready);
+    assert_eq!(readiness.reasons, vec![NotReadyReason::SnapshotUnavailable]);
+}
+
+// Checks the readiness of the node that follows the anchoring chain by the observer.
+// result: The node is ready after the observer finds the anchoring transaction
+#[test]
+fn test_api_readiness() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    let client = TestClient::default();
+    let requests = client.requests();
+    let api = PublicApi::new(testkit.blockchain_mut().clone())
+        .with_relay(Box::new(client))
+        .with_observer();
+    let block_count_request = || {
+        request! {
+            method: "getblockcount",
+            params: [],
+            response: 1_200
+        }
+    };
+
+    requests.expect(vec![block_count_request()]);
+    let readiness = api.readiness().unwrap();
+    assert!(!readiness.ready);
+    assert_eq!(readiness.reasons, vec![NotReadyReason::ObserverLagging]);
+    assert_eq!(readiness.observer_lag, None);
+
+    let anchored_tx = testkit.latest_anchored_tx();
+    put_anchoring_tx_chain(&mut testkit, &[(0, anchored_tx)]);
+    requests.expect(vec![block_count_request()]);
+    let readiness = api.readiness().unwrap();
+    assert!(readiness.ready);
+    assert!(readiness.reasons.is_empty());
+    assert_eq!(readiness.checks.rpc, Some(true));
+    assert_eq!(readiness.observer_lag, Some(testkit.height().0));
+
+    requests.expect(vec![block_count_request()]);
+    let response =
+        iron_test::request::get("http://localhost:3000/v1/ready", Headers::new(), &api.handler())
+            .unwrap();
+    assert_eq!(response.status, Some(status::Ok));
+    let body = iron_test::response::extract_body_to_string(response);
+    let body: Readiness = serde_json::from_str(&body).unwrap();
+    assert_eq!(body, readiness);
+}
+
+// Checks the readiness of the node whose anchoring lags behind.
+// result: The node is not ready, the readiness probe gets `503`
+#[test]
+fn test_api_readiness_lagging() {
+    let mut testkit = AnchoringTestKit::default();
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    let api = PublicApi::new(testkit.blockchain_mut().clone());
+    assert!(api.readiness().unwrap().ready);
+
+    let api = api.with_max_anchoring_lag(1);
+    let readiness = api.readiness().unwrap();
+    assert!(!readiness.ready);
+    assert_eq!(readiness.reasons, vec![NotReadyReason::Lagging]);
+    assert!(readiness.checks.lagging);
+
+    let response =
+        iron_test::request::get("http://localhost:3000/v1/ready", Headers::new(), &api.handler())
+            .unwrap();
+    assert_eq!(response.status, Some(status::ServiceUnavailable));
+    let body = iron_test::response::extract_body_to_string(response);
+    let body: Readiness = serde_json::from_str(&body).unwrap();
+    assert_eq!(body, readiness);
+}
+
+// Tries to get the sync status when the bitcoind becomes unreachable.
+// result: The last known status marked as stale
+#[test]
+fn test_api_sync_status_relay_unavailable() {
+    let mut testkit = AnchoringTestKit::default();
+    let funding_tx = testkit.current_funding_tx();
+
+    let client = TestClient::default();
+    let requests = client.requests();
+    let api = PublicApi::new(testkit.blockchain_mut().clone())
+        .with_relay(Box::new(client))
+        .with_sync_status_cache_timeout(0);
+    let unavailable = || {
+        request! {
+            method: "getblockcount",
+            params: [],
+            error: RpcError::NoInformation("Connection refused".to_string())
+        }
+    };
+
+    requests.expect(vec![unavailable()]);
+    assert!(api.sync_status().is_err());
+
+    requests.expect(vec![
+        request! {
+            method: "getblockcount",
+            params: [],
+            response: 1_200
+        },
+        confirmations_request(&funding_tx, 10),
+    ]);
+    let status = api.sync_status().unwrap();
+    assert!(!status.stale);
+
+    requests.expect(vec![unavailable()]);
+    let stale_status = api.sync_status().unwrap();
+    assert!(stale_status.stale);
+    assert_eq!(stale_status.bitcoin_height, status.bitcoin_height);
+    assert_eq!(stale_status.confirmations, 10);
+    assert_eq!(stale_status.updated_at, status.updated_at);
+}
+
+// Tries to get the sync status without the configured relay.
+#[test]
+fn test_api_sync_status_without_relay() {
+    let mut testkit = AnchoringTestKit::default();
+    let api = PublicApi::new(testkit.blockchain_mut().clone());
+    let handler = api.handler();
+
+    let error =
+        iron_test::request::get("http://localhost:3000/v1/sync_status", Headers::new(), &handler)
+            .unwrap_err();
+    assert_eq!(error.response.status, Some(status::InternalServerError));
+}
+
+// Fires the block header proof requests faster than their rate limit allows.
+// result: The requests are rejected with 429 after the bucket drains,
+// other routes are not limited
+#[test]
+fn test_api_rate_limit() {
+    let mut testkit = AnchoringTestKit::default();
+    let mut rate_limits = RateLimitConfig::default();
+    rate_limits.routes.insert(
+        "/v1/block_header_proof/".to_owned(),
+        RateLimit {
+            burst: 3,
+            per_second: 1,
+        },
+    );
+    let handler = PublicApi::new(testkit.blockchain_mut().clone())
+        .with_rate_limits(rate_limits)
+        .handler();
+    let get_status = |url: &str| {
+        iron_test::request::get(url, Headers::new(), &handler)
+            .map(|response| response.status)
+            .unwrap_or_else(|error| error.response.status)
+    };
+
+    let statuses = (0..5)
+        .map(|_| get_status("http://localhost:3000/v1/block_header_proof/100"))
+        .collect::<Vec<_>>();
+    assert!(
+        statuses[..3]
+            .iter()
+            .all(|code| *code == Some(status::NotFound))
+    );
+    assert!(
+        statuses[3..]
+            .iter()
+            .all(|code| *code == Some(status::TooManyRequests))
+    );
+
+    for _ in 0..5 {
+        assert_eq!(
+            get_status("http://localhost:3000/v1/actual_lect/"),
+            Some(status::Ok)
+        );
+    }
 }
@@ -24,13 +24,16 @@ use exonum::messages::Message;
 
 use exonum_testkit::{TestKit, TestNetworkConfiguration};
 
-use exonum_btc_anchoring::blockchain::dto::{MsgAnchoringSignature, MsgAnchoringUpdateLatest};
+use exonum_btc_anchoring::blockchain::dto::{MsgAnchoringAnchorRequest, MsgAnchoringFeeRate,
+                                            MsgAnchoringReplaceRequest, MsgAnchoringSignature,
+                                            MsgAnchoringUpdateLatest};
 use exonum_btc_anchoring::blockchain::schema::AnchoringSchema;
 use exonum_btc_anchoring::details::btc;
-use exonum_btc_anchoring::details::btc::transactions::{BitcoinTx, RawBitcoinTx, TxFromRaw};
+use exonum_btc_anchoring::details::btc::transactions::{AnchoringTx, BitcoinTx, RawBitcoinTx,
+                                                       TxFromRaw};
 use exonum_btc_anchoring::{AnchoringConfig, ANCHORING_SERVICE_NAME};
 
-use super::{AnchoringTestKit, TestRequest};
+use super::{AnchoringTestKit, TestRequest, CHECK_LECT_FREQUENCY};
 
 pub use super::secp256k1_hack::sign_tx_input_with_nonce;
 pub use bitcoinrpc::Error as RpcError;
@@ -69,6 +72,33 @@ pub fn gen_service_tx_lect_wrong(
     )
 }
 
+pub fn gen_service_tx_fee_rate(
+    testkit: &TestKit,
+    validator: ValidatorId,
+    fee_per_kb: u64,
+) -> MsgAnchoringFeeRate {
+    let keypair = testkit.network().validators()[validator.0 as usize].service_keypair();
+    MsgAnchoringFeeRate::new(keypair.0, validator, fee_per_kb, keypair.1)
+}
+
+pub fn gen_service_tx_replace_request(
+    testkit: &TestKit,
+    validator: ValidatorId,
+    tx: &AnchoringTx,
+) -> MsgAnchoringReplaceRequest {
+    let keypair = testkit.network().validators()[validator.0 as usize].service_keypair();
+    MsgAnchoringReplaceRequest::new(keypair.0, validator, tx.clone(), keypair.1)
+}
+
+pub fn gen_service_tx_anchor_request(
+    testkit: &TestKit,
+    validator: ValidatorId,
+    height: Height,
+) -> MsgAnchoringAnchorRequest {
+    let keypair = testkit.network().validators()[validator.0 as usize].service_keypair();
+    MsgAnchoringAnchorRequest::new(keypair.0, validator, height.0, keypair.1)
+}
+
 pub fn dump_lects(testkit: &TestKit, id: ValidatorId) -> Vec<BitcoinTx> {
     let anchoring_schema = AnchoringSchema::new(testkit.snapshot());
     let key = &anchoring_schema.actual_anchoring_config().anchoring_keys[id.0 as usize];
@@ -148,6 +178,37 @@ pub fn confirmations_request(raw: &RawBitcoinTx, confirmations: u64) -> TestRequ
     }
 }
 
+pub fn confirmations_in_block_request(
+    raw: &RawBitcoinTx,
+    confirmations: u64,
+    block_hash: &btc::BlockHash,
+) -> TestRequest {
+    let mut request = confirmations_request(raw, confirmations);
+    if let Ok(ref mut response) = request.response {
+        response["blockhash"] = json!(block_hash);
+    }
+    request
+}
+
+pub fn get_block_header_request(block_hash: &btc::BlockHash, confirmations: i64) -> TestRequest {
+    request! {
+        method: "getblockheader",
+        params: [block_hash],
+        response: {
+            "hash": block_hash,
+            "confirmations": confirmations
+        }
+    }
+}
+
+pub fn get_block_count_request(count: u64) -> TestRequest {
+    request! {
+        method: "getblockcount",
+        params: [],
+        response: count
+    }
+}
+
 pub fn get_transaction_request(raw: &RawBitcoinTx) -> TestRequest {
     let tx = BitcoinTx::from_raw(raw.clone()).unwrap();
     request! {
@@ -431,6 +492,33 @@ pub fn anchor_second_block_normal(testkit: &mut AnchoringTestKit) {
     testkit.create_block_with_transactions(txs);
 }
 
+/// Creates blocks until the lect check height at which the given validator takes its turn
+/// to top up the anchoring address, the height itself is not created. At the lect check
+/// heights of the other turns the latest anchored tx is reported as the lect.
+pub fn create_blocks_until_top_up_turn(testkit: &mut AnchoringTestKit, validator: ValidatorId) {
+    let anchored_tx = testkit.latest_anchored_tx();
+    let anchoring_addr = testkit.current_addr();
+    let validators_count = testkit.network().validators().len() as u64;
+    loop {
+        let height = testkit.next_check_lect_height();
+        testkit.create_blocks_until(height);
+        let round = height.next().0 / CHECK_LECT_FREQUENCY;
+        if round % validators_count == u64::from(validator.0) {
+            return;
+        }
+
+        testkit.requests().expect(vec![
+            request! {
+                method: "listunspent",
+                params: [0, 9_999_999, [&anchoring_addr]],
+                response: [listunspent_entry(&anchored_tx, &anchoring_addr, 1)]
+            },
+            get_transaction_request(&anchored_tx),
+        ]);
+        testkit.create_block();
+    }
+}
+
 /// Anchor genesis block using funding tx
 pub fn anchor_first_block_without_other_signatures(testkit: &mut AnchoringTestKit) {
     let requests = testkit.requests();
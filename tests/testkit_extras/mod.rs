@@ -25,6 +25,8 @@ use exonum::helpers::{Height, ValidatorId};
 use exonum_testkit::{TestKit, TestKitBuilder};
 
 pub use self::rpc::{TestClient, TestRequest, TestRequests};
+use exonum_btc_anchoring::api::subscriptions::LectsNotifier;
+use exonum_btc_anchoring::blockchain::AnchoringSchema;
 use exonum_btc_anchoring::blockchain::dto::MsgAnchoringSignature;
 use exonum_btc_anchoring::details::btc;
 use exonum_btc_anchoring::details::btc::transactions::{AnchoringTx, FundingTx, RawBitcoinTx,
@@ -50,6 +52,7 @@ pub struct AnchoringTestKit {
     inner: TestKit,
     requests: TestRequests,
     handler: Arc<Mutex<AnchoringHandler>>,
+    notifier: LectsNotifier,
     errors_receiver: mpsc::Receiver<HandlerError>,
     nodes: Vec<AnchoringNodeConfig>,
     latest_anchored_tx: Option<(AnchoringTx, Vec<MsgAnchoringSignature>)>,
@@ -77,15 +80,41 @@ impl Default for AnchoringTestKit {
 
 impl AnchoringTestKit {
     pub fn new() -> AnchoringTestKit {
+        AnchoringTestKit::create(false, |_| {})
+    }
+
+    /// Creates testkit for the auditor node in the observer only mode without private keys.
+    pub fn new_observer() -> AnchoringTestKit {
+        AnchoringTestKit::create(true, |_| {})
+    }
+
+    /// Creates testkit with the genesis anchoring configuration changed by the given closure.
+    pub fn with_config<F>(modify: F) -> AnchoringTestKit
+    where
+        F: FnOnce(&mut AnchoringConfig),
+    {
+        AnchoringTestKit::create(false, modify)
+    }
+
+    fn create<F>(observer_only: bool, modify: F) -> AnchoringTestKit
+    where
+        F: FnOnce(&mut AnchoringConfig),
+    {
         let mut client = TestClient::default();
         let (mut common, mut nodes) = gen_sandbox_anchoring_config(&mut client);
         // Change default anchoring configs
         common.frequency = ANCHORING_FREQUENCY;
         common.utxo_confirmations = ANCHORING_UTXO_CONFIRMATIONS;
+        modify(&mut common);
         for node in &mut nodes {
             node.check_lect_frequency = CHECK_LECT_FREQUENCY;
+            node.required_confirmations = 0;
         }
 
+        // The funding transaction is checked by the service at startup.
+        if let Some(ref funding_tx) = common.funding_tx {
+            client.requests().expect(vec![helpers::confirmations_request(funding_tx, 100)]);
+        }
         client.requests().expect(vec![request! {
             method: "importaddress",
             params: [
@@ -96,10 +125,18 @@ impl AnchoringTestKit {
             ]
         }]);
         let requests = client.requests();
-        let service =
-            AnchoringService::new_with_client(Box::new(client), common.clone(), nodes[0].clone());
+        let (builder, node) = if observer_only {
+            let mut node = nodes[0].clone();
+            node.private_keys.clear();
+            node.observer_only = true;
+            (TestKitBuilder::auditor(), node)
+        } else {
+            (TestKitBuilder::validator(), nodes[0].clone())
+        };
+        let service = AnchoringService::new_with_client(Box::new(client), common.clone(), node);
         let handler = service.handler();
-        let testkit = TestKitBuilder::validator()
+        let notifier = service.notifier();
+        let testkit = builder
             .with_validators(4)
             .with_service(service)
             .create();
@@ -110,6 +147,7 @@ impl AnchoringTestKit {
         AnchoringTestKit {
             inner: testkit,
             handler,
+            notifier,
             requests,
             nodes,
             latest_anchored_tx: None,
@@ -125,6 +163,10 @@ impl AnchoringTestKit {
         self.handler.lock().unwrap()
     }
 
+    pub fn notifier(&self) -> LectsNotifier {
+        self.notifier.clone()
+    }
+
     pub fn take_handler_errors(&mut self) -> Vec<HandlerError> {
         self.errors_receiver.try_iter().collect()
     }
@@ -228,8 +270,11 @@ impl AnchoringTestKit {
                     (tx.0.clone(), input)
                 });
 
+            let table_root = AnchoringSchema::new(self.snapshot()).anchored_table_root(height);
             let mut builder = TransactionBuilder::with_prev_tx(&prev_tx, prev_tx_input)
                 .payload(height, block_hash)
+                .table_root(table_root)
+                .anchor_output(self.current_cfg().anchor_output())
                 .prev_tx_chain(prev_tx_chain)
                 .send_to(addr.clone())
                 .fee(1000);
@@ -277,7 +322,8 @@ impl AnchoringTestKit {
         for (validator, priv_key) in priv_keys.iter().enumerate() {
             let validator = ValidatorId(validator as u16);
             for input in tx.inputs() {
-                let prev_tx = &prev_txs[input as usize];
+                let prev_hash = tx.input[input as usize].prev_hash;
+                let prev_tx = prev_txs.iter().find(|tx| tx.txid() == prev_hash).unwrap();
                 let signature = tx.sign_input(&redeem_script, input, prev_tx, priv_key);
                 let keypair = self.validator(validator).service_keypair();
                 signs.push(MsgAnchoringSignature::new(
@@ -295,7 +341,7 @@ impl AnchoringTestKit {
 }
 
 /// Generates config for 4 validators and 4000 funds
-fn gen_sandbox_anchoring_config(
+pub fn gen_sandbox_anchoring_config(
     client: &mut TestClient,
 ) -> (AnchoringConfig, Vec<AnchoringNodeConfig>) {
     let requests = vec![
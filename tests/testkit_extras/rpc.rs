@@ -26,8 +26,10 @@ use serde_json::value::{from_value, Value};
 use exonum::encoding::serialize::FromHex;
 
 use exonum_btc_anchoring::details::btc;
-use exonum_btc_anchoring::details::btc::transactions::{BitcoinTx, FundingTx, TxKind};
-use exonum_btc_anchoring::details::rpc::{AnchoringRpcConfig, BitcoinRelay, TxInfo, SATOSHI_DIVISOR};
+use exonum_btc_anchoring::details::btc::transactions::{BitcoinTx, FundingTx};
+use exonum_btc_anchoring::details::rpc::{block_is_in_best_chain, fee_rate_from_estimate,
+                                         AnchoringRpcConfig, BitcoinRelay, RawTxInfo, TxInfo,
+                                         SATOSHI_DIVISOR};
 
 #[derive(Debug)]
 pub struct TestRequest {
@@ -127,7 +129,7 @@ impl TestClient {
         self.request("getrawtransaction", params)
     }
 
-    pub fn getrawtransaction_verbose(&self, txid: &str) -> Result<RawTransactionInfo> {
+    pub fn getrawtransaction_verbose(&self, txid: &str) -> Result<RawTxInfo> {
         let params = json!([txid, 1]).as_array().cloned().unwrap();
         self.request("getrawtransaction", params)
     }
@@ -164,6 +166,18 @@ impl TestClient {
             Err(e) => Err(e),
         }
     }
+
+    pub fn estimatesmartfee(&self, conf_target: u16) -> Result<Value> {
+        self.request("estimatesmartfee", vec![json!(conf_target)])
+    }
+
+    pub fn getblockcount(&self) -> Result<u64> {
+        self.request("getblockcount", Vec::new())
+    }
+
+    pub fn getblockheader(&self, hash: &str) -> Result<Value> {
+        self.request("getblockheader", vec![Value::String(hash.to_owned())])
+    }
 }
 
 impl BitcoinRelay for TestClient {
@@ -211,22 +225,36 @@ impl BitcoinRelay for TestClient {
             let txid = btc::TxId::from_hex(&info.txid).unwrap();
             let confirmations = Some(info.confirmations);
             if let Some(raw_tx) = self.get_transaction(txid)? {
-                match TxKind::from(raw_tx) {
-                    TxKind::Anchoring(tx) => txs.push(TxInfo {
-                        body: tx.into(),
-                        confirmations,
-                    }),
-                    TxKind::FundingTx(tx) => txs.push(TxInfo {
-                        body: tx.into(),
-                        confirmations,
-                    }),
-                    TxKind::Other(_) => {}
-                }
+                // The transactions are classified by the caller according to the payload
+                // prefix of its configuration.
+                txs.push(TxInfo {
+                    body: BitcoinTx::from(raw_tx),
+                    confirmations,
+                    block_hash: None,
+                    block_time: None,
+                });
             }
         }
         Ok(txs)
     }
 
+    fn estimate_fee(&self, conf_target: u16) -> Result<Option<u64>> {
+        let response = self.estimatesmartfee(conf_target)?;
+        Ok(fee_rate_from_estimate(&response))
+    }
+
+    fn get_block_count(&self) -> Result<u64> {
+        self.getblockcount()
+    }
+
+    fn is_block_in_best_chain(&self, block_hash: btc::BlockHash) -> Result<bool> {
+        match self.getblockheader(&block_hash.to_string()) {
+            Ok(header) => Ok(block_is_in_best_chain(&header)),
+            Err(Error::NoInformation(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
     fn config(&self) -> AnchoringRpcConfig {
         self.rpc.clone()
     }
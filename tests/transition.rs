@@ -30,10 +30,15 @@ extern crate secp256k1;
 extern crate serde;
 #[macro_use]
 extern crate serde_json;
+extern crate ws;
 
 #[macro_use]
 pub mod testkit_extras;
 
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
 use bitcoin::network::constants::Network;
 use rand::{SeedableRng, StdRng};
 
@@ -41,11 +46,15 @@ use exonum::blockchain::Transaction;
 use exonum::crypto::{gen_keypair_from_seed, CryptoHash, Seed};
 use exonum::encoding::serialize::FromHex;
 use exonum::helpers::{Height, ValidatorId};
-use exonum_testkit::{TestNetworkConfiguration, TestNode};
+use exonum_testkit::{ApiKind, TestNetworkConfiguration, TestNode};
 
+use exonum_btc_anchoring::api::subscriptions::{LectsServer, WebSocketConfig,
+                                               TRANSITION_RESOURCE};
+use exonum_btc_anchoring::api::TransitionStatus;
 use exonum_btc_anchoring::blockchain::AnchoringSchema;
 use exonum_btc_anchoring::details::btc;
 use exonum_btc_anchoring::details::btc::transactions::{FundingTx, TransactionBuilder};
+use exonum_btc_anchoring::handler::TransitionEvent;
 use exonum_btc_anchoring::observer::AnchoringChainObserver;
 use exonum_btc_anchoring::{AnchoringConfig, AnchoringNodeConfig, ANCHORING_SERVICE_NAME};
 use testkit_extras::helpers::*;
@@ -207,6 +216,104 @@ fn gen_following_cfg_add_two_validators_changed_self_key(
     (cfg_proposal, anchoring_cfg, new_nodes)
 }
 
+fn transition_status(testkit: &AnchoringTestKit) -> TransitionStatus {
+    testkit
+        .api()
+        .get(ApiKind::Service(ANCHORING_SERVICE_NAME), "/v1/transition")
+}
+
+// We commit a new configuration and check the transition status before the transfer
+// transaction is signed
+// problems:
+// - none
+// result: transition is in progress without signatures
+#[test]
+fn test_transit_status_pending() {
+    let cfg_change_height = Height(16);
+
+    let mut testkit = AnchoringTestKit::default();
+    let requests = testkit.requests();
+
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    let (cfg_proposal, following_cfg) = gen_following_cfg(&mut testkit, cfg_change_height, None);
+    testkit.commit_configuration_change(cfg_proposal);
+    let (_, following_addr) = following_cfg.redeem_script();
+
+    let anchored_tx = testkit.latest_anchored_tx();
+    requests.expect(vec![
+        request! {
+            method: "importaddress",
+            params: [&following_addr, "multisig", false, false]
+        },
+        confirmations_request(&anchored_tx, 10),
+    ]);
+    testkit.create_block();
+
+    assert_eq!(
+        transition_status(&testkit),
+        TransitionStatus {
+            in_progress: true,
+            actual_address: testkit.current_addr(),
+            following_address: Some(following_addr),
+            collected_signatures: 0,
+        }
+    );
+}
+
+// We commit a new configuration and check the transition status while the signatures
+// for the transfer transaction are being collected
+// problems:
+// - none
+// result: transition is in progress with the committed signatures
+#[test]
+fn test_transit_status_collecting_signatures() {
+    let cfg_change_height = Height(16);
+
+    let mut testkit = AnchoringTestKit::default();
+    let requests = testkit.requests();
+
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    let (cfg_proposal, following_cfg) = gen_following_cfg(&mut testkit, cfg_change_height, None);
+    testkit.commit_configuration_change(cfg_proposal);
+    let (_, following_addr) = following_cfg.redeem_script();
+
+    let anchored_tx = testkit.latest_anchored_tx();
+    requests.expect(vec![
+        request! {
+            method: "importaddress",
+            params: [&following_addr, "multisig", false, false]
+        },
+        confirmations_request(&anchored_tx, 10),
+    ]);
+    testkit.create_block();
+
+    requests.expect(vec![confirmations_request(&anchored_tx, 100)]);
+    let (_, mut signatures) = testkit.gen_anchoring_tx_with_signatures(
+        Height::zero(),
+        anchored_tx.payload().block_hash,
+        &[],
+        None,
+        &following_addr,
+    );
+    testkit.create_block();
+    assert!(testkit.is_tx_in_pool(&signatures[0].hash()));
+
+    testkit.create_block_with_transactions(signatures.drain(0..2));
+    assert_eq!(
+        transition_status(&testkit),
+        TransitionStatus {
+            in_progress: true,
+            actual_address: testkit.current_addr(),
+            following_address: Some(following_addr),
+            collected_signatures: 2,
+        }
+    );
+}
+
 // We commit a new configuration and take actions to transit tx chain to the new address
 // problems:
 // - none
@@ -443,6 +550,143 @@ fn test_transit_unchanged_self_key_normal() {
     testkit.create_block_with_transactions(lects);
 }
 
+struct TransitionSubscriber {
+    out: ws::Sender,
+    remaining: usize,
+    opened: mpsc::Sender<()>,
+    received: mpsc::Sender<String>,
+}
+
+impl ws::Handler for TransitionSubscriber {
+    fn on_open(&mut self, _: ws::Handshake) -> ws::Result<()> {
+        self.opened.send(()).unwrap();
+        Ok(())
+    }
+
+    fn on_message(&mut self, msg: ws::Message) -> ws::Result<()> {
+        self.received.send(msg.into_text()?).unwrap();
+        self.remaining -= 1;
+        if self.remaining == 0 {
+            self.out.close(ws::CloseCode::Normal)?;
+        }
+        Ok(())
+    }
+}
+
+// We subscribe to the transition events via WebSocket and take actions
+// to transit tx chain to the new address
+// problems:
+// - none
+// result: the events of all transition stages are delivered in order
+#[test]
+fn test_transit_websocket_events() {
+    let cfg_change_height = Height(16);
+
+    let mut testkit = AnchoringTestKit::default();
+    let requests = testkit.requests();
+
+    anchor_first_block(&mut testkit);
+    anchor_first_block_lect_normal(&mut testkit);
+
+    let notifier = testkit.notifier();
+    let config = WebSocketConfig {
+        listen_address: "127.0.0.1:0".parse().unwrap(),
+        out_buffer_capacity: 64 * 1024,
+    };
+    let server = LectsServer::bind(&notifier, &config).unwrap();
+    let url = format!("ws://{}{}", server.local_addr().unwrap(), TRANSITION_RESOURCE);
+    let server_thread = thread::spawn(move || server.run().unwrap());
+
+    let (opened_tx, opened_rx) = mpsc::channel();
+    let (received_tx, received_rx) = mpsc::channel();
+    let client_thread = thread::spawn(move || {
+        ws::connect(url, |out| TransitionSubscriber {
+            out,
+            remaining: 4,
+            opened: opened_tx.clone(),
+            received: received_tx.clone(),
+        }).unwrap();
+    });
+    opened_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+
+    let anchoring_addr = testkit.current_addr();
+    let (cfg_proposal, following_cfg) =
+        gen_following_cfg_unchanged_self_key(&mut testkit, cfg_change_height, None);
+    testkit.commit_configuration_change(cfg_proposal);
+    let (_, following_addr) = following_cfg.redeem_script();
+
+    let anchored_tx = testkit.latest_anchored_tx();
+    requests.expect(vec![
+        request! {
+            method: "importaddress",
+            params: [&following_addr, "multisig", false, false]
+        },
+        confirmations_request(&anchored_tx, 10),
+    ]);
+    testkit.create_block();
+
+    requests.expect(vec![confirmations_request(&anchored_tx, 100)]);
+    let (_, signatures) = testkit.gen_anchoring_tx_with_signatures(
+        Height::zero(),
+        anchored_tx.payload().block_hash,
+        &[],
+        None,
+        &following_addr,
+    );
+    let transition_tx = testkit.latest_anchored_tx();
+    testkit.create_block();
+
+    requests.expect(vec![get_transaction_request(&transition_tx)]);
+    testkit.create_block_with_transactions(signatures);
+
+    let lects = (0..4)
+        .map(ValidatorId)
+        .map(|id| gen_service_tx_lect(&testkit, id, &transition_tx, 2))
+        .map(Box::<Transaction>::from)
+        .collect::<Vec<_>>();
+    requests.expect(vec![confirmations_request(&transition_tx, 0)]);
+    testkit.create_block_with_transactions(lects);
+
+    for i in testkit.height().next().0..cfg_change_height.previous().0 {
+        requests.expect(vec![confirmations_request(&transition_tx, 15 + i)]);
+        testkit.create_block();
+    }
+    requests.expect(vec![confirmations_request(&transition_tx, 30)]);
+    testkit.create_block();
+
+    let events = (0..4)
+        .map(|_| {
+            let msg = received_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+            serde_json::from_str::<TransitionEvent>(&msg).unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        events,
+        vec![
+            TransitionEvent::Started {
+                from: anchoring_addr,
+                to: following_addr.clone(),
+            },
+            TransitionEvent::TransferBuilt {
+                to: following_addr.clone(),
+                txid: transition_tx.id(),
+            },
+            TransitionEvent::SignaturesCollected {
+                to: following_addr.clone(),
+                txid: transition_tx.id(),
+            },
+            TransitionEvent::TransferConfirmed {
+                to: following_addr,
+                txid: transition_tx.id(),
+            },
+        ]
+    );
+
+    client_thread.join().unwrap();
+    notifier.shutdown();
+    server_thread.join().unwrap();
+}
+
 // We commit a new configuration with confirmed funding tx
 // and take actions to transit tx chain to the new address
 // problems:
@@ -1820,6 +2064,7 @@ fn test_transit_changed_self_key_observer() {
             ]
         },
         get_transaction_request(&third_anchored_tx),
+        get_block_count_request(1_000),
         confirmations_request(&third_anchored_tx, 100),
         get_transaction_request(&transition_tx),
         confirmations_request(&transition_tx, 150),